@@ -0,0 +1,588 @@
+//! Bounded-memory analysis history
+//!
+//! Instead of keeping processed buffers around, every frame is reduced to a
+//! handful of features (levels, clip count, VAD state, spectral centroid).
+//! Recent frames are kept individually; older ones are folded into blocks
+//! that double in span whenever the block store fills up. Any window, from
+//! the last few seconds to the whole session, is answered from those
+//! summaries at a fixed memory cost.
+
+use std::collections::VecDeque;
+use std::f32::consts::PI;
+use serde::Serialize;
+
+use super::quality::{power_to_db, SpeechDetector};
+use super::types::AudioBuffer;
+
+/// Samples below this magnitude count as silent
+const SILENCE_THRESHOLD: f32 = 0.001;
+
+/// Samples at or above this magnitude count as clipped
+const CLIPPING_THRESHOLD: f32 = 0.95;
+
+/// Largest FFT used for the spectral centroid
+const MAX_FFT_SIZE: usize = 1024;
+
+/// Default number of individual frames kept
+const DEFAULT_RECENT_FRAMES: usize = 512;
+
+/// Default number of older blocks kept; must be even
+const DEFAULT_BLOCK_CAPACITY: usize = 1024;
+
+/// Initial span of one older block
+const DEFAULT_BLOCK_MS: f64 = 1000.0;
+
+/// Features extracted from one processed frame
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct FrameFeatures {
+    /// Session time at the start of the frame
+    pub start_ms: f64,
+    pub duration_ms: f64,
+    pub samples: u32,
+    pub rms: f32,
+    pub peak: f32,
+    pub clipped: u32,
+    pub silent: u32,
+    pub is_speech: bool,
+    pub spectral_centroid_hz: f32,
+}
+
+/// Additive summary over a run of frames
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct FeatureSummary {
+    pub start_ms: f64,
+    pub end_ms: f64,
+    pub frames: u64,
+    pub samples: u64,
+    pub energy: f64,
+    pub peak: f32,
+    pub clipped: u64,
+    pub silent: u64,
+    pub speech_frames: u64,
+    pub speech_energy: f64,
+    pub speech_samples: u64,
+    pub noise_energy: f64,
+    pub noise_samples: u64,
+    centroid_weighted: f64,
+    centroid_weight: f64,
+}
+
+impl FeatureSummary {
+    /// Empty summary starting at `start_ms`
+    fn starting_at(start_ms: f64) -> Self {
+        Self {
+            start_ms,
+            end_ms: start_ms,
+            ..Self::default()
+        }
+    }
+
+    /// Fold one frame in
+    pub fn add_frame(&mut self, frame: &FrameFeatures) {
+        if self.frames == 0 && self.start_ms == self.end_ms {
+            self.start_ms = self.start_ms.min(frame.start_ms);
+        }
+        self.end_ms = self.end_ms.max(frame.start_ms + frame.duration_ms);
+
+        let energy = (frame.rms as f64).powi(2) * frame.samples as f64;
+        self.frames += 1;
+        self.samples += frame.samples as u64;
+        self.energy += energy;
+        self.peak = self.peak.max(frame.peak);
+        self.clipped += frame.clipped as u64;
+        self.silent += frame.silent as u64;
+
+        if frame.is_speech {
+            self.speech_frames += 1;
+            self.speech_energy += energy;
+            self.speech_samples += frame.samples as u64;
+        } else {
+            self.noise_energy += energy;
+            self.noise_samples += frame.samples as u64;
+        }
+
+        self.centroid_weighted += frame.spectral_centroid_hz as f64 * energy;
+        self.centroid_weight += energy;
+    }
+
+    /// Fold another summary in
+    pub fn merge(&mut self, other: &FeatureSummary) {
+        if other.frames == 0 {
+            return;
+        }
+        if self.frames == 0 {
+            *self = FeatureSummary { start_ms: self.start_ms.min(other.start_ms), ..*other };
+            return;
+        }
+
+        self.start_ms = self.start_ms.min(other.start_ms);
+        self.end_ms = self.end_ms.max(other.end_ms);
+        self.frames += other.frames;
+        self.samples += other.samples;
+        self.energy += other.energy;
+        self.peak = self.peak.max(other.peak);
+        self.clipped += other.clipped;
+        self.silent += other.silent;
+        self.speech_frames += other.speech_frames;
+        self.speech_energy += other.speech_energy;
+        self.speech_samples += other.speech_samples;
+        self.noise_energy += other.noise_energy;
+        self.noise_samples += other.noise_samples;
+        self.centroid_weighted += other.centroid_weighted;
+        self.centroid_weight += other.centroid_weight;
+    }
+
+    /// Turn the summary into analysis results
+    pub fn analysis(&self) -> AudioAnalysis {
+        if self.samples == 0 {
+            return AudioAnalysis::empty();
+        }
+
+        let samples = self.samples as f64;
+        let average_level = (self.energy / samples).sqrt() as f32;
+        let dynamic_range = if average_level > 0.0 {
+            20.0 * (self.peak / average_level).log10()
+        } else {
+            0.0
+        };
+
+        // Speech power against the power of everything else
+        let estimated_snr = if self.speech_samples > 0 && self.noise_samples > 0 {
+            let speech = power_to_db(self.speech_energy / self.speech_samples as f64);
+            let noise = power_to_db(self.noise_energy / self.noise_samples as f64);
+            (speech - noise) as f32
+        } else {
+            -100.0
+        };
+
+        AudioAnalysis {
+            duration_ms: self.end_ms - self.start_ms,
+            average_level,
+            peak_level: self.peak,
+            dynamic_range,
+            silence_percentage: (self.silent as f64 / samples * 100.0) as f32,
+            clipping_percentage: (self.clipped as f64 / samples * 100.0) as f32,
+            speech_percentage: (self.speech_frames as f64 / self.frames as f64 * 100.0) as f32,
+            spectral_centroid_hz: if self.centroid_weight > 0.0 {
+                (self.centroid_weighted / self.centroid_weight) as f32
+            } else {
+                0.0
+            },
+            estimated_snr,
+        }
+    }
+}
+
+/// Audio analysis results
+#[derive(Debug, Clone, Serialize)]
+pub struct AudioAnalysis {
+    pub duration_ms: f64,
+    pub average_level: f32,
+    pub peak_level: f32,
+    pub dynamic_range: f32,
+    pub silence_percentage: f32,
+    pub clipping_percentage: f32,
+    pub speech_percentage: f32,
+    /// Energy-weighted mean spectral centroid
+    pub spectral_centroid_hz: f32,
+    pub estimated_snr: f32, // Speech-to-noise ratio estimate
+}
+
+impl AudioAnalysis {
+    /// Analysis of no audio at all
+    pub fn empty() -> Self {
+        Self {
+            duration_ms: 0.0,
+            average_level: 0.0,
+            peak_level: 0.0,
+            dynamic_range: 0.0,
+            silence_percentage: 100.0,
+            clipping_percentage: 0.0,
+            speech_percentage: 0.0,
+            spectral_centroid_hz: 0.0,
+            estimated_snr: -100.0,
+        }
+    }
+}
+
+/// Turns buffers into `FrameFeatures`, reusing its FFT scratch space
+#[derive(Debug, Clone)]
+pub struct FeatureExtractor {
+    speech_detector: SpeechDetector,
+    elapsed_ms: f64,
+    fft_re: Vec<f32>,
+    fft_im: Vec<f32>,
+}
+
+impl Default for FeatureExtractor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FeatureExtractor {
+    pub fn new() -> Self {
+        Self {
+            speech_detector: SpeechDetector::new(),
+            elapsed_ms: 0.0,
+            fft_re: vec![0.0; MAX_FFT_SIZE],
+            fft_im: vec![0.0; MAX_FFT_SIZE],
+        }
+    }
+
+    /// Extract features from the next buffer of the session
+    pub fn extract(&mut self, buffer: &AudioBuffer) -> FrameFeatures {
+        let samples = &buffer.samples;
+        let duration_ms = buffer.duration_ms();
+
+        let mut energy = 0.0f64;
+        let mut peak = 0.0f32;
+        let mut clipped = 0;
+        let mut silent = 0;
+        for &sample in samples {
+            let magnitude = sample.abs();
+            energy += (sample as f64).powi(2);
+            peak = peak.max(magnitude);
+            if magnitude >= CLIPPING_THRESHOLD {
+                clipped += 1;
+            }
+            if magnitude < SILENCE_THRESHOLD {
+                silent += 1;
+            }
+        }
+
+        let power = if samples.is_empty() { 0.0 } else { energy / samples.len() as f64 };
+        let is_speech = !samples.is_empty() && self.speech_detector.classify(power_to_db(power), duration_ms);
+
+        let features = FrameFeatures {
+            start_ms: self.elapsed_ms,
+            duration_ms,
+            samples: samples.len() as u32,
+            rms: power.sqrt() as f32,
+            peak,
+            clipped,
+            silent,
+            is_speech,
+            spectral_centroid_hz: self.spectral_centroid(buffer),
+        };
+
+        self.elapsed_ms += duration_ms;
+        features
+    }
+
+    /// Magnitude-weighted mean frequency of the first channel
+    fn spectral_centroid(&mut self, buffer: &AudioBuffer) -> f32 {
+        let channels = buffer.channels.max(1) as usize;
+        let frames = buffer.samples.len() / channels;
+        if frames < 2 {
+            return 0.0;
+        }
+
+        // Largest power of two that fits, Hann windowed
+        let n = 1usize << (usize::BITS - 1 - frames.min(MAX_FFT_SIZE).leading_zeros());
+        for i in 0..n {
+            let window = 0.5 - 0.5 * (2.0 * PI * i as f32 / (n - 1) as f32).cos();
+            self.fft_re[i] = buffer.samples[i * channels] * window;
+            self.fft_im[i] = 0.0;
+        }
+        fft_in_place(&mut self.fft_re[..n], &mut self.fft_im[..n]);
+
+        let bin_hz = buffer.sample_rate as f32 / n as f32;
+        let (weighted, total) = (1..n / 2).fold((0.0f32, 0.0f32), |(weighted, total), k| {
+            let magnitude = self.fft_re[k].hypot(self.fft_im[k]);
+            (weighted + k as f32 * bin_hz * magnitude, total + magnitude)
+        });
+
+        if total > f32::EPSILON { weighted / total } else { 0.0 }
+    }
+}
+
+/// Iterative radix-2 FFT; `re.len()` must be a power of two
+pub(crate) fn fft_in_place(re: &mut [f32], im: &mut [f32]) {
+    let n = re.len();
+
+    // Bit-reversal permutation
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+
+    let mut len = 2;
+    while len <= n {
+        let angle = -2.0 * PI / len as f32;
+        for start in (0..n).step_by(len) {
+            for k in 0..len / 2 {
+                let (sin, cos) = (angle * k as f32).sin_cos();
+                let a = start + k;
+                let b = a + len / 2;
+                let tr = re[b] * cos - im[b] * sin;
+                let ti = re[b] * sin + im[b] * cos;
+                re[b] = re[a] - tr;
+                im[b] = im[a] - ti;
+                re[a] += tr;
+                im[a] += ti;
+            }
+        }
+        len <<= 1;
+    }
+}
+
+/// Rolling store of frame features with fixed memory use
+#[derive(Debug, Clone)]
+pub struct FeatureHistory {
+    recent: VecDeque<FrameFeatures>,
+    recent_capacity: usize,
+    blocks: VecDeque<FeatureSummary>,
+    block_capacity: usize,
+    block_ms: f64,
+    open_block: FeatureSummary,
+    session: FeatureSummary,
+}
+
+impl Default for FeatureHistory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FeatureHistory {
+    /// History with the default capacities
+    pub fn new() -> Self {
+        Self::with_capacity(DEFAULT_RECENT_FRAMES, DEFAULT_BLOCK_CAPACITY, DEFAULT_BLOCK_MS)
+    }
+
+    /// History keeping `recent_frames` individual frames and `block_capacity`
+    /// older blocks that start out `block_ms` long
+    pub fn with_capacity(recent_frames: usize, block_capacity: usize, block_ms: f64) -> Self {
+        let block_capacity = (block_capacity.max(2) + 1) & !1;
+        Self {
+            recent: VecDeque::with_capacity(recent_frames.max(1)),
+            recent_capacity: recent_frames.max(1),
+            blocks: VecDeque::with_capacity(block_capacity),
+            block_capacity,
+            block_ms: block_ms.max(1.0),
+            open_block: FeatureSummary::starting_at(0.0),
+            session: FeatureSummary::starting_at(0.0),
+        }
+    }
+
+    /// Record the features of the next frame
+    pub fn push(&mut self, frame: FrameFeatures) {
+        if self.recent.len() == self.recent_capacity {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(frame);
+
+        // Close blocks until the frame's start falls in the open one
+        while frame.start_ms >= self.open_block.start_ms + self.block_ms {
+            let next_start = self.open_block.start_ms + self.block_ms;
+            let closed = std::mem::replace(&mut self.open_block, FeatureSummary::starting_at(next_start));
+            self.blocks.push_back(closed);
+
+            if self.blocks.len() == self.block_capacity {
+                self.compact();
+            }
+        }
+        self.open_block.add_frame(&frame);
+        self.session.add_frame(&frame);
+    }
+
+    /// Merge neighbouring blocks pairwise, doubling the block span
+    fn compact(&mut self) {
+        let merged: Vec<FeatureSummary> = self.blocks
+            .drain(..)
+            .collect::<Vec<_>>()
+            .chunks(2)
+            .map(|pair| {
+                let mut block = pair[0];
+                if let Some(second) = pair.get(1) {
+                    block.merge(second);
+                }
+                block
+            })
+            .collect();
+        self.blocks.extend(merged);
+        self.block_ms *= 2.0;
+    }
+
+    /// Session time covered so far
+    pub fn duration_ms(&self) -> f64 {
+        self.session.end_ms
+    }
+
+    /// Number of frames recorded over the session
+    pub fn frame_count(&self) -> u64 {
+        self.session.frames
+    }
+
+    /// Individually kept recent frames, oldest first
+    pub fn recent_frames(&self) -> impl Iterator<Item = &FrameFeatures> {
+        self.recent.iter()
+    }
+
+    /// Summary over the whole session
+    pub fn session_summary(&self) -> FeatureSummary {
+        self.session
+    }
+
+    /// Summary over the last `window_ms` of audio
+    ///
+    /// Within the recent frames the window is exact; further back it is
+    /// rounded out to whole blocks.
+    pub fn summary_for_last(&self, window_ms: f64) -> FeatureSummary {
+        let now = self.duration_ms();
+        let cutoff = now - window_ms;
+        if cutoff <= 0.0 {
+            return self.session;
+        }
+
+        let oldest_recent = self.recent.front().map_or(now, |frame| frame.start_ms);
+        let mut summary = FeatureSummary::starting_at(cutoff.max(0.0));
+
+        // Recent frames alone cover the window
+        if cutoff >= oldest_recent {
+            for frame in self.recent.iter().filter(|f| f.start_ms >= cutoff) {
+                summary.add_frame(frame);
+            }
+            return summary;
+        }
+
+        // Blocks up to the end of the one holding the oldest recent frame,
+        // exact frames after that. Blocks are contiguous and all `block_ms` long.
+        let blocks = self.blocks.iter().chain(std::iter::once(&self.open_block));
+        let boundary = blocks.clone()
+            .map(|block| block.start_ms + self.block_ms)
+            .find(|&end| end > oldest_recent)
+            .unwrap_or(now);
+
+        for block in blocks {
+            if block.start_ms < boundary && block.start_ms + self.block_ms > cutoff {
+                summary.merge(block);
+            }
+        }
+        for frame in self.recent.iter().filter(|f| f.start_ms >= boundary) {
+            summary.add_frame(frame);
+        }
+
+        summary
+    }
+
+    /// Analysis over the last `window_ms` of audio
+    pub fn analyze_last(&self, window_ms: f64) -> AudioAnalysis {
+        self.summary_for_last(window_ms).analysis()
+    }
+
+    /// Analysis over the whole session
+    pub fn analyze_session(&self) -> AudioAnalysis {
+        self.session.analysis()
+    }
+
+    /// Forget everything recorded so far
+    pub fn clear(&mut self) {
+        self.recent.clear();
+        self.blocks.clear();
+        self.open_block = FeatureSummary::starting_at(0.0);
+        self.session = FeatureSummary::starting_at(0.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tone(frequency: f32, amplitude: f32, len: usize) -> AudioBuffer {
+        let samples = (0..len)
+            .map(|i| amplitude * (2.0 * PI * frequency * i as f32 / 16000.0).sin())
+            .collect();
+        AudioBuffer::new(samples, 16000, 1)
+    }
+
+    fn frame(start_ms: f64, rms: f32) -> FrameFeatures {
+        FrameFeatures {
+            start_ms,
+            duration_ms: 100.0,
+            samples: 1600,
+            rms,
+            peak: rms * 1.4,
+            clipped: 0,
+            silent: 0,
+            is_speech: false,
+            spectral_centroid_hz: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_spectral_centroid_follows_frequency() {
+        let mut extractor = FeatureExtractor::new();
+        let low = extractor.extract(&tone(250.0, 0.5, 1024));
+        let high = extractor.extract(&tone(3000.0, 0.5, 1024));
+
+        assert!((low.spectral_centroid_hz - 250.0).abs() < 60.0, "{}", low.spectral_centroid_hz);
+        assert!((high.spectral_centroid_hz - 3000.0).abs() < 150.0, "{}", high.spectral_centroid_hz);
+        assert!((low.rms - 0.5 / 2f32.sqrt()).abs() < 0.01);
+        assert_eq!(high.start_ms, 64.0);
+    }
+
+    #[test]
+    fn test_memory_stays_bounded() {
+        let mut history = FeatureHistory::with_capacity(16, 8, 1000.0);
+        for i in 0..10_000 {
+            history.push(frame(i as f64 * 100.0, 0.1));
+        }
+
+        assert_eq!(history.recent.len(), 16);
+        assert!(history.blocks.len() < 8);
+        assert_eq!(history.frame_count(), 10_000);
+        assert_eq!(history.duration_ms(), 1_000_000.0);
+    }
+
+    #[test]
+    fn test_windows_match_direct_computation() {
+        let mut history = FeatureHistory::with_capacity(20, 8, 1000.0);
+        let frames: Vec<FrameFeatures> = (0..600)
+            .map(|i| frame(i as f64 * 100.0, if i < 300 { 0.1 } else { 0.4 }))
+            .collect();
+        for f in &frames {
+            history.push(*f);
+        }
+
+        // Inside the recent frames: exact
+        let last_second = history.summary_for_last(1000.0);
+        assert_eq!(last_second.frames, 10);
+        assert!((last_second.analysis().average_level - 0.4).abs() < 1e-6);
+
+        // Across blocks: whole session and the louder half
+        assert_eq!(history.summary_for_last(f64::MAX).frames, 600);
+        let last_half = history.summary_for_last(30_000.0);
+        assert!((last_half.frames as i64 - 300).abs() <= 80, "{}", last_half.frames);
+        assert!(last_half.analysis().average_level > 0.35);
+
+        let session = history.analyze_session();
+        let expected = ((0.1f64.powi(2) + 0.4f64.powi(2)) / 2.0).sqrt() as f32;
+        assert!((session.average_level - expected).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_snr_from_speech_and_noise_frames() {
+        let mut extractor = FeatureExtractor::new();
+        let mut history = FeatureHistory::new();
+
+        for i in 0..50 {
+            let amplitude = if i % 5 == 4 { 0.3 } else { 0.003 };
+            history.push(extractor.extract(&tone(440.0, amplitude, 320)));
+        }
+
+        let analysis = history.analyze_session();
+        assert!((analysis.estimated_snr - 40.0).abs() < 1.0, "{}", analysis.estimated_snr);
+        assert!((analysis.speech_percentage - 20.0).abs() < 0.1);
+    }
+}
@@ -0,0 +1,663 @@
+//! Compressed Opus archives of recordings
+//!
+//! Recordings are stored as Opus in an Ogg container (RFC 7845) at a bitrate
+//! tuned for speech. The writer accepts samples incrementally, so it can
+//! follow a live recording as well as transcode a finished file. The reader
+//! decodes archives back into capture-format buffers for playback and
+//! re-transcription, trimming the encoder delay and end padding so the
+//! decoded audio lines up with the original timeline.
+
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Read, Seek, Write};
+use std::path::{Path, PathBuf};
+use audiopus::coder::{Decoder, Encoder};
+use audiopus::packet::Packet;
+use audiopus::{Application, Bitrate, Channels, MutSignals, SampleRate, Signal};
+use ogg::{PacketReader, PacketWriteEndInfo, PacketWriter};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info};
+
+use super::import::AudioFileImporter;
+use super::types::{AudioBuffer, AudioConfig, AudioError, AudioResult};
+
+/// Bitrate used for speech archives; transparent for voice at 16kHz
+pub const DEFAULT_SPEECH_BITRATE: u32 = 24_000;
+
+/// Extension given to archive files
+pub const ARCHIVE_EXTENSION: &str = "opus";
+
+/// Opus timestamps are always counted at 48kHz
+const GRANULE_RATE: u64 = 48_000;
+
+/// Length of one encoded packet
+const FRAME_MS: u32 = 20;
+
+/// Packets per Ogg page; a page ends every second so a crash loses little
+const PACKETS_PER_PAGE: u64 = 50;
+
+/// Largest packet the encoder may produce (RFC 6716 recommends 4000 bytes)
+const MAX_PACKET_BYTES: usize = 4000;
+
+/// Longest Opus packet is 120ms
+const MAX_PACKET_MS: usize = 120;
+
+const VENDOR: &str = "MeetingMind";
+
+/// When recordings are compressed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArchiveMode {
+    /// Keep recordings as captured
+    Disabled,
+    /// Transcode once the session has been finalized
+    #[default]
+    AfterSession,
+    /// Encode alongside capture
+    WhileRecording,
+}
+
+/// How recordings are archived
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ArchiveSettings {
+    pub mode: ArchiveMode,
+    /// Target bitrate in bits per second
+    pub bitrate_bps: u32,
+    /// Keep the uncompressed original next to the archive
+    pub keep_original: bool,
+}
+
+impl Default for ArchiveSettings {
+    fn default() -> Self {
+        Self {
+            mode: ArchiveMode::AfterSession,
+            bitrate_bps: DEFAULT_SPEECH_BITRATE,
+            keep_original: true,
+        }
+    }
+}
+
+impl ArchiveSettings {
+    /// Check the bitrate is one Opus can produce
+    pub fn validate(&self) -> AudioResult<()> {
+        if !(6_000..=510_000).contains(&self.bitrate_bps) {
+            return Err(AudioError::UnsupportedFormat {
+                details: format!("Opus bitrate must be 6-510 kbps, got {} bps", self.bitrate_bps),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Result of archiving a recording
+#[derive(Debug, Clone, Serialize)]
+pub struct ArchiveSummary {
+    pub archive_path: PathBuf,
+    pub duration_ms: f64,
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub bitrate_bps: u32,
+    /// Size of the recording that was archived; zero for live archives
+    pub original_bytes: u64,
+    pub archive_bytes: u64,
+    pub original_kept: bool,
+}
+
+impl ArchiveSummary {
+    /// How many times smaller the archive is than the original
+    pub fn compression_ratio(&self) -> Option<f64> {
+        (self.archive_bytes > 0 && self.original_bytes > 0)
+            .then(|| self.original_bytes as f64 / self.archive_bytes as f64)
+    }
+}
+
+/// Where the archive of the recording at `original` is written
+pub fn archive_path_for(original: &Path) -> PathBuf {
+    original.with_extension(ARCHIVE_EXTENSION)
+}
+
+/// Whether `path` looks like an Opus archive
+pub fn is_archive(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.eq_ignore_ascii_case(ARCHIVE_EXTENSION))
+        .unwrap_or(false)
+}
+
+/// Opus sample rate to archive `sample_rate` audio at
+///
+/// Rates Opus supports are kept; anything else is resampled up to the next
+/// supported rate.
+pub fn archive_sample_rate(sample_rate: u32) -> u32 {
+    [8000, 12000, 16000, 24000, 48000]
+        .into_iter()
+        .find(|&rate| rate >= sample_rate)
+        .unwrap_or(48000)
+}
+
+fn opus_rate(sample_rate: u32) -> AudioResult<SampleRate> {
+    match sample_rate {
+        8000 => Ok(SampleRate::Hz8000),
+        12000 => Ok(SampleRate::Hz12000),
+        16000 => Ok(SampleRate::Hz16000),
+        24000 => Ok(SampleRate::Hz24000),
+        48000 => Ok(SampleRate::Hz48000),
+        other => Err(AudioError::UnsupportedFormat {
+            details: format!("Opus needs 8, 12, 16, 24 or 48kHz audio, got {}Hz", other),
+        }),
+    }
+}
+
+fn opus_channels(channels: u16) -> AudioResult<Channels> {
+    match channels {
+        1 => Ok(Channels::Mono),
+        2 => Ok(Channels::Stereo),
+        other => Err(AudioError::UnsupportedFormat {
+            details: format!("Opus archives hold mono or stereo audio, got {} channels", other),
+        }),
+    }
+}
+
+/// Streams interleaved samples into an Ogg Opus file
+pub struct OpusArchiveWriter<W: Write> {
+    encoder: Encoder,
+    packets: PacketWriter<W>,
+    serial: u32,
+    sample_rate: u32,
+    channels: u16,
+    bitrate_bps: u32,
+    /// Interleaved samples per packet
+    frame_len: usize,
+    /// Packet length at 48kHz
+    granule_step: u64,
+    pre_skip: u64,
+    pending: Vec<f32>,
+    encoded: Vec<u8>,
+    /// Newest packet, held back so the last one can be flagged end-of-stream
+    held: Option<Box<[u8]>>,
+    packets_encoded: u64,
+    input_frames: u64,
+}
+
+impl OpusArchiveWriter<BufWriter<File>> {
+    /// Create an archive file at `path`
+    pub fn create(path: &Path, sample_rate: u32, channels: u16, bitrate_bps: u32) -> AudioResult<Self> {
+        Self::new(BufWriter::new(File::create(path)?), sample_rate, channels, bitrate_bps)
+    }
+}
+
+impl<W: Write> OpusArchiveWriter<W> {
+    /// Start an archive stream and write its headers
+    pub fn new(writer: W, sample_rate: u32, channels: u16, bitrate_bps: u32) -> AudioResult<Self> {
+        let mut encoder = Encoder::new(opus_rate(sample_rate)?, opus_channels(channels)?, Application::Voip)?;
+        encoder.set_bitrate(Bitrate::BitsPerSecond(bitrate_bps as i32))?;
+        encoder.set_signal(Signal::Voice)?;
+        encoder.set_vbr(true)?;
+
+        let pre_skip = encoder.lookahead()? as u64 * GRANULE_RATE / sample_rate as u64;
+        let frame_frames = (sample_rate * FRAME_MS / 1000) as usize;
+
+        let mut archive = Self {
+            encoder,
+            packets: PacketWriter::new(writer),
+            serial: rand::random(),
+            sample_rate,
+            channels,
+            bitrate_bps,
+            frame_len: frame_frames * channels as usize,
+            granule_step: GRANULE_RATE * FRAME_MS as u64 / 1000,
+            pre_skip,
+            pending: Vec::with_capacity(frame_frames * channels as usize * 2),
+            encoded: vec![0; MAX_PACKET_BYTES],
+            held: None,
+            packets_encoded: 0,
+            input_frames: 0,
+        };
+        archive.write_headers()?;
+
+        debug!("Opus archive stream: {}Hz x{} at {} bps, pre-skip {}",
+               sample_rate, channels, bitrate_bps, pre_skip);
+        Ok(archive)
+    }
+
+    fn write_headers(&mut self) -> AudioResult<()> {
+        let mut head = Vec::with_capacity(19);
+        head.extend_from_slice(b"OpusHead");
+        head.push(1); // version
+        head.push(self.channels as u8);
+        head.extend_from_slice(&(self.pre_skip as u16).to_le_bytes());
+        head.extend_from_slice(&self.sample_rate.to_le_bytes());
+        head.extend_from_slice(&0i16.to_le_bytes()); // output gain
+        head.push(0); // channel mapping family: mono/stereo
+
+        let mut tags = Vec::with_capacity(16 + VENDOR.len());
+        tags.extend_from_slice(b"OpusTags");
+        tags.extend_from_slice(&(VENDOR.len() as u32).to_le_bytes());
+        tags.extend_from_slice(VENDOR.as_bytes());
+        tags.extend_from_slice(&0u32.to_le_bytes()); // no user comments
+
+        // Each header gets a page of its own
+        self.packets.write_packet(head.into_boxed_slice(), self.serial, PacketWriteEndInfo::EndPage, 0)?;
+        self.packets.write_packet(tags.into_boxed_slice(), self.serial, PacketWriteEndInfo::EndPage, 0)?;
+        Ok(())
+    }
+
+    /// Append interleaved samples
+    pub fn write_samples(&mut self, samples: &[f32]) -> AudioResult<()> {
+        self.input_frames += (samples.len() / self.channels as usize) as u64;
+        self.pending.extend_from_slice(samples);
+
+        let mut offset = 0;
+        while self.pending.len() - offset >= self.frame_len {
+            let frame = self.pending[offset..offset + self.frame_len].to_vec();
+            self.encode_frame(&frame)?;
+            offset += self.frame_len;
+        }
+        self.pending.drain(..offset);
+        Ok(())
+    }
+
+    fn encode_frame(&mut self, frame: &[f32]) -> AudioResult<()> {
+        let len = self.encoder.encode_float(frame, &mut self.encoded)?;
+        let packet: Box<[u8]> = self.encoded[..len].into();
+        self.packets_encoded += 1;
+
+        if let Some(previous) = self.held.replace(packet) {
+            let index = self.packets_encoded - 1;
+            let end = if index.is_multiple_of(PACKETS_PER_PAGE) {
+                PacketWriteEndInfo::EndPage
+            } else {
+                PacketWriteEndInfo::NormalPacket
+            };
+            self.packets.write_packet(previous, self.serial, end, index * self.granule_step)?;
+        }
+        Ok(())
+    }
+
+    /// Audio written so far
+    pub fn duration_ms(&self) -> f64 {
+        self.input_frames as f64 * 1000.0 / self.sample_rate as f64
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    pub fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    pub fn bitrate_bps(&self) -> u32 {
+        self.bitrate_bps
+    }
+
+    /// Encode what is left, close the stream and return the underlying writer
+    pub fn finish(mut self) -> AudioResult<W> {
+        // The final granule position trims padding, but only if the decoder
+        // produces at least pre-skip plus the input length
+        let end_granule = self.pre_skip + self.input_frames * GRANULE_RATE / self.sample_rate as u64;
+        let silence = vec![0.0; self.frame_len];
+
+        if !self.pending.is_empty() {
+            let mut frame = std::mem::take(&mut self.pending);
+            frame.resize(self.frame_len, 0.0);
+            self.encode_frame(&frame)?;
+        }
+        while self.packets_encoded == 0 || self.packets_encoded * self.granule_step < end_granule {
+            self.encode_frame(&silence)?;
+        }
+
+        if let Some(last) = self.held.take() {
+            self.packets.write_packet(last, self.serial, PacketWriteEndInfo::EndStream, end_granule)?;
+        }
+
+        let mut writer = self.packets.into_inner();
+        writer.flush()?;
+        Ok(writer)
+    }
+}
+
+/// Stream parameters from an archive's `OpusHead` packet
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct OpusHeader {
+    pub channels: u16,
+    /// Samples at 48kHz to drop from the start of the decoded audio
+    pub pre_skip: u16,
+    /// Sample rate of the audio before encoding
+    pub input_sample_rate: u32,
+}
+
+impl OpusHeader {
+    fn parse(packet: &[u8]) -> AudioResult<Self> {
+        if packet.len() < 19 || &packet[..8] != b"OpusHead" {
+            return Err(AudioError::UnsupportedFormat {
+                details: "Missing OpusHead header".to_string(),
+            });
+        }
+        if packet[18] != 0 {
+            return Err(AudioError::UnsupportedFormat {
+                details: format!("Unsupported Opus channel mapping family {}", packet[18]),
+            });
+        }
+
+        Ok(Self {
+            channels: packet[9] as u16,
+            pre_skip: u16::from_le_bytes([packet[10], packet[11]]),
+            input_sample_rate: u32::from_le_bytes([packet[12], packet[13], packet[14], packet[15]]),
+        })
+    }
+}
+
+/// Decodes an Ogg Opus archive block by block
+pub struct OpusArchiveReader<R: Read + Seek> {
+    packets: PacketReader<R>,
+    decoder: Decoder,
+    header: OpusHeader,
+    sample_rate: u32,
+    /// Decoded frames to drop from the start
+    skip_frames: u64,
+    /// Frames decoded so far, including skipped ones
+    decoded_frames: u64,
+    pcm: Vec<f32>,
+}
+
+impl OpusArchiveReader<BufReader<File>> {
+    /// Open the archive at `path`
+    pub fn open(path: &Path) -> AudioResult<Self> {
+        Self::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: Read + Seek> OpusArchiveReader<R> {
+    /// Read the stream headers and set up decoding
+    ///
+    /// Audio is decoded at the rate it was recorded at when Opus supports
+    /// it, and at 48kHz otherwise.
+    pub fn new(reader: R) -> AudioResult<Self> {
+        let mut packets = PacketReader::new(reader);
+
+        let head = packets.read_packet()?.ok_or_else(|| AudioError::UnsupportedFormat {
+            details: "Empty Ogg stream".to_string(),
+        })?;
+        let header = OpusHeader::parse(&head.data)?;
+
+        let tags = packets.read_packet()?;
+        if !tags.is_some_and(|tags| tags.data.starts_with(b"OpusTags")) {
+            return Err(AudioError::UnsupportedFormat {
+                details: "Missing OpusTags header".to_string(),
+            });
+        }
+
+        let sample_rate = match header.input_sample_rate {
+            rate @ (8000 | 12000 | 16000 | 24000) => rate,
+            _ => 48000,
+        };
+        let decoder = Decoder::new(opus_rate(sample_rate)?, opus_channels(header.channels)?)?;
+        let max_frames = sample_rate as usize * MAX_PACKET_MS / 1000;
+
+        Ok(Self {
+            packets,
+            decoder,
+            header,
+            sample_rate,
+            skip_frames: header.pre_skip as u64 * sample_rate as u64 / GRANULE_RATE,
+            decoded_frames: 0,
+            pcm: vec![0.0; max_frames * header.channels as usize],
+        })
+    }
+
+    pub fn header(&self) -> OpusHeader {
+        self.header
+    }
+
+    /// Rate the decoded buffers use
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    pub fn channels(&self) -> u16 {
+        self.header.channels
+    }
+
+    /// Decode the next packet, or `None` at the end of the stream
+    pub fn next_buffer(&mut self) -> AudioResult<Option<AudioBuffer>> {
+        let channels = self.header.channels as usize;
+
+        loop {
+            let Some(packet) = self.packets.read_packet()? else {
+                return Ok(None);
+            };
+            if packet.data.is_empty() {
+                continue;
+            }
+
+            let frames = self.decoder.decode_float(
+                Some(Packet::try_from(packet.data.as_slice())?),
+                MutSignals::try_from(self.pcm.as_mut_slice())?,
+                false,
+            )? as u64;
+            let start = self.decoded_frames;
+            self.decoded_frames += frames;
+
+            // Drop the encoder delay, and the padding after the last sample
+            let mut end = self.decoded_frames;
+            if packet.last_in_stream() {
+                end = end.min(packet.absgp_page() * self.sample_rate as u64 / GRANULE_RATE);
+            }
+            let first = start.max(self.skip_frames);
+            if first >= end {
+                if packet.last_in_stream() {
+                    return Ok(None);
+                }
+                continue;
+            }
+
+            let from = (first - start) as usize * channels;
+            let to = (end - start) as usize * channels;
+            return Ok(Some(AudioBuffer::new(
+                self.pcm[from..to].to_vec(),
+                self.sample_rate,
+                self.header.channels,
+            )));
+        }
+    }
+}
+
+/// Decode a whole archive into one buffer
+pub fn decode_archive(path: &Path) -> AudioResult<AudioBuffer> {
+    let mut reader = OpusArchiveReader::open(path)?;
+    let mut samples = Vec::new();
+    while let Some(buffer) = reader.next_buffer()? {
+        samples.extend_from_slice(&buffer.samples);
+    }
+    Ok(AudioBuffer::new(samples, reader.sample_rate(), reader.channels()))
+}
+
+/// Transcode the recording at `source` into an Opus archive next to it
+///
+/// The audio is converted as described by `config` (its sample rate moved to
+/// the nearest rate Opus supports) and encoded at the configured bitrate. The
+/// archive is written under a temporary name and only renamed into place once
+/// complete; the original is removed afterwards unless the settings keep it.
+/// Blocking; run it on a blocking thread.
+pub fn archive_recording(source: &Path, config: &AudioConfig, settings: &ArchiveSettings) -> AudioResult<ArchiveSummary> {
+    settings.validate()?;
+    if is_archive(source) {
+        return Err(AudioError::UnsupportedFormat {
+            details: format!("{} is already an Opus archive", source.display()),
+        });
+    }
+
+    let archive_path = archive_path_for(source);
+    let partial_path = archive_path.with_extension("opus.part");
+    let original_bytes = fs::metadata(source)?.len();
+
+    let result = encode_file(source, &partial_path, config, settings.bitrate_bps);
+    let (sample_rate, channels, duration_ms) = match result {
+        Ok(encoded) => encoded,
+        Err(e) => {
+            let _ = fs::remove_file(&partial_path);
+            return Err(e);
+        }
+    };
+    fs::rename(&partial_path, &archive_path)?;
+
+    if !settings.keep_original {
+        fs::remove_file(source)?;
+        debug!("Removed original recording {}", source.display());
+    }
+
+    let summary = ArchiveSummary {
+        archive_bytes: fs::metadata(&archive_path)?.len(),
+        archive_path,
+        duration_ms,
+        sample_rate,
+        channels,
+        bitrate_bps: settings.bitrate_bps,
+        original_bytes,
+        original_kept: settings.keep_original,
+    };
+
+    info!("Archived {} as {} ({:.1}s, {} -> {} bytes)",
+          source.display(), summary.archive_path.display(),
+          summary.duration_ms / 1000.0, summary.original_bytes, summary.archive_bytes);
+    Ok(summary)
+}
+
+fn encode_file(source: &Path, dest: &Path, config: &AudioConfig, bitrate_bps: u32) -> AudioResult<(u32, u16, f64)> {
+    let config = AudioConfig {
+        sample_rate: archive_sample_rate(config.sample_rate),
+        ..config.clone()
+    };
+    let importer = AudioFileImporter::new(config);
+
+    // The channel count is only known once the first chunk is decoded
+    let mut writer: Option<OpusArchiveWriter<BufWriter<File>>> = None;
+    importer.import(
+        source,
+        |chunk| {
+            let archive = match writer.as_mut() {
+                Some(archive) => archive,
+                None => writer.insert(OpusArchiveWriter::create(
+                    dest,
+                    chunk.buffer.sample_rate,
+                    chunk.buffer.channels,
+                    bitrate_bps,
+                )?),
+            };
+            archive.write_samples(&chunk.buffer.samples)
+        },
+        |_| {},
+    )?;
+
+    let writer = writer.ok_or_else(|| AudioError::UnsupportedFormat {
+        details: format!("No audio to archive in {}", source.display()),
+    })?;
+    let format = (writer.sample_rate(), writer.channels(), writer.duration_ms());
+    writer.finish()?;
+    Ok(format)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use crate::audio::test_wav::write_wav;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("meetingmind-archive-{}-{}", uuid::Uuid::new_v4(), name))
+    }
+
+    fn speech_like(sample_rate: u32, seconds: f32) -> Vec<f32> {
+        let frames = (sample_rate as f32 * seconds) as usize;
+        (0..frames)
+            .map(|i| {
+                let t = i as f32 / sample_rate as f32;
+                let envelope = 0.5 + 0.5 * (t * 3.0 * std::f32::consts::TAU).sin();
+                envelope * 0.3 * (t * 220.0 * std::f32::consts::TAU).sin()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_round_trip_preserves_length_and_signal() {
+        let input = speech_like(16000, 1.33);
+
+        let mut writer = OpusArchiveWriter::new(Cursor::new(Vec::new()), 16000, 1, DEFAULT_SPEECH_BITRATE).unwrap();
+        // Uneven writes, as a live capture would make them
+        for block in input.chunks(1000) {
+            writer.write_samples(block).unwrap();
+        }
+        let bytes = writer.finish().unwrap().into_inner();
+
+        let mut reader = OpusArchiveReader::new(Cursor::new(bytes)).unwrap();
+        assert_eq!(reader.header().input_sample_rate, 16000);
+        assert_eq!(reader.sample_rate(), 16000);
+        let mut output = Vec::new();
+        while let Some(buffer) = reader.next_buffer().unwrap() {
+            output.extend_from_slice(&buffer.samples);
+        }
+
+        assert_eq!(output.len(), input.len());
+
+        // Lossy, but the waveform must still line up with the original
+        let dot: f32 = input.iter().zip(&output).map(|(a, b)| a * b).sum();
+        let norm = |s: &[f32]| s.iter().map(|x| x * x).sum::<f32>().sqrt();
+        let correlation = dot / (norm(&input) * norm(&output));
+        assert!(correlation > 0.9, "correlation {}", correlation);
+    }
+
+    #[test]
+    fn test_empty_stream_is_still_valid() {
+        let writer = OpusArchiveWriter::new(Cursor::new(Vec::new()), 48000, 2, 32_000).unwrap();
+        let bytes = writer.finish().unwrap().into_inner();
+
+        let mut reader = OpusArchiveReader::new(Cursor::new(bytes)).unwrap();
+        assert_eq!(reader.channels(), 2);
+        assert!(reader.next_buffer().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_rejects_unsupported_formats() {
+        assert!(OpusArchiveWriter::new(Cursor::new(Vec::new()), 44100, 1, 24_000).is_err());
+        assert!(OpusArchiveWriter::new(Cursor::new(Vec::new()), 16000, 4, 24_000).is_err());
+        assert!(ArchiveSettings { bitrate_bps: 1000, ..Default::default() }.validate().is_err());
+        assert_eq!(archive_sample_rate(44100), 48000);
+        assert_eq!(archive_sample_rate(16000), 16000);
+    }
+
+    #[test]
+    fn test_archive_recording_replaces_original_when_configured() {
+        let source = temp_path("meeting.wav");
+        write_wav(&source, 16000, 1, &speech_like(16000, 3.0));
+
+        let settings = ArchiveSettings { keep_original: false, ..Default::default() };
+        let summary = archive_recording(&source, &AudioConfig::default(), &settings).unwrap();
+
+        assert!(!source.exists());
+        assert!(!summary.original_kept);
+        assert_eq!(summary.archive_path, archive_path_for(&source));
+        assert!(summary.compression_ratio().unwrap() > 8.0, "{:?}", summary);
+
+        let decoded = decode_archive(&summary.archive_path).unwrap();
+        assert_eq!((decoded.sample_rate, decoded.channels), (16000, 1));
+        assert!((decoded.duration_ms() - 3000.0).abs() < 1.0, "{}", decoded.duration_ms());
+
+        fs::remove_file(&summary.archive_path).ok();
+    }
+
+    #[test]
+    fn test_archive_recording_keeps_original_by_default() {
+        let source = temp_path("memo.wav");
+        write_wav(&source, 44100, 1, &speech_like(44100, 1.0));
+
+        let summary = archive_recording(&source, &AudioConfig::default(), &ArchiveSettings::default()).unwrap();
+
+        assert!(source.exists());
+        assert!(summary.original_kept);
+        assert_eq!(summary.sample_rate, 16000);
+
+        fs::remove_file(&source).ok();
+        fs::remove_file(&summary.archive_path).ok();
+    }
+}
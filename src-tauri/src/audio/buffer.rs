@@ -2,7 +2,7 @@
 
 use std::sync::{Arc, RwLock};
 use std::time::{Duration, Instant};
-use tracing::{debug, warn};
+use tracing::debug;
 
 use super::types::{AudioBuffer, AudioError, AudioResult, AudioStats};
 
@@ -51,19 +51,20 @@ impl AudioRingBuffer {
         let available_space = inner.capacity - inner.size;
         
         if samples.len() > available_space {
-            // Update stats for buffer overrun
+            // Update stats for buffer overrun; writers on the audio thread
+            // can't log, so the caller decides whether to report it
             if let Ok(mut stats) = self.stats.write() {
                 stats.buffer_overruns += 1;
             }
             
-            warn!("Audio buffer overrun: {} samples, {} available", samples.len(), available_space);
             return Err(AudioError::BufferOverflow { size: samples.len() });
         }
         
         let mut written = 0;
         for &sample in samples {
-            inner.buffer[inner.write_pos] = sample;
-            inner.write_pos = (inner.write_pos + 1) % inner.capacity;
+            let write_pos = inner.write_pos;
+            inner.buffer[write_pos] = sample;
+            inner.write_pos = (write_pos + 1) % inner.capacity;
             written += 1;
         }
         
@@ -104,8 +105,8 @@ impl AudioRingBuffer {
             return Ok(0);
         }
         
-        for i in 0..available_samples {
-            output[i] = inner.buffer[inner.read_pos];
+        for sample in output.iter_mut().take(available_samples) {
+            *sample = inner.buffer[inner.read_pos];
             inner.read_pos = (inner.read_pos + 1) % inner.capacity;
         }
         
@@ -208,9 +209,24 @@ impl AudioRingBuffer {
             .unwrap_or(0)
     }
     
+    /// Sample rate of the audio held in the buffer
+    pub fn sample_rate(&self) -> u32 {
+        self.inner.read()
+            .map(|inner| inner.sample_rate)
+            .unwrap_or(0)
+    }
+    
+    /// Interleaved channel count of the audio held in the buffer
+    pub fn channels(&self) -> u16 {
+        self.inner.read()
+            .map(|inner| inner.channels)
+            .unwrap_or(0)
+    }
+    
     /// Check if the buffer has been written to recently
     pub fn has_recent_activity(&self, timeout: Duration) -> bool {
         self.inner.read()
+            .ok()
             .and_then(|inner| inner.last_write_time)
             .map(|last_write| last_write.elapsed() < timeout)
             .unwrap_or(false)
@@ -1,32 +1,93 @@
 //! Audio capture service implementation using CPAL
 
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, RwLock, atomic::{AtomicBool, Ordering}};
-use std::thread;
 use std::time::{Duration, Instant};
-use cpal::{Device, Stream, StreamConfig, traits::{DeviceTrait, StreamTrait}};
-use tokio::sync::{mpsc, broadcast};
+use cpal::{Device, traits::DeviceTrait};
+use tokio::sync::{broadcast, watch};
+use tokio::task::JoinHandle;
 use tracing::{debug, info, warn, error, instrument};
 
 use super::types::{
     AudioBuffer, AudioConfig, AudioError, AudioResult, AudioCaptureStatus, 
-    AudioProcessor, AudioLevelMonitor, AudioStats
+    AudioLevelMonitor, AudioStats, ChannelGain, ChannelMap
 };
 use super::devices::AudioDeviceManager;
 use super::buffer::AudioRingBuffer;
+use super::conversion::{downmix_into, mix_into, LinearResampler};
+use super::peaks::{PeakAccumulator, PeakPyramid, PeakSlice, DEFAULT_PEAK_BLOCK};
+use super::processing::{AudioProcessingPipeline, AudioQualityValidator};
+use super::quality::{AudioQualityReport, QualityTracker, QualityWarning};
+use super::validation::{QualityEvent, ValidationMonitor};
+use super::worker::{ProcessingWorker, ThreadPriority};
+use super::archive::{archive_sample_rate, ArchiveSettings, ArchiveSummary, OpusArchiveWriter};
+use super::drift::{DriftCompensator, DriftCounters};
+use super::streams::CaptureStreams;
+
+/// Capacity of the real-time peak tap in floats (~30s of peaks at 16kHz)
+const PEAK_TAP_CAPACITY: usize = 4096;
+
+/// How often tapped peaks are folded into the waveform overview
+const PEAK_COLLECT_INTERVAL: Duration = Duration::from_millis(250);
+
+/// How often live recording quality is checked against the thresholds
+const QUALITY_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How often buffer validation findings are turned into quality events
+const VALIDATION_EVENT_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Seconds of audio the processing worker can fall behind by
+const PROCESSING_TAP_SECONDS: usize = 2;
+
+/// Seconds of audio the live archive tap can hold between encoder runs
+const ARCHIVE_TAP_SECONDS: usize = 2;
+
+/// How often tapped audio is encoded into the live archive
+const ARCHIVE_ENCODE_INTERVAL: Duration = Duration::from_millis(250);
+
+/// How often the secondary device's clock is compared with the primary's
+const DRIFT_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How often track audio is moved from the ring buffers to subscribers
+const TRACK_READ_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Audio from one capture track, at the target sample rate
+#[derive(Debug, Clone)]
+pub struct TrackAudio {
+    /// Index of the track: the channel map's tracks, then the secondary device's
+    pub track: usize,
+    pub buffer: AudioBuffer,
+}
 
 /// Audio capture service for system audio capture
 pub struct AudioCaptureService {
     device_manager: Arc<RwLock<AudioDeviceManager>>,
-    current_stream: Arc<RwLock<Option<Stream>>>,
+    streams: CaptureStreams,
     ring_buffer: Option<AudioRingBuffer>,
+    track_buffers: Vec<AudioRingBuffer>,
+    peak_tap: Option<AudioRingBuffer>,
+    peak_pyramid: Arc<RwLock<PeakPyramid>>,
+    live_archive: Option<(PathBuf, ArchiveSettings)>,
+    live_archive_summary: Arc<RwLock<Option<ArchiveSummary>>>,
+    /// Encoder of the current capture's live archive, until taken
+    live_archive_task: Option<JoinHandle<Option<ArchiveSummary>>>,
     status: Arc<RwLock<AudioCaptureStatus>>,
     is_running: Arc<AtomicBool>,
+    /// Held while the current capture's background tasks should keep
+    /// running; dropping it ends them
+    capture_tasks: Option<watch::Sender<()>>,
     level_monitor: Arc<RwLock<AudioLevelMonitor>>,
+    quality_tracker: Arc<RwLock<QualityTracker>>,
+    validation_monitor: Arc<RwLock<ValidationMonitor>>,
+    processing_worker: Option<ProcessingWorker>,
     
     // Communication channels
-    audio_sender: Option<mpsc::UnboundedSender<AudioBuffer>>,
+    processed_broadcaster: broadcast::Sender<AudioBuffer>,
+    track_broadcaster: broadcast::Sender<TrackAudio>,
     status_broadcaster: broadcast::Sender<AudioCaptureStatus>,
     level_broadcaster: broadcast::Sender<f32>,
+    quality_broadcaster: broadcast::Sender<QualityWarning>,
+    quality_event_broadcaster: broadcast::Sender<QualityEvent>,
     
     // Configuration
     config: AudioConfig,
@@ -42,19 +103,36 @@ impl AudioCaptureService {
         let device_manager = Arc::new(RwLock::new(AudioDeviceManager::new()?));
         let (status_broadcaster, _) = broadcast::channel(16);
         let (level_broadcaster, _) = broadcast::channel(64);
+        let (quality_broadcaster, _) = broadcast::channel(16);
+        let (quality_event_broadcaster, _) = broadcast::channel(32);
+        let (processed_broadcaster, _) = broadcast::channel(256);
+        let (track_broadcaster, _) = broadcast::channel(256);
         
         info!("Created new audio capture service");
         
         Ok(Self {
             device_manager,
-            current_stream: Arc::new(RwLock::new(None)),
+            streams: CaptureStreams::spawn()?,
             ring_buffer: None,
+            track_buffers: Vec::new(),
+            peak_tap: None,
+            peak_pyramid: Arc::new(RwLock::new(PeakPyramid::new(16000, DEFAULT_PEAK_BLOCK))),
+            live_archive: None,
+            live_archive_summary: Arc::new(RwLock::new(None)),
+            live_archive_task: None,
             status: Arc::new(RwLock::new(AudioCaptureStatus::Stopped)),
             is_running: Arc::new(AtomicBool::new(false)),
+            capture_tasks: None,
             level_monitor: Arc::new(RwLock::new(AudioLevelMonitor::new())),
-            audio_sender: None,
+            quality_tracker: Arc::new(RwLock::new(QualityTracker::new(16000))),
+            validation_monitor: Arc::new(RwLock::new(ValidationMonitor::new(Default::default()))),
+            processing_worker: None,
+            processed_broadcaster,
+            track_broadcaster,
             status_broadcaster,
             level_broadcaster,
+            quality_broadcaster,
+            quality_event_broadcaster,
             config: AudioConfig::default(),
             stats: Arc::new(RwLock::new(AudioStats::default())),
             start_time: Arc::new(RwLock::new(None)),
@@ -92,16 +170,18 @@ impl AudioCaptureService {
             device_manager.get_default_input_device()?
         };
         
-        // Set up audio stream
-        self.setup_audio_stream(&device).await?;
-        
-        // Start the stream
-        if let Ok(stream_guard) = self.current_stream.read() {
-            if let Some(ref stream) = *stream_guard {
-                stream.play().map_err(AudioError::Stream)?;
-                info!("Audio stream started successfully");
-            }
+        // Set up and start the audio streams
+        let started = match self.setup_audio_stream(&device).await {
+            Ok(()) => self.streams.play(),
+            Err(e) => Err(e),
+        };
+        if let Err(e) = started {
+            // Capture never ran, so nothing would end the tasks set up for it
+            self.capture_tasks.take();
+            self.update_status(AudioCaptureStatus::Stopped).await?;
+            return Err(e);
         }
+        info!("Audio streams started successfully");
         
         // Update state
         self.is_running.store(true, Ordering::Relaxed);
@@ -125,16 +205,24 @@ impl AudioCaptureService {
         // Update status
         self.update_status(AudioCaptureStatus::Stopping).await?;
         
-        // Stop the stream
-        if let Ok(mut stream_guard) = self.current_stream.write() {
-            if let Some(stream) = stream_guard.take() {
-                drop(stream); // This stops the stream
-                info!("Audio stream stopped");
-            }
+        // Stop the streams
+        self.streams.close()?;
+        info!("Audio streams stopped");
+        
+        // Let the processing worker finish the audio it was handed
+        if let Some(worker) = self.processing_worker.take() {
+            worker.stop();
         }
         
-        // Clear buffer
-        if let Some(ref buffer) = self.ring_buffer {
+        // Let the background tasks drain what is left and end
+        self.capture_tasks.take();
+        
+        // End the processed and track streams for their subscribers; the next capture gets new ones
+        self.processed_broadcaster = broadcast::channel(256).0;
+        self.track_broadcaster = broadcast::channel(256).0;
+        
+        // Clear buffers
+        for buffer in &self.track_buffers {
             buffer.clear()?;
         }
         
@@ -150,173 +238,539 @@ impl AudioCaptureService {
     async fn setup_audio_stream(&mut self, device: &Device) -> AudioResult<()> {
         info!("Setting up audio stream");
         
+        // Streams left over from a setup that failed part way are replaced
+        self.streams.close()?;
+        
+        // Replacing the stop signal ends any tasks of an earlier capture
+        self.capture_tasks = Some(watch::channel(()).0);
+        
         // Find best configuration
         let stream_config = {
             let device_manager = self.device_manager.read()
                 .map_err(|_| AudioError::Internal { 
                     message: "Failed to acquire device manager lock".to_string() 
                 })?;
-            device_manager.find_best_input_config(device, self.config.sample_rate)?
+            device_manager.find_best_input_config_for_channels(
+                device, 
+                self.config.sample_rate, 
+                self.config.channel_map.required_channels()
+            )?
         };
         
         debug!("Using stream config: {:?}", stream_config);
         
-        // Create ring buffer
+        // Make sure the channel map fits the channels the device actually opened with
+        self.config.channel_map.validate(stream_config.channels)?;
+        
+        // Create one ring buffer per track, holding converted (target format) samples
         let buffer_capacity = self.config.buffer_size * 4; // 4x buffer size for safety
-        let ring_buffer = AudioRingBuffer::new(
-            buffer_capacity, 
-            stream_config.sample_rate.0, 
-            stream_config.channels
+        let track_buffers: Vec<AudioRingBuffer> = plan_tracks(&self.config, stream_config.channels)
+            .iter()
+            .map(|source| {
+                let output_channels = match source {
+                    TrackSource::Passthrough => stream_config.channels,
+                    _ => 1,
+                };
+                AudioRingBuffer::new(buffer_capacity, self.config.sample_rate, output_channels)
+            })
+            .collect();
+        let ring_buffer = track_buffers[0].clone();
+        let primary_channels = match plan_tracks(&self.config, stream_config.channels)[0] {
+            TrackSource::Passthrough => stream_config.channels,
+            _ => 1,
+        };
+        
+        // All scratch memory for the callback is allocated here, not on the audio thread
+        let peak_tap = AudioRingBuffer::new(PEAK_TAP_CAPACITY, self.config.sample_rate, 2);
+        let processing_tap = AudioRingBuffer::new(
+            self.config.sample_rate as usize * primary_channels as usize * PROCESSING_TAP_SECONDS,
+            self.config.sample_rate,
+            primary_channels,
         );
+        *self.peak_pyramid.write().unwrap() = PeakPyramid::new(self.config.sample_rate, DEFAULT_PEAK_BLOCK);
+        *self.quality_tracker.write().unwrap() = QualityTracker::new(self.config.sample_rate);
+        *self.validation_monitor.write().unwrap() = ValidationMonitor::new(self.config.validation);
         
-        // Create audio processing channel
-        let (audio_tx, mut audio_rx) = mpsc::unbounded_channel::<AudioBuffer>();
-        self.audio_sender = Some(audio_tx);
+        let mut callback = CaptureCallback::new(
+            track_buffers.clone(),
+            Arc::clone(&self.level_monitor),
+            self.level_broadcaster.clone(),
+            Arc::clone(&self.stats),
+            stream_config.sample_rate.0,
+            stream_config.channels,
+            &self.config,
+        )
+        .with_peak_tap(peak_tap.clone(), DEFAULT_PEAK_BLOCK)
+        .with_processing_tap(processing_tap.clone())
+        .with_quality_tracker(Arc::clone(&self.quality_tracker))
+        .with_validation(
+            AudioQualityValidator::with_config(self.config.validation),
+            Arc::clone(&self.validation_monitor),
+        );
         
-        // Clone shared state for the audio callback
-        let buffer_clone = ring_buffer.clone();
-        let level_monitor_clone = Arc::clone(&self.level_monitor);
-        let level_broadcaster_clone = self.level_broadcaster.clone();
-        let stats_clone = Arc::clone(&self.stats);
-        let target_sample_rate = self.config.sample_rate;
-        let target_channels = self.config.channels;
+        // Encode the primary track to Opus as it is captured, if requested
+        let archive = match &self.live_archive {
+            Some((path, settings)) => {
+                let writer = OpusArchiveWriter::create(
+                    path, 
+                    self.config.sample_rate, 
+                    primary_channels, 
+                    settings.bitrate_bps
+                )?;
+                let capacity = self.config.sample_rate as usize * primary_channels as usize * ARCHIVE_TAP_SECONDS;
+                let tap = AudioRingBuffer::new(capacity, self.config.sample_rate, primary_channels);
+                callback = callback.with_archive_tap(tap.clone());
+                *self.live_archive_summary.write().unwrap() = None;
+                Some((path.clone(), writer, tap))
+            }
+            None => None,
+        };
+        
+        // A second device gets a track of its own, resampled onto the primary clock
+        let mut secondary_track = None;
+        if let Some(name) = self.config.secondary_device.clone() {
+            let counters = Arc::new(DriftCounters::new());
+            let track = AudioRingBuffer::new(buffer_capacity, self.config.sample_rate, 1);
+            let secondary_rate = self.setup_secondary_stream(&name, track.clone(), Arc::clone(&counters))?;
+            callback = callback.with_drift_counters(Arc::clone(&counters));
+            secondary_track = Some((track, counters, secondary_rate));
+        }
         
         // Build input stream
-        let stream = device.build_input_stream(
-            &stream_config,
-            move |data: &[f32], _: &cpal::InputCallbackInfo| {
-                // Handle audio data in callback
-                if let Err(e) = Self::handle_audio_callback(
-                    data,
-                    &buffer_clone,
-                    &level_monitor_clone,
-                    &level_broadcaster_clone,
-                    &stats_clone,
-                    stream_config.sample_rate.0,
-                    stream_config.channels,
-                    target_sample_rate,
-                    target_channels,
-                ) {
-                    error!("Audio callback error: {}", e);
-                }
-            },
-            move |err| {
-                error!("Audio stream error: {}", err);
-            },
-            None, // No timeout
-        ).map_err(AudioError::Cpal)?;
-        
-        // Store the stream and buffer
-        *self.current_stream.write().unwrap() = Some(stream);
+        let device = device.clone();
+        let primary_config = stream_config.clone();
+        self.streams.open(Box::new(move || {
+            device.build_input_stream(
+                &primary_config,
+                move |data: &[f32], _: &cpal::InputCallbackInfo| {
+                    callback.process(data);
+                },
+                move |err| {
+                    error!("Audio stream error: {}", err);
+                },
+                None, // No timeout
+            ).map_err(AudioError::Cpal)
+        }))?;
+        
+        // Store the buffers
         self.ring_buffer = Some(ring_buffer);
+        self.track_buffers = track_buffers;
+        if let Some((track, counters, secondary_rate)) = secondary_track {
+            self.track_buffers.push(track);
+            self.spawn_drift_monitor(counters, stream_config.sample_rate.0, secondary_rate);
+        }
+        self.peak_tap = Some(peak_tap);
         
-        // Spawn audio processing task
-        self.spawn_audio_processor(audio_rx).await;
+        // Processing runs on its own thread; replacing a previous worker
+        // lets it finish what its tap still holds
+        self.processing_worker = Some(ProcessingWorker::spawn(
+            processing_tap,
+            AudioProcessingPipeline::for_capture(self.config.validation),
+            self.config.worker,
+            Arc::clone(&self.stats),
+            self.processed_broadcaster.clone(),
+        )?);
+        
+        // Spawn waveform overview and monitoring tasks
+        self.spawn_peak_collector();
+        self.spawn_quality_monitor();
+        self.spawn_validation_monitor();
+        self.spawn_track_reader();
+        self.live_archive_task = archive.map(|(path, writer, tap)| self.spawn_archive_encoder(path, writer, tap));
         
         info!("Audio stream setup completed");
         Ok(())
     }
     
-    /// Handle audio data in the stream callback
-    fn handle_audio_callback(
-        data: &[f32],
-        ring_buffer: &AudioRingBuffer,
-        level_monitor: &Arc<RwLock<AudioLevelMonitor>>,
-        level_broadcaster: &broadcast::Sender<f32>,
-        stats: &Arc<RwLock<AudioStats>>,
-        source_sample_rate: u32,
-        source_channels: u16,
-        target_sample_rate: u32,
-        target_channels: u16,
-    ) -> AudioResult<()> {
-        // Create audio buffer from the input data
-        let mut audio_buffer = AudioBuffer::new(
-            data.to_vec(), 
-            source_sample_rate, 
-            source_channels
+    /// Open the secondary device's stream, writing into `track`
+    ///
+    /// Returns the device's nominal sample rate. The stream is started
+    /// alongside the primary one.
+    fn setup_secondary_stream(&self, name: &str, track: AudioRingBuffer, counters: Arc<DriftCounters>) -> AudioResult<u32> {
+        let (device, stream_config) = {
+            let mut device_manager = self.device_manager.write()
+                .map_err(|_| AudioError::Internal { 
+                    message: "Failed to acquire device manager lock".to_string() 
+                })?;
+            let device = device_manager.get_input_device_by_name(name)?;
+            let stream_config = device_manager.find_best_input_config_for_channels(
+                &device, 
+                self.config.sample_rate, 
+                1
+            )?;
+            (device, stream_config)
+        };
+        
+        info!("Using secondary audio device {}: {:?}", name, stream_config);
+        
+        let mut callback = SecondaryCaptureCallback::new(
+            track,
+            counters,
+            stream_config.sample_rate.0,
+            stream_config.channels,
+            &self.config,
         );
         
-        // Convert to target format if necessary
-        if source_channels != target_channels && target_channels == 1 {
-            audio_buffer = audio_buffer.to_mono();
-        }
+        let sample_rate = stream_config.sample_rate.0;
+        self.streams.open(Box::new(move || {
+            device.build_input_stream(
+                &stream_config,
+                move |data: &[f32], _: &cpal::InputCallbackInfo| {
+                    callback.process(data);
+                },
+                move |err| {
+                    error!("Secondary audio stream error: {}", err);
+                },
+                None,
+            ).map_err(AudioError::Cpal)
+        }))?;
         
-        // Resample if necessary (simple implementation for now)
-        if source_sample_rate != target_sample_rate {
-            audio_buffer = Self::resample_audio_buffer(audio_buffer, target_sample_rate)?;
+        Ok(sample_rate)
+    }
+    
+    /// Receiver for a background task of the current capture
+    ///
+    /// It closes when the capture stops; without a capture it is already
+    /// closed, so the task ends after one pass.
+    pub fn stop_signal(&self) -> watch::Receiver<()> {
+        match &self.capture_tasks {
+            Some(sender) => sender.subscribe(),
+            None => watch::channel(()).1,
         }
+    }
+    
+    /// Spawn the task that measures clock drift between the two devices and
+    /// steers the secondary device's resampler
+    fn spawn_drift_monitor(&self, counters: Arc<DriftCounters>, primary_rate: u32, secondary_rate: u32) {
+        let stop = self.stop_signal();
+        let stats = Arc::clone(&self.stats);
+        let mut compensator = DriftCompensator::new(primary_rate, secondary_rate, self.config.sample_rate);
         
-        // Update level monitor
-        if let Ok(mut monitor) = level_monitor.write() {
-            monitor.update(&audio_buffer);
-            let rms_level = monitor.rms_level();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(DRIFT_CHECK_INTERVAL);
+            let mut reported = false;
             
-            // Broadcast level update (non-blocking)
-            let _ = level_broadcaster.send(rms_level);
-        }
+            loop {
+                interval.tick().await;
+                
+                let ppm = compensator.update(&counters);
+                if let (Some(ppm), false) = (ppm, reported) {
+                    info!("Secondary device clock offset: {:.1}ppm", ppm);
+                    reported = true;
+                }
+                if let Ok(mut stats) = stats.write() {
+                    stats.clock_drift_ppm = ppm;
+                }
+                
+                if capture_ended(&stop) {
+                    break;
+                }
+            }
+            
+            debug!("Drift monitor task ended");
+        });
+    }
+    
+    /// Spawn the task that moves tapped peaks into the waveform overview
+    fn spawn_peak_collector(&self) {
+        let Some(tap) = self.peak_tap.clone() else { return };
+        let pyramid = Arc::clone(&self.peak_pyramid);
+        let stop = self.stop_signal();
         
-        // Write to ring buffer
-        if let Err(e) = ring_buffer.write(&audio_buffer.samples) {
-            warn!("Failed to write to ring buffer: {}", e);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(PEAK_COLLECT_INTERVAL);
             
-            // Update stats
-            if let Ok(mut stats_guard) = stats.write() {
-                stats_guard.buffer_overruns += 1;
+            loop {
+                interval.tick().await;
+                
+                if let Ok(mut pyramid) = pyramid.write() {
+                    if let Err(e) = pyramid.drain_tap(&tap) {
+                        warn!("Failed to collect waveform peaks: {}", e);
+                    }
+                }
+                
+                if capture_ended(&stop) && tap.available() == 0 {
+                    break;
+                }
             }
-        }
-        
-        Ok(())
+            
+            debug!("Peak collector task ended");
+        });
     }
     
-    /// Simple audio resampling (basic implementation)
-    fn resample_audio_buffer(
-        buffer: AudioBuffer, 
-        target_sample_rate: u32
-    ) -> AudioResult<AudioBuffer> {
-        let ratio = target_sample_rate as f64 / buffer.sample_rate as f64;
+    /// Spawn the task that hands each track's audio to `subscribe_track_audio`
+    ///
+    /// Runs for every capture, so the track buffers the callback writes are
+    /// always drained; with a single track, subscribers get the primary one.
+    fn spawn_track_reader(&self) {
+        let tracks = self.track_buffers.clone();
+        let stop = self.stop_signal();
+        let broadcaster = self.track_broadcaster.clone();
         
-        if (ratio - 1.0).abs() < 0.001 {
-            // No resampling needed
-            return Ok(buffer);
-        }
-        
-        let target_length = (buffer.samples.len() as f64 * ratio) as usize;
-        let mut resampled = Vec::with_capacity(target_length);
-        
-        for i in 0..target_length {
-            let source_index = (i as f64 / ratio) as usize;
-            if source_index < buffer.samples.len() {
-                resampled.push(buffer.samples[source_index]);
-            } else {
-                resampled.push(0.0);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(TRACK_READ_INTERVAL);
+            
+            loop {
+                interval.tick().await;
+                
+                for (track, ring_buffer) in tracks.iter().enumerate() {
+                    match ring_buffer.read_buffer(ring_buffer.available()) {
+                        Ok(Some(buffer)) => {
+                            let _ = broadcaster.send(TrackAudio { track, buffer });
+                        }
+                        Ok(None) => {}
+                        Err(e) => warn!("Failed to read track {}: {}", track, e),
+                    }
+                }
+                
+                if capture_ended(&stop) {
+                    break;
+                }
             }
-        }
+            
+            debug!("Track reader task ended");
+        });
+    }
+    
+    /// Spawn the task that warns when live recording quality drops below the thresholds
+    fn spawn_quality_monitor(&self) {
+        let tracker = Arc::clone(&self.quality_tracker);
+        let stop = self.stop_signal();
+        let broadcaster = self.quality_broadcaster.clone();
+        let thresholds = self.config.quality_thresholds;
         
-        Ok(AudioBuffer::new(resampled, target_sample_rate, buffer.channels))
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(QUALITY_CHECK_INTERVAL);
+            let mut active: Vec<QualityWarning> = Vec::new();
+            
+            loop {
+                interval.tick().await;
+                
+                let warnings = match tracker.read() {
+                    Ok(tracker) => tracker.live_warnings(&thresholds),
+                    Err(_) => break,
+                };
+                
+                // Only report problems as they appear, not on every check
+                for warning in &warnings {
+                    if !active.iter().any(|w| w.same_kind(warning)) {
+                        warn!("Recording quality warning: {}", warning);
+                        let _ = broadcaster.send(*warning);
+                    }
+                }
+                active = warnings;
+                
+                if capture_ended(&stop) {
+                    break;
+                }
+            }
+            
+            debug!("Quality monitor task ended");
+        });
     }
     
-    /// Spawn audio processing task
-    async fn spawn_audio_processor(&self, mut audio_rx: mpsc::UnboundedReceiver<AudioBuffer>) {
-        let ring_buffer = self.ring_buffer.as_ref().unwrap().clone();
+    /// Spawn the task that turns buffer validation findings into quality events
+    fn spawn_validation_monitor(&self) {
+        let monitor = Arc::clone(&self.validation_monitor);
+        let stop = self.stop_signal();
+        let broadcaster = self.quality_event_broadcaster.clone();
         
         tokio::spawn(async move {
-            debug!("Audio processor task started");
+            let mut interval = tokio::time::interval(VALIDATION_EVENT_INTERVAL);
             
-            while let Some(_audio_buffer) = audio_rx.recv().await {
-                // Here we can add additional audio processing
-                // For now, the ring buffer handles the data
+            loop {
+                interval.tick().await;
+                
+                let events = match monitor.write() {
+                    Ok(mut monitor) => monitor.drain_events(),
+                    Err(_) => break,
+                };
                 
-                // Could add:
-                // - Noise reduction
-                // - Audio enhancement
-                // - Format conversion
-                // - Streaming to transcription service
+                for event in events {
+                    warn!("Audio validation: {}", event.issue);
+                    let _ = broadcaster.send(event);
+                }
+                
+                if capture_ended(&stop) {
+                    break;
+                }
             }
             
-            debug!("Audio processor task ended");
+            debug!("Validation monitor task ended");
         });
     }
     
+    /// Spawn the task that encodes tapped audio into the live archive
+    fn spawn_archive_encoder(
+        &self, 
+        path: PathBuf, 
+        mut writer: OpusArchiveWriter<std::io::BufWriter<std::fs::File>>, 
+        tap: AudioRingBuffer,
+    ) -> JoinHandle<Option<ArchiveSummary>> {
+        let stop = self.stop_signal();
+        let summary = Arc::clone(&self.live_archive_summary);
+        
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(ARCHIVE_ENCODE_INTERVAL);
+            let mut scratch = vec![0.0f32; tap.capacity()];
+            
+            loop {
+                interval.tick().await;
+                
+                let drained = tap.read(&mut scratch)
+                    .and_then(|read| writer.write_samples(&scratch[..read]));
+                if let Err(e) = drained {
+                    error!("Live archive encoding failed, archive abandoned: {}", e);
+                    return None;
+                }
+                
+                if capture_ended(&stop) && tap.available() == 0 {
+                    break;
+                }
+            }
+            
+            let duration_ms = writer.duration_ms();
+            let (sample_rate, channels, bitrate_bps) = (writer.sample_rate(), writer.channels(), writer.bitrate_bps());
+            let finished = match writer.finish().and_then(|_| Ok(std::fs::metadata(&path)?.len())) {
+                Ok(archive_bytes) => {
+                    info!("Live archive written: {} ({:.1}s, {} bytes)", 
+                          path.display(), duration_ms / 1000.0, archive_bytes);
+                    let finished = ArchiveSummary {
+                        archive_path: path,
+                        duration_ms,
+                        sample_rate,
+                        channels,
+                        bitrate_bps,
+                        original_bytes: 0,
+                        archive_bytes,
+                        original_kept: true,
+                    };
+                    *summary.write().unwrap() = Some(finished.clone());
+                    Some(finished)
+                }
+                Err(e) => {
+                    error!("Failed to finish live archive {}: {}", path.display(), e);
+                    None
+                }
+            };
+            
+            debug!("Archive encoder task ended");
+            finished
+        })
+    }
+    
+    /// Encode the next recording to an Opus archive at `path` while it is captured
+    ///
+    /// Takes effect when capture (re)starts; `None` turns live archiving off.
+    pub fn archive_while_recording(&mut self, target: Option<(PathBuf, ArchiveSettings)>) -> AudioResult<()> {
+        if let Some((_, settings)) = &target {
+            settings.validate()?;
+            if archive_sample_rate(self.config.sample_rate) != self.config.sample_rate {
+                return Err(AudioError::UnsupportedFormat {
+                    details: format!("Live archives need an Opus sample rate, capture uses {}Hz", 
+                                     self.config.sample_rate),
+                });
+            }
+        }
+        self.live_archive = target;
+        Ok(())
+    }
+    
+    /// The current capture's live archive encoder, which resolves to the
+    /// archive once capture has stopped and it has been closed
+    pub fn take_live_archive(&mut self) -> Option<JoinHandle<Option<ArchiveSummary>>> {
+        self.live_archive_task.take()
+    }
+    
+    /// The finished live archive, once capture has stopped and it has been closed
+    pub fn live_archive_summary(&self) -> Option<ArchiveSummary> {
+        self.live_archive_summary.read().ok().and_then(|summary| summary.clone())
+    }
+    
+    /// Summarise recording quality for the current session
+    pub fn quality_report(&self) -> AudioResult<AudioQualityReport> {
+        let stats = self.get_stats();
+        let tracker = self.quality_tracker.read()
+            .map_err(|_| AudioError::Internal { 
+                message: "Failed to acquire quality tracker lock".to_string() 
+            })?;
+        Ok(tracker.report(&stats, &self.config.quality_thresholds))
+    }
+    
+    /// Subscribe to live recording quality warnings
+    pub fn subscribe_quality_warnings(&self) -> broadcast::Receiver<QualityWarning> {
+        self.quality_broadcaster.subscribe()
+    }
+    
+    /// Subscribe to the audio of every capture track, including the
+    /// secondary device's, while there is more than one
+    ///
+    /// Each track's buffers arrive in order and together cover the whole
+    /// capture, so a track's timeline is its sample count. The stream closes
+    /// when capture stops.
+    pub fn subscribe_track_audio(&self) -> broadcast::Receiver<TrackAudio> {
+        self.track_broadcaster.subscribe()
+    }
+    
+    /// Subscribe to primary-track audio as it leaves the processing worker
+    ///
+    /// The stream closes when capture stops.
+    pub fn subscribe_processed_audio(&self) -> broadcast::Receiver<AudioBuffer> {
+        self.processed_broadcaster.subscribe()
+    }
+    
+    /// Scheduling the processing worker was given, while capturing
+    pub fn processing_priority(&self) -> Option<ThreadPriority> {
+        self.processing_worker.as_ref().and_then(|worker| worker.priority())
+    }
+    
+    /// Subscribe to rate-limited events from validating captured buffers
+    pub fn subscribe_quality_events(&self) -> broadcast::Receiver<QualityEvent> {
+        self.quality_event_broadcaster.subscribe()
+    }
+    
+    /// Drain any peaks still sitting in the tap into the overview
+    fn collect_pending_peaks(&self) -> AudioResult<()> {
+        if let Some(ref tap) = self.peak_tap {
+            let mut pyramid = self.peak_pyramid.write()
+                .map_err(|_| AudioError::Internal { 
+                    message: "Failed to acquire peak overview lock".to_string() 
+                })?;
+            pyramid.drain_tap(tap)?;
+        }
+        Ok(())
+    }
+    
+    /// Get waveform peaks for a time range of the current recording
+    ///
+    /// The zoom level is chosen so the range is drawn in at most `max_points` peaks.
+    pub fn waveform_peaks(&self, start_ms: f64, end_ms: f64, max_points: usize) -> AudioResult<PeakSlice> {
+        self.collect_pending_peaks()?;
+        
+        let pyramid = self.peak_pyramid.read()
+            .map_err(|_| AudioError::Internal { 
+                message: "Failed to acquire peak overview lock".to_string() 
+            })?;
+        let level = pyramid.level_for_width(start_ms, end_ms, max_points);
+        Ok(pyramid.peaks(start_ms, end_ms, level))
+    }
+    
+    /// Persist the waveform overview next to the recording at `audio_path`
+    pub fn save_waveform_peaks(&self, audio_path: &Path) -> AudioResult<PathBuf> {
+        self.collect_pending_peaks()?;
+        
+        let mut pyramid = self.peak_pyramid.read()
+            .map_err(|_| AudioError::Internal { 
+                message: "Failed to acquire peak overview lock".to_string() 
+            })?
+            .clone();
+        pyramid.finish();
+        
+        let path = PeakPyramid::sidecar_path(audio_path);
+        pyramid.save(&path)?;
+        Ok(path)
+    }
+    
     /// Update capture status and broadcast to subscribers
     async fn update_status(&self, new_status: AudioCaptureStatus) -> AudioResult<()> {
         *self.status.write().unwrap() = new_status;
@@ -364,7 +818,6 @@ impl AudioCaptureService {
         if let Some(ref buffer) = self.ring_buffer {
             let buffer_stats = buffer.stats();
             stats.samples_processed = buffer_stats.samples_processed;
-            stats.buffer_overruns = buffer_stats.buffer_overruns;
             stats.buffer_underruns = buffer_stats.buffer_underruns;
             stats.average_latency_ms = buffer.current_latency_ms();
         }
@@ -419,15 +872,17 @@ impl AudioCaptureService {
         
         // Restart capture if it was running
         if was_running {
-            self.setup_audio_stream(&device).await?;
-            
-            if let Ok(stream_guard) = self.current_stream.read() {
-                if let Some(ref stream) = *stream_guard {
-                    stream.play().map_err(AudioError::Stream)?;
-                    self.is_running.store(true, Ordering::Relaxed);
-                    self.update_status(AudioCaptureStatus::Running).await?;
-                }
+            let started = match self.setup_audio_stream(&device).await {
+                Ok(()) => self.streams.play(),
+                Err(e) => Err(e),
+            };
+            if let Err(e) = started {
+                self.capture_tasks.take();
+                self.update_status(AudioCaptureStatus::Stopped).await?;
+                return Err(e);
             }
+            self.is_running.store(true, Ordering::Relaxed);
+            self.update_status(AudioCaptureStatus::Running).await?;
         }
         
         info!("Successfully switched to audio device: {}", device_name);
@@ -443,6 +898,19 @@ impl AudioCaptureService {
         }
     }
     
+    /// Read audio buffer from a specific channel-map track
+    pub fn read_track_buffer(&self, track: usize, samples_to_read: usize) -> AudioResult<Option<AudioBuffer>> {
+        match self.track_buffers.get(track) {
+            Some(buffer) => buffer.read_buffer(samples_to_read),
+            None => Ok(None),
+        }
+    }
+    
+    /// Number of tracks produced by the active channel map
+    pub fn track_count(&self) -> usize {
+        self.track_buffers.len()
+    }
+    
     /// Get current buffer utilization
     pub fn buffer_utilization(&self) -> f32 {
         if let Some(ref buffer) = self.ring_buffer {
@@ -473,13 +941,346 @@ impl AudioCaptureService {
     }
 }
 
+/// Where a capture track takes its samples from
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum TrackSource {
+    /// Interleaved device frames, unchanged apart from resampling
+    Passthrough,
+    /// Average of every device channel
+    Average,
+    /// Weighted mix of selected device channels
+    Mix(Vec<ChannelGain>),
+}
+
+/// Work out the tracks a configuration produces for a device with `source_channels`
+pub(super) fn plan_tracks(config: &AudioConfig, source_channels: u16) -> Vec<TrackSource> {
+    match &config.channel_map {
+        ChannelMap::Average if config.channels != 1 || source_channels == 1 => {
+            vec![TrackSource::Passthrough]
+        }
+        ChannelMap::Average => vec![TrackSource::Average],
+        ChannelMap::Mix { inputs } => vec![TrackSource::Mix(inputs.clone())],
+        ChannelMap::Tracks { tracks } => {
+            tracks.iter().cloned().map(TrackSource::Mix).collect()
+        }
+    }
+}
+
+/// Per-track conversion state and scratch memory
+struct CaptureTrack {
+    source: TrackSource,
+    ring_buffer: AudioRingBuffer,
+    resampler: LinearResampler,
+    mix_scratch: Vec<f32>,
+    resample_scratch: Vec<f32>,
+}
+
+/// Real-time state owned by the CPAL input callback
+///
+/// Channel mapping, resampling and level metering all run in place on scratch
+/// buffers sized at construction, so once the stream is running the callback
+/// never allocates. Oversized callbacks are processed in scratch-sized chunks.
+pub struct CaptureCallback {
+    tracks: Vec<CaptureTrack>,
+    level_monitor: Arc<RwLock<AudioLevelMonitor>>,
+    level_broadcaster: broadcast::Sender<f32>,
+    stats: Arc<RwLock<AudioStats>>,
+    source_sample_rate: u32,
+    source_channels: u16,
+    max_frames: usize,
+    /// Mono mix of a multichannel primary track, for metering and the overview
+    monitor_scratch: Vec<f32>,
+    peak_tap: Option<(PeakAccumulator, AudioRingBuffer)>,
+    quality_tracker: Option<Arc<RwLock<QualityTracker>>>,
+    validation: Option<(AudioQualityValidator, Arc<RwLock<ValidationMonitor>>)>,
+    processing_tap: Option<AudioRingBuffer>,
+    archive_tap: Option<AudioRingBuffer>,
+    drift_counters: Option<Arc<DriftCounters>>,
+}
+
+impl CaptureCallback {
+    /// Create callback state for a device stream and preallocate its scratch buffers
+    ///
+    /// `ring_buffers` receives one buffer per track produced by the config's
+    /// channel map; the first track drives the level meter.
+    pub fn new(
+        ring_buffers: Vec<AudioRingBuffer>,
+        level_monitor: Arc<RwLock<AudioLevelMonitor>>,
+        level_broadcaster: broadcast::Sender<f32>,
+        stats: Arc<RwLock<AudioStats>>,
+        source_sample_rate: u32,
+        source_channels: u16,
+        config: &AudioConfig,
+    ) -> Self {
+        let max_frames = config.buffer_size.max(1);
+        
+        let tracks: Vec<CaptureTrack> = plan_tracks(config, source_channels)
+            .into_iter()
+            .zip(ring_buffers)
+            .map(|(source, ring_buffer)| {
+                let output_channels = match source {
+                    TrackSource::Passthrough => source_channels,
+                    _ => 1,
+                };
+                let resampler = LinearResampler::new(
+                    source_sample_rate, 
+                    config.sample_rate, 
+                    output_channels
+                );
+                let mix_len = if source == TrackSource::Passthrough { 0 } else { max_frames };
+                let resample_len = resampler.max_output_frames(max_frames) * output_channels as usize;
+                
+                CaptureTrack {
+                    source,
+                    ring_buffer,
+                    resampler,
+                    mix_scratch: vec![0.0; mix_len],
+                    resample_scratch: vec![0.0; resample_len],
+                }
+            })
+            .collect();
+        let monitor_len = match tracks.first() {
+            Some(track) if track.source == TrackSource::Passthrough && source_channels > 1 => {
+                track.resampler.max_output_frames(max_frames)
+            }
+            _ => 0,
+        };
+        
+        Self {
+            tracks,
+            level_monitor,
+            level_broadcaster,
+            stats,
+            source_sample_rate,
+            source_channels,
+            max_frames,
+            monitor_scratch: vec![0.0; monitor_len],
+            peak_tap: None,
+            quality_tracker: None,
+            validation: None,
+            processing_tap: None,
+            archive_tap: None,
+            drift_counters: None,
+        }
+    }
+    
+    /// Also summarise the primary track into `block`-sized peaks written to `tap`
+    pub fn with_peak_tap(mut self, tap: AudioRingBuffer, block: usize) -> Self {
+        self.peak_tap = Some((PeakAccumulator::new(block), tap));
+        self
+    }
+    
+    /// Also feed the primary track into a session quality tracker
+    pub fn with_quality_tracker(mut self, tracker: Arc<RwLock<QualityTracker>>) -> Self {
+        self.quality_tracker = Some(tracker);
+        self
+    }
+    
+    /// Also validate each device buffer, collecting findings in `monitor`
+    pub fn with_validation(
+        mut self, 
+        validator: AudioQualityValidator, 
+        monitor: Arc<RwLock<ValidationMonitor>>
+    ) -> Self {
+        self.validation = Some((validator, monitor));
+        self
+    }
+    
+    /// Also copy the primary track into `tap` for the processing worker
+    pub fn with_processing_tap(mut self, tap: AudioRingBuffer) -> Self {
+        self.processing_tap = Some(tap);
+        self
+    }
+    
+    /// Also copy the primary track into `tap` for the live archive encoder
+    pub fn with_archive_tap(mut self, tap: AudioRingBuffer) -> Self {
+        self.archive_tap = Some(tap);
+        self
+    }
+    
+    /// Also count frames for clock-drift measurement against a second device
+    pub fn with_drift_counters(mut self, counters: Arc<DriftCounters>) -> Self {
+        self.drift_counters = Some(counters);
+        self
+    }
+    
+    /// Handle one block of interleaved input samples from the device
+    pub fn process(&mut self, data: &[f32]) {
+        // Validate the buffer as the device delivered it
+        if let Some((validator, monitor)) = self.validation.as_ref() {
+            let findings = validator.inspect(data, self.source_sample_rate, self.source_channels);
+            if let Ok(mut monitor) = monitor.write() {
+                monitor.observe(&findings, data.len());
+            }
+        }
+        
+        let chunk_len = self.max_frames * self.source_channels.max(1) as usize;
+        for chunk in data.chunks(chunk_len) {
+            for index in 0..self.tracks.len() {
+                self.process_track(index, chunk);
+            }
+        }
+    }
+    
+    fn process_track(&mut self, index: usize, data: &[f32]) {
+        let source_channels = self.source_channels;
+        let track = &mut self.tracks[index];
+        
+        // Apply the channel map
+        let converted: &[f32] = match &track.source {
+            TrackSource::Passthrough => data,
+            TrackSource::Average => {
+                let frames = downmix_into(data, source_channels, &mut track.mix_scratch);
+                &track.mix_scratch[..frames]
+            }
+            TrackSource::Mix(gains) => {
+                let frames = mix_into(data, source_channels, gains, &mut track.mix_scratch);
+                &track.mix_scratch[..frames]
+            }
+        };
+        
+        // Resample to the target rate
+        let samples = if track.resampler.is_passthrough() {
+            converted
+        } else {
+            let written = track.resampler.process_into(converted, &mut track.resample_scratch);
+            &track.resample_scratch[..written]
+        };
+        
+        // Update level monitor, waveform overview and quality from the primary track
+        if index == 0 {
+            // These expect mono, so a multichannel passthrough track is averaged for them
+            let mono: &[f32] = if self.monitor_scratch.is_empty() {
+                samples
+            } else {
+                let frames = downmix_into(samples, source_channels, &mut self.monitor_scratch);
+                &self.monitor_scratch[..frames]
+            };
+            
+            if let Ok(mut monitor) = self.level_monitor.write() {
+                monitor.update_samples(mono);
+                let rms_level = monitor.rms_level();
+                
+                // Broadcast level update (non-blocking)
+                let _ = self.level_broadcaster.send(rms_level);
+            }
+            
+            if let Some((accumulator, tap)) = self.peak_tap.as_mut() {
+                if accumulator.push(mono, tap) > 0 {
+                    if let Ok(mut stats_guard) = self.stats.write() {
+                        stats_guard.buffer_overruns += 1;
+                    }
+                }
+            }
+            
+            if let Some(tracker) = self.quality_tracker.as_ref() {
+                if let Ok(mut tracker) = tracker.write() {
+                    tracker.push_samples(mono);
+                }
+            }
+            
+            if let Some(tap) = self.processing_tap.as_ref() {
+                if tap.write(samples).is_err() {
+                    if let Ok(mut stats_guard) = self.stats.write() {
+                        stats_guard.buffer_overruns += 1;
+                    }
+                }
+            }
+            
+            if let Some(tap) = self.archive_tap.as_ref() {
+                if tap.write(samples).is_err() {
+                    if let Ok(mut stats_guard) = self.stats.write() {
+                        stats_guard.buffer_overruns += 1;
+                    }
+                }
+            }
+            
+            if let Some(counters) = self.drift_counters.as_ref() {
+                let track_channels = match track.source {
+                    TrackSource::Passthrough => source_channels as usize,
+                    _ => 1,
+                };
+                counters.record_primary(
+                    data.len() / source_channels.max(1) as usize, 
+                    samples.len() / track_channels.max(1)
+                );
+            }
+        }
+        
+        // Write to ring buffer; an overrun is counted in the ring's own stats,
+        // as only losses on the taps reach the capture's quality report
+        let _ = track.ring_buffer.write(samples);
+    }
+}
+
+/// Real-time state for the input callback of a second capture device
+///
+/// The device is downmixed to mono and resampled to the target rate, with the
+/// resampling ratio nudged by the drift monitor so the track keeps pace with
+/// the primary device's clock. Like `CaptureCallback`, it never allocates once
+/// the stream is running.
+pub struct SecondaryCaptureCallback {
+    ring_buffer: AudioRingBuffer,
+    counters: Arc<DriftCounters>,
+    resampler: LinearResampler,
+    source_channels: u16,
+    max_frames: usize,
+    mix_scratch: Vec<f32>,
+    resample_scratch: Vec<f32>,
+}
+
+impl SecondaryCaptureCallback {
+    /// Create callback state for the secondary device's stream
+    pub fn new(
+        ring_buffer: AudioRingBuffer,
+        counters: Arc<DriftCounters>,
+        source_sample_rate: u32,
+        source_channels: u16,
+        config: &AudioConfig,
+    ) -> Self {
+        let max_frames = config.buffer_size.max(1);
+        let resampler = LinearResampler::new(source_sample_rate, config.sample_rate, 1);
+        let resample_len = resampler.max_output_frames(max_frames);
+        
+        Self {
+            ring_buffer,
+            counters,
+            resampler,
+            source_channels,
+            max_frames,
+            mix_scratch: vec![0.0; max_frames],
+            resample_scratch: vec![0.0; resample_len],
+        }
+    }
+    
+    /// Handle one block of interleaved input samples from the device
+    pub fn process(&mut self, data: &[f32]) {
+        let chunk_len = self.max_frames * self.source_channels.max(1) as usize;
+        for chunk in data.chunks(chunk_len) {
+            let frames = downmix_into(chunk, self.source_channels, &mut self.mix_scratch);
+            
+            self.resampler.adjust_rate(self.counters.correction());
+            let written = self.resampler.process_into(&self.mix_scratch[..frames], &mut self.resample_scratch);
+            self.counters.record_secondary(frames, written);
+            
+            // Overruns are counted in the track ring's own stats
+            let _ = self.ring_buffer.write(&self.resample_scratch[..written]);
+        }
+    }
+}
+
+/// Whether the capture a background task belongs to has ended
+fn capture_ended(stop: &watch::Receiver<()>) -> bool {
+    stop.has_changed().is_err()
+}
+
 impl Drop for AudioCaptureService {
     fn drop(&mut self) {
         if self.is_running() {
             warn!("AudioCaptureService dropped while still running, stopping capture");
             // We can't use async in Drop, so we'll just clean up synchronously
             self.is_running.store(false, Ordering::Relaxed);
-            *self.current_stream.write().unwrap() = None;
+            let _ = self.streams.close();
         }
     }
 }
@@ -487,7 +1288,10 @@ impl Drop for AudioCaptureService {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use tokio::time::timeout;
+    use crate::audio::test_alloc::count_allocations;
+    use crate::audio::types::AudioFormat;
+    use crate::audio::processing::ValidationConfig;
+    use crate::audio::validation::ValidationIssue;
 
     #[tokio::test]
     async fn test_audio_capture_service_creation() {
@@ -499,13 +1303,32 @@ mod tests {
         assert!(!service.is_running());
     }
     
+    #[tokio::test]
+    async fn test_capture_tasks_end_when_capture_fails_to_start() {
+        let mut service = AudioCaptureService::new().unwrap();
+        service.capture_tasks = Some(watch::channel(()).0);
+        service.spawn_quality_monitor();
+        service.spawn_validation_monitor();
+        assert!(Arc::strong_count(&service.quality_tracker) > 1);
+        
+        // The streams never started, so nothing but the dropped signal stops the tasks
+        service.capture_tasks.take();
+        let deadline = Instant::now() + QUALITY_CHECK_INTERVAL * 3;
+        while Arc::strong_count(&service.quality_tracker) > 1 || Arc::strong_count(&service.validation_monitor) > 1 {
+            assert!(Instant::now() < deadline, "monitor tasks kept running");
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    }
+    
     #[tokio::test]
     async fn test_audio_capture_service_with_config() {
         let config = AudioConfig {
             sample_rate: 48000,
             channels: 2,
             buffer_size: 2048,
-            format: super::types::AudioFormat::F32,
+            format: AudioFormat::F32,
+            channel_map: ChannelMap::Average,
+            ..AudioConfig::default()
         };
         
         let result = AudioCaptureService::with_config(config.clone());
@@ -562,6 +1385,307 @@ mod tests {
         assert_eq!(service.current_audio_level_db(), -100.0);
     }
     
+    fn create_test_callback_with_map(
+        ring_buffers: &[AudioRingBuffer], 
+        max_frames: usize, 
+        channel_map: ChannelMap
+    ) -> CaptureCallback {
+        let (level_broadcaster, _) = broadcast::channel(64);
+        let config = AudioConfig {
+            buffer_size: max_frames,
+            channel_map,
+            ..AudioConfig::default()
+        };
+        CaptureCallback::new(
+            ring_buffers.to_vec(),
+            Arc::new(RwLock::new(AudioLevelMonitor::new())),
+            level_broadcaster,
+            Arc::new(RwLock::new(AudioStats::default())),
+            48000,
+            2,
+            &config,
+        )
+    }
+    
+    fn create_test_callback(ring_buffer: &AudioRingBuffer, max_frames: usize) -> CaptureCallback {
+        create_test_callback_with_map(std::slice::from_ref(ring_buffer), max_frames, ChannelMap::Average)
+    }
+    
+    #[test]
+    fn test_capture_callback_converts_to_target_format() {
+        let ring_buffer = AudioRingBuffer::new(4096, 16000, 1);
+        let mut callback = create_test_callback(&ring_buffer, 512);
+        
+        // 10ms of 48kHz stereo becomes 160 mono samples at 16kHz
+        callback.process(&vec![0.25; 960]);
+        
+        assert_eq!(ring_buffer.available(), 160);
+    }
+    
+    #[test]
+    fn test_passthrough_capture_meters_the_mono_mix() {
+        let ring_buffer = AudioRingBuffer::new(32000, 16000, 2);
+        let peak_tap = AudioRingBuffer::new(PEAK_TAP_CAPACITY, 16000, 2);
+        let tracker = Arc::new(RwLock::new(QualityTracker::new(16000)));
+        let (level_broadcaster, _) = broadcast::channel(64);
+        let config = AudioConfig { channels: 2, buffer_size: 512, ..AudioConfig::default() };
+        let mut callback = CaptureCallback::new(
+            vec![ring_buffer.clone()],
+            Arc::new(RwLock::new(AudioLevelMonitor::new())),
+            level_broadcaster,
+            Arc::new(RwLock::new(AudioStats::default())),
+            48000,
+            2,
+            &config,
+        )
+        .with_peak_tap(peak_tap.clone(), DEFAULT_PEAK_BLOCK)
+        .with_quality_tracker(Arc::clone(&tracker));
+        
+        // One second of 48kHz stereo, loud on the left only
+        let input: Vec<f32> = (0..960).map(|i| if i % 2 == 0 { 0.8 } else { 0.0 }).collect();
+        for _ in 0..100 {
+            callback.process(&input);
+        }
+        
+        // The track itself stays stereo
+        assert_eq!(ring_buffer.available(), 32000);
+        assert!((tracker.read().unwrap().duration_ms() - 1000.0).abs() < 1.0);
+        let mut peaks = vec![0.0; PEAK_TAP_CAPACITY];
+        let read = peak_tap.read(&mut peaks).unwrap();
+        assert_eq!(read / 2, 16000 / DEFAULT_PEAK_BLOCK);
+        assert!(peaks[..read].chunks(2).all(|peak| (peak[1] - 0.4).abs() < 1e-3), "{:?}", &peaks[..4]);
+    }
+    
+    #[test]
+    fn test_capture_callback_chunks_oversized_input() {
+        let ring_buffer = AudioRingBuffer::new(4096, 16000, 1);
+        let mut callback = create_test_callback(&ring_buffer, 128);
+        
+        callback.process(&vec![0.25; 960]);
+        
+        assert_eq!(ring_buffer.available(), 160);
+    }
+    
+    #[test]
+    fn test_capture_callback_honors_channel_selection() {
+        let ring_buffer = AudioRingBuffer::new(4096, 16000, 1);
+        let mut callback = create_test_callback_with_map(
+            std::slice::from_ref(&ring_buffer), 
+            512, 
+            ChannelMap::select(0)
+        );
+        
+        // Mic on channel 0, out-of-phase bleed on channel 1 (averaging would cancel it)
+        let input: Vec<f32> = (0..960).map(|i| if i % 2 == 0 { 0.5 } else { -0.5 }).collect();
+        callback.process(&input);
+        
+        let buffer = ring_buffer.read_buffer(160).unwrap().unwrap();
+        assert!(buffer.samples[1..].iter().all(|&s| (s - 0.5).abs() < 1e-6));
+    }
+    
+    #[test]
+    fn test_capture_callback_routes_tracks_separately() {
+        let tracks = vec![
+            AudioRingBuffer::new(4096, 16000, 1),
+            AudioRingBuffer::new(4096, 16000, 1),
+        ];
+        let mut callback = create_test_callback_with_map(&tracks, 512, ChannelMap::split(&[0, 1]));
+        
+        let input: Vec<f32> = (0..960).map(|i| if i % 2 == 0 { 0.25 } else { 0.75 }).collect();
+        callback.process(&input);
+        
+        let first = tracks[0].read_buffer(160).unwrap().unwrap();
+        let second = tracks[1].read_buffer(160).unwrap().unwrap();
+        assert_eq!(first.samples.len(), 160);
+        assert!((first.samples[80] - 0.25).abs() < 1e-6);
+        assert!((second.samples[80] - 0.75).abs() < 1e-6);
+    }
+    
+    #[test]
+    fn test_secondary_track_follows_primary_clock() {
+        // Both devices claim 16kHz, but the secondary one really runs 200ppm fast
+        let config = AudioConfig { buffer_size: 320, ..AudioConfig::default() };
+        let counters = Arc::new(DriftCounters::new());
+        let stats = Arc::new(RwLock::new(AudioStats::default()));
+        let primary_track = AudioRingBuffer::new(32000, 16000, 1);
+        let secondary_track = AudioRingBuffer::new(32000, 16000, 1);
+        let (level_broadcaster, _) = broadcast::channel(64);
+        
+        let mut primary = CaptureCallback::new(
+            vec![primary_track.clone()],
+            Arc::new(RwLock::new(AudioLevelMonitor::new())),
+            level_broadcaster,
+            Arc::clone(&stats),
+            16000,
+            1,
+            &config,
+        )
+        .with_drift_counters(Arc::clone(&counters));
+        let mut secondary = SecondaryCaptureCallback::new(
+            secondary_track.clone(), Arc::clone(&counters), 16000, 1, &config
+        );
+        let mut compensator = DriftCompensator::new(16000, 16000, 16000);
+        
+        // The secondary device delivers small blocks, as loopback devices tend to
+        let block = vec![0.1; 320];
+        let secondary_block = vec![0.1; 40];
+        let secondary_rate = 16000.0 * (1.0 + 200e-6);
+        let mut secondary_due = 0.0;
+        let mut drain = vec![0.0; 32000];
+        let offset = |counters: &DriftCounters| {
+            let sample = counters.snapshot();
+            sample.secondary_output as i64 - sample.primary_output as i64
+        };
+        
+        let mut initial_offset = None;
+        let mut ppm = None;
+        for _second in 0..300 {
+            for _ in 0..50 {
+                primary.process(&block);
+                secondary_due += secondary_rate / 50.0;
+                while secondary_due >= 40.0 {
+                    secondary.process(&secondary_block);
+                    secondary_due -= 40.0;
+                }
+            }
+            ppm = compensator.update(&counters).or(ppm);
+            initial_offset.get_or_insert(offset(&counters));
+            primary_track.read(&mut drain).unwrap();
+            secondary_track.read(&mut drain).unwrap();
+        }
+        
+        let ppm = ppm.unwrap();
+        assert!((ppm - 200.0).abs() < 5.0, "{}", ppm);
+        
+        // Uncorrected, the tracks would be 960 frames (60ms) apart by now
+        let misalignment = offset(&counters) - initial_offset.unwrap();
+        assert!(misalignment.abs() < 160, "tracks drifted {} frames apart", misalignment);
+    }
+    
+    #[test]
+    fn test_capture_callback_does_not_allocate_when_warm() {
+        let ring_buffer = AudioRingBuffer::new(4096, 16000, 1);
+        let peak_tap = AudioRingBuffer::new(PEAK_TAP_CAPACITY, 16000, 2);
+        let processing_tap = AudioRingBuffer::new(4096, 16000, 1);
+        let mut callback = create_test_callback(&ring_buffer, 512)
+            .with_peak_tap(peak_tap.clone(), DEFAULT_PEAK_BLOCK)
+            .with_processing_tap(processing_tap.clone())
+            .with_quality_tracker(Arc::new(RwLock::new(QualityTracker::new(16000))))
+            .with_validation(
+                AudioQualityValidator::new(),
+                Arc::new(RwLock::new(ValidationMonitor::new(Default::default()))),
+            );
+        let input: Vec<f32> = (0..960).map(|i| (i as f32 * 0.05).sin() * 0.5).collect();
+        let mut drain = vec![0.0; 4096];
+        
+        // Warm up lazily-initialised state (tracing callsites, locks)
+        callback.process(&input);
+        ring_buffer.read(&mut drain).unwrap();
+        processing_tap.read(&mut drain).unwrap();
+        
+        let allocations = count_allocations(|| {
+            for _ in 0..100 {
+                callback.process(&input);
+                ring_buffer.read(&mut drain).unwrap();
+                processing_tap.read(&mut drain).unwrap();
+            }
+        });
+        
+        assert_eq!(allocations, 0, "audio callback allocated on the real-time path");
+    }
+    
+    #[test]
+    fn test_capture_callback_does_not_allocate_when_nothing_drains_it() {
+        let ring_buffer = AudioRingBuffer::new(4096, 16000, 1);
+        let processing_tap = AudioRingBuffer::new(4096, 16000, 1);
+        let mut callback = create_test_callback(&ring_buffer, 512)
+            .with_processing_tap(processing_tap.clone());
+        let input: Vec<f32> = (0..960).map(|i| (i as f32 * 0.05).sin() * 0.5).collect();
+        
+        // Fill both buffers so every further write overruns
+        while ring_buffer.available() + 160 <= ring_buffer.capacity() {
+            callback.process(&input);
+        }
+        callback.process(&input);
+        
+        // With a subscriber installed, as in the app, logging an overrun would allocate
+        let subscriber = tracing_subscriber::fmt().with_writer(std::io::sink).finish();
+        let allocations = tracing::subscriber::with_default(subscriber, || {
+            count_allocations(|| {
+                for _ in 0..100 {
+                    callback.process(&input);
+                }
+            })
+        });
+        
+        assert_eq!(allocations, 0, "audio callback allocated while its buffers were full");
+    }
+    
+    #[test]
+    fn test_only_lost_tap_audio_counts_as_overruns() {
+        let ring_buffer = AudioRingBuffer::new(4096, 16000, 1);
+        let processing_tap = AudioRingBuffer::new(4096, 16000, 1);
+        let mut callback = create_test_callback(&ring_buffer, 512)
+            .with_processing_tap(processing_tap.clone());
+        let stats = Arc::clone(&callback.stats);
+        
+        // The track ring fills up but the processing tap is drained
+        let mut drain = vec![0.0; 4096];
+        for _ in 0..40 {
+            callback.process(&vec![0.25; 960]);
+            processing_tap.read(&mut drain).unwrap();
+        }
+        assert!(ring_buffer.stats().buffer_overruns > 0);
+        assert_eq!(stats.read().unwrap().buffer_overruns, 0);
+        
+        // Audio the processing tap can't take is lost
+        for _ in 0..40 {
+            callback.process(&vec![0.25; 960]);
+        }
+        assert!(stats.read().unwrap().buffer_overruns > 0);
+    }
+    
+    #[test]
+    fn test_capture_callback_validates_device_buffers() {
+        let ring_buffer = AudioRingBuffer::new(4096, 16000, 1);
+        let monitor = Arc::new(RwLock::new(ValidationMonitor::new(Default::default())));
+        let validator = AudioQualityValidator::with_config(ValidationConfig {
+            clipping_threshold: 0.9,
+            ..Default::default()
+        });
+        let mut callback = create_test_callback(&ring_buffer, 512)
+            .with_validation(validator, Arc::clone(&monitor));
+        
+        // 10ms of 48kHz stereo with one clipped frame
+        let mut input = vec![0.2f32; 960];
+        input[100] = 0.95;
+        input[101] = -0.95;
+        callback.process(&input);
+        
+        let events = monitor.write().unwrap().drain_events();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].at_ms, 10.0);
+        match events[0].issue {
+            ValidationIssue::Clipping { clipped_samples, .. } => assert_eq!(clipped_samples, 2),
+            other => panic!("unexpected issue {:?}", other),
+        }
+    }
+    
+    #[test]
+    fn test_capture_callback_feeds_peak_tap() {
+        let ring_buffer = AudioRingBuffer::new(4096, 16000, 1);
+        let peak_tap = AudioRingBuffer::new(PEAK_TAP_CAPACITY, 16000, 2);
+        let mut callback = create_test_callback(&ring_buffer, 512)
+            .with_peak_tap(peak_tap.clone(), 80);
+        
+        // 160 samples after resampling => two 80-sample peaks
+        callback.process(&vec![0.5; 960]);
+        
+        let mut pyramid = PeakPyramid::new(16000, 80);
+        assert_eq!(pyramid.drain_tap(&peak_tap).unwrap(), 2);
+        assert_eq!(pyramid.total_samples(), 160);
+    }
+    
     // Note: Testing actual audio capture requires audio devices,
     // which may not be available in CI environments.
     // Additional integration tests should be run on systems with audio hardware.
@@ -0,0 +1,256 @@
+//! Allocation-free sample conversion for the real-time capture path
+//!
+//! Everything in here writes into caller-provided slices so it can run inside
+//! the CPAL callback without touching the allocator.
+
+use super::types::ChannelGain;
+
+/// Downmix interleaved frames into mono by averaging all channels
+///
+/// Returns the number of frames written, bounded by the output length.
+pub fn downmix_into(input: &[f32], channels: u16, output: &mut [f32]) -> usize {
+    let channels = channels.max(1) as usize;
+    let frames = (input.len() / channels).min(output.len());
+
+    if channels == 1 {
+        output[..frames].copy_from_slice(&input[..frames]);
+        return frames;
+    }
+
+    let scale = 1.0 / channels as f32;
+    for (frame, out) in input.chunks_exact(channels).zip(output.iter_mut()).take(frames) {
+        *out = frame.iter().sum::<f32>() * scale;
+    }
+
+    frames
+}
+
+/// Mix selected channels of interleaved frames into mono using per-channel gains
+///
+/// Channels outside the frame are ignored; validate the map against the
+/// device first. Returns the number of frames written.
+pub fn mix_into(input: &[f32], channels: u16, gains: &[ChannelGain], output: &mut [f32]) -> usize {
+    let channels = channels.max(1) as usize;
+    let frames = (input.len() / channels).min(output.len());
+
+    for (frame, out) in input.chunks_exact(channels).zip(output.iter_mut()).take(frames) {
+        *out = gains.iter()
+            .filter_map(|g| frame.get(g.channel as usize).map(|&s| s * g.gain))
+            .sum();
+    }
+
+    frames
+}
+
+/// Streaming linear-interpolation resampler
+///
+/// The fractional read position and the last input frame are carried across
+/// calls, so consecutive blocks resample as one continuous signal.
+#[derive(Debug, Clone)]
+pub struct LinearResampler {
+    source_rate: u32,
+    target_rate: u32,
+    channels: usize,
+    /// Input frames consumed per output frame at the nominal rates
+    nominal_step: f64,
+    /// Input frames consumed per output frame, including any rate adjustment
+    step: f64,
+    /// Read position relative to the previous block's last frame
+    position: f64,
+    last_frame: Vec<f32>,
+}
+
+impl LinearResampler {
+    /// Create a resampler for interleaved audio with the given channel count
+    pub fn new(source_rate: u32, target_rate: u32, channels: u16) -> Self {
+        let channels = channels.max(1) as usize;
+        let step = source_rate as f64 / target_rate.max(1) as f64;
+        Self {
+            source_rate,
+            target_rate,
+            channels,
+            nominal_step: step,
+            step,
+            position: 1.0,
+            last_frame: vec![0.0; channels],
+        }
+    }
+
+    /// Whether input is copied through unchanged
+    pub fn is_passthrough(&self) -> bool {
+        self.source_rate == self.target_rate && self.step == self.nominal_step
+    }
+
+    /// Scale the source rate by `ratio` to follow a source clock that runs
+    /// fast (above 1.0) or slow (below 1.0) against its nominal rate
+    ///
+    /// Takes effect from the next block without disturbing the carried phase.
+    pub fn adjust_rate(&mut self, ratio: f64) {
+        self.step = self.nominal_step * ratio;
+    }
+
+    /// Source sample rate
+    pub fn source_rate(&self) -> u32 {
+        self.source_rate
+    }
+
+    /// Target sample rate
+    pub fn target_rate(&self) -> u32 {
+        self.target_rate
+    }
+
+    /// Upper bound on output frames produced for `input_frames` input frames
+    pub fn max_output_frames(&self, input_frames: usize) -> usize {
+        // Leave room for rate adjustments of up to 1%
+        (input_frames as f64 / (self.nominal_step * 0.99)).ceil() as usize + 1
+    }
+
+    /// Resample interleaved `input` into `output`, returning the samples written
+    ///
+    /// `output` must hold at least `max_output_frames` frames for the input;
+    /// anything beyond its length is dropped rather than reallocated.
+    pub fn process_into(&mut self, input: &[f32], output: &mut [f32]) -> usize {
+        let channels = self.channels;
+        let input_frames = input.len() / channels;
+
+        if self.is_passthrough() {
+            let samples = (input_frames * channels).min(output.len());
+            output[..samples].copy_from_slice(&input[..samples]);
+            return samples;
+        }
+
+        if input_frames == 0 {
+            return 0;
+        }
+
+        let output_frames = output.len() / channels;
+        let mut written = 0;
+        let mut position = self.position;
+
+        // Virtual frame 0 is the previous block's last frame, frame k is input[k - 1]
+        while position < input_frames as f64 && written < output_frames {
+            let index = position as usize;
+            let frac = (position - index as f64) as f32;
+
+            for channel in 0..channels {
+                let s0 = if index == 0 {
+                    self.last_frame[channel]
+                } else {
+                    input[(index - 1) * channels + channel]
+                };
+                let s1 = input[index * channels + channel];
+                output[written * channels + channel] = s0 + (s1 - s0) * frac;
+            }
+
+            written += 1;
+            position += self.step;
+        }
+
+        self.position = position - input_frames as f64;
+        self.last_frame
+            .copy_from_slice(&input[(input_frames - 1) * channels..input_frames * channels]);
+
+        written * channels
+    }
+
+    /// Forget the carried phase and history
+    pub fn reset(&mut self) {
+        self.position = 1.0;
+        self.last_frame.iter_mut().for_each(|s| *s = 0.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_downmix_into_averages_channels() {
+        let stereo = [0.5, 0.3, 0.8, 0.2];
+        let mut mono = [0.0; 4];
+
+        let frames = downmix_into(&stereo, 2, &mut mono);
+
+        assert_eq!(frames, 2);
+        assert!((mono[0] - 0.4).abs() < 0.001);
+        assert!((mono[1] - 0.5).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_mix_into_applies_channel_gains() {
+        // Channel 0 is the mic, channel 1 is an out-of-phase copy that averaging would cancel
+        let frames = [0.5, -0.5, 0.2, -0.2];
+        let mut mono = [0.0; 2];
+
+        let written = mix_into(&frames, 2, &[ChannelGain::new(0, 1.0)], &mut mono);
+        assert_eq!(written, 2);
+        assert_eq!(mono, [0.5, 0.2]);
+
+        mix_into(&frames, 2, &[ChannelGain::new(0, 0.5), ChannelGain::new(1, -0.5)], &mut mono);
+        assert!((mono[0] - 0.5).abs() < 1e-6);
+        assert!((mono[1] - 0.2).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_resampler_passthrough() {
+        let mut resampler = LinearResampler::new(16000, 16000, 1);
+        let input = [0.1, 0.2, 0.3];
+        let mut output = [0.0; 8];
+
+        let written = resampler.process_into(&input, &mut output);
+
+        assert!(resampler.is_passthrough());
+        assert_eq!(&output[..written], &input);
+    }
+
+    #[test]
+    fn test_resampler_downsamples_by_integer_ratio() {
+        let mut resampler = LinearResampler::new(48000, 16000, 1);
+        let input: Vec<f32> = (0..480).map(|i| i as f32).collect();
+        let mut output = vec![0.0; resampler.max_output_frames(input.len())];
+
+        let written = resampler.process_into(&input, &mut output);
+
+        assert_eq!(written, 160);
+        assert_eq!(output[0], 0.0);
+        assert_eq!(output[1], 3.0);
+        assert_eq!(output[159], 477.0);
+    }
+
+    #[test]
+    fn test_resampler_is_continuous_across_blocks() {
+        let input: Vec<f32> = (0..4410).map(|i| (i as f32 * 0.01).sin()).collect();
+
+        let mut whole = LinearResampler::new(44100, 16000, 1);
+        let mut expected = vec![0.0; whole.max_output_frames(input.len())];
+        let expected_len = whole.process_into(&input, &mut expected);
+
+        let mut chunked = LinearResampler::new(44100, 16000, 1);
+        let mut actual = Vec::new();
+        for block in input.chunks(441) {
+            let mut out = vec![0.0; chunked.max_output_frames(block.len())];
+            let written = chunked.process_into(block, &mut out);
+            actual.extend_from_slice(&out[..written]);
+        }
+
+        assert_eq!(actual.len(), expected_len);
+        for (a, e) in actual.iter().zip(&expected[..expected_len]) {
+            assert!((a - e).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn test_rate_adjustment_tracks_fast_source() {
+        // A 16kHz source running 500ppm fast must be consumed slightly faster
+        let input = vec![0.25; 16008];
+        let mut resampler = LinearResampler::new(16000, 16000, 1);
+        resampler.adjust_rate(1.0005);
+        assert!(!resampler.is_passthrough());
+
+        let mut output = vec![0.0; resampler.max_output_frames(input.len())];
+        let written = resampler.process_into(&input, &mut output);
+
+        assert!((written as i64 - 16000).abs() <= 1, "{}", written);
+        assert!(output[..written].iter().all(|&s| (s - 0.25).abs() < 1e-6));
+    }
+}
@@ -1,6 +1,5 @@
 //! Audio device management and enumeration
 
-use std::sync::Arc;
 use cpal::{Device, Host, traits::{DeviceTrait, HostTrait}};
 use tracing::{debug, info, warn, error};
 
@@ -209,8 +208,7 @@ impl AudioDeviceManager {
     /// Get supported configurations for a device
     pub fn get_supported_input_configs(&self, device: &Device) -> AudioResult<Vec<cpal::SupportedStreamConfigRange>> {
         device.supported_input_configs()
-            .map_err(|e| AudioError::Config(e.into()))?
-            .collect::<Result<Vec<_>, _>>()
+            .map(|configs| configs.collect())
             .map_err(|e| AudioError::Internal { 
                 message: format!("Failed to collect supported configs: {}", e) 
             })
@@ -219,8 +217,7 @@ impl AudioDeviceManager {
     /// Get supported configurations for an output device
     pub fn get_supported_output_configs(&self, device: &Device) -> AudioResult<Vec<cpal::SupportedStreamConfigRange>> {
         device.supported_output_configs()
-            .map_err(|e| AudioError::Config(e.into()))?
-            .collect::<Result<Vec<_>, _>>()
+            .map(|configs| configs.collect())
             .map_err(|e| AudioError::Internal { 
                 message: format!("Failed to collect supported configs: {}", e) 
             })
@@ -228,20 +225,41 @@ impl AudioDeviceManager {
     
     /// Find the best matching input configuration for our requirements
     pub fn find_best_input_config(&self, device: &Device, sample_rate: u32) -> AudioResult<cpal::StreamConfig> {
+        self.find_best_input_config_for_channels(device, sample_rate, 1)
+    }
+    
+    /// Find the best input configuration that exposes at least `min_channels` channels
+    pub fn find_best_input_config_for_channels(
+        &self, 
+        device: &Device, 
+        sample_rate: u32, 
+        min_channels: u16
+    ) -> AudioResult<cpal::StreamConfig> {
         let default_config = device.default_input_config()
             .map_err(AudioError::Config)?;
         
         debug!("Default input config: {:?}", default_config);
         
-        // Try to find a configuration that matches our sample rate
+        // Try to find a configuration that matches our sample rate and channel needs
         let supported_configs = self.get_supported_input_configs(device)?;
         
         for config_range in supported_configs {
+            if config_range.channels() < min_channels {
+                continue;
+            }
+            
             if config_range.min_sample_rate().0 <= sample_rate 
                 && config_range.max_sample_rate().0 >= sample_rate {
                 
+                // Prefer mono or stereo unless the channel map needs more
+                let channels = if min_channels > 2 {
+                    config_range.channels()
+                } else {
+                    config_range.channels().min(2)
+                };
+                
                 let config = cpal::StreamConfig {
-                    channels: config_range.channels().min(2), // Prefer mono or stereo
+                    channels,
                     sample_rate: cpal::SampleRate(sample_rate),
                     buffer_size: cpal::BufferSize::Default,
                 };
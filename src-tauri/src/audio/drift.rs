@@ -0,0 +1,276 @@
+//! Clock-drift compensation between two capture devices
+//!
+//! Separate devices sample against independent crystals whose real rates are
+//! off their nominal rates by tens of ppm, enough to pull two tracks seconds
+//! apart over a long meeting. Both capture callbacks count the frames they
+//! receive; a monitor task regresses the secondary device's count against the
+//! primary's to measure the offset, then hands a rate correction back to the
+//! secondary callback's resampler so its track follows the primary clock.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tracing::warn;
+
+/// Primary-device audio needed before a drift estimate is trusted
+const MIN_ESTIMATE_SPAN: Duration = Duration::from_secs(10);
+
+/// Larger offsets mean a stalled or restarted device, not clock drift
+const MAX_PLAUSIBLE_PPM: f64 = 1000.0;
+
+/// Time over which leftover misalignment between the tracks is pulled back in
+const PHASE_TIME_CONSTANT: Duration = Duration::from_secs(30);
+
+/// Cap on the extra rate change used to close misalignment
+const MAX_PHASE_CORRECTION: f64 = 100e-6;
+
+/// Smoothing for the block-quantised track offset
+const OFFSET_SMOOTHING: f64 = 0.1;
+
+/// Frame counters shared between the two capture callbacks and the monitor
+///
+/// Callbacks only do relaxed atomic adds and loads, so this is safe to touch
+/// from the real-time threads.
+#[derive(Debug)]
+pub struct DriftCounters {
+    primary_frames: AtomicU64,
+    secondary_frames: AtomicU64,
+    primary_output: AtomicU64,
+    secondary_output: AtomicU64,
+    /// Resampling ratio for the secondary device, as `f64` bits
+    correction: AtomicU64,
+}
+
+/// A consistent-enough reading of the counters
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DriftSample {
+    /// Device frames received from each device
+    pub primary_frames: u64,
+    pub secondary_frames: u64,
+    /// Frames written to each device's track at the target rate
+    pub primary_output: u64,
+    pub secondary_output: u64,
+}
+
+impl Default for DriftCounters {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DriftCounters {
+    pub fn new() -> Self {
+        Self {
+            primary_frames: AtomicU64::new(0),
+            secondary_frames: AtomicU64::new(0),
+            primary_output: AtomicU64::new(0),
+            secondary_output: AtomicU64::new(0),
+            correction: AtomicU64::new(1.0f64.to_bits()),
+        }
+    }
+
+    /// Count a primary callback block: device frames in, track frames out
+    pub fn record_primary(&self, input_frames: usize, output_frames: usize) {
+        self.primary_frames.fetch_add(input_frames as u64, Ordering::Relaxed);
+        self.primary_output.fetch_add(output_frames as u64, Ordering::Relaxed);
+    }
+
+    /// Count a secondary callback block: device frames in, track frames out
+    pub fn record_secondary(&self, input_frames: usize, output_frames: usize) {
+        self.secondary_frames.fetch_add(input_frames as u64, Ordering::Relaxed);
+        self.secondary_output.fetch_add(output_frames as u64, Ordering::Relaxed);
+    }
+
+    /// Rate ratio the secondary resampler should apply
+    pub fn correction(&self) -> f64 {
+        f64::from_bits(self.correction.load(Ordering::Relaxed))
+    }
+
+    pub fn set_correction(&self, ratio: f64) {
+        self.correction.store(ratio.to_bits(), Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> DriftSample {
+        DriftSample {
+            primary_frames: self.primary_frames.load(Ordering::Relaxed),
+            secondary_frames: self.secondary_frames.load(Ordering::Relaxed),
+            primary_output: self.primary_output.load(Ordering::Relaxed),
+            secondary_output: self.secondary_output.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Least-squares estimate of one device clock against another
+///
+/// Fits secondary frame counts against primary ones over the whole session.
+/// Callback block sizes make each reading jittery, but the jitter averages
+/// out as the span grows while crystal offsets stay put.
+#[derive(Debug, Clone)]
+pub struct DriftEstimator {
+    primary_rate: f64,
+    secondary_rate: f64,
+    origin: Option<(u64, u64)>,
+    count: u64,
+    mean_x: f64,
+    mean_y: f64,
+    m2_x: f64,
+    c_xy: f64,
+    span: f64,
+}
+
+impl DriftEstimator {
+    /// Create an estimator for devices with the given nominal rates
+    pub fn new(primary_rate: u32, secondary_rate: u32) -> Self {
+        Self {
+            primary_rate: primary_rate.max(1) as f64,
+            secondary_rate: secondary_rate.max(1) as f64,
+            origin: None,
+            count: 0,
+            mean_x: 0.0,
+            mean_y: 0.0,
+            m2_x: 0.0,
+            c_xy: 0.0,
+            span: 0.0,
+        }
+    }
+
+    /// Add a reading of cumulative device frame counts
+    pub fn add(&mut self, primary_frames: u64, secondary_frames: u64) {
+        // Work relative to the first reading so the sums stay well conditioned
+        let (x0, y0) = *self.origin.get_or_insert((primary_frames, secondary_frames));
+        let x = primary_frames as f64 - x0 as f64;
+        let y = secondary_frames as f64 - y0 as f64;
+
+        self.count += 1;
+        let dx = x - self.mean_x;
+        self.mean_x += dx / self.count as f64;
+        self.mean_y += (y - self.mean_y) / self.count as f64;
+        self.m2_x += dx * (x - self.mean_x);
+        self.c_xy += dx * (y - self.mean_y);
+        self.span = self.span.max(x);
+    }
+
+    /// Primary-device time covered by the readings
+    pub fn span(&self) -> Duration {
+        Duration::from_secs_f64(self.span / self.primary_rate)
+    }
+
+    /// Secondary clock offset against the primary in ppm, once enough audio
+    /// has been seen; positive when the secondary device runs fast
+    pub fn ppm(&self) -> Option<f64> {
+        if self.count < 3 || self.span() < MIN_ESTIMATE_SPAN || self.m2_x <= 0.0 {
+            return None;
+        }
+        let slope = self.c_xy / self.m2_x;
+        Some((slope * self.primary_rate / self.secondary_rate - 1.0) * 1e6)
+    }
+
+    /// Start over, e.g. after a device restart
+    pub fn reset(&mut self) {
+        *self = Self::new(self.primary_rate as u32, self.secondary_rate as u32);
+    }
+}
+
+/// Turns drift estimates into the secondary device's resampling ratio
+#[derive(Debug, Clone)]
+pub struct DriftCompensator {
+    estimator: DriftEstimator,
+    target_rate: f64,
+    /// Track offset when compensation began; startup latency is left alone
+    baseline_offset: Option<f64>,
+    smoothed_offset: Option<f64>,
+}
+
+impl DriftCompensator {
+    pub fn new(primary_rate: u32, secondary_rate: u32, target_rate: u32) -> Self {
+        Self {
+            estimator: DriftEstimator::new(primary_rate, secondary_rate),
+            target_rate: target_rate.max(1) as f64,
+            baseline_offset: None,
+            smoothed_offset: None,
+        }
+    }
+
+    /// Read the counters, refine the estimate and publish a new correction
+    ///
+    /// Returns the measured offset in ppm once there is one.
+    pub fn update(&mut self, counters: &DriftCounters) -> Option<f64> {
+        let sample = counters.snapshot();
+        if sample.primary_frames == 0 || sample.secondary_frames == 0 {
+            return None;
+        }
+
+        self.estimator.add(sample.primary_frames, sample.secondary_frames);
+        let ppm = self.estimator.ppm()?;
+
+        if ppm.abs() > MAX_PLAUSIBLE_PPM {
+            warn!("Implausible clock drift of {:.0}ppm, restarting estimate", ppm);
+            self.estimator.reset();
+            self.baseline_offset = None;
+            self.smoothed_offset = None;
+            counters.set_correction(1.0);
+            return None;
+        }
+
+        // Frames the secondary track is ahead of the primary one
+        let offset = sample.secondary_output as f64 - sample.primary_output as f64;
+        let smoothed = match self.smoothed_offset {
+            Some(previous) => previous + OFFSET_SMOOTHING * (offset - previous),
+            None => offset,
+        };
+        self.smoothed_offset = Some(smoothed);
+        let baseline = *self.baseline_offset.get_or_insert(smoothed);
+
+        // A track that has got ahead consumes its input slightly faster
+        let excess_seconds = (smoothed - baseline) / self.target_rate;
+        let phase = (excess_seconds / PHASE_TIME_CONSTANT.as_secs_f64())
+            .clamp(-MAX_PHASE_CORRECTION, MAX_PHASE_CORRECTION);
+
+        counters.set_correction((1.0 + ppm * 1e-6) * (1.0 + phase));
+        Some(ppm)
+    }
+
+    pub fn estimator(&self) -> &DriftEstimator {
+        &self.estimator
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimator_recovers_offset_through_block_jitter() {
+        // 48kHz primary, 44.1kHz secondary running 80ppm fast, read once a
+        // second at arbitrary points inside 1024-frame callback blocks
+        let mut estimator = DriftEstimator::new(48000, 44100);
+        let secondary_rate = 44100.0 * (1.0 + 80e-6);
+
+        for second in 0..600u64 {
+            let jitter = |seed: u64| (seed * 7919 % 1024) as f64;
+            let primary = (second as f64 * 48000.0 - jitter(second)).max(0.0);
+            let secondary = (second as f64 * secondary_rate - jitter(second + 13)).max(0.0);
+            estimator.add(primary as u64, secondary as u64);
+
+            if second == 5 {
+                assert!(estimator.ppm().is_none(), "too early for an estimate");
+            }
+        }
+
+        let ppm = estimator.ppm().unwrap();
+        assert!((ppm - 80.0).abs() < 2.0, "{}", ppm);
+    }
+
+    #[test]
+    fn test_compensator_ignores_implausible_jumps() {
+        let counters = DriftCounters::new();
+        let mut compensator = DriftCompensator::new(16000, 16000, 16000);
+
+        // The secondary device delivers half the audio it should
+        for second in 1..=20u64 {
+            counters.record_primary(16000, 16000);
+            counters.record_secondary(8000, 8000);
+            assert!(compensator.update(&counters).is_none(), "second {}", second);
+        }
+        assert_eq!(counters.correction(), 1.0);
+    }
+}
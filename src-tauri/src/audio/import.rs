@@ -0,0 +1,484 @@
+//! Importing existing recordings (WAV, FLAC, MP3, Ogg Vorbis) and our own
+//! Opus archives
+//!
+//! Files are decoded packet by packet and pushed through the same channel
+//! mapping and resampling code as live capture, then cut into fixed-length
+//! chunks whose offsets follow the file's own timeline.
+
+use std::fs::File;
+use std::io::ErrorKind;
+use std::path::Path;
+use std::time::Duration;
+use serde::Serialize;
+use symphonia::core::audio::SampleBuffer;
+use symphonia::core::codecs::{DecoderOptions, CODEC_TYPE_NULL};
+use symphonia::core::errors::Error as DecodeError;
+use symphonia::core::formats::FormatOptions;
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+use tracing::{debug, info, warn};
+
+use super::archive::{is_archive, OpusArchiveReader};
+use super::capture::{plan_tracks, TrackSource};
+use super::conversion::{downmix_into, mix_into, LinearResampler};
+use super::types::{AudioBuffer, AudioConfig, AudioError, AudioResult};
+
+/// Default length of the chunks handed to transcription
+pub const DEFAULT_IMPORT_CHUNK: Duration = Duration::from_secs(30);
+
+/// File extensions the importer accepts
+pub const SUPPORTED_EXTENSIONS: &[&str] = &["wav", "flac", "mp3", "ogg", "oga", "opus"];
+
+/// A converted slice of an imported file
+#[derive(Debug, Clone)]
+pub struct AudioChunk {
+    pub index: usize,
+    /// Offset of the first sample from the start of the file
+    pub start_ms: f64,
+    pub buffer: AudioBuffer,
+}
+
+/// Import progress, reported after every chunk
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct ImportProgress {
+    pub processed_ms: f64,
+    /// Known up front for most containers
+    pub total_ms: Option<f64>,
+    pub chunks: usize,
+}
+
+impl ImportProgress {
+    /// Completed fraction, if the total length is known
+    pub fn fraction(&self) -> Option<f32> {
+        self.total_ms
+            .filter(|&total| total > 0.0)
+            .map(|total| (self.processed_ms / total).min(1.0) as f32)
+    }
+}
+
+/// What an import produced
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportSummary {
+    pub codec: String,
+    pub source_sample_rate: u32,
+    pub source_channels: u16,
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub duration_ms: f64,
+    pub chunks: usize,
+}
+
+/// Decodes audio files into capture-format chunks
+pub struct AudioFileImporter {
+    config: AudioConfig,
+    chunk_duration: Duration,
+}
+
+impl AudioFileImporter {
+    /// Create an importer producing audio in the format described by `config`
+    pub fn new(config: AudioConfig) -> Self {
+        Self {
+            config,
+            chunk_duration: DEFAULT_IMPORT_CHUNK,
+        }
+    }
+
+    /// Use chunks of `duration` instead of the default
+    pub fn with_chunk_duration(mut self, duration: Duration) -> Self {
+        self.chunk_duration = duration.max(Duration::from_millis(10));
+        self
+    }
+
+    /// Whether `path` has an extension the importer understands
+    pub fn is_supported(path: &Path) -> bool {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| SUPPORTED_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()))
+            .unwrap_or(false)
+    }
+
+    /// Decode `path`, handing each converted chunk to `on_chunk`
+    ///
+    /// Blocking; run it on a blocking thread. An error from `on_chunk` stops
+    /// the import and is returned as is.
+    pub fn import<C, P>(&self, path: &Path, mut on_chunk: C, mut on_progress: P) -> AudioResult<ImportSummary>
+    where
+        C: FnMut(AudioChunk) -> AudioResult<()>,
+        P: FnMut(ImportProgress),
+    {
+        info!("Importing audio file: {}", path.display());
+
+        let (codec, total_ms, converter) = if is_archive(path) {
+            self.decode_archive(path, &mut on_chunk, &mut on_progress)?
+        } else {
+            self.decode_media(path, &mut on_chunk, &mut on_progress)?
+        };
+
+        let Some(mut converter) = converter else {
+            return Err(AudioError::UnsupportedFormat {
+                details: format!("No decodable audio in {}", path.display()),
+            });
+        };
+
+        if let Some(chunk) = converter.flush() {
+            on_chunk(chunk)?;
+        }
+        let progress = converter.progress(total_ms);
+        on_progress(ImportProgress {
+            total_ms: Some(progress.processed_ms),
+            ..progress
+        });
+
+        let summary = ImportSummary {
+            codec,
+            source_sample_rate: converter.source_rate,
+            source_channels: converter.source_channels,
+            sample_rate: self.config.sample_rate,
+            channels: converter.output_channels,
+            duration_ms: progress.processed_ms,
+            chunks: converter.chunks,
+        };
+
+        info!("Imported {} ({}, {:.1}s, {} chunks)",
+              path.display(), summary.codec, summary.duration_ms / 1000.0, summary.chunks);
+        Ok(summary)
+    }
+
+    /// Decode a file with symphonia, returning the codec, length and converter state
+    fn decode_media<C, P>(&self, path: &Path, on_chunk: &mut C, on_progress: &mut P) -> AudioResult<DecodedStream>
+    where
+        C: FnMut(AudioChunk) -> AudioResult<()>,
+        P: FnMut(ImportProgress),
+    {
+        let source = MediaSourceStream::new(Box::new(File::open(path)?), Default::default());
+        let mut hint = Hint::new();
+        if let Some(ext) = path.extension().and_then(|ext| ext.to_str()) {
+            hint.with_extension(ext);
+        }
+
+        let probed = symphonia::default::get_probe().format(
+            &hint,
+            source,
+            &FormatOptions::default(),
+            &MetadataOptions::default(),
+        )?;
+        let mut format = probed.format;
+
+        let track = format.tracks()
+            .iter()
+            .find(|track| track.codec_params.codec != CODEC_TYPE_NULL)
+            .ok_or_else(|| AudioError::UnsupportedFormat {
+                details: format!("No audio track in {}", path.display()),
+            })?;
+        let track_id = track.id;
+        let params = track.codec_params.clone();
+
+        let codec = symphonia::default::get_codecs()
+            .get_codec(params.codec)
+            .map(|descriptor| descriptor.short_name.to_string())
+            .unwrap_or_else(|| "unknown".to_string());
+        let total_ms = match (params.n_frames, params.sample_rate) {
+            (Some(frames), Some(rate)) if rate > 0 => Some(frames as f64 * 1000.0 / rate as f64),
+            _ => None,
+        };
+
+        let mut decoder = symphonia::default::get_codecs().make(&params, &DecoderOptions::default())?;
+        let mut converter: Option<ImportConverter> = None;
+        let mut sample_buffer: Option<SampleBuffer<f32>> = None;
+
+        loop {
+            let packet = match format.next_packet() {
+                Ok(packet) => packet,
+                Err(DecodeError::IoError(e)) if e.kind() == ErrorKind::UnexpectedEof => break,
+                Err(DecodeError::ResetRequired) => break,
+                Err(e) => return Err(e.into()),
+            };
+            if packet.track_id() != track_id {
+                continue;
+            }
+
+            let decoded = match decoder.decode(&packet) {
+                Ok(decoded) => decoded,
+                Err(DecodeError::DecodeError(e)) => {
+                    // Corrupt packets are skipped rather than failing the import
+                    warn!("Skipping undecodable packet in {}: {}", path.display(), e);
+                    continue;
+                }
+                Err(e) => return Err(e.into()),
+            };
+
+            let spec = *decoded.spec();
+            let samples = match sample_buffer.as_mut() {
+                Some(buffer) if buffer.capacity() >= decoded.capacity() * spec.channels.count() => buffer,
+                _ => sample_buffer.insert(SampleBuffer::new(decoded.capacity() as u64, spec)),
+            };
+            samples.copy_interleaved_ref(decoded);
+
+            let converter = match converter.as_mut() {
+                Some(converter) => converter,
+                None => converter.insert(ImportConverter::new(
+                    &self.config,
+                    spec.rate,
+                    spec.channels.count() as u16,
+                    self.chunk_duration,
+                )),
+            };
+
+            for chunk in converter.push(samples.samples()) {
+                on_chunk(chunk)?;
+                on_progress(converter.progress(total_ms));
+            }
+        }
+
+        Ok((codec, total_ms, converter))
+    }
+
+    /// Decode one of our own Opus archives, which symphonia can't read
+    fn decode_archive<C, P>(&self, path: &Path, on_chunk: &mut C, on_progress: &mut P) -> AudioResult<DecodedStream>
+    where
+        C: FnMut(AudioChunk) -> AudioResult<()>,
+        P: FnMut(ImportProgress),
+    {
+        let mut reader = OpusArchiveReader::open(path)?;
+        let mut converter = ImportConverter::new(
+            &self.config,
+            reader.sample_rate(),
+            reader.channels(),
+            self.chunk_duration,
+        );
+
+        while let Some(buffer) = reader.next_buffer()? {
+            for chunk in converter.push(&buffer.samples) {
+                on_chunk(chunk)?;
+                on_progress(converter.progress(None));
+            }
+        }
+
+        Ok(("opus".to_string(), None, Some(converter)))
+    }
+}
+
+/// Codec name, total length if known, and the converter that consumed the audio
+type DecodedStream = (String, Option<f64>, Option<ImportConverter>);
+
+/// Channel mapping, resampling and chunking for one imported stream
+struct ImportConverter {
+    source: TrackSource,
+    source_rate: u32,
+    source_channels: u16,
+    output_channels: u16,
+    sample_rate: u32,
+    resampler: LinearResampler,
+    mix_scratch: Vec<f32>,
+    resample_scratch: Vec<f32>,
+    pending: Vec<f32>,
+    chunk_samples: usize,
+    emitted_samples: u64,
+    chunks: usize,
+}
+
+impl ImportConverter {
+    fn new(config: &AudioConfig, source_rate: u32, source_channels: u16, chunk: Duration) -> Self {
+        // Imports produce a single track; multi-track maps use their first track
+        let source = plan_tracks(config, source_channels)
+            .into_iter()
+            .next()
+            .unwrap_or(TrackSource::Average);
+        let output_channels = match source {
+            TrackSource::Passthrough => source_channels,
+            _ => 1,
+        };
+        let chunk_frames = (config.sample_rate as f64 * chunk.as_secs_f64()).round() as usize;
+
+        debug!("Import conversion: {}Hz x{} -> {}Hz x{} ({:?})",
+               source_rate, source_channels, config.sample_rate, output_channels, source);
+
+        Self {
+            source,
+            source_rate,
+            source_channels,
+            output_channels,
+            sample_rate: config.sample_rate,
+            resampler: LinearResampler::new(source_rate, config.sample_rate, output_channels),
+            mix_scratch: Vec::new(),
+            resample_scratch: Vec::new(),
+            pending: Vec::new(),
+            chunk_samples: chunk_frames.max(1) * output_channels as usize,
+            emitted_samples: 0,
+            chunks: 0,
+        }
+    }
+
+    /// Convert interleaved source samples, returning any chunks completed
+    fn push(&mut self, interleaved: &[f32]) -> Vec<AudioChunk> {
+        let frames = interleaved.len() / self.source_channels.max(1) as usize;
+        if self.source != TrackSource::Passthrough && self.mix_scratch.len() < frames {
+            self.mix_scratch.resize(frames, 0.0);
+        }
+
+        let converted: &[f32] = match &self.source {
+            TrackSource::Passthrough => interleaved,
+            TrackSource::Average => {
+                let written = downmix_into(interleaved, self.source_channels, &mut self.mix_scratch);
+                &self.mix_scratch[..written]
+            }
+            TrackSource::Mix(gains) => {
+                let written = mix_into(interleaved, self.source_channels, gains, &mut self.mix_scratch);
+                &self.mix_scratch[..written]
+            }
+        };
+
+        if self.resampler.is_passthrough() {
+            self.pending.extend_from_slice(converted);
+        } else {
+            let needed = self.resampler.max_output_frames(frames) * self.output_channels as usize;
+            if self.resample_scratch.len() < needed {
+                self.resample_scratch.resize(needed, 0.0);
+            }
+            let written = self.resampler.process_into(converted, &mut self.resample_scratch);
+            self.pending.extend_from_slice(&self.resample_scratch[..written]);
+        }
+
+        let mut chunks = Vec::new();
+        while self.pending.len() >= self.chunk_samples {
+            let rest = self.pending.split_off(self.chunk_samples);
+            let samples = std::mem::replace(&mut self.pending, rest);
+            chunks.push(self.make_chunk(samples));
+        }
+        chunks
+    }
+
+    /// Emit whatever is left as a final, shorter chunk
+    fn flush(&mut self) -> Option<AudioChunk> {
+        if self.pending.is_empty() {
+            return None;
+        }
+        let samples = std::mem::take(&mut self.pending);
+        Some(self.make_chunk(samples))
+    }
+
+    fn make_chunk(&mut self, samples: Vec<f32>) -> AudioChunk {
+        let chunk = AudioChunk {
+            index: self.chunks,
+            start_ms: self.samples_to_ms(self.emitted_samples),
+            buffer: AudioBuffer::new(samples, self.sample_rate, self.output_channels),
+        };
+        self.emitted_samples += chunk.buffer.samples.len() as u64;
+        self.chunks += 1;
+        chunk
+    }
+
+    fn samples_to_ms(&self, samples: u64) -> f64 {
+        samples as f64 * 1000.0 / (self.sample_rate.max(1) as f64 * self.output_channels.max(1) as f64)
+    }
+
+    fn progress(&self, total_ms: Option<f64>) -> ImportProgress {
+        ImportProgress {
+            processed_ms: self.samples_to_ms(self.emitted_samples + self.pending.len() as u64),
+            total_ms,
+            chunks: self.chunks,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::test_wav::write_wav;
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("meetingmind-import-{}-{}", uuid::Uuid::new_v4(), name))
+    }
+
+    #[test]
+    fn test_import_wav_follows_file_timeline() {
+        // 2.5s of 44.1kHz stereo with an out-of-phase second channel
+        let path = temp_path("stereo.wav");
+        let frames = 44100 * 5 / 2;
+        let samples: Vec<f32> = (0..frames)
+            .flat_map(|i| {
+                let s = (i as f32 * 0.01).sin() * 0.5;
+                [s, s * 0.5]
+            })
+            .collect();
+        write_wav(&path, 44100, 2, &samples);
+
+        let importer = AudioFileImporter::new(AudioConfig::default())
+            .with_chunk_duration(Duration::from_secs(1));
+        let mut chunks = Vec::new();
+        let mut progress = Vec::new();
+        let summary = importer
+            .import(&path, |chunk| { chunks.push(chunk); Ok(()) }, |p| progress.push(p))
+            .unwrap();
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(summary.codec, "pcm_s16le");
+        assert_eq!((summary.source_sample_rate, summary.source_channels), (44100, 2));
+        assert_eq!((summary.sample_rate, summary.channels), (16000, 1));
+        assert!((summary.duration_ms - 2500.0).abs() < 1.0, "{}", summary.duration_ms);
+
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks.iter().map(|c| c.start_ms).collect::<Vec<_>>(), vec![0.0, 1000.0, 2000.0]);
+        assert_eq!(chunks[0].buffer.samples.len(), 16000);
+        assert!((chunks[2].buffer.duration_ms() - 500.0).abs() < 1.0);
+
+        assert_eq!(progress.last().unwrap().fraction(), Some(1.0));
+        assert!(progress.windows(2).all(|w| w[0].processed_ms <= w[1].processed_ms));
+    }
+
+    #[test]
+    fn test_import_stops_when_consumer_fails() {
+        let path = temp_path("mono.wav");
+        write_wav(&path, 16000, 1, &vec![0.1; 16000 * 3]);
+
+        let importer = AudioFileImporter::new(AudioConfig::default())
+            .with_chunk_duration(Duration::from_secs(1));
+        let mut seen = 0;
+        let result = importer.import(
+            &path,
+            |_| {
+                seen += 1;
+                Err(AudioError::Internal { message: "transcription queue closed".to_string() })
+            },
+            |_| {},
+        );
+        std::fs::remove_file(&path).ok();
+
+        assert!(result.is_err());
+        assert_eq!(seen, 1);
+    }
+
+    #[test]
+    fn test_import_reads_opus_archives() {
+        let source = temp_path("archived.wav");
+        write_wav(&source, 16000, 1, &vec![0.1; 16000 * 2]);
+        let archive = crate::audio::archive::archive_recording(
+            &source,
+            &AudioConfig::default(),
+            &crate::audio::ArchiveSettings::default(),
+        )
+        .unwrap();
+        std::fs::remove_file(&source).ok();
+
+        let importer = AudioFileImporter::new(AudioConfig::default())
+            .with_chunk_duration(Duration::from_secs(1));
+        let mut chunks = Vec::new();
+        let summary = importer
+            .import(&archive.archive_path, |chunk| { chunks.push(chunk); Ok(()) }, |_| {})
+            .unwrap();
+        std::fs::remove_file(&archive.archive_path).ok();
+
+        assert_eq!(summary.codec, "opus");
+        assert!((summary.duration_ms - 2000.0).abs() < 1.0, "{}", summary.duration_ms);
+        assert_eq!(chunks.len(), 2);
+    }
+
+    #[test]
+    fn test_supported_extensions() {
+        assert!(AudioFileImporter::is_supported(Path::new("call.MP3")));
+        assert!(AudioFileImporter::is_supported(Path::new("memo.flac")));
+        assert!(AudioFileImporter::is_supported(Path::new("standup.opus")));
+        assert!(!AudioFileImporter::is_supported(Path::new("notes.txt")));
+    }
+}
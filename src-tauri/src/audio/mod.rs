@@ -1,21 +1,49 @@
 //! Audio capture and processing functionality
 
+pub mod analysis;
+pub mod archive;
 pub mod buffer;
 pub mod capture;
+pub mod conversion;
 pub mod devices;
+pub mod drift;
+pub mod import;
+pub mod peaks;
 pub mod processing;
+pub mod quality;
+pub mod streams;
 pub mod types;
+pub mod validation;
+pub mod worker;
+
+#[cfg(test)]
+mod test_alloc;
+#[cfg(test)]
+pub(crate) mod test_wav;
 
 // Re-export main types and services for easy access
-pub use capture::AudioCaptureService;
+pub use capture::{AudioCaptureService, CaptureCallback, SecondaryCaptureCallback, TrackAudio};
 pub use devices::AudioDeviceManager;
 pub use processing::{
     AudioProcessingPipeline, AudioQualityValidator, NoiseGateProcessor,
-    AutomaticGainControl, AudioFormatConverter, AudioAnalyzer, AudioAnalysis
+    AutomaticGainControl, AudioFormatConverter, AudioAnalyzer, BufferFindings,
+    ValidationConfig
+};
+pub use archive::{
+    archive_recording, decode_archive, ArchiveMode, ArchiveSettings, ArchiveSummary,
+    OpusArchiveReader, OpusArchiveWriter
 };
+pub use analysis::{AudioAnalysis, FeatureExtractor, FeatureHistory, FeatureSummary, FrameFeatures};
 pub use buffer::{AudioRingBuffer, MultiChannelAudioBuffer};
+pub use conversion::{downmix_into, mix_into, LinearResampler};
+pub use drift::{DriftCompensator, DriftCounters, DriftEstimator, DriftSample};
+pub use import::{AudioChunk, AudioFileImporter, ImportProgress, ImportSummary};
+pub use peaks::{Peak, PeakAccumulator, PeakPyramid, PeakSlice};
+pub use quality::{AudioQualityReport, QualityThresholds, QualityTracker, QualityWarning, SpeechDetector};
+pub use validation::{QualityEvent, ValidationIssue, ValidationMonitor};
+pub use worker::{BudgetAction, BudgetTracker, ProcessingWorker, ThreadPriority, WorkerConfig};
 pub use types::{
     AudioBuffer, AudioConfig, AudioDevice, AudioDeviceType, AudioError,
     AudioCaptureStatus, AudioProcessor, AudioStats, AudioLevelMonitor,
-    AudioFormat, RingBuffer, AudioResult
+    AudioFormat, RingBuffer, AudioResult, ChannelGain, ChannelMap
 };
\ No newline at end of file
@@ -0,0 +1,497 @@
+//! Multi-resolution min/max peak overview for drawing recording waveforms
+//!
+//! Level 0 holds one min/max pair per `base_block` samples and every level
+//! above halves the resolution, so any time range can be drawn at any zoom
+//! without decoding the recording itself. The pyramid is persisted as a small
+//! binary sidecar next to the audio file.
+
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info};
+
+use super::buffer::AudioRingBuffer;
+use super::types::{AudioError, AudioResult};
+
+/// Samples summarised by one level-0 peak (16ms at 16kHz)
+pub const DEFAULT_PEAK_BLOCK: usize = 256;
+
+/// File extension of the peak sidecar written next to a recording
+pub const PEAK_FILE_EXTENSION: &str = "peaks";
+
+const PEAK_FILE_MAGIC: &[u8; 4] = b"MMPK";
+const PEAK_FILE_VERSION: u32 = 1;
+/// Bytes before the first level: magic, version, rate, block, samples and level count
+const PEAK_FILE_HEADER_BYTES: u64 = 28;
+/// Bytes of a level's length, and of each peak in it
+const PEAK_RECORD_BYTES: u64 = 8;
+
+/// Minimum and maximum sample value over a block of audio
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Peak {
+    pub min: f32,
+    pub max: f32,
+}
+
+impl Peak {
+    /// Combine two peaks covering adjacent blocks
+    pub fn merge(self, other: Peak) -> Peak {
+        Peak {
+            min: self.min.min(other.min),
+            max: self.max.max(other.max),
+        }
+    }
+}
+
+/// Peaks for a time range at one zoom level
+#[derive(Debug, Clone, Serialize)]
+pub struct PeakSlice {
+    pub level: usize,
+    pub samples_per_peak: usize,
+    pub sample_rate: u32,
+    /// Start time of the first returned peak
+    pub start_ms: f64,
+    pub peaks: Vec<Peak>,
+}
+
+/// Accumulates level-0 peaks on the real-time thread
+///
+/// Completed blocks are pushed into a preallocated tap buffer as `[min, max]`
+/// pairs, so this never allocates.
+#[derive(Debug, Clone)]
+pub struct PeakAccumulator {
+    block: usize,
+    count: usize,
+    min: f32,
+    max: f32,
+}
+
+impl PeakAccumulator {
+    pub fn new(block: usize) -> Self {
+        Self {
+            block: block.max(1),
+            count: 0,
+            min: f32::MAX,
+            max: f32::MIN,
+        }
+    }
+
+    /// Fold samples in, writing each completed block's peak to `tap`
+    ///
+    /// Returns how many peaks were lost to a full tap.
+    pub fn push(&mut self, samples: &[f32], tap: &AudioRingBuffer) -> usize {
+        let mut lost = 0;
+        for &sample in samples {
+            self.min = self.min.min(sample);
+            self.max = self.max.max(sample);
+            self.count += 1;
+
+            if self.count == self.block {
+                if tap.write(&[self.min, self.max]).is_err() {
+                    lost += 1;
+                }
+                self.count = 0;
+                self.min = f32::MAX;
+                self.max = f32::MIN;
+            }
+        }
+        lost
+    }
+}
+
+/// Multi-resolution min/max peak pyramid
+#[derive(Debug, Clone)]
+pub struct PeakPyramid {
+    sample_rate: u32,
+    base_block: usize,
+    total_samples: u64,
+    levels: Vec<Vec<Peak>>,
+    pending: Option<Peak>,
+    pending_samples: usize,
+}
+
+impl PeakPyramid {
+    /// Create an empty pyramid with `base_block` samples per level-0 peak
+    pub fn new(sample_rate: u32, base_block: usize) -> Self {
+        Self {
+            sample_rate,
+            base_block: base_block.max(1),
+            total_samples: 0,
+            levels: vec![Vec::new()],
+            pending: None,
+            pending_samples: 0,
+        }
+    }
+
+    /// Build a pyramid from a complete signal
+    pub fn from_samples(samples: &[f32], sample_rate: u32, base_block: usize) -> Self {
+        let mut pyramid = Self::new(sample_rate, base_block);
+        pyramid.push_samples(samples);
+        pyramid.finish();
+        pyramid
+    }
+
+    /// Fold raw samples into the pyramid
+    pub fn push_samples(&mut self, samples: &[f32]) {
+        for &sample in samples {
+            let peak = Peak { min: sample, max: sample };
+            self.pending = Some(match self.pending {
+                Some(pending) => pending.merge(peak),
+                None => peak,
+            });
+            self.pending_samples += 1;
+
+            if self.pending_samples == self.base_block {
+                if let Some(peak) = self.pending.take() {
+                    self.push_peak(peak);
+                }
+                self.pending_samples = 0;
+            }
+        }
+    }
+
+    /// Append a completed level-0 peak covering `base_block` samples
+    pub fn push_peak(&mut self, peak: Peak) {
+        self.total_samples += self.base_block as u64;
+        self.push_at_level(0, peak);
+    }
+
+    /// Drain `[min, max]` pairs written by a `PeakAccumulator` into the pyramid
+    pub fn drain_tap(&mut self, tap: &AudioRingBuffer) -> AudioResult<usize> {
+        let mut pairs = [0.0f32; 2 * 256];
+        let mut drained = 0;
+
+        loop {
+            // Only read whole pairs so min/max never get out of step
+            let whole_pairs = tap.available() / 2 * 2;
+            if whole_pairs == 0 {
+                break;
+            }
+
+            let to_read = whole_pairs.min(pairs.len());
+            let read = tap.read(&mut pairs[..to_read])?;
+            for pair in pairs[..read].chunks_exact(2) {
+                self.push_peak(Peak { min: pair[0], max: pair[1] });
+                drained += 1;
+            }
+
+            if read < to_read {
+                break;
+            }
+        }
+
+        Ok(drained)
+    }
+
+    /// Flush the trailing partial block and any unpaired peaks up the levels
+    pub fn finish(&mut self) {
+        if let Some(peak) = self.pending.take() {
+            self.total_samples += self.pending_samples as u64;
+            self.push_at_level(0, peak);
+            self.pending_samples = 0;
+        }
+
+        // Carry odd trailing peaks up so the coarsest level covers everything
+        let mut level = 0;
+        while level < self.levels.len() {
+            let len = self.levels[level].len();
+            if len > 1 && len % 2 == 1 {
+                let last = self.levels[level][len - 1];
+                self.push_at_level(level + 1, last);
+            }
+            level += 1;
+        }
+    }
+
+    fn push_at_level(&mut self, level: usize, peak: Peak) {
+        if self.levels.len() <= level {
+            self.levels.push(Vec::new());
+        }
+
+        self.levels[level].push(peak);
+
+        let len = self.levels[level].len();
+        if len.is_multiple_of(2) {
+            let merged = self.levels[level][len - 2].merge(self.levels[level][len - 1]);
+            self.push_at_level(level + 1, merged);
+        }
+    }
+
+    /// Sample rate of the summarised audio
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Total samples summarised so far
+    pub fn total_samples(&self) -> u64 {
+        self.total_samples
+    }
+
+    /// Duration covered by the pyramid in milliseconds
+    pub fn duration_ms(&self) -> f64 {
+        self.total_samples as f64 / self.sample_rate.max(1) as f64 * 1000.0
+    }
+
+    /// Number of zoom levels (0 is the finest)
+    pub fn level_count(&self) -> usize {
+        self.levels.len()
+    }
+
+    /// Samples summarised by one peak at `level`
+    pub fn samples_per_peak(&self, level: usize) -> usize {
+        self.base_block << level
+    }
+
+    /// Finest level that draws `start_ms..end_ms` in at most `max_points` peaks
+    pub fn level_for_width(&self, start_ms: f64, end_ms: f64, max_points: usize) -> usize {
+        let range_samples = ((end_ms - start_ms).max(0.0) / 1000.0 * self.sample_rate as f64) as usize;
+        let max_points = max_points.max(1);
+
+        (0..self.levels.len())
+            .find(|&level| range_samples.div_ceil(self.samples_per_peak(level)) <= max_points)
+            .unwrap_or(self.levels.len() - 1)
+    }
+
+    /// Peaks covering `start_ms..end_ms` at the given zoom level
+    pub fn peaks(&self, start_ms: f64, end_ms: f64, level: usize) -> PeakSlice {
+        let level = level.min(self.levels.len() - 1);
+        let samples_per_peak = self.samples_per_peak(level);
+        let ms_per_peak = samples_per_peak as f64 / self.sample_rate.max(1) as f64 * 1000.0;
+        let peaks = &self.levels[level];
+
+        let first = ((start_ms.max(0.0) / ms_per_peak).floor() as usize).min(peaks.len());
+        let last = ((end_ms.max(0.0) / ms_per_peak).ceil() as usize).clamp(first, peaks.len());
+
+        PeakSlice {
+            level,
+            samples_per_peak,
+            sample_rate: self.sample_rate,
+            start_ms: first as f64 * ms_per_peak,
+            peaks: peaks[first..last].to_vec(),
+        }
+    }
+
+    /// Path of the peak sidecar for a recording
+    pub fn sidecar_path(audio_path: &Path) -> PathBuf {
+        audio_path.with_extension(PEAK_FILE_EXTENSION)
+    }
+
+    /// Write the pyramid to `path`
+    pub fn save(&self, path: &Path) -> AudioResult<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+
+        writer.write_all(PEAK_FILE_MAGIC)?;
+        writer.write_all(&PEAK_FILE_VERSION.to_le_bytes())?;
+        writer.write_all(&self.sample_rate.to_le_bytes())?;
+        writer.write_all(&(self.base_block as u32).to_le_bytes())?;
+        writer.write_all(&self.total_samples.to_le_bytes())?;
+        writer.write_all(&(self.levels.len() as u32).to_le_bytes())?;
+
+        for level in &self.levels {
+            writer.write_all(&(level.len() as u64).to_le_bytes())?;
+            for peak in level {
+                writer.write_all(&peak.min.to_le_bytes())?;
+                writer.write_all(&peak.max.to_le_bytes())?;
+            }
+        }
+
+        writer.flush()?;
+        info!("Saved peak overview with {} levels to {}", self.levels.len(), path.display());
+        Ok(())
+    }
+
+    /// Read a pyramid previously written with `save`
+    pub fn load(path: &Path) -> AudioResult<Self> {
+        let file = File::open(path)?;
+        // Lengths in the file are checked against what it can hold before
+        // anything is allocated for them
+        let mut remaining = file.metadata()?.len().saturating_sub(PEAK_FILE_HEADER_BYTES);
+        let truncated = || AudioError::UnsupportedFormat {
+            details: format!("Peak overview {} is truncated", path.display()),
+        };
+        let mut reader = BufReader::new(file);
+
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != PEAK_FILE_MAGIC {
+            return Err(AudioError::UnsupportedFormat {
+                details: format!("{} is not a peak overview file", path.display()),
+            });
+        }
+
+        let version = read_u32(&mut reader)?;
+        if version != PEAK_FILE_VERSION {
+            return Err(AudioError::UnsupportedFormat {
+                details: format!("Unsupported peak overview version {}", version),
+            });
+        }
+
+        let sample_rate = read_u32(&mut reader)?;
+        let base_block = (read_u32(&mut reader)? as usize).max(1);
+        let total_samples = read_u64(&mut reader)?;
+        let level_count = read_u32(&mut reader)? as u64;
+        if level_count > remaining / PEAK_RECORD_BYTES {
+            return Err(truncated());
+        }
+        // Every level's block length has to fit in a usize
+        if level_count > (usize::BITS - base_block.ilog2()) as u64 {
+            return Err(AudioError::UnsupportedFormat {
+                details: format!("Peak overview {} has too many levels ({})", path.display(), level_count),
+            });
+        }
+
+        let mut levels = Vec::with_capacity(level_count as usize);
+        for _ in 0..level_count {
+            remaining -= PEAK_RECORD_BYTES;
+            let len = read_u64(&mut reader)?;
+            if len > remaining / PEAK_RECORD_BYTES {
+                return Err(truncated());
+            }
+            remaining -= len * PEAK_RECORD_BYTES;
+            let mut level = Vec::with_capacity(len as usize);
+            for _ in 0..len {
+                let min = f32::from_le_bytes(read_array(&mut reader)?);
+                let max = f32::from_le_bytes(read_array(&mut reader)?);
+                level.push(Peak { min, max });
+            }
+            levels.push(level);
+        }
+
+        if levels.is_empty() {
+            levels.push(Vec::new());
+        }
+
+        debug!("Loaded peak overview from {}", path.display());
+        Ok(Self {
+            sample_rate,
+            base_block,
+            total_samples,
+            levels,
+            pending: None,
+            pending_samples: 0,
+        })
+    }
+}
+
+fn read_array<const N: usize>(reader: &mut impl Read) -> AudioResult<[u8; N]> {
+    let mut bytes = [0u8; N];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn read_u32(reader: &mut impl Read) -> AudioResult<u32> {
+    Ok(u32::from_le_bytes(read_array(reader)?))
+}
+
+fn read_u64(reader: &mut impl Read) -> AudioResult<u64> {
+    Ok(u64::from_le_bytes(read_array(reader)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ramp(len: usize) -> Vec<f32> {
+        (0..len).map(|i| (i as f32 / len as f32) * 2.0 - 1.0).collect()
+    }
+
+    #[test]
+    fn test_pyramid_levels_halve_resolution() {
+        let pyramid = PeakPyramid::from_samples(&ramp(16 * 8), 16000, 16);
+
+        assert_eq!(pyramid.level_count(), 4);
+        assert_eq!(pyramid.peaks(0.0, f64::MAX, 0).peaks.len(), 8);
+        assert_eq!(pyramid.peaks(0.0, f64::MAX, 1).peaks.len(), 4);
+        assert_eq!(pyramid.peaks(0.0, f64::MAX, 3).peaks.len(), 1);
+
+        let top = pyramid.peaks(0.0, f64::MAX, 3).peaks[0];
+        assert_eq!(top.min, -1.0);
+        assert!(top.max > 0.98);
+    }
+
+    #[test]
+    fn test_peaks_for_time_range() {
+        // 1 second at 1kHz with 10-sample blocks => 100 level-0 peaks of 10ms
+        let pyramid = PeakPyramid::from_samples(&ramp(1000), 1000, 10);
+
+        let slice = pyramid.peaks(250.0, 500.0, 0);
+        assert_eq!(slice.peaks.len(), 25);
+        assert_eq!(slice.start_ms, 250.0);
+
+        let level = pyramid.level_for_width(0.0, 1000.0, 30);
+        assert!(pyramid.peaks(0.0, 1000.0, level).peaks.len() <= 30);
+        assert!(pyramid.peaks(0.0, 1000.0, level - 1).peaks.len() > 30);
+    }
+
+    #[test]
+    fn test_accumulator_tap_matches_direct_build() {
+        let samples = ramp(4096);
+        let tap = AudioRingBuffer::new(1024, 16000, 2);
+        let mut accumulator = PeakAccumulator::new(64);
+
+        let mut pyramid = PeakPyramid::new(16000, 64);
+        for chunk in samples.chunks(100) {
+            accumulator.push(chunk, &tap);
+            pyramid.drain_tap(&tap).unwrap();
+        }
+        pyramid.finish();
+
+        let direct = PeakPyramid::from_samples(&samples, 16000, 64);
+        assert_eq!(pyramid.level_count(), direct.level_count());
+        assert_eq!(pyramid.peaks(0.0, f64::MAX, 0).peaks, direct.peaks(0.0, f64::MAX, 0).peaks);
+    }
+
+    #[test]
+    fn test_save_and_load_round_trip() {
+        let pyramid = PeakPyramid::from_samples(&ramp(5000), 16000, 32);
+        let audio_path = std::env::temp_dir()
+            .join(format!("peaks-test-{}.wav", uuid::Uuid::new_v4()));
+        let path = PeakPyramid::sidecar_path(&audio_path);
+
+        pyramid.save(&path).unwrap();
+        let loaded = PeakPyramid::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(path.extension().unwrap(), PEAK_FILE_EXTENSION);
+        assert_eq!(loaded.total_samples(), pyramid.total_samples());
+        assert_eq!(loaded.level_count(), pyramid.level_count());
+        assert_eq!(loaded.peaks(0.0, f64::MAX, 2).peaks, pyramid.peaks(0.0, f64::MAX, 2).peaks);
+    }
+
+    #[test]
+    fn test_load_rejects_lengths_the_file_cannot_hold() {
+        let pyramid = PeakPyramid::from_samples(&ramp(5000), 16000, 32);
+        let path = std::env::temp_dir().join(format!("peaks-test-{}.peaks", uuid::Uuid::new_v4()));
+        pyramid.save(&path).unwrap();
+
+        // Claim an enormous first level
+        let mut bytes = std::fs::read(&path).unwrap();
+        let at = PEAK_FILE_HEADER_BYTES as usize;
+        bytes[at..at + 8].copy_from_slice(&u64::MAX.to_le_bytes());
+        std::fs::write(&path, &bytes).unwrap();
+        let result = PeakPyramid::load(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(matches!(result, Err(AudioError::UnsupportedFormat { .. })), "{:?}", result.err());
+    }
+
+    #[test]
+    fn test_load_rejects_more_levels_than_block_lengths_can_hold() {
+        let pyramid = PeakPyramid::from_samples(&ramp(5000), 16000, 32);
+        let path = std::env::temp_dir().join(format!("peaks-test-{}.peaks", uuid::Uuid::new_v4()));
+        pyramid.save(&path).unwrap();
+
+        // Claim 100 levels and make them all empty, so the file holds them
+        let levels = 100u32;
+        let mut bytes = std::fs::read(&path).unwrap()[..PEAK_FILE_HEADER_BYTES as usize].to_vec();
+        let at = PEAK_FILE_HEADER_BYTES as usize - 4;
+        bytes[at..].copy_from_slice(&levels.to_le_bytes());
+        bytes.extend(std::iter::repeat_n(0u8, levels as usize * 8));
+        std::fs::write(&path, &bytes).unwrap();
+        let result = PeakPyramid::load(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(matches!(result, Err(AudioError::UnsupportedFormat { .. })), "{:?}", result.err());
+    }
+}
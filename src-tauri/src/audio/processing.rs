@@ -1,22 +1,33 @@
 //! Audio processing pipeline and quality validation
 
-use std::sync::Arc;
-use std::collections::VecDeque;
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
 use tracing::{debug, info, warn};
 
 use super::types::{
     AudioBuffer, AudioError, AudioResult, AudioProcessor, AudioStats, 
     AudioLevelMonitor, AudioFormat
 };
+use super::analysis::{AudioAnalysis, FeatureExtractor, FeatureHistory, FeatureSummary};
+
+/// Window covered by `analyze_recent_audio`
+const RECENT_ANALYSIS_WINDOW: Duration = Duration::from_secs(10);
+
+/// A processor in the pipeline and whether it may be skipped under load
+struct PipelineStage {
+    processor: Box<dyn AudioProcessor>,
+    optional: bool,
+    enabled: bool,
+}
 
 /// Audio processor for real-time audio processing and quality monitoring
 pub struct AudioProcessingPipeline {
-    processors: Vec<Box<dyn AudioProcessor>>,
+    processors: Vec<PipelineStage>,
     level_monitor: AudioLevelMonitor,
     quality_validator: AudioQualityValidator,
     stats: AudioStats,
-    buffer_history: VecDeque<AudioBuffer>,
-    max_history_size: usize,
+    feature_extractor: FeatureExtractor,
+    history: FeatureHistory,
 }
 
 impl AudioProcessingPipeline {
@@ -27,15 +38,67 @@ impl AudioProcessingPipeline {
             level_monitor: AudioLevelMonitor::new(),
             quality_validator: AudioQualityValidator::new(),
             stats: AudioStats::default(),
-            buffer_history: VecDeque::new(),
-            max_history_size: 100, // Keep last 100 buffers for analysis
+            feature_extractor: FeatureExtractor::new(),
+            history: FeatureHistory::new(),
         }
     }
     
+    /// Create the pipeline run behind live capture
+    ///
+    /// Noise gating and gain tracking are optional and are the first things
+    /// dropped when processing falls behind.
+    pub fn for_capture(validation: ValidationConfig) -> Self {
+        let mut pipeline = Self::new();
+        pipeline.set_validation_config(validation);
+        pipeline.add_optional_processor(Box::new(NoiseGateProcessor::new(0.01)));
+        pipeline.add_optional_processor(Box::new(AutomaticGainControl::new(0.3)));
+        pipeline
+    }
+    
     /// Add a processor to the pipeline
     pub fn add_processor(&mut self, processor: Box<dyn AudioProcessor>) {
         info!("Adding audio processor to pipeline");
-        self.processors.push(processor);
+        self.processors.push(PipelineStage { processor, optional: false, enabled: true });
+    }
+    
+    /// Add a processor that can be disabled when processing falls behind
+    pub fn add_optional_processor(&mut self, processor: Box<dyn AudioProcessor>) {
+        info!("Adding optional audio processor to pipeline");
+        self.processors.push(PipelineStage { processor, optional: true, enabled: true });
+    }
+    
+    /// Disable the last-added optional stage that is still running
+    ///
+    /// Returns false when there is nothing left to disable.
+    pub fn disable_optional_stage(&mut self) -> bool {
+        match self.processors.iter_mut().rev().find(|stage| stage.optional && stage.enabled) {
+            Some(stage) => {
+                stage.enabled = false;
+                true
+            }
+            None => false,
+        }
+    }
+    
+    /// Re-enable the most recently disabled optional stage
+    pub fn enable_optional_stage(&mut self) -> bool {
+        match self.processors.iter_mut().find(|stage| stage.optional && !stage.enabled) {
+            Some(stage) => {
+                stage.enabled = true;
+                true
+            }
+            None => false,
+        }
+    }
+    
+    /// Number of optional stages currently disabled
+    pub fn disabled_stages(&self) -> usize {
+        self.processors.iter().filter(|stage| !stage.enabled).count()
+    }
+    
+    /// Replace the thresholds used to validate buffers
+    pub fn set_validation_config(&mut self, config: ValidationConfig) {
+        self.quality_validator.set_config(config);
     }
     
     /// Process an audio buffer through the pipeline
@@ -50,15 +113,13 @@ impl AudioProcessingPipeline {
         }
         
         // Process through all processors
-        for processor in &mut self.processors {
-            processor.process(&buffer)?;
+        for stage in self.processors.iter_mut().filter(|stage| stage.enabled) {
+            stage.processor.process(&buffer)?;
         }
         
-        // Store in history for analysis
-        self.buffer_history.push_back(buffer.clone());
-        if self.buffer_history.len() > self.max_history_size {
-            self.buffer_history.pop_front();
-        }
+        // Keep a feature summary of the buffer for analysis
+        let features = self.feature_extractor.extract(&buffer);
+        self.history.push(features);
         
         // Update statistics
         self.update_stats(&buffer);
@@ -99,37 +160,47 @@ impl AudioProcessingPipeline {
         debug!("Reset audio processing statistics");
     }
     
-    /// Get recent audio history for analysis
-    pub fn get_recent_history(&self, count: usize) -> Vec<AudioBuffer> {
-        let start_idx = if self.buffer_history.len() > count {
-            self.buffer_history.len() - count
-        } else {
-            0
-        };
-        
-        self.buffer_history.range(start_idx..).cloned().collect()
+    /// Get the rolling feature history
+    pub fn history(&self) -> &FeatureHistory {
+        &self.history
+    }
+    
+    /// Analyze the last `window` of processed audio
+    pub fn analyze_window(&self, window: Duration) -> AudioAnalysis {
+        self.history.analyze_last(window.as_secs_f64() * 1000.0)
+    }
+    
+    /// Analyze everything processed this session
+    pub fn analyze_session(&self) -> AudioAnalysis {
+        self.history.analyze_session()
     }
     
     /// Analyze recent audio for patterns or issues
     pub fn analyze_recent_audio(&self) -> AudioAnalysis {
-        let recent_buffers = self.get_recent_history(10);
-        AudioAnalyzer::analyze(&recent_buffers)
+        self.analyze_window(RECENT_ANALYSIS_WINDOW)
     }
 }
 
-/// Audio quality validator
-pub struct AudioQualityValidator {
-    min_sample_rate: u32,
-    max_sample_rate: u32,
-    min_buffer_duration_ms: f64,
-    max_buffer_duration_ms: f64,
-    silence_threshold: f32,
-    clipping_threshold: f32,
+/// Thresholds used by `AudioQualityValidator`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ValidationConfig {
+    pub min_sample_rate: u32,
+    pub max_sample_rate: u32,
+    pub min_buffer_duration_ms: f64,
+    pub max_buffer_duration_ms: f64,
+    /// RMS below this is considered silence
+    pub silence_threshold: f32,
+    /// Absolute sample values at or above this are considered clipped
+    pub clipping_threshold: f32,
+    /// Continuous silence needed before it is reported
+    pub silence_alert_ms: f64,
+    /// Minimum time between two events of the same kind
+    pub event_interval_ms: u64,
 }
 
-impl AudioQualityValidator {
-    /// Create a new audio quality validator with default parameters
-    pub fn new() -> Self {
+impl Default for ValidationConfig {
+    fn default() -> Self {
         Self {
             min_sample_rate: 8000,   // Minimum for speech
             max_sample_rate: 192000, // Maximum reasonable
@@ -137,58 +208,161 @@ impl AudioQualityValidator {
             max_buffer_duration_ms: 1000.0, // 1s maximum
             silence_threshold: 0.001, // Below this is considered silence
             clipping_threshold: 0.95, // Above this is considered clipping
+            silence_alert_ms: 10_000.0,
+            event_interval_ms: 2000,
+        }
+    }
+}
+
+impl ValidationConfig {
+    /// Check the thresholds make sense together
+    pub fn validate(&self) -> AudioResult<()> {
+        let problem = if self.min_sample_rate > self.max_sample_rate {
+            Some("minimum sample rate is above the maximum")
+        } else if self.min_buffer_duration_ms > self.max_buffer_duration_ms {
+            Some("minimum buffer duration is above the maximum")
+        } else if !(0.0..=1.0).contains(&self.silence_threshold) {
+            Some("silence threshold must be between 0 and 1")
+        } else if !(0.0..=1.0).contains(&self.clipping_threshold) || self.clipping_threshold == 0.0 {
+            Some("clipping threshold must be above 0 and at most 1")
+        } else {
+            None
+        };
+
+        match problem {
+            Some(problem) => Err(AudioError::UnsupportedFormat {
+                details: format!("Invalid validation thresholds: {}", problem),
+            }),
+            None => Ok(()),
         }
     }
+}
+
+/// What the validator found in one block of samples
+///
+/// Plain data, so it can be produced on the real-time thread.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct BufferFindings {
+    pub duration_ms: f64,
+    pub peak: f32,
+    pub rms: f32,
+    pub clipped_samples: usize,
+    pub non_finite_samples: usize,
+    pub first_non_finite: Option<usize>,
+    pub sample_rate_out_of_range: bool,
+    pub too_short: bool,
+    pub too_long: bool,
+    pub silent: bool,
+}
+
+/// Audio quality validator
+#[derive(Debug, Clone, Default)]
+pub struct AudioQualityValidator {
+    config: ValidationConfig,
+}
+
+impl AudioQualityValidator {
+    /// Create a new audio quality validator with default parameters
+    pub fn new() -> Self {
+        Self::with_config(ValidationConfig::default())
+    }
+    
+    /// Create a validator with custom thresholds
+    pub fn with_config(config: ValidationConfig) -> Self {
+        Self { config }
+    }
+    
+    /// Current thresholds
+    pub fn config(&self) -> &ValidationConfig {
+        &self.config
+    }
+    
+    /// Replace all thresholds
+    pub fn set_config(&mut self, config: ValidationConfig) {
+        self.config = config;
+    }
+    
+    /// Inspect interleaved samples without allocating
+    pub fn inspect(&self, samples: &[f32], sample_rate: u32, channels: u16) -> BufferFindings {
+        let config = &self.config;
+        let mut findings = BufferFindings {
+            duration_ms: samples.len() as f64 / (sample_rate.max(1) as f64 * channels.max(1) as f64) * 1000.0,
+            sample_rate_out_of_range: sample_rate < config.min_sample_rate || sample_rate > config.max_sample_rate,
+            ..BufferFindings::default()
+        };
+        findings.too_short = findings.duration_ms < config.min_buffer_duration_ms;
+        findings.too_long = findings.duration_ms > config.max_buffer_duration_ms;
+        
+        let mut sum_squares = 0.0f64;
+        for (i, &sample) in samples.iter().enumerate() {
+            if !sample.is_finite() {
+                findings.non_finite_samples += 1;
+                findings.first_non_finite.get_or_insert(i);
+                continue;
+            }
+            let magnitude = sample.abs();
+            findings.peak = findings.peak.max(magnitude);
+            if magnitude >= config.clipping_threshold {
+                findings.clipped_samples += 1;
+            }
+            sum_squares += (sample * sample) as f64;
+        }
+        
+        let finite = samples.len() - findings.non_finite_samples;
+        if finite > 0 {
+            findings.rms = (sum_squares / finite as f64).sqrt() as f32;
+        }
+        findings.silent = finite > 0 && findings.rms < config.silence_threshold;
+        
+        findings
+    }
     
     /// Validate an audio buffer for quality issues
+    ///
+    /// Unusable audio (sample rate out of range, NaN or infinite samples) is
+    /// an error; everything else is only logged.
     pub fn validate(&self, buffer: &AudioBuffer) -> AudioResult<()> {
+        let findings = self.inspect(&buffer.samples, buffer.sample_rate, buffer.channels);
+        
         // Check sample rate
-        if buffer.sample_rate < self.min_sample_rate {
+        if buffer.sample_rate < self.config.min_sample_rate {
             return Err(AudioError::UnsupportedFormat {
                 details: format!("Sample rate {} too low (minimum: {})", 
-                    buffer.sample_rate, self.min_sample_rate)
+                    buffer.sample_rate, self.config.min_sample_rate)
             });
         }
         
-        if buffer.sample_rate > self.max_sample_rate {
+        if buffer.sample_rate > self.config.max_sample_rate {
             return Err(AudioError::UnsupportedFormat {
                 details: format!("Sample rate {} too high (maximum: {})", 
-                    buffer.sample_rate, self.max_sample_rate)
+                    buffer.sample_rate, self.config.max_sample_rate)
             });
         }
         
         // Check buffer duration
-        let duration = buffer.duration_ms();
-        if duration < self.min_buffer_duration_ms {
-            warn!("Buffer duration too short: {:.2}ms", duration);
+        if findings.too_short {
+            warn!("Buffer duration too short: {:.2}ms", findings.duration_ms);
         }
         
-        if duration > self.max_buffer_duration_ms {
-            warn!("Buffer duration too long: {:.2}ms", duration);
+        if findings.too_long {
+            warn!("Buffer duration too long: {:.2}ms", findings.duration_ms);
         }
         
         // Check for silence (not necessarily an error, but worth noting)
-        if buffer.rms_level() < self.silence_threshold {
+        if findings.silent {
             debug!("Detected silence in audio buffer");
         }
         
         // Check for clipping
-        let max_sample = buffer.samples.iter()
-            .map(|&s| s.abs())
-            .max_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal))
-            .unwrap_or(0.0);
-        
-        if max_sample >= self.clipping_threshold {
-            warn!("Potential clipping detected: peak level {:.3}", max_sample);
+        if findings.clipped_samples > 0 {
+            warn!("Potential clipping detected: peak level {:.3}", findings.peak);
         }
         
         // Check for NaN or infinite values
-        for (i, &sample) in buffer.samples.iter().enumerate() {
-            if !sample.is_finite() {
-                return Err(AudioError::Internal {
-                    message: format!("Invalid sample at index {}: {}", i, sample)
-                });
-            }
+        if let Some(i) = findings.first_non_finite {
+            return Err(AudioError::Internal {
+                message: format!("Invalid sample at index {}: {}", i, buffer.samples[i])
+            });
         }
         
         Ok(())
@@ -196,14 +370,20 @@ impl AudioQualityValidator {
     
     /// Set custom quality parameters
     pub fn set_sample_rate_range(&mut self, min: u32, max: u32) {
-        self.min_sample_rate = min;
-        self.max_sample_rate = max;
+        self.config.min_sample_rate = min;
+        self.config.max_sample_rate = max;
     }
     
     /// Set buffer duration range
     pub fn set_duration_range(&mut self, min_ms: f64, max_ms: f64) {
-        self.min_buffer_duration_ms = min_ms;
-        self.max_buffer_duration_ms = max_ms;
+        self.config.min_buffer_duration_ms = min_ms;
+        self.config.max_buffer_duration_ms = max_ms;
+    }
+    
+    /// Set the silence and clipping levels
+    pub fn set_level_thresholds(&mut self, silence: f32, clipping: f32) {
+        self.config.silence_threshold = silence;
+        self.config.clipping_threshold = clipping;
     }
 }
 
@@ -341,93 +521,28 @@ impl AudioProcessor for AudioFormatConverter {
     }
 }
 
-/// Audio analysis results
-#[derive(Debug, Clone)]
-pub struct AudioAnalysis {
-    pub average_level: f32,
-    pub peak_level: f32,
-    pub dynamic_range: f32,
-    pub silence_percentage: f32,
-    pub clipping_percentage: f32,
-    pub estimated_snr: f32, // Signal-to-noise ratio estimate
-}
-
 /// Audio analyzer for pattern detection and quality analysis
 pub struct AudioAnalyzer;
 
 impl AudioAnalyzer {
     /// Analyze a collection of audio buffers
     pub fn analyze(buffers: &[AudioBuffer]) -> AudioAnalysis {
-        if buffers.is_empty() {
-            return AudioAnalysis {
-                average_level: 0.0,
-                peak_level: 0.0,
-                dynamic_range: 0.0,
-                silence_percentage: 100.0,
-                clipping_percentage: 0.0,
-                estimated_snr: -100.0,
-            };
-        }
-        
-        let mut total_samples = 0;
-        let mut level_sum = 0.0;
-        let mut peak_level = 0.0;
-        let mut silent_samples = 0;
-        let mut clipped_samples = 0;
-        
-        let silence_threshold = 0.001;
-        let clipping_threshold = 0.95;
+        let mut extractor = FeatureExtractor::new();
+        let mut summary = FeatureSummary::default();
         
         for buffer in buffers {
-            total_samples += buffer.samples.len();
-            level_sum += buffer.rms_level();
-            peak_level = peak_level.max(buffer.samples.iter()
-                .map(|&s| s.abs())
-                .fold(0.0f32, f32::max));
-            
-            for &sample in &buffer.samples {
-                let abs_sample = sample.abs();
-                if abs_sample < silence_threshold {
-                    silent_samples += 1;
-                }
-                if abs_sample >= clipping_threshold {
-                    clipped_samples += 1;
-                }
-            }
+            summary.add_frame(&extractor.extract(buffer));
         }
         
-        let average_level = level_sum / buffers.len() as f32;
-        let silence_percentage = (silent_samples as f32 / total_samples as f32) * 100.0;
-        let clipping_percentage = (clipped_samples as f32 / total_samples as f32) * 100.0;
-        
-        // Simple dynamic range calculation
-        let dynamic_range = if average_level > 0.0 {
-            20.0 * (peak_level / average_level).log10()
-        } else {
-            0.0
-        };
-        
-        // Estimate SNR (very basic)
-        let estimated_snr = if average_level > silence_threshold {
-            20.0 * (average_level / silence_threshold).log10()
-        } else {
-            -100.0
-        };
-        
-        AudioAnalysis {
-            average_level,
-            peak_level,
-            dynamic_range,
-            silence_percentage,
-            clipping_percentage,
-            estimated_snr,
-        }
+        summary.analysis()
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
 
     #[test]
     fn test_audio_processing_pipeline_creation() {
@@ -436,6 +551,44 @@ mod tests {
         assert_eq!(pipeline.peak_level(), 0.0);
     }
     
+    /// Counts the buffers it sees
+    struct CountingProcessor(Arc<AtomicUsize>);
+    
+    impl AudioProcessor for CountingProcessor {
+        fn process(&mut self, _buffer: &AudioBuffer) -> AudioResult<()> {
+            self.0.fetch_add(1, Ordering::Relaxed);
+            Ok(())
+        }
+        
+        fn stats(&self) -> AudioStats {
+            AudioStats::default()
+        }
+    }
+    
+    #[test]
+    fn test_optional_stages_degrade_in_reverse_order() {
+        let counts: Vec<_> = (0..3).map(|_| Arc::new(AtomicUsize::new(0))).collect();
+        let mut pipeline = AudioProcessingPipeline::new();
+        pipeline.add_processor(Box::new(CountingProcessor(Arc::clone(&counts[0]))));
+        pipeline.add_optional_processor(Box::new(CountingProcessor(Arc::clone(&counts[1]))));
+        pipeline.add_optional_processor(Box::new(CountingProcessor(Arc::clone(&counts[2]))));
+        let buffer = AudioBuffer::new(vec![0.1; 320], 16000, 1);
+        
+        assert!(pipeline.disable_optional_stage());
+        pipeline.process(buffer.clone()).unwrap();
+        assert!(pipeline.disable_optional_stage());
+        assert!(!pipeline.disable_optional_stage(), "required stages are never disabled");
+        pipeline.process(buffer.clone()).unwrap();
+        assert_eq!(pipeline.disabled_stages(), 2);
+        
+        // Stages come back in the order they were added
+        assert!(pipeline.enable_optional_stage());
+        pipeline.process(buffer).unwrap();
+        
+        let seen: Vec<usize> = counts.iter().map(|c| c.load(Ordering::Relaxed)).collect();
+        assert_eq!(seen, vec![3, 2, 0]);
+    }
+    
     #[test]
     fn test_audio_quality_validator() {
         let validator = AudioQualityValidator::new();
@@ -449,6 +602,30 @@ mod tests {
         assert!(validator.validate(&invalid_buffer).is_err());
     }
     
+    #[test]
+    fn test_validator_uses_configured_thresholds() {
+        let config = ValidationConfig {
+            min_sample_rate: 16000,
+            clipping_threshold: 0.5,
+            silence_threshold: 0.01,
+            ..Default::default()
+        };
+        let validator = AudioQualityValidator::with_config(config);
+        
+        let buffer = AudioBuffer::new(vec![0.6, -0.7, 0.1, 0.2], 8000, 1);
+        assert!(validator.validate(&buffer).is_err());
+        
+        let findings = validator.inspect(&[0.6, -0.7, 0.1, f32::NAN], 16000, 1);
+        assert_eq!(findings.clipped_samples, 2);
+        assert_eq!(findings.non_finite_samples, 1);
+        assert_eq!(findings.first_non_finite, Some(3));
+        assert!(!findings.silent);
+        assert!(validator.inspect(&[0.005; 160], 16000, 1).silent);
+        
+        assert!(ValidationConfig { clipping_threshold: 1.5, ..config }.validate().is_err());
+        assert!(config.validate().is_ok());
+    }
+    
     #[test]
     fn test_noise_gate_processor() {
         let mut processor = NoiseGateProcessor::new(0.1);
@@ -482,6 +659,23 @@ mod tests {
         assert_eq!(analysis.clipping_percentage, 0.0);
     }
     
+    #[test]
+    fn test_pipeline_keeps_features_not_buffers() {
+        let mut pipeline = AudioProcessingPipeline::new();
+        
+        for _ in 0..1000 {
+            pipeline.process(AudioBuffer::new(vec![0.25; 320], 16000, 1)).unwrap();
+        }
+        
+        // 20s processed; the 10s window only sees its half
+        let recent = pipeline.analyze_recent_audio();
+        let session = pipeline.analyze_session();
+        assert!((recent.duration_ms - 10_000.0).abs() < 1.0);
+        assert!((session.duration_ms - 20_000.0).abs() < 1.0);
+        assert!((session.average_level - 0.25).abs() < 1e-4);
+        assert_eq!(pipeline.history().frame_count(), 1000);
+    }
+    
     #[test]
     fn test_audio_analyzer_empty() {
         let buffers = vec![];
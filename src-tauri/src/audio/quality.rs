@@ -0,0 +1,632 @@
+//! Session-level audio quality tracking
+//!
+//! `QualityTracker` follows the signal frame by frame: a minimum-statistics
+//! noise floor, the level of frames that stand clear of it (speech), clipped
+//! samples, digital dropouts and EBU R128 style gated loudness. All state is
+//! fixed-size, so it can be fed from the capture callback and summarised into
+//! an `AudioQualityReport` for the whole session at any point.
+
+use std::f64::consts::PI;
+use std::fmt;
+use serde::{Deserialize, Serialize};
+
+use super::types::AudioStats;
+
+/// Length of one analysis frame
+const FRAME_MS: f64 = 20.0;
+
+/// A frame this far above the noise floor counts as speech
+const SPEECH_MARGIN_DB: f64 = 9.0;
+
+/// Frames quieter than this are never treated as speech
+const SPEECH_MIN_DBFS: f64 = -60.0;
+
+/// How fast the noise floor may rise while the signal stays above it
+const NOISE_FLOOR_RISE_DB_PER_SEC: f64 = 0.5;
+
+/// Time constant of the recent levels used for live warnings
+const RECENT_TIME_CONSTANT_MS: f64 = 5000.0;
+
+/// Audio analysed before live warnings are raised
+const LIVE_WARMUP_MS: f64 = 3000.0;
+
+/// Sample magnitude treated as clipped
+const CLIP_LEVEL: f32 = 0.99;
+
+/// Exact-zero run that counts as a dropout
+const DROPOUT_MIN_MS: f64 = 5.0;
+
+/// Floor for power values so silence maps to a finite level
+const MIN_POWER: f64 = 1e-10;
+
+/// Loudness histogram range and resolution
+const LOUDNESS_MIN_LUFS: f64 = -70.0;
+const LOUDNESS_MAX_LUFS: f64 = 5.0;
+const LOUDNESS_BIN_LU: f64 = 0.1;
+
+/// Thresholds below which recording quality is reported as poor
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QualityThresholds {
+    /// Minimum acceptable speech-to-noise ratio
+    pub min_snr_db: f32,
+    /// Maximum acceptable fraction of clipped samples
+    pub max_clipping_ratio: f32,
+    /// Maximum acceptable number of dropouts
+    pub max_dropouts: u32,
+    /// Integrated loudness below which the recording is too quiet
+    pub min_loudness_lufs: f32,
+}
+
+impl Default for QualityThresholds {
+    fn default() -> Self {
+        Self {
+            min_snr_db: 15.0,
+            max_clipping_ratio: 0.001,
+            max_dropouts: 0,
+            min_loudness_lufs: -40.0,
+        }
+    }
+}
+
+/// A quality problem found in a recording
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum QualityWarning {
+    LowSnr { snr_db: f32, threshold_db: f32 },
+    Clipping { ratio: f32, threshold: f32 },
+    Dropouts { count: u32 },
+    BufferOverruns { count: u64 },
+    TooQuiet { loudness_lufs: f32, threshold_lufs: f32 },
+}
+
+impl QualityWarning {
+    /// Whether two warnings are about the same problem, ignoring values
+    pub fn same_kind(&self, other: &QualityWarning) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(other)
+    }
+}
+
+impl fmt::Display for QualityWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::LowSnr { snr_db, threshold_db } => {
+                write!(f, "Low signal-to-noise ratio: {:.1} dB (minimum {:.1} dB)", snr_db, threshold_db)
+            }
+            Self::Clipping { ratio, threshold } => {
+                write!(f, "Clipping on {:.2}% of samples (maximum {:.2}%)", ratio * 100.0, threshold * 100.0)
+            }
+            Self::Dropouts { count } => write!(f, "{} audio dropouts", count),
+            Self::BufferOverruns { count } => write!(f, "{} buffer overruns", count),
+            Self::TooQuiet { loudness_lufs, threshold_lufs } => {
+                write!(f, "Recording too quiet: {:.1} LUFS (minimum {:.1} LUFS)", loudness_lufs, threshold_lufs)
+            }
+        }
+    }
+}
+
+/// Audio quality summary for a whole recording session
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AudioQualityReport {
+    pub duration_ms: f64,
+    /// Average level of non-speech frames
+    pub noise_floor_dbfs: Option<f32>,
+    /// Average level of speech frames
+    pub speech_level_dbfs: Option<f32>,
+    pub snr_db: Option<f32>,
+    /// Fraction of frames classified as speech
+    pub speech_ratio: f32,
+    pub clipped_samples: u64,
+    pub clipping_ratio: f32,
+    pub dropouts: u32,
+    pub buffer_overruns: u64,
+    pub buffer_underruns: u64,
+    /// Gated integrated loudness (ITU-R BS.1770)
+    pub integrated_loudness_lufs: Option<f32>,
+    pub warnings: Vec<QualityWarning>,
+}
+
+impl AudioQualityReport {
+    /// Whether every metric is within the thresholds it was checked against
+    pub fn is_acceptable(&self) -> bool {
+        self.warnings.is_empty()
+    }
+}
+
+/// Biquad filter in transposed direct form II
+#[derive(Debug, Clone, Copy)]
+struct Biquad {
+    b0: f64,
+    b1: f64,
+    b2: f64,
+    a1: f64,
+    a2: f64,
+    z1: f64,
+    z2: f64,
+}
+
+impl Biquad {
+    fn process(&mut self, x: f64) -> f64 {
+        let y = self.b0 * x + self.z1;
+        self.z1 = self.b1 * x - self.a1 * y + self.z2;
+        self.z2 = self.b2 * x - self.a2 * y;
+        y
+    }
+}
+
+/// BS.1770 K-weighting: a high-shelf pre-filter followed by a high-pass
+fn k_weighting(sample_rate: u32) -> [Biquad; 2] {
+    let fs = sample_rate.max(1) as f64;
+
+    let f0 = 1681.974450955533;
+    let gain_db = 3.999843853973347;
+    let q = 0.7071752369554196;
+    let k = (PI * f0 / fs).tan();
+    let vh = 10f64.powf(gain_db / 20.0);
+    let vb = vh.powf(0.4996667741545416);
+    let a0 = 1.0 + k / q + k * k;
+    let shelf = Biquad {
+        b0: (vh + vb * k / q + k * k) / a0,
+        b1: 2.0 * (k * k - vh) / a0,
+        b2: (vh - vb * k / q + k * k) / a0,
+        a1: 2.0 * (k * k - 1.0) / a0,
+        a2: (1.0 - k / q + k * k) / a0,
+        z1: 0.0,
+        z2: 0.0,
+    };
+
+    let f0 = 38.13547087602444;
+    let q = 0.5003270373238773;
+    let k = (PI * f0 / fs).tan();
+    let a0 = 1.0 + k / q + k * k;
+    let high_pass = Biquad {
+        b0: 1.0,
+        b1: -2.0,
+        b2: 1.0,
+        a1: 2.0 * (k * k - 1.0) / a0,
+        a2: (1.0 - k / q + k * k) / a0,
+        z1: 0.0,
+        z2: 0.0,
+    };
+
+    [shelf, high_pass]
+}
+
+/// Energy-based voice activity detection against a minimum-statistics noise floor
+///
+/// The floor follows dips in level immediately and rises slowly while the
+/// signal stays above it; frames well clear of the floor count as speech.
+#[derive(Debug, Clone, Default)]
+pub struct SpeechDetector {
+    floor_db: Option<f64>,
+}
+
+impl SpeechDetector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Classify a frame of `duration_ms` at `level_db`, updating the floor
+    pub fn classify(&mut self, level_db: f64, duration_ms: f64) -> bool {
+        let rise = NOISE_FLOOR_RISE_DB_PER_SEC * duration_ms / 1000.0;
+        let floor = match self.floor_db {
+            Some(floor) if level_db >= floor => (floor + rise).min(level_db),
+            _ => level_db,
+        };
+        self.floor_db = Some(floor);
+
+        level_db > floor + SPEECH_MARGIN_DB && level_db > SPEECH_MIN_DBFS
+    }
+
+    /// Current noise floor estimate
+    pub fn floor_db(&self) -> Option<f64> {
+        self.floor_db
+    }
+}
+
+pub(crate) fn power_to_db(power: f64) -> f64 {
+    10.0 * power.max(MIN_POWER).log10()
+}
+
+fn db_to_power(db: f64) -> f64 {
+    10f64.powf(db / 10.0)
+}
+
+/// Tracks noise floor, speech level, clipping, dropouts and loudness over a session
+#[derive(Debug, Clone)]
+pub struct QualityTracker {
+    sample_rate: u32,
+    frame_len: usize,
+    total_samples: u64,
+
+    // Current analysis frame
+    frame_energy: f64,
+    frame_count: usize,
+    frame_clipped: usize,
+
+    // Noise floor and speech level
+    speech_detector: SpeechDetector,
+    speech_power_sum: f64,
+    speech_frames: u64,
+    noise_power_sum: f64,
+    noise_frames: u64,
+    recent_alpha: f64,
+    recent_speech_power: Option<f64>,
+    recent_noise_power: Option<f64>,
+    recent_clip_ratio: f64,
+
+    // Clipping and dropouts
+    clipped_samples: u64,
+    zero_run: usize,
+    dropout_min_samples: usize,
+    heard_signal: bool,
+    dropouts: u32,
+
+    // Loudness
+    k_filter: [Biquad; 2],
+    sub_block_len: usize,
+    sub_block_energy: f64,
+    sub_block_count: usize,
+    sub_blocks: [f64; 4],
+    sub_blocks_seen: usize,
+    loudness_histogram: Vec<u32>,
+}
+
+impl QualityTracker {
+    /// Create a tracker for mono audio at `sample_rate`
+    pub fn new(sample_rate: u32) -> Self {
+        let rate = sample_rate.max(1) as f64;
+        let bins = ((LOUDNESS_MAX_LUFS - LOUDNESS_MIN_LUFS) / LOUDNESS_BIN_LU).round() as usize + 1;
+
+        Self {
+            sample_rate,
+            frame_len: ((rate * FRAME_MS / 1000.0) as usize).max(1),
+            total_samples: 0,
+            frame_energy: 0.0,
+            frame_count: 0,
+            frame_clipped: 0,
+            speech_detector: SpeechDetector::new(),
+            speech_power_sum: 0.0,
+            speech_frames: 0,
+            noise_power_sum: 0.0,
+            noise_frames: 0,
+            recent_alpha: FRAME_MS / RECENT_TIME_CONSTANT_MS,
+            recent_speech_power: None,
+            recent_noise_power: None,
+            recent_clip_ratio: 0.0,
+            clipped_samples: 0,
+            zero_run: 0,
+            dropout_min_samples: ((rate * DROPOUT_MIN_MS / 1000.0) as usize).max(1),
+            heard_signal: false,
+            dropouts: 0,
+            k_filter: k_weighting(sample_rate),
+            sub_block_len: ((rate * 0.1) as usize).max(1),
+            sub_block_energy: 0.0,
+            sub_block_count: 0,
+            sub_blocks: [0.0; 4],
+            sub_blocks_seen: 0,
+            loudness_histogram: vec![0; bins],
+        }
+    }
+
+    /// Sample rate the tracker was created for
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Duration of audio analysed so far
+    pub fn duration_ms(&self) -> f64 {
+        self.total_samples as f64 * 1000.0 / self.sample_rate.max(1) as f64
+    }
+
+    /// Analyse a block of mono samples; never allocates
+    pub fn push_samples(&mut self, samples: &[f32]) {
+        for &sample in samples {
+            self.total_samples += 1;
+            let magnitude = sample.abs();
+
+            // Clipping
+            if magnitude >= CLIP_LEVEL {
+                self.clipped_samples += 1;
+                self.frame_clipped += 1;
+            }
+
+            // Dropouts: a run of exact zeros after the signal has been live
+            if sample == 0.0 {
+                self.zero_run += 1;
+                if self.zero_run == self.dropout_min_samples && self.heard_signal {
+                    self.dropouts += 1;
+                }
+            } else {
+                self.zero_run = 0;
+                self.heard_signal = true;
+            }
+
+            // Frame level
+            let x = sample as f64;
+            self.frame_energy += x * x;
+            self.frame_count += 1;
+            if self.frame_count == self.frame_len {
+                self.finish_frame();
+            }
+
+            // K-weighted loudness
+            let shelved = self.k_filter[0].process(x);
+            let weighted = self.k_filter[1].process(shelved);
+            self.sub_block_energy += weighted * weighted;
+            self.sub_block_count += 1;
+            if self.sub_block_count == self.sub_block_len {
+                self.finish_sub_block();
+            }
+        }
+    }
+
+    fn finish_frame(&mut self) {
+        let power = self.frame_energy / self.frame_count as f64;
+        let clip_ratio = self.frame_clipped as f64 / self.frame_count as f64;
+        self.frame_energy = 0.0;
+        self.frame_count = 0;
+        self.frame_clipped = 0;
+
+        let is_speech = self.speech_detector.classify(power_to_db(power), FRAME_MS);
+        let alpha = self.recent_alpha;
+        let smooth = |recent: Option<f64>| Some(recent.map_or(power, |r| r + alpha * (power - r)));
+
+        if is_speech {
+            self.speech_power_sum += power;
+            self.speech_frames += 1;
+            self.recent_speech_power = smooth(self.recent_speech_power);
+        } else {
+            self.noise_power_sum += power.max(MIN_POWER);
+            self.noise_frames += 1;
+            self.recent_noise_power = smooth(self.recent_noise_power);
+        }
+
+        self.recent_clip_ratio += alpha * (clip_ratio - self.recent_clip_ratio);
+    }
+
+    fn finish_sub_block(&mut self) {
+        let mean_square = self.sub_block_energy / self.sub_block_count as f64;
+        self.sub_block_energy = 0.0;
+        self.sub_block_count = 0;
+
+        self.sub_blocks[self.sub_blocks_seen % 4] = mean_square;
+        self.sub_blocks_seen += 1;
+
+        // 400ms gating blocks with 75% overlap
+        if self.sub_blocks_seen >= 4 {
+            let block_power = self.sub_blocks.iter().sum::<f64>() / 4.0;
+            let loudness = -0.691 + power_to_db(block_power);
+            if loudness >= LOUDNESS_MIN_LUFS {
+                let bin = ((loudness.min(LOUDNESS_MAX_LUFS) - LOUDNESS_MIN_LUFS) / LOUDNESS_BIN_LU) as usize;
+                self.loudness_histogram[bin] += 1;
+            }
+        }
+    }
+
+    fn bin_loudness(bin: usize) -> f64 {
+        LOUDNESS_MIN_LUFS + (bin as f64 + 0.5) * LOUDNESS_BIN_LU
+    }
+
+    /// Gated integrated loudness over the whole session
+    pub fn integrated_loudness(&self) -> Option<f64> {
+        let mean_power = |from_bin: usize| {
+            let (count, sum) = self.loudness_histogram.iter()
+                .enumerate()
+                .skip(from_bin)
+                .fold((0u64, 0.0), |(count, sum), (bin, &n)| {
+                    (count + n as u64, sum + n as f64 * db_to_power(Self::bin_loudness(bin) + 0.691))
+                });
+            (count > 0).then(|| sum / count as f64)
+        };
+
+        // Absolute gate is the histogram floor; relative gate sits 10 LU below
+        let ungated = mean_power(0)?;
+        let relative_gate = -0.691 + power_to_db(ungated) - 10.0;
+        let gate_bin = ((relative_gate - LOUDNESS_MIN_LUFS) / LOUDNESS_BIN_LU).max(0.0).ceil() as usize;
+
+        mean_power(gate_bin).map(|power| -0.691 + power_to_db(power))
+    }
+
+    /// Average level of non-speech frames
+    pub fn noise_floor_db(&self) -> Option<f64> {
+        (self.noise_frames > 0).then(|| power_to_db(self.noise_power_sum / self.noise_frames as f64))
+    }
+
+    /// Average level of speech frames
+    pub fn speech_level_db(&self) -> Option<f64> {
+        (self.speech_frames > 0).then(|| power_to_db(self.speech_power_sum / self.speech_frames as f64))
+    }
+
+    /// Speech-to-noise ratio over the whole session
+    pub fn snr_db(&self) -> Option<f64> {
+        Some(self.speech_level_db()? - self.noise_floor_db()?)
+    }
+
+    /// Speech-to-noise ratio over roughly the last few seconds
+    pub fn recent_snr_db(&self) -> Option<f64> {
+        Some(power_to_db(self.recent_speech_power?) - power_to_db(self.recent_noise_power?))
+    }
+
+    /// Number of dropouts detected so far
+    pub fn dropouts(&self) -> u32 {
+        self.dropouts
+    }
+
+    /// Problems with the last few seconds of audio, for warning while recording
+    pub fn live_warnings(&self, thresholds: &QualityThresholds) -> Vec<QualityWarning> {
+        let mut warnings = Vec::new();
+        if self.duration_ms() < LIVE_WARMUP_MS {
+            return warnings;
+        }
+
+        if let Some(snr) = self.recent_snr_db() {
+            if snr < thresholds.min_snr_db as f64 {
+                warnings.push(QualityWarning::LowSnr {
+                    snr_db: snr as f32,
+                    threshold_db: thresholds.min_snr_db,
+                });
+            }
+        }
+
+        if self.recent_clip_ratio > thresholds.max_clipping_ratio as f64 {
+            warnings.push(QualityWarning::Clipping {
+                ratio: self.recent_clip_ratio as f32,
+                threshold: thresholds.max_clipping_ratio,
+            });
+        }
+
+        if self.dropouts > thresholds.max_dropouts {
+            warnings.push(QualityWarning::Dropouts { count: self.dropouts });
+        }
+
+        warnings
+    }
+
+    /// Summarise the session, checking it against `thresholds`
+    pub fn report(&self, stats: &AudioStats, thresholds: &QualityThresholds) -> AudioQualityReport {
+        let total_frames = self.speech_frames + self.noise_frames;
+        let clipping_ratio = if self.total_samples > 0 {
+            self.clipped_samples as f32 / self.total_samples as f32
+        } else {
+            0.0
+        };
+
+        let mut report = AudioQualityReport {
+            duration_ms: self.duration_ms(),
+            noise_floor_dbfs: self.noise_floor_db().map(|db| db as f32),
+            speech_level_dbfs: self.speech_level_db().map(|db| db as f32),
+            snr_db: self.snr_db().map(|db| db as f32),
+            speech_ratio: if total_frames > 0 {
+                self.speech_frames as f32 / total_frames as f32
+            } else {
+                0.0
+            },
+            clipped_samples: self.clipped_samples,
+            clipping_ratio,
+            dropouts: self.dropouts,
+            buffer_overruns: stats.buffer_overruns,
+            buffer_underruns: stats.buffer_underruns,
+            integrated_loudness_lufs: self.integrated_loudness().map(|lufs| lufs as f32),
+            warnings: Vec::new(),
+        };
+
+        if let Some(snr_db) = report.snr_db {
+            if snr_db < thresholds.min_snr_db {
+                report.warnings.push(QualityWarning::LowSnr { snr_db, threshold_db: thresholds.min_snr_db });
+            }
+        }
+        if clipping_ratio > thresholds.max_clipping_ratio {
+            report.warnings.push(QualityWarning::Clipping {
+                ratio: clipping_ratio,
+                threshold: thresholds.max_clipping_ratio,
+            });
+        }
+        if self.dropouts > thresholds.max_dropouts {
+            report.warnings.push(QualityWarning::Dropouts { count: self.dropouts });
+        }
+        if stats.buffer_overruns > 0 {
+            report.warnings.push(QualityWarning::BufferOverruns { count: stats.buffer_overruns });
+        }
+        if let Some(loudness_lufs) = report.integrated_loudness_lufs {
+            if loudness_lufs < thresholds.min_loudness_lufs {
+                report.warnings.push(QualityWarning::TooQuiet {
+                    loudness_lufs,
+                    threshold_lufs: thresholds.min_loudness_lufs,
+                });
+            }
+        }
+
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deterministic white noise in [-amplitude, amplitude]
+    fn noise(len: usize, amplitude: f32, seed: u32) -> Vec<f32> {
+        let mut state = seed.max(1);
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                (state as f32 / u32::MAX as f32 * 2.0 - 1.0) * amplitude
+            })
+            .collect()
+    }
+
+    /// One second of noise, then alternating bursts of tone-plus-noise and noise
+    fn speech_like(sample_rate: u32, seconds: usize, tone: f32, noise_amp: f32) -> Vec<f32> {
+        let rate = sample_rate as usize;
+        let background = noise(rate * seconds, noise_amp, 7);
+        background.iter()
+            .enumerate()
+            .map(|(i, &n)| {
+                let talking = i >= rate && (i / (rate / 2)).is_multiple_of(2);
+                let t = i as f32 / sample_rate as f32;
+                if talking { n + tone * (2.0 * std::f32::consts::PI * 440.0 * t).sin() } else { n }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_snr_tracks_noise_not_constant_reference() {
+        let mut quiet_room = QualityTracker::new(16000);
+        quiet_room.push_samples(&speech_like(16000, 10, 0.3, 0.001));
+
+        let mut noisy_room = QualityTracker::new(16000);
+        noisy_room.push_samples(&speech_like(16000, 10, 0.3, 0.05));
+
+        let quiet_snr = quiet_room.snr_db().unwrap();
+        let noisy_snr = noisy_room.snr_db().unwrap();
+
+        assert!(quiet_snr > 40.0, "quiet room SNR {}", quiet_snr);
+        assert!(noisy_snr < 20.0, "noisy room SNR {}", noisy_snr);
+        assert!(noisy_room.noise_floor_db().unwrap() > quiet_room.noise_floor_db().unwrap() + 30.0);
+    }
+
+    #[test]
+    fn test_counts_clipping_and_dropouts() {
+        let mut tracker = QualityTracker::new(16000);
+        let mut samples = speech_like(16000, 2, 0.3, 0.01);
+        samples[8000..8160].iter_mut().for_each(|s| *s = 0.0); // 10ms gap
+        samples[20000..20100].iter_mut().for_each(|s| *s = 1.0);
+        tracker.push_samples(&samples);
+
+        let report = tracker.report(&AudioStats::default(), &QualityThresholds::default());
+
+        assert_eq!(report.dropouts, 1);
+        assert_eq!(report.clipped_samples, 100);
+        assert!(report.warnings.iter().any(|w| matches!(w, QualityWarning::Dropouts { count: 1 })));
+    }
+
+    #[test]
+    fn test_integrated_loudness_of_full_scale_tone() {
+        // BS.1770: a 0 dBFS 1kHz sine reads about -3 LUFS
+        let mut tracker = QualityTracker::new(48000);
+        let tone: Vec<f32> = (0..48000 * 3)
+            .map(|i| (2.0 * std::f32::consts::PI * 1000.0 * i as f32 / 48000.0).sin())
+            .collect();
+        tracker.push_samples(&tone);
+
+        let loudness = tracker.integrated_loudness().unwrap();
+        assert!((loudness + 3.0).abs() < 0.3, "loudness {}", loudness);
+    }
+
+    #[test]
+    fn test_report_warns_below_threshold() {
+        let mut tracker = QualityTracker::new(16000);
+        tracker.push_samples(&speech_like(16000, 10, 0.2, 0.03));
+
+        let lenient = QualityThresholds { min_snr_db: 0.0, ..Default::default() };
+        let strict = QualityThresholds { min_snr_db: 30.0, ..Default::default() };
+        let stats = AudioStats::default();
+
+        assert!(!tracker.report(&stats, &lenient).warnings.iter().any(|w| matches!(w, QualityWarning::LowSnr { .. })));
+        assert!(tracker.report(&stats, &strict).warnings.iter().any(|w| matches!(w, QualityWarning::LowSnr { .. })));
+        assert!(tracker.live_warnings(&strict).iter().any(|w| matches!(w, QualityWarning::LowSnr { .. })));
+    }
+}
@@ -0,0 +1,102 @@
+//! Thread that owns the capture's device streams
+//!
+//! A `cpal::Stream` is neither `Send` nor `Sync` on every platform, so it has
+//! to stay on the thread that built it. The streams live on a thread of their
+//! own and the capture service drives them through a channel, which keeps the
+//! service itself shareable between the async runtime's threads.
+
+use std::sync::mpsc;
+use std::thread::JoinHandle;
+use cpal::{Stream, traits::StreamTrait};
+use tracing::debug;
+
+use super::types::{AudioError, AudioResult};
+
+/// Builds a stream on the stream thread
+pub type StreamBuilder = Box<dyn FnOnce() -> AudioResult<Stream> + Send>;
+
+enum StreamCommand {
+    Open(StreamBuilder, mpsc::Sender<AudioResult<()>>),
+    Play(mpsc::Sender<AudioResult<()>>),
+    Close(mpsc::Sender<()>),
+}
+
+/// Handle to the thread holding the open streams
+///
+/// Dropping the handle closes every stream and ends the thread.
+pub struct CaptureStreams {
+    commands: Option<mpsc::Sender<StreamCommand>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl CaptureStreams {
+    pub fn spawn() -> AudioResult<Self> {
+        let (commands, receiver) = mpsc::channel();
+        let thread = std::thread::Builder::new()
+            .name("audio-streams".to_string())
+            .spawn(move || run(receiver))
+            .map_err(|e| AudioError::Internal {
+                message: format!("Failed to spawn audio stream thread: {}", e),
+            })?;
+        Ok(Self { commands: Some(commands), thread: Some(thread) })
+    }
+
+    /// Build a stream and keep it open alongside the others
+    pub fn open(&self, build: StreamBuilder) -> AudioResult<()> {
+        self.request(|reply| StreamCommand::Open(build, reply))?
+    }
+
+    /// Start every open stream, in the order they were opened
+    pub fn play(&self) -> AudioResult<()> {
+        self.request(StreamCommand::Play)?
+    }
+
+    /// Stop and drop every open stream
+    pub fn close(&self) -> AudioResult<()> {
+        self.request(StreamCommand::Close)
+    }
+
+    fn request<T>(&self, command: impl FnOnce(mpsc::Sender<T>) -> StreamCommand) -> AudioResult<T> {
+        let lost = || AudioError::Internal { message: "Audio stream thread has stopped".to_string() };
+        let (reply, response) = mpsc::channel();
+        self.commands
+            .as_ref()
+            .ok_or_else(lost)?
+            .send(command(reply))
+            .map_err(|_| lost())?;
+        response.recv().map_err(|_| lost())
+    }
+}
+
+impl Drop for CaptureStreams {
+    fn drop(&mut self) {
+        // Hanging up ends the thread's loop, which drops its streams
+        self.commands.take();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn run(commands: mpsc::Receiver<StreamCommand>) {
+    let mut streams: Vec<Stream> = Vec::new();
+    while let Ok(command) = commands.recv() {
+        match command {
+            StreamCommand::Open(build, reply) => {
+                let result = build().map(|stream| streams.push(stream));
+                let _ = reply.send(result);
+            }
+            StreamCommand::Play(reply) => {
+                let result = streams
+                    .iter()
+                    .try_for_each(|stream| stream.play().map_err(AudioError::from));
+                let _ = reply.send(result);
+            }
+            StreamCommand::Close(reply) => {
+                debug!("Closing {} audio stream(s)", streams.len());
+                streams.clear();
+                let _ = reply.send(());
+            }
+        }
+    }
+}
@@ -11,6 +11,11 @@ fn create_test_config() -> AudioConfig {
         channels: 1,
         buffer_size: 1024,
         format: AudioFormat::F32,
+        channel_map: ChannelMap::Average,
+        quality_thresholds: QualityThresholds::default(),
+        secondary_device: None,
+        validation: ValidationConfig::default(),
+        worker: WorkerConfig::default(),
     }
 }
 
@@ -37,6 +42,11 @@ async fn test_audio_capture_service_with_custom_config() {
         channels: 2,
         buffer_size: 2048,
         format: AudioFormat::F32,
+        channel_map: ChannelMap::Average,
+        quality_thresholds: QualityThresholds::default(),
+        secondary_device: None,
+        validation: ValidationConfig::default(),
+        worker: WorkerConfig::default(),
     };
     
     let service = AudioCaptureService::with_config(config.clone());
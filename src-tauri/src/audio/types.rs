@@ -1,10 +1,12 @@
 //! Audio processing types and error definitions
 
-use std::sync::Arc;
-use std::time::Duration;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+use super::processing::ValidationConfig;
+use super::quality::QualityThresholds;
+use super::worker::WorkerConfig;
+
 /// Custom error types for audio processing operations
 #[derive(Debug, Error)]
 pub enum AudioError {
@@ -26,6 +28,9 @@ pub enum AudioError {
     #[error("Stream error: {0}")]
     Stream(#[from] cpal::StreamError),
     
+    #[error("Failed to start stream: {0}")]
+    Play(#[from] cpal::PlayStreamError),
+    
     #[error("Device enumeration error: {0}")]
     DeviceEnumeration(#[from] cpal::DevicesError),
     
@@ -38,6 +43,18 @@ pub enum AudioError {
     #[error("Audio service already running")]
     AlreadyRunning,
     
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    
+    #[error("Failed to decode audio: {0}")]
+    Decode(#[from] symphonia::core::errors::Error),
+    
+    #[error("Opus codec error: {0}")]
+    Opus(#[from] audiopus::Error),
+    
+    #[error("Ogg stream error: {0}")]
+    Ogg(#[from] ogg::OggReadError),
+    
     #[error("Internal error: {message}")]
     Internal { message: String },
 }
@@ -65,6 +82,14 @@ pub struct AudioConfig {
     pub channels: u16,
     pub buffer_size: usize,
     pub format: AudioFormat,
+    pub channel_map: ChannelMap,
+    pub quality_thresholds: QualityThresholds,
+    /// Second input device (e.g. a loopback) captured as an extra track
+    pub secondary_device: Option<String>,
+    /// Thresholds for validating captured buffers
+    pub validation: ValidationConfig,
+    /// Scheduling and CPU budget of the processing worker
+    pub worker: WorkerConfig,
 }
 
 impl Default for AudioConfig {
@@ -74,10 +99,116 @@ impl Default for AudioConfig {
             channels: 1,         // Mono
             buffer_size: 1024,   // ~64ms at 16kHz
             format: AudioFormat::F32,
+            channel_map: ChannelMap::Average,
+            quality_thresholds: QualityThresholds::default(),
+            secondary_device: None,
+            validation: ValidationConfig::default(),
+            worker: WorkerConfig::default(),
         }
     }
 }
 
+/// A device input channel (zero-based) and the gain applied to it
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ChannelGain {
+    pub channel: u16,
+    pub gain: f32,
+}
+
+impl ChannelGain {
+    pub fn new(channel: u16, gain: f32) -> Self {
+        Self { channel, gain }
+    }
+}
+
+/// How device input channels are turned into mono capture tracks
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum ChannelMap {
+    /// Average every device channel into a single track
+    #[default]
+    Average,
+    /// Mix the listed channels, each with its own gain, into a single track
+    Mix { inputs: Vec<ChannelGain> },
+    /// Route each mix to its own track (e.g. mic on 0, instrument on 1)
+    Tracks { tracks: Vec<Vec<ChannelGain>> },
+}
+
+impl ChannelMap {
+    /// Capture a single device channel at unity gain
+    pub fn select(channel: u16) -> Self {
+        Self::Mix { inputs: vec![ChannelGain::new(channel, 1.0)] }
+    }
+    
+    /// Route each listed channel to its own track at unity gain
+    pub fn split(channels: &[u16]) -> Self {
+        Self::Tracks {
+            tracks: channels.iter().map(|&c| vec![ChannelGain::new(c, 1.0)]).collect(),
+        }
+    }
+    
+    /// Number of tracks this map produces
+    pub fn track_count(&self) -> usize {
+        match self {
+            Self::Average | Self::Mix { .. } => 1,
+            Self::Tracks { tracks } => tracks.len(),
+        }
+    }
+    
+    /// Minimum number of device channels needed to satisfy the map
+    pub fn required_channels(&self) -> u16 {
+        let highest = match self {
+            Self::Average => None,
+            Self::Mix { inputs } => inputs.iter().map(|g| g.channel).max(),
+            Self::Tracks { tracks } => tracks.iter().flatten().map(|g| g.channel).max(),
+        };
+        highest.map(|c| c + 1).unwrap_or(1)
+    }
+    
+    /// Check the map against the channel count the device was opened with
+    pub fn validate(&self, device_channels: u16) -> AudioResult<()> {
+        let mixes: Vec<&Vec<ChannelGain>> = match self {
+            Self::Average => return Ok(()),
+            Self::Mix { inputs } => vec![inputs],
+            Self::Tracks { tracks } => {
+                if tracks.is_empty() {
+                    return Err(AudioError::UnsupportedFormat {
+                        details: "Channel map has no tracks".to_string(),
+                    });
+                }
+                tracks.iter().collect()
+            }
+        };
+        
+        for (track, inputs) in mixes.into_iter().enumerate() {
+            if inputs.is_empty() {
+                return Err(AudioError::UnsupportedFormat {
+                    details: format!("Channel map track {} has no input channels", track),
+                });
+            }
+            
+            for input in inputs {
+                if input.channel >= device_channels {
+                    return Err(AudioError::UnsupportedFormat {
+                        details: format!(
+                            "Channel map uses input channel {} but the device has {} channels",
+                            input.channel, device_channels
+                        ),
+                    });
+                }
+                
+                if !input.gain.is_finite() {
+                    return Err(AudioError::UnsupportedFormat {
+                        details: format!("Invalid gain {} for input channel {}", input.gain, input.channel),
+                    });
+                }
+            }
+        }
+        
+        Ok(())
+    }
+}
+
 /// Supported audio formats
 #[derive(Debug, Clone, Copy)]
 pub enum AudioFormat {
@@ -144,6 +275,15 @@ pub struct AudioStats {
     pub average_latency_ms: f64,
     pub peak_level: f32,
     pub rms_level: f32,
+    /// Measured clock offset of the secondary device against the primary
+    /// one, once known; positive when the secondary device runs fast
+    pub clock_drift_ppm: Option<f64>,
+    /// Frames whose processing took longer than the worker's budget
+    pub deadline_misses: u64,
+    /// Smoothed processing time as a fraction of real time
+    pub processing_load: f32,
+    /// Optional processing stages disabled to stay within budget
+    pub degraded_stages: u32,
 }
 
 impl Default for AudioStats {
@@ -155,6 +295,10 @@ impl Default for AudioStats {
             average_latency_ms: 0.0,
             peak_level: 0.0,
             rms_level: 0.0,
+            clock_drift_ppm: None,
+            deadline_misses: 0,
+            processing_load: 0.0,
+            degraded_stages: 0,
         }
     }
 }
@@ -267,11 +411,21 @@ impl AudioLevelMonitor {
     
     /// Update levels with new audio buffer
     pub fn update(&mut self, buffer: &AudioBuffer) {
+        self.update_samples(&buffer.samples);
+    }
+    
+    /// Update levels from raw samples without building an `AudioBuffer`
+    pub fn update_samples(&mut self, samples: &[f32]) {
         // Calculate current RMS level
-        self.rms_level = buffer.rms_level();
+        self.rms_level = if samples.is_empty() {
+            0.0
+        } else {
+            let sum_squares: f32 = samples.iter().map(|&sample| sample * sample).sum();
+            (sum_squares / samples.len() as f32).sqrt()
+        };
         
         // Update peak level with decay
-        let current_peak = buffer.samples.iter()
+        let current_peak = samples.iter()
             .map(|&s| s.abs())
             .max_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal))
             .unwrap_or(0.0);
@@ -401,4 +555,21 @@ mod tests {
         assert!((mono_buffer.samples[0] - 0.4).abs() < 0.001);
         assert!((mono_buffer.samples[1] - 0.5).abs() < 0.001);
     }
+    
+    #[test]
+    fn test_channel_map_validation() {
+        assert!(ChannelMap::Average.validate(1).is_ok());
+        assert!(ChannelMap::select(1).validate(2).is_ok());
+        assert_eq!(ChannelMap::select(3).required_channels(), 4);
+        
+        // Channel index beyond the device's channel count
+        let result = ChannelMap::select(2).validate(2);
+        assert!(matches!(result, Err(AudioError::UnsupportedFormat { .. })));
+        
+        // Empty track
+        let empty = ChannelMap::Tracks { tracks: vec![vec![]] };
+        assert!(empty.validate(2).is_err());
+        
+        assert_eq!(ChannelMap::split(&[0, 1]).track_count(), 2);
+    }
 }
\ No newline at end of file
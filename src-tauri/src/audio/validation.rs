@@ -0,0 +1,289 @@
+//! Live quality events from buffer validation
+//!
+//! The capture callback runs every device buffer through
+//! `AudioQualityValidator::inspect` and hands the findings to a
+//! `ValidationMonitor`. The monitor only adds up counters, so it is cheap to
+//! feed from the real-time thread; a monitor task drains it periodically into
+//! typed `QualityEvent`s, at most one per kind per `event_interval_ms`.
+
+use std::fmt;
+use serde::{Deserialize, Serialize};
+
+use super::processing::{BufferFindings, ValidationConfig};
+
+/// Kinds of validation issue, used to rate-limit each one separately
+const ISSUE_KINDS: usize = 4;
+
+/// A problem found while validating captured buffers
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ValidationIssue {
+    /// Samples at or above the clipping threshold since the last report
+    Clipping { clipped_samples: u64, ratio: f32, peak: f32 },
+    /// The input has stayed below the silence threshold this long
+    Silence { duration_ms: f64, threshold: f32 },
+    /// NaN or infinite samples since the last report
+    InvalidSamples { count: u64 },
+    /// Device buffers longer than the configured maximum since the last report
+    OversizedBuffer { count: u32, duration_ms: f64, max_duration_ms: f64 },
+}
+
+impl ValidationIssue {
+    fn index(&self) -> usize {
+        match self {
+            Self::Clipping { .. } => 0,
+            Self::Silence { .. } => 1,
+            Self::InvalidSamples { .. } => 2,
+            Self::OversizedBuffer { .. } => 3,
+        }
+    }
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Clipping { clipped_samples, ratio, peak } => write!(
+                f, "{} clipped samples ({:.2}%), peak {:.3}", clipped_samples, ratio * 100.0, peak
+            ),
+            Self::Silence { duration_ms, .. } => {
+                write!(f, "No input signal for {:.1}s", duration_ms / 1000.0)
+            }
+            Self::InvalidSamples { count } => write!(f, "{} NaN or infinite samples", count),
+            Self::OversizedBuffer { count, duration_ms, max_duration_ms } => write!(
+                f, "{} device buffers of up to {:.0}ms (maximum {:.0}ms)", count, duration_ms, max_duration_ms
+            ),
+        }
+    }
+}
+
+/// A validation issue and when in the session it was reported
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct QualityEvent {
+    /// Captured audio time at which the event was raised
+    pub at_ms: f64,
+    pub issue: ValidationIssue,
+}
+
+/// Collects buffer findings and turns them into rate-limited events
+///
+/// Fixed-size state only; `observe` never allocates.
+#[derive(Debug, Clone)]
+pub struct ValidationMonitor {
+    config: ValidationConfig,
+    elapsed_ms: f64,
+    total_samples: u64,
+    clipped_samples: u64,
+    peak: f32,
+    invalid_samples: u64,
+    oversized_buffers: u32,
+    longest_buffer_ms: f64,
+    silence_ms: f64,
+    silence_reported: bool,
+    last_reported: [Option<f64>; ISSUE_KINDS],
+}
+
+impl ValidationMonitor {
+    pub fn new(config: ValidationConfig) -> Self {
+        Self {
+            config,
+            elapsed_ms: 0.0,
+            total_samples: 0,
+            clipped_samples: 0,
+            peak: 0.0,
+            invalid_samples: 0,
+            oversized_buffers: 0,
+            longest_buffer_ms: 0.0,
+            silence_ms: 0.0,
+            silence_reported: false,
+            last_reported: [None; ISSUE_KINDS],
+        }
+    }
+
+    pub fn config(&self) -> &ValidationConfig {
+        &self.config
+    }
+
+    /// Captured audio time seen so far
+    pub fn elapsed_ms(&self) -> f64 {
+        self.elapsed_ms
+    }
+
+    /// Add the findings for one buffer of `samples` interleaved samples
+    pub fn observe(&mut self, findings: &BufferFindings, samples: usize) {
+        self.elapsed_ms += findings.duration_ms;
+        self.total_samples += samples as u64;
+        self.clipped_samples += findings.clipped_samples as u64;
+        self.peak = self.peak.max(findings.peak);
+        self.invalid_samples += findings.non_finite_samples as u64;
+
+        if findings.too_long {
+            self.oversized_buffers += 1;
+            self.longest_buffer_ms = self.longest_buffer_ms.max(findings.duration_ms);
+        }
+
+        // A stretch of silence is reported once, then again only after sound returns
+        if findings.silent {
+            self.silence_ms += findings.duration_ms;
+        } else if samples > 0 {
+            self.silence_ms = 0.0;
+            self.silence_reported = false;
+        }
+    }
+
+    /// Take the events that are due
+    ///
+    /// Counts behind a rate-limited kind keep accumulating, so its next event
+    /// covers everything since the previous one.
+    pub fn drain_events(&mut self) -> Vec<QualityEvent> {
+        let mut events = Vec::new();
+
+        if self.clipped_samples > 0 {
+            let issue = ValidationIssue::Clipping {
+                clipped_samples: self.clipped_samples,
+                ratio: (self.clipped_samples as f64 / self.total_samples.max(1) as f64) as f32,
+                peak: self.peak,
+            };
+            if self.report(issue, &mut events) {
+                self.clipped_samples = 0;
+                self.total_samples = 0;
+                self.peak = 0.0;
+            }
+        }
+
+        if !self.silence_reported && self.silence_ms >= self.config.silence_alert_ms {
+            let issue = ValidationIssue::Silence {
+                duration_ms: self.silence_ms,
+                threshold: self.config.silence_threshold,
+            };
+            self.silence_reported = self.report(issue, &mut events);
+        }
+
+        if self.invalid_samples > 0 {
+            let issue = ValidationIssue::InvalidSamples { count: self.invalid_samples };
+            if self.report(issue, &mut events) {
+                self.invalid_samples = 0;
+            }
+        }
+
+        if self.oversized_buffers > 0 {
+            let issue = ValidationIssue::OversizedBuffer {
+                count: self.oversized_buffers,
+                duration_ms: self.longest_buffer_ms,
+                max_duration_ms: self.config.max_buffer_duration_ms,
+            };
+            if self.report(issue, &mut events) {
+                self.oversized_buffers = 0;
+                self.longest_buffer_ms = 0.0;
+            }
+        }
+
+        // Counts that were not reported carry over, but keep the clipping
+        // ratio about recent audio rather than the whole session
+        if self.clipped_samples == 0 {
+            self.total_samples = 0;
+            self.peak = 0.0;
+        }
+
+        events
+    }
+
+    /// Queue `issue` unless one of its kind was reported too recently
+    fn report(&mut self, issue: ValidationIssue, events: &mut Vec<QualityEvent>) -> bool {
+        let slot = &mut self.last_reported[issue.index()];
+        if let Some(last) = *slot {
+            if self.elapsed_ms - last < self.config.event_interval_ms as f64 {
+                return false;
+            }
+        }
+        *slot = Some(self.elapsed_ms);
+        events.push(QualityEvent { at_ms: self.elapsed_ms, issue });
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::processing::AudioQualityValidator;
+
+    fn feed(monitor: &mut ValidationMonitor, validator: &AudioQualityValidator, samples: &[f32]) {
+        let findings = validator.inspect(samples, 16000, 1);
+        monitor.observe(&findings, samples.len());
+    }
+
+    #[test]
+    fn test_events_are_rate_limited_per_kind() {
+        let config = ValidationConfig { event_interval_ms: 1000, ..Default::default() };
+        let validator = AudioQualityValidator::with_config(config);
+        let mut monitor = ValidationMonitor::new(config);
+
+        // 100ms buffers that all clip, drained after every buffer
+        let mut clipping = vec![0.1f32; 1600];
+        clipping[0] = 1.0;
+        let mut reported = Vec::new();
+        for _ in 0..25 {
+            feed(&mut monitor, &validator, &clipping);
+            reported.extend(monitor.drain_events());
+        }
+
+        assert_eq!(reported.len(), 3, "{:?}", reported);
+        assert_eq!(reported[0].at_ms, 100.0);
+        // Suppressed buffers are folded into the next event
+        match reported[1].issue {
+            ValidationIssue::Clipping { clipped_samples, .. } => assert_eq!(clipped_samples, 10),
+            other => panic!("unexpected issue {:?}", other),
+        }
+
+        // Other kinds are not held back by clipping events
+        let mut invalid = clipping.clone();
+        invalid[5] = f32::NAN;
+        feed(&mut monitor, &validator, &invalid);
+        let events = monitor.drain_events();
+        assert!(events.iter().any(|e| matches!(e.issue, ValidationIssue::InvalidSamples { count: 1 })));
+    }
+
+    #[test]
+    fn test_silence_reported_once_per_stretch() {
+        let config = ValidationConfig { silence_alert_ms: 500.0, ..Default::default() };
+        let validator = AudioQualityValidator::with_config(config);
+        let mut monitor = ValidationMonitor::new(config);
+        let silence = vec![0.0f32; 1600];
+        let speech = vec![0.2f32; 1600];
+
+        let mut silences = 0;
+        for round in 0..3 {
+            for _ in 0..30 {
+                feed(&mut monitor, &validator, &silence);
+                silences += monitor.drain_events()
+                    .iter()
+                    .filter(|e| matches!(e.issue, ValidationIssue::Silence { .. }))
+                    .count();
+            }
+            assert_eq!(silences, round + 1);
+            feed(&mut monitor, &validator, &speech);
+        }
+    }
+
+    #[test]
+    fn test_oversized_buffers_follow_config() {
+        let config = ValidationConfig { max_buffer_duration_ms: 50.0, ..Default::default() };
+        let validator = AudioQualityValidator::with_config(config);
+        let mut monitor = ValidationMonitor::new(config);
+
+        feed(&mut monitor, &validator, &vec![0.2f32; 800]);
+        assert!(monitor.drain_events().is_empty());
+
+        feed(&mut monitor, &validator, &vec![0.2f32; 1600]);
+        let events = monitor.drain_events();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].issue, ValidationIssue::OversizedBuffer {
+            count: 1,
+            duration_ms: 100.0,
+            max_duration_ms: 50.0,
+        });
+        assert_eq!(
+            serde_json::to_value(events[0]).unwrap()["issue"]["kind"],
+            "oversized_buffer"
+        );
+    }
+}
@@ -0,0 +1,456 @@
+//! Real-time processing worker
+//!
+//! Processing that follows capture runs on a dedicated thread rather than on
+//! the async runtime, so IPC and database work can't delay it. The thread asks
+//! for real-time scheduling where the OS allows it, reads fixed-size frames
+//! from a tap fed by the capture callback and times every frame against a
+//! budget. Sustained overruns disable optional pipeline stages one at a time;
+//! a long clean stretch brings them back.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+use tracing::{debug, error, info, warn};
+
+use super::buffer::AudioRingBuffer;
+use super::processing::AudioProcessingPipeline;
+use super::types::{AudioBuffer, AudioError, AudioResult, AudioStats};
+
+/// Smoothing for the reported processing load
+const LOAD_SMOOTHING: f64 = 0.05;
+
+/// Scheduling settings and CPU budget for the processing worker
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WorkerConfig {
+    /// Audio processed per pipeline run
+    pub frame_ms: f64,
+    /// Ask the OS for real-time scheduling
+    pub realtime_priority: bool,
+    /// Share of a frame's duration its processing may take
+    pub budget_ratio: f64,
+    /// Frames over which deadline misses are counted
+    pub miss_window: u32,
+    /// Misses within one window that disable an optional stage
+    pub degrade_misses: u32,
+    /// Consecutive frames on budget before a disabled stage is re-enabled
+    pub recover_frames: u32,
+}
+
+impl Default for WorkerConfig {
+    fn default() -> Self {
+        Self {
+            frame_ms: 20.0,
+            realtime_priority: true,
+            budget_ratio: 0.5,
+            miss_window: 50,     // 1s of 20ms frames
+            degrade_misses: 5,
+            recover_frames: 1500, // 30s of 20ms frames
+        }
+    }
+}
+
+impl WorkerConfig {
+    /// Processing time allowed per frame
+    pub fn budget(&self) -> Duration {
+        Duration::from_secs_f64(self.frame_ms * self.budget_ratio / 1000.0)
+    }
+
+    pub fn validate(&self) -> AudioResult<()> {
+        if !(1.0..=1000.0).contains(&self.frame_ms) {
+            return Err(AudioError::UnsupportedFormat {
+                details: format!("Worker frame of {}ms is out of range (1-1000ms)", self.frame_ms),
+            });
+        }
+        if !(self.budget_ratio > 0.0 && self.budget_ratio <= 1.0) {
+            return Err(AudioError::UnsupportedFormat {
+                details: format!("Worker budget ratio {} must be above 0 and at most 1", self.budget_ratio),
+            });
+        }
+        if self.miss_window == 0 || self.degrade_misses == 0 || self.degrade_misses > self.miss_window {
+            return Err(AudioError::UnsupportedFormat {
+                details: "Worker miss window must hold at least the misses that trigger degradation".to_string(),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Scheduling the worker thread ended up with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ThreadPriority {
+    /// Real-time scheduling class
+    Realtime,
+    /// Normal scheduling with a raised priority
+    Elevated,
+    /// Whatever the thread was given; elevation was not allowed or not asked for
+    Normal,
+}
+
+/// What to do after timing a frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BudgetAction {
+    None,
+    /// Too many recent misses; shed an optional stage
+    Degrade,
+    /// On budget for long enough; restore a stage
+    Recover,
+}
+
+/// Times frames against the per-frame budget
+#[derive(Debug, Clone)]
+pub struct BudgetTracker {
+    config: WorkerConfig,
+    budget: Duration,
+    frame: Duration,
+    deadline_misses: u64,
+    window_frames: u32,
+    window_misses: u32,
+    clean_frames: u32,
+    load: f64,
+}
+
+impl BudgetTracker {
+    pub fn new(config: WorkerConfig) -> Self {
+        Self {
+            config,
+            budget: config.budget(),
+            frame: Duration::from_secs_f64(config.frame_ms / 1000.0),
+            deadline_misses: 0,
+            window_frames: 0,
+            window_misses: 0,
+            clean_frames: 0,
+            load: 0.0,
+        }
+    }
+
+    /// Record how long a frame took to process
+    ///
+    /// `degraded` says whether any stage is currently disabled, so recovery
+    /// is only suggested when there is something to recover.
+    pub fn record(&mut self, elapsed: Duration, degraded: bool) -> BudgetAction {
+        let ratio = elapsed.as_secs_f64() / self.frame.as_secs_f64();
+        self.load += LOAD_SMOOTHING * (ratio - self.load);
+
+        self.window_frames += 1;
+        if elapsed > self.budget {
+            self.deadline_misses += 1;
+            self.window_misses += 1;
+            self.clean_frames = 0;
+        } else {
+            self.clean_frames += 1;
+        }
+
+        if self.window_misses >= self.config.degrade_misses {
+            self.window_frames = 0;
+            self.window_misses = 0;
+            return BudgetAction::Degrade;
+        }
+        if self.window_frames >= self.config.miss_window {
+            self.window_frames = 0;
+            self.window_misses = 0;
+        }
+
+        if degraded && self.clean_frames >= self.config.recover_frames {
+            self.clean_frames = 0;
+            return BudgetAction::Recover;
+        }
+        BudgetAction::None
+    }
+
+    pub fn deadline_misses(&self) -> u64 {
+        self.deadline_misses
+    }
+
+    /// Smoothed processing time as a fraction of frame duration
+    pub fn load(&self) -> f64 {
+        self.load
+    }
+}
+
+/// Handle to the running processing thread
+pub struct ProcessingWorker {
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+    priority: Arc<RwLock<Option<ThreadPriority>>>,
+}
+
+impl ProcessingWorker {
+    /// Start processing frames from `tap` through `pipeline`
+    ///
+    /// Processed frames are published on `output`; timing goes into `stats`.
+    pub fn spawn(
+        tap: AudioRingBuffer,
+        pipeline: AudioProcessingPipeline,
+        config: WorkerConfig,
+        stats: Arc<RwLock<AudioStats>>,
+        output: broadcast::Sender<AudioBuffer>,
+    ) -> AudioResult<Self> {
+        config.validate()?;
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let priority = Arc::new(RwLock::new(None));
+        let thread_stop = Arc::clone(&stop);
+        let thread_priority = Arc::clone(&priority);
+
+        let handle = std::thread::Builder::new()
+            .name("audio-processing".to_string())
+            .spawn(move || {
+                let granted = if config.realtime_priority {
+                    request_realtime_priority()
+                } else {
+                    ThreadPriority::Normal
+                };
+                info!("Audio processing worker started with {:?} priority", granted);
+                if let Ok(mut slot) = thread_priority.write() {
+                    *slot = Some(granted);
+                }
+
+                run_worker(tap, pipeline, config, stats, output, thread_stop);
+            })
+            .map_err(|e| AudioError::Internal {
+                message: format!("Failed to start audio processing thread: {}", e),
+            })?;
+
+        Ok(Self { stop, handle: Some(handle), priority })
+    }
+
+    /// Scheduling the thread was given, once it has started
+    pub fn priority(&self) -> Option<ThreadPriority> {
+        self.priority.read().ok().and_then(|priority| *priority)
+    }
+
+    /// Process what is left in the tap, then end the thread
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            if handle.join().is_err() {
+                error!("Audio processing thread panicked");
+            }
+        }
+    }
+}
+
+impl Drop for ProcessingWorker {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+fn run_worker(
+    tap: AudioRingBuffer,
+    mut pipeline: AudioProcessingPipeline,
+    config: WorkerConfig,
+    stats: Arc<RwLock<AudioStats>>,
+    output: broadcast::Sender<AudioBuffer>,
+    stop: Arc<AtomicBool>,
+) {
+    let sample_rate = tap.sample_rate();
+    let channels = tap.channels();
+    let frame_frames = ((sample_rate as f64 * config.frame_ms / 1000.0).round() as usize).max(1);
+    let frame_len = frame_frames * channels.max(1) as usize;
+    let poll = Duration::from_secs_f64(config.frame_ms / 4000.0);
+
+    let mut tracker = BudgetTracker::new(config);
+    let mut frame = vec![0.0f32; frame_len];
+
+    loop {
+        let stopping = stop.load(Ordering::Relaxed);
+        let available = tap.available();
+
+        // Whole frames while running; whatever remains once stopping
+        let len = if available >= frame_len {
+            frame_len
+        } else if stopping && available > 0 {
+            available - available % channels.max(1) as usize
+        } else {
+            0
+        };
+
+        if len == 0 {
+            if stopping {
+                break;
+            }
+            std::thread::sleep(poll);
+            continue;
+        }
+
+        let read = match tap.read(&mut frame[..len]) {
+            Ok(read) => read,
+            Err(e) => {
+                error!("Audio processing worker failed to read audio: {}", e);
+                break;
+            }
+        };
+        if read == 0 {
+            break;
+        }
+
+        let started = Instant::now();
+        let buffer = AudioBuffer::new(frame[..read].to_vec(), sample_rate, channels);
+        match pipeline.process(buffer) {
+            Ok(processed) => {
+                let _ = output.send(processed);
+            }
+            Err(e) => warn!("Audio processing failed: {}", e),
+        }
+        let elapsed = started.elapsed();
+
+        match tracker.record(elapsed, pipeline.disabled_stages() > 0) {
+            BudgetAction::Degrade => {
+                if pipeline.disable_optional_stage() {
+                    warn!(
+                        "Audio processing over budget ({:?} for a {}ms frame), disabled an optional stage",
+                        elapsed, config.frame_ms
+                    );
+                }
+            }
+            BudgetAction::Recover => {
+                if pipeline.enable_optional_stage() {
+                    info!("Audio processing back on budget, re-enabled an optional stage");
+                }
+            }
+            BudgetAction::None => {}
+        }
+
+        if let Ok(mut stats) = stats.write() {
+            stats.deadline_misses = tracker.deadline_misses();
+            stats.processing_load = tracker.load() as f32;
+            stats.degraded_stages = pipeline.disabled_stages() as u32;
+        }
+    }
+
+    debug!("Audio processing worker ended");
+}
+
+/// Ask for real-time scheduling for the calling thread, falling back to a
+/// raised priority and then to leaving it alone
+#[cfg(unix)]
+fn request_realtime_priority() -> ThreadPriority {
+    // SAFETY: only the calling thread's scheduling is changed, with a
+    // fully initialised parameter struct
+    unsafe {
+        let policy = libc::SCHED_FIFO;
+        let min = libc::sched_get_priority_min(policy);
+        let max = libc::sched_get_priority_max(policy);
+        if min >= 0 && max >= min {
+            // Stay in the lower half so device and system threads still win
+            let param = libc::sched_param { sched_priority: min + (max - min) / 4 };
+            if libc::pthread_setschedparam(libc::pthread_self(), policy, &param) == 0 {
+                return ThreadPriority::Realtime;
+            }
+        }
+
+        #[cfg(target_os = "linux")]
+        {
+            // Per-thread nice value
+            let tid = libc::syscall(libc::SYS_gettid) as libc::id_t;
+            if libc::setpriority(libc::PRIO_PROCESS, tid, -10) == 0 {
+                return ThreadPriority::Elevated;
+            }
+        }
+    }
+
+    debug!("Real-time scheduling not permitted for the audio processing thread");
+    ThreadPriority::Normal
+}
+
+#[cfg(not(unix))]
+fn request_realtime_priority() -> ThreadPriority {
+    debug!("Real-time scheduling not supported on this platform");
+    ThreadPriority::Normal
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::types::AudioProcessor;
+
+    /// Stage that takes a fixed time per buffer
+    struct SlowProcessor(Duration);
+
+    impl AudioProcessor for SlowProcessor {
+        fn process(&mut self, _buffer: &AudioBuffer) -> AudioResult<()> {
+            std::thread::sleep(self.0);
+            Ok(())
+        }
+
+        fn stats(&self) -> AudioStats {
+            AudioStats::default()
+        }
+    }
+
+    #[test]
+    fn test_budget_tracker_degrades_and_recovers() {
+        let config = WorkerConfig { miss_window: 10, degrade_misses: 3, recover_frames: 20, ..Default::default() };
+        let mut tracker = BudgetTracker::new(config);
+        let slow = Duration::from_millis(15);
+        let fast = Duration::from_millis(2);
+
+        // Misses spread over separate windows don't add up
+        for i in 0..30 {
+            let elapsed = if i % 10 == 0 { slow } else { fast };
+            assert_eq!(tracker.record(elapsed, false), BudgetAction::None, "frame {}", i);
+        }
+        assert_eq!(tracker.deadline_misses(), 3);
+
+        assert_eq!(tracker.record(slow, false), BudgetAction::None);
+        assert_eq!(tracker.record(slow, false), BudgetAction::None);
+        assert_eq!(tracker.record(slow, false), BudgetAction::Degrade);
+
+        let actions: Vec<_> = (0..20).map(|_| tracker.record(fast, true)).collect();
+        assert_eq!(actions.iter().filter(|a| **a == BudgetAction::Recover).count(), 1);
+        assert_eq!(actions[19], BudgetAction::Recover);
+        assert_eq!(tracker.deadline_misses(), 6);
+    }
+
+    #[test]
+    fn test_worker_sheds_slow_stage_and_reports_misses() {
+        let tap = AudioRingBuffer::new(16000, 16000, 1);
+        let stats = Arc::new(RwLock::new(AudioStats::default()));
+        let (output, mut processed) = broadcast::channel(64);
+
+        // 20ms frames with a 4ms budget, and a stage that takes 10ms
+        let config = WorkerConfig {
+            budget_ratio: 0.2,
+            miss_window: 4,
+            degrade_misses: 2,
+            realtime_priority: false,
+            ..Default::default()
+        };
+        let mut pipeline = AudioProcessingPipeline::new();
+        pipeline.add_optional_processor(Box::new(SlowProcessor(Duration::from_millis(10))));
+
+        // 400ms of audio plus a partial frame
+        tap.write(&vec![0.1; 6400 + 100]).unwrap();
+        let worker = ProcessingWorker::spawn(tap.clone(), pipeline, config, Arc::clone(&stats), output).unwrap();
+        worker.stop();
+
+        let stats = stats.read().unwrap().clone();
+        assert!(stats.deadline_misses >= 2, "{:?}", stats);
+        assert!(stats.deadline_misses < 20, "slow stage kept running: {:?}", stats);
+        assert_eq!(stats.degraded_stages, 1);
+
+        let mut samples = 0;
+        while let Ok(buffer) = processed.try_recv() {
+            samples += buffer.samples.len();
+        }
+        assert_eq!(samples, 6500, "every sample is processed, including the tail");
+        assert_eq!(tap.available(), 0);
+    }
+
+    #[test]
+    fn test_worker_config_validation() {
+        assert!(WorkerConfig::default().validate().is_ok());
+        assert!(WorkerConfig { budget_ratio: 0.0, ..Default::default() }.validate().is_err());
+        assert!(WorkerConfig { degrade_misses: 60, ..Default::default() }.validate().is_err());
+    }
+}
@@ -0,0 +1,70 @@
+//! Tauri command handlers for application information
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+/// Version of the running build
+#[derive(Debug, Serialize, Clone)]
+pub struct AppVersion {
+    pub version: String,
+    /// "development" for debug builds, "release" otherwise
+    pub build: String,
+}
+
+/// What the application is
+#[derive(Debug, Serialize, Clone)]
+pub struct AppInfo {
+    pub name: String,
+    pub version: String,
+    pub description: String,
+}
+
+/// State of the backend components
+#[derive(Debug, Serialize, Clone)]
+pub struct HealthStatus {
+    pub status: String,
+    pub timestamp: DateTime<Utc>,
+    pub components: ComponentHealth,
+}
+
+/// Whether each component has been started
+#[derive(Debug, Serialize, Clone)]
+pub struct ComponentHealth {
+    pub database: String,
+    pub audio: String,
+    pub ai: String,
+}
+
+/// Get the application version
+#[tauri::command]
+pub async fn get_app_version() -> Result<AppVersion, String> {
+    Ok(AppVersion {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        build: if cfg!(debug_assertions) { "development" } else { "release" }.to_string(),
+    })
+}
+
+/// Get the application's name, version and description
+#[tauri::command]
+pub async fn get_app_info() -> Result<AppInfo, String> {
+    Ok(AppInfo {
+        name: "MeetingMind".to_string(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        description: "Privacy-first AI Meeting Assistant".to_string(),
+    })
+}
+
+/// Report that the backend is up; components are started on demand by
+/// their own init commands
+#[tauri::command]
+pub async fn health_check() -> Result<HealthStatus, String> {
+    Ok(HealthStatus {
+        status: "healthy".to_string(),
+        timestamp: Utc::now(),
+        components: ComponentHealth {
+            database: "not_initialized".to_string(),
+            audio: "not_initialized".to_string(),
+            ai: "not_initialized".to_string(),
+        },
+    })
+}
@@ -1,14 +1,26 @@
 //! Tauri command handlers for audio operations
 
-use std::sync::{Arc, Mutex};
-use tauri::{State, Manager, AppHandle, Window};
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tauri::{State, AppHandle, Emitter};
 use serde::{Serialize, Deserialize};
 use tracing::{info, error, debug};
 
 use crate::audio::{
-    AudioCaptureService, AudioDevice, AudioCaptureStatus, AudioStats,
-    AudioConfig, AudioFormat, AudioError
+    AudioCaptureService, AudioFileImporter, AudioDevice, AudioCaptureStatus, AudioStats,
+    AudioConfig, AudioFormat, ChannelMap, PeakPyramid, PeakSlice,
+    AudioQualityReport, QualityThresholds, ArchiveMode, ArchiveSettings, ArchiveSummary,
+    QualityEvent, ValidationConfig, WorkerConfig
 };
+use crate::config::AppConfig;
+use crate::meeting::{
+    archive_meeting_audio, finish_live_meeting, import_recording, start_live_meeting, ImportedMeeting,
+    MeetingImportProgress,
+};
+use crate::storage::{DatabaseService, JobPriority};
+use crate::transcription::{track_roles, TranscriptionService};
+use super::transcription::{transcription_queue, TranscriptionQueueState, TranscriptionServiceState, TranscriptUpdateEvent};
 
 /// Audio service state managed by Tauri
 pub type AudioServiceState = Arc<Mutex<Option<AudioCaptureService>>>;
@@ -18,6 +30,16 @@ pub type AudioServiceState = Arc<Mutex<Option<AudioCaptureService>>>;
 pub struct StartCaptureRequest {
     pub device_name: Option<String>,
     pub config: Option<AudioCaptureConfig>,
+    /// Opus file the recording is encoded to when archiving while recording;
+    /// defaults to one in the recordings directory
+    #[serde(default)]
+    pub archive_path: Option<String>,
+    /// Overrides the configured archive settings for this capture
+    #[serde(default)]
+    pub archive_settings: Option<ArchiveSettings>,
+    /// Title of the meeting the capture is stored as
+    #[serde(default)]
+    pub title: Option<String>,
 }
 
 /// Audio configuration for frontend
@@ -26,6 +48,19 @@ pub struct AudioCaptureConfig {
     pub sample_rate: u32,
     pub channels: u16,
     pub buffer_size: usize,
+    #[serde(default)]
+    pub channel_map: ChannelMap,
+    #[serde(default)]
+    pub quality_thresholds: QualityThresholds,
+    /// Second input device captured as an extra, drift-corrected track
+    #[serde(default)]
+    pub secondary_device: Option<String>,
+    /// Thresholds for buffer validation and quality events
+    #[serde(default)]
+    pub validation: ValidationConfig,
+    /// Scheduling and CPU budget of the processing thread
+    #[serde(default)]
+    pub worker: WorkerConfig,
 }
 
 impl From<AudioCaptureConfig> for AudioConfig {
@@ -35,6 +70,11 @@ impl From<AudioCaptureConfig> for AudioConfig {
             channels: config.channels,
             buffer_size: config.buffer_size,
             format: AudioFormat::F32,
+            channel_map: config.channel_map,
+            quality_thresholds: config.quality_thresholds,
+            secondary_device: config.secondary_device,
+            validation: config.validation,
+            worker: config.worker,
         }
     }
 }
@@ -45,6 +85,11 @@ impl From<AudioConfig> for AudioCaptureConfig {
             sample_rate: config.sample_rate,
             channels: config.channels,
             buffer_size: config.buffer_size,
+            channel_map: config.channel_map,
+            quality_thresholds: config.quality_thresholds,
+            secondary_device: config.secondary_device,
+            validation: config.validation,
+            worker: config.worker,
         }
     }
 }
@@ -65,6 +110,14 @@ pub struct AudioStatusEvent {
     pub timestamp: u64,
 }
 
+/// Audio quality event from buffer validation
+#[derive(Debug, Serialize, Clone)]
+pub struct AudioQualityEvent {
+    #[serde(flatten)]
+    pub event: QualityEvent,
+    pub timestamp: u64,
+}
+
 /// Audio device change event
 #[derive(Debug, Serialize, Clone)]
 pub struct AudioDeviceChangeEvent {
@@ -79,15 +132,19 @@ pub async fn init_audio_service(
 ) -> Result<(), String> {
     info!("Initializing audio service");
     
-    let mut audio_service_guard = audio_state.lock()
-        .map_err(|e| format!("Failed to acquire audio service lock: {}", e))?;
+    let mut audio_service_guard = audio_state.lock().await;
     
     if audio_service_guard.is_some() {
         debug!("Audio service already initialized");
         return Ok(());
     }
     
-    match AudioCaptureService::new() {
+    // Validation thresholds and capture defaults come from the app config
+    let config = AppConfig::load()
+        .and_then(|config| config.validate().map(|_| config))
+        .map_err(|e| format!("Failed to load configuration: {}", e))?;
+    
+    match AudioCaptureService::with_config(config.capture_config()) {
         Ok(service) => {
             *audio_service_guard = Some(service);
             info!("Audio service initialized successfully");
@@ -107,8 +164,7 @@ pub async fn get_audio_input_devices(
 ) -> Result<Vec<AudioDevice>, String> {
     debug!("Getting audio input devices");
     
-    let audio_service_guard = audio_state.lock()
-        .map_err(|e| format!("Failed to acquire audio service lock: {}", e))?;
+    let audio_service_guard = audio_state.lock().await;
     
     match audio_service_guard.as_ref() {
         Some(service) => {
@@ -135,12 +191,13 @@ pub async fn get_audio_input_devices(
 pub async fn start_audio_capture(
     request: StartCaptureRequest,
     audio_state: State<'_, AudioServiceState>,
+    transcription_state: State<'_, TranscriptionServiceState>,
+    db_state: State<'_, DatabaseService>,
     app_handle: AppHandle,
-) -> Result<(), String> {
+) -> Result<i64, String> {
     info!("Starting audio capture with request: {:?}", request);
     
-    let mut audio_service_guard = audio_state.lock()
-        .map_err(|e| format!("Failed to acquire audio service lock: {}", e))?;
+    let mut audio_service_guard = audio_state.lock().await;
     
     match audio_service_guard.as_mut() {
         Some(service) => {
@@ -149,6 +206,32 @@ pub async fn start_audio_capture(
                 service.set_config(config.into());
             }
             
+            // Only archiving while recording involves capture; the job queue
+            // archives after the session
+            let storage = AppConfig::load()
+                .map_err(|e| format!("Failed to load configuration: {}", e))?
+                .storage;
+            let archive = request.archive_settings.unwrap_or(storage.archive);
+            let live_archive = match archive.mode {
+                ArchiveMode::WhileRecording => {
+                    let path = match request.archive_path {
+                        Some(path) => PathBuf::from(path),
+                        None => {
+                            std::fs::create_dir_all(&storage.recordings_dir)
+                                .map_err(|e| format!("Failed to create recordings directory: {}", e))?;
+                            let name = format!("meeting-{}.opus", chrono::Local::now().format("%Y%m%d-%H%M%S"));
+                            storage.recordings_dir.join(name)
+                        }
+                    };
+                    Some((path, archive))
+                }
+                ArchiveMode::Disabled | ArchiveMode::AfterSession => None,
+            };
+            if let Err(e) = service.archive_while_recording(live_archive) {
+                error!("Failed to set up live archive: {}", e);
+                return Err(format!("Failed to set up live archive: {}", e));
+            }
+            
             // Switch device if specified
             if let Some(device_name) = request.device_name {
                 if let Err(e) = service.switch_device(&device_name).await {
@@ -162,10 +245,51 @@ pub async fn start_audio_capture(
                 Ok(()) => {
                     info!("Audio capture started successfully");
                     
-                    // Start event broadcasting
-                    start_audio_event_broadcasting(service, &app_handle).await;
+                    let meeting_id = match start_live_meeting(&db_state, request.title).await {
+                        Ok(meeting_id) => meeting_id,
+                        Err(e) => {
+                            error!("Failed to create meeting for capture: {}", e);
+                            let _ = service.stop_capture().await;
+                            return Err(format!("Failed to create meeting for capture: {}", e));
+                        }
+                    };
                     
-                    Ok(())
+                    // Start event broadcasting, with live transcripts if transcription is set up
+                    let transcription_service_guard = transcription_state.lock()
+                        .map_err(|e| format!("Failed to acquire transcription service lock: {}", e))?;
+                    start_audio_event_broadcasting(service, transcription_service_guard.as_ref(), &app_handle);
+                    let live_transcript = transcription_service_guard.as_ref()
+                        .map(|transcription| transcription.stream_capture(service.subscribe_processed_audio()));
+                    drop(transcription_service_guard);
+                    let live_archive = service.take_live_archive();
+                    
+                    // Store the session once capture stops
+                    let db = db_state.inner().clone();
+                    let mut stopped = service.stop_signal();
+                    tokio::spawn(async move {
+                        let transcript = match live_transcript {
+                            Some(handle) => match handle.await {
+                                Ok(transcript) => Some(transcript),
+                                Err(e) => {
+                                    error!("Live transcription task failed: {}", e);
+                                    None
+                                }
+                            },
+                            None => {
+                                let _ = stopped.changed().await;
+                                None
+                            }
+                        };
+                        let archive = match live_archive {
+                            Some(handle) => handle.await.ok().flatten(),
+                            None => None,
+                        };
+                        if let Err(e) = finish_live_meeting(&db, meeting_id, transcript.as_ref(), archive.as_ref()).await {
+                            error!("Failed to store live meeting {}: {}", meeting_id, e);
+                        }
+                    });
+                    
+                    Ok(meeting_id)
                 }
                 Err(e) => {
                     error!("Failed to start audio capture: {}", e);
@@ -187,8 +311,7 @@ pub async fn stop_audio_capture(
 ) -> Result<(), String> {
     info!("Stopping audio capture");
     
-    let mut audio_service_guard = audio_state.lock()
-        .map_err(|e| format!("Failed to acquire audio service lock: {}", e))?;
+    let mut audio_service_guard = audio_state.lock().await;
     
     match audio_service_guard.as_mut() {
         Some(service) => {
@@ -217,8 +340,7 @@ pub async fn get_audio_capture_status(
 ) -> Result<AudioCaptureStatus, String> {
     debug!("Getting audio capture status");
     
-    let audio_service_guard = audio_state.lock()
-        .map_err(|e| format!("Failed to acquire audio service lock: {}", e))?;
+    let audio_service_guard = audio_state.lock().await;
     
     match audio_service_guard.as_ref() {
         Some(service) => {
@@ -236,8 +358,7 @@ pub async fn get_audio_capture_status(
 pub async fn get_audio_levels(
     audio_state: State<'_, AudioServiceState>,
 ) -> Result<AudioLevelEvent, String> {
-    let audio_service_guard = audio_state.lock()
-        .map_err(|e| format!("Failed to acquire audio service lock: {}", e))?;
+    let audio_service_guard = audio_state.lock().await;
     
     match audio_service_guard.as_ref() {
         Some(service) => {
@@ -262,8 +383,7 @@ pub async fn get_audio_stats(
 ) -> Result<AudioStats, String> {
     debug!("Getting audio statistics");
     
-    let audio_service_guard = audio_state.lock()
-        .map_err(|e| format!("Failed to acquire audio service lock: {}", e))?;
+    let audio_service_guard = audio_state.lock().await;
     
     match audio_service_guard.as_ref() {
         Some(service) => {
@@ -276,6 +396,94 @@ pub async fn get_audio_stats(
     }
 }
 
+/// Get the audio quality report for the current recording session
+#[tauri::command]
+pub async fn get_audio_quality_report(
+    audio_state: State<'_, AudioServiceState>,
+) -> Result<AudioQualityReport, String> {
+    debug!("Getting audio quality report");
+    
+    let audio_service_guard = audio_state.lock().await;
+    
+    match audio_service_guard.as_ref() {
+        Some(service) => {
+            service.quality_report()
+                .map_err(|e| format!("Failed to get audio quality report: {}", e))
+        }
+        None => {
+            error!("Audio service not initialized");
+            Err("Audio service not initialized".to_string())
+        }
+    }
+}
+
+/// Get waveform peaks for a time range of the current recording
+#[tauri::command]
+pub async fn get_waveform_peaks(
+    start_ms: f64,
+    end_ms: f64,
+    max_points: usize,
+    audio_state: State<'_, AudioServiceState>,
+) -> Result<PeakSlice, String> {
+    let audio_service_guard = audio_state.lock().await;
+    
+    match audio_service_guard.as_ref() {
+        Some(service) => {
+            service.waveform_peaks(start_ms, end_ms, max_points)
+                .map_err(|e| format!("Failed to get waveform peaks: {}", e))
+        }
+        None => {
+            error!("Audio service not initialized");
+            Err("Audio service not initialized".to_string())
+        }
+    }
+}
+
+/// Write the current recording's waveform overview next to its audio file
+#[tauri::command]
+pub async fn save_waveform_peaks(
+    audio_path: String,
+    audio_state: State<'_, AudioServiceState>,
+) -> Result<String, String> {
+    info!("Saving waveform overview for: {}", audio_path);
+    
+    let audio_service_guard = audio_state.lock().await;
+    
+    match audio_service_guard.as_ref() {
+        Some(service) => {
+            match service.save_waveform_peaks(&PathBuf::from(&audio_path)) {
+                Ok(path) => Ok(path.to_string_lossy().into_owned()),
+                Err(e) => {
+                    error!("Failed to save waveform overview: {}", e);
+                    Err(format!("Failed to save waveform overview: {}", e))
+                }
+            }
+        }
+        None => {
+            error!("Audio service not initialized");
+            Err("Audio service not initialized".to_string())
+        }
+    }
+}
+
+/// Load waveform peaks for a past recording from its sidecar file
+#[tauri::command]
+pub async fn load_waveform_peaks(
+    audio_path: String,
+    start_ms: f64,
+    end_ms: f64,
+    max_points: usize,
+) -> Result<PeakSlice, String> {
+    debug!("Loading waveform overview for: {}", audio_path);
+    
+    let sidecar = PeakPyramid::sidecar_path(&PathBuf::from(&audio_path));
+    let pyramid = PeakPyramid::load(&sidecar)
+        .map_err(|e| format!("Failed to load waveform overview: {}", e))?;
+    
+    let level = pyramid.level_for_width(start_ms, end_ms, max_points);
+    Ok(pyramid.peaks(start_ms, end_ms, level))
+}
+
 /// Set audio device
 #[tauri::command]
 pub async fn set_audio_device(
@@ -284,8 +492,7 @@ pub async fn set_audio_device(
 ) -> Result<(), String> {
     info!("Setting audio device to: {}", device_name);
     
-    let mut audio_service_guard = audio_state.lock()
-        .map_err(|e| format!("Failed to acquire audio service lock: {}", e))?;
+    let mut audio_service_guard = audio_state.lock().await;
     
     match audio_service_guard.as_mut() {
         Some(service) => {
@@ -314,8 +521,7 @@ pub async fn get_audio_config(
 ) -> Result<AudioCaptureConfig, String> {
     debug!("Getting audio configuration");
     
-    let audio_service_guard = audio_state.lock()
-        .map_err(|e| format!("Failed to acquire audio service lock: {}", e))?;
+    let audio_service_guard = audio_state.lock().await;
     
     match audio_service_guard.as_ref() {
         Some(service) => {
@@ -336,8 +542,7 @@ pub async fn set_audio_config(
 ) -> Result<(), String> {
     info!("Updating audio configuration: {:?}", config);
     
-    let mut audio_service_guard = audio_state.lock()
-        .map_err(|e| format!("Failed to acquire audio service lock: {}", e))?;
+    let mut audio_service_guard = audio_state.lock().await;
     
     match audio_service_guard.as_mut() {
         Some(service) => {
@@ -352,6 +557,103 @@ pub async fn set_audio_config(
     }
 }
 
+/// Import an existing recording as a meeting
+#[tauri::command]
+pub async fn import_audio_file(
+    path: String,
+    title: Option<String>,
+    audio_state: State<'_, AudioServiceState>,
+    db_state: State<'_, DatabaseService>,
+    queue_state: State<'_, TranscriptionQueueState>,
+    app_handle: AppHandle,
+) -> Result<ImportedMeeting, String> {
+    info!("Importing audio file: {}", path);
+    
+    // The recording is transcribed by the job queue, so it has to be running
+    let queue = transcription_queue(&queue_state)?;
+    
+    // Convert to the same format live capture uses
+    let config = {
+        let audio_service_guard = audio_state.lock().await;
+        audio_service_guard.as_ref()
+            .map(|service| service.config().clone())
+            .unwrap_or_default()
+    };
+    
+    let (progress_tx, mut progress_rx) = tokio::sync::mpsc::unbounded_channel::<MeetingImportProgress>();
+    
+    // Forward progress to the frontend
+    let app_handle_progress = app_handle.clone();
+    tokio::spawn(async move {
+        while let Some(progress) = progress_rx.recv().await {
+            if let Err(e) = app_handle_progress.emit("audio_import_progress", &progress) {
+                error!("Failed to emit import progress event: {}", e);
+            }
+        }
+    });
+    
+    let imported = import_recording(
+        &db_state,
+        AudioFileImporter::new(config),
+        &PathBuf::from(&path),
+        title,
+        None,
+        progress_tx,
+    )
+    .await
+    .map_err(|e| {
+        error!("Failed to import audio file: {}", e);
+        format!("Failed to import audio file: {}", e)
+    })?;
+    
+    queue.enqueue(imported.meeting_id, JobPriority::Normal)
+        .await
+        .map_err(|e| {
+            error!("Failed to queue transcription of imported meeting {}: {}", imported.meeting_id, e);
+            format!("Failed to queue transcription: {}", e)
+        })?;
+    
+    Ok(imported)
+}
+
+/// Compress a finished meeting's recording to Opus
+#[tauri::command]
+pub async fn archive_meeting_recording(
+    meeting_id: i64,
+    settings: Option<ArchiveSettings>,
+    audio_state: State<'_, AudioServiceState>,
+    db_state: State<'_, DatabaseService>,
+) -> Result<ArchiveSummary, String> {
+    info!("Archiving recording of meeting {}", meeting_id);
+    
+    let config = {
+        let audio_service_guard = audio_state.lock().await;
+        audio_service_guard.as_ref()
+            .map(|service| service.config().clone())
+            .unwrap_or_default()
+    };
+    
+    archive_meeting_audio(&db_state, meeting_id, config, settings.unwrap_or_default())
+        .await
+        .map_err(|e| {
+            error!("Failed to archive meeting recording: {}", e);
+            format!("Failed to archive meeting recording: {}", e)
+        })
+}
+
+/// Get the archive written during the last recording, once it is complete
+#[tauri::command]
+pub async fn get_live_archive_summary(
+    audio_state: State<'_, AudioServiceState>,
+) -> Result<Option<ArchiveSummary>, String> {
+    let audio_service_guard = audio_state.lock().await;
+    
+    match audio_service_guard.as_ref() {
+        Some(service) => Ok(service.live_archive_summary()),
+        None => Err("Audio service not initialized".to_string()),
+    }
+}
+
 /// Refresh audio device list
 #[tauri::command]
 pub async fn refresh_audio_devices(
@@ -360,8 +662,7 @@ pub async fn refresh_audio_devices(
 ) -> Result<Vec<AudioDevice>, String> {
     info!("Refreshing audio devices");
     
-    let audio_service_guard = audio_state.lock()
-        .map_err(|e| format!("Failed to acquire audio service lock: {}", e))?;
+    let audio_service_guard = audio_state.lock().await;
     
     match audio_service_guard.as_ref() {
         Some(service) => {
@@ -382,7 +683,7 @@ pub async fn refresh_audio_devices(
                         timestamp: chrono::Utc::now().timestamp_millis() as u64,
                     };
                     
-                    if let Err(e) = app_handle.emit_all("audio_devices_changed", &event) {
+                    if let Err(e) = app_handle.emit("audio_devices_changed", &event) {
                         error!("Failed to emit device change event: {}", e);
                     }
                     
@@ -402,15 +703,19 @@ pub async fn refresh_audio_devices(
 }
 
 /// Start broadcasting audio events to the frontend
-async fn start_audio_event_broadcasting(
+fn start_audio_event_broadcasting(
     service: &mut AudioCaptureService,
+    transcription: Option<&TranscriptionService>,
     app_handle: &AppHandle,
 ) {
     let mut status_rx = service.subscribe_status();
     let mut level_rx = service.subscribe_levels();
+    let mut quality_rx = service.subscribe_quality_events();
+    let mut capture_stopped = service.stop_signal();
     
     let app_handle_status = app_handle.clone();
     let app_handle_level = app_handle.clone();
+    let app_handle_quality = app_handle.clone();
     
     // Spawn status event broadcaster
     tokio::spawn(async move {
@@ -420,7 +725,7 @@ async fn start_audio_event_broadcasting(
                 timestamp: chrono::Utc::now().timestamp_millis() as u64,
             };
             
-            if let Err(e) = app_handle_status.emit_all("audio_status_changed", &event) {
+            if let Err(e) = app_handle_status.emit("audio_status_changed", &event) {
                 error!("Failed to emit status change event: {}", e);
             }
         }
@@ -445,7 +750,7 @@ async fn start_audio_event_broadcasting(
                     timestamp: chrono::Utc::now().timestamp_millis() as u64,
                 };
                 
-                if let Err(e) = app_handle_level.emit_all("audio_level_update", &event) {
+                if let Err(e) = app_handle_level.emit("audio_level_update", &event) {
                     error!("Failed to emit level update event: {}", e);
                 } else {
                     last_emit = std::time::Instant::now();
@@ -453,4 +758,63 @@ async fn start_audio_event_broadcasting(
             }
         }
     });
-}
\ No newline at end of file
+    
+    // Spawn quality event broadcaster for this capture; events are already rate-limited
+    tokio::spawn(async move {
+        loop {
+            // Events still queued when capture stops are forwarded first
+            let received = tokio::select! {
+                biased;
+                received = quality_rx.recv() => received,
+                _ = capture_stopped.changed() => break,
+            };
+            let event = match received {
+                Ok(event) => event,
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                    debug!("Skipped {} audio quality events", skipped);
+                    continue;
+                }
+                Err(_) => break,
+            };
+            
+            let event = AudioQualityEvent {
+                event,
+                timestamp: chrono::Utc::now().timestamp_millis() as u64,
+            };
+            
+            if let Err(e) = app_handle_quality.emit("audio_quality_event", &event) {
+                error!("Failed to emit audio quality event: {}", e);
+            }
+        }
+    });
+    
+    // Forward live transcript updates; the caller starts the transcriber
+    if let Some(transcription) = transcription {
+        let mut transcript_rx = transcription.subscribe_transcript();
+        let roles = track_roles(service.track_count(), service.config().secondary_device.is_some());
+        transcription.attribute_tracks(service.subscribe_track_audio(), roles);
+        let app_handle_transcript = app_handle.clone();
+        
+        tokio::spawn(async move {
+            loop {
+                let event = match transcript_rx.recv().await {
+                    Ok(event) => event,
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                        error!("Skipped {} transcript updates", skipped);
+                        continue;
+                    }
+                    Err(_) => break,
+                };
+                
+                let event = TranscriptUpdateEvent {
+                    event,
+                    timestamp: chrono::Utc::now().timestamp_millis() as u64,
+                };
+                
+                if let Err(e) = app_handle_transcript.emit("transcript_update", &event) {
+                    error!("Failed to emit transcript update: {}", e);
+                }
+            }
+        });
+    }
+}
@@ -1,8 +1,11 @@
-// Audio commands temporarily disabled for basic testing
-// pub mod audio;
+pub mod app;
+pub mod audio;
+pub mod transcription;
 
 // Re-export all command functions
-// pub use audio::*;
+pub use app::*;
+pub use audio::*;
+pub use transcription::*;
 
 #[cfg(test)]
-mod tests;
\ No newline at end of file
+mod tests;
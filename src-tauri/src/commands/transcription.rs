@@ -0,0 +1,652 @@
+//! Tauri command handlers for transcription
+
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Emitter, State};
+use serde::Serialize;
+use tracing::{info, error};
+
+use crate::config::{AIConfig, AppConfig};
+use crate::meeting;
+use crate::meeting::{JobEvent, RetranscriptionBatch, TextChange, TranscriptionQueue, VersionComparison};
+use crate::storage::{
+    DatabaseService, JobPriority, MeetingSpeaker, SegmentEdit, Speaker, StoredSegment, StoredVocabularyTerm, StoredWord, Transcript,
+    TranscriptRevision, TranscriptionJob, VocabularyScope,
+};
+use crate::transcription::{
+    configured_engine, CaptionConfig, CaptionFormat, DiskUsage, Hardware, HttpDownloader, InstalledModel,
+    LanguageSwitch, ModelKind, ModelRegistry, TranscriptEvent, TranscriptionService, TranscriptionStats,
+    Vocabulary, VocabularyTerm,
+};
+
+/// Transcription service state managed by Tauri
+pub type TranscriptionServiceState = Arc<Mutex<Option<TranscriptionService>>>;
+
+/// Background transcription job queue managed by Tauri
+pub type TranscriptionQueueState = Arc<Mutex<Option<TranscriptionQueue>>>;
+
+/// Live transcript update event
+#[derive(Debug, Serialize, Clone)]
+pub struct TranscriptUpdateEvent {
+    #[serde(flatten)]
+    pub event: TranscriptEvent,
+    pub timestamp: u64,
+}
+
+/// Initialize the transcription service with the configured engine
+#[tauri::command]
+pub async fn init_transcription_service(
+    transcription_state: State<'_, TranscriptionServiceState>,
+) -> Result<String, String> {
+    info!("Initializing transcription service");
+
+    let config = AppConfig::load().map_err(|e| format!("Failed to load configuration: {}", e))?;
+    config.validate().map_err(|e| format!("Invalid configuration: {}", e))?;
+
+    // Model loading and the cloud client's setup both block
+    let ai = config.ai.clone();
+    let engine = tokio::task::spawn_blocking(move || configured_engine(&ai))
+        .await
+        .map_err(|e| format!("Transcription engine setup failed: {}", e))?
+        .map_err(|e| {
+            error!("Failed to load transcription engine: {}", e);
+            format!("Failed to load transcription engine: {}", e)
+        })?;
+    let service = TranscriptionService::with_config(engine, config.ai.transcription.clone());
+    let engine_name = service.engine_name().to_string();
+
+    let mut transcription_service_guard = transcription_state.lock()
+        .map_err(|e| format!("Failed to acquire transcription service lock: {}", e))?;
+    *transcription_service_guard = Some(service);
+
+    info!("Transcription service initialized with {}", engine_name);
+    Ok(engine_name)
+}
+
+/// Get transcription statistics
+#[tauri::command]
+pub async fn get_transcription_stats(
+    transcription_state: State<'_, TranscriptionServiceState>,
+) -> Result<TranscriptionStats, String> {
+    let transcription_service_guard = transcription_state.lock()
+        .map_err(|e| format!("Failed to acquire transcription service lock: {}", e))?;
+
+    match transcription_service_guard.as_ref() {
+        Some(service) => Ok(service.stats()),
+        None => Err("Transcription service not initialized".to_string()),
+    }
+}
+
+/// Pin a meeting's language, or restrict detection to candidate languages
+///
+/// The live transcription service picks the setting up for its next session.
+#[tauri::command]
+pub async fn set_meeting_languages(
+    meeting_id: i64,
+    pinned: Option<String>,
+    candidates: Vec<String>,
+    db_state: State<'_, DatabaseService>,
+    transcription_state: State<'_, TranscriptionServiceState>,
+) -> Result<(), String> {
+    info!("Setting languages for meeting {}: pinned {:?}, candidates {:?}", meeting_id, pinned, candidates);
+
+    db_state.meetings()
+        .set_languages(meeting_id, pinned.as_deref(), &candidates)
+        .await
+        .map_err(|e| {
+            error!("Failed to set meeting languages: {}", e);
+            format!("Failed to set meeting languages: {}", e)
+        })?;
+
+    let mut transcription_service_guard = transcription_state.lock()
+        .map_err(|e| format!("Failed to acquire transcription service lock: {}", e))?;
+    if let Some(service) = transcription_service_guard.as_mut() {
+        service.set_language_policy(pinned, candidates);
+    }
+
+    Ok(())
+}
+
+/// Get the points in a stored transcript where the spoken language changes
+#[tauri::command]
+pub async fn get_language_switches(
+    transcription_id: i64,
+    db_state: State<'_, DatabaseService>,
+) -> Result<Vec<LanguageSwitch>, String> {
+    db_state.transcripts()
+        .language_switches(transcription_id)
+        .await
+        .map_err(|e| format!("Failed to load language switches: {}", e))
+}
+
+/// Get the words of a stored transcript spoken between two timeline positions
+#[tauri::command]
+pub async fn get_words_between(
+    transcription_id: i64,
+    start_ms: f64,
+    end_ms: f64,
+    db_state: State<'_, DatabaseService>,
+) -> Result<Vec<StoredWord>, String> {
+    db_state.transcripts()
+        .words_between(transcription_id, start_ms, end_ms)
+        .await
+        .map_err(|e| format!("Failed to load words: {}", e))
+}
+
+/// Work out who spoke each segment of a meeting's transcript
+///
+/// Voices recognised from earlier meetings come back with their names.
+#[tauri::command]
+pub async fn diarize_meeting(
+    meeting_id: i64,
+    db_state: State<'_, DatabaseService>,
+) -> Result<Vec<MeetingSpeaker>, String> {
+    info!("Diarizing meeting {}", meeting_id);
+
+    let config = AppConfig::load()
+        .and_then(|config| config.validate().map(|_| config))
+        .map_err(|e| format!("Failed to load configuration: {}", e))?;
+
+    meeting::diarize_meeting(&db_state, meeting_id, config.capture_config(), config.ai.diarization)
+        .await
+        .map_err(|e| {
+            error!("Failed to diarize meeting: {}", e);
+            format!("Failed to diarize meeting: {}", e)
+        })
+}
+
+/// Make a readable copy of a transcript: punctuated sentences, numbers as
+/// digits and, if configured, without fillers
+///
+/// The transcript it was made from is kept as it is.
+#[tauri::command]
+pub async fn format_transcript(
+    transcription_id: i64,
+    db_state: State<'_, DatabaseService>,
+) -> Result<Transcript, String> {
+    info!("Formatting transcript {}", transcription_id);
+
+    let config = AppConfig::load()
+        .and_then(|config| config.validate().map(|_| config))
+        .map_err(|e| format!("Failed to load configuration: {}", e))?;
+
+    meeting::format_transcript(&db_state, transcription_id, config.ai.formatting)
+        .await
+        .map_err(|e| {
+            error!("Failed to format transcript: {}", e);
+            format!("Failed to format transcript: {}", e)
+        })
+}
+
+/// Export a stored transcript as SRT, WebVTT or TTML captions
+///
+/// `options` replace the configured caption settings for this export, e.g.
+/// to shift the cues by an offset.
+#[tauri::command]
+pub async fn export_captions(
+    transcription_id: i64,
+    format: CaptionFormat,
+    options: Option<CaptionConfig>,
+    db_state: State<'_, DatabaseService>,
+) -> Result<String, String> {
+    let config = match options {
+        Some(options) => options,
+        None => AppConfig::load()
+            .and_then(|config| config.validate().map(|_| config))
+            .map_err(|e| format!("Failed to load configuration: {}", e))?
+            .ai
+            .captions,
+    };
+
+    meeting::export_captions(&db_state, transcription_id, format, config)
+        .await
+        .map_err(|e| {
+            error!("Failed to export captions: {}", e);
+            format!("Failed to export captions: {}", e)
+        })
+}
+
+/// Get a stored transcript's segments with their words
+#[tauri::command]
+pub async fn get_transcript_segments(
+    transcription_id: i64,
+    db_state: State<'_, DatabaseService>,
+) -> Result<Vec<StoredSegment>, String> {
+    db_state.transcripts()
+        .segments(transcription_id)
+        .await
+        .map_err(|e| format!("Failed to load transcript segments: {}", e))
+}
+
+/// Correct a transcript: change a segment's text or speaker, split or merge
+/// segments, or undo an earlier edit
+#[tauri::command]
+pub async fn edit_transcript(
+    transcription_id: i64,
+    edit: SegmentEdit,
+    author: String,
+    db_state: State<'_, DatabaseService>,
+) -> Result<TranscriptRevision, String> {
+    meeting::edit_transcript(&db_state, transcription_id, edit, &author)
+        .await
+        .map_err(|e| {
+            error!("Failed to edit transcript: {}", e);
+            format!("Failed to edit transcript: {}", e)
+        })
+}
+
+/// Get every edit made to a transcript, oldest first
+#[tauri::command]
+pub async fn get_transcript_history(
+    transcription_id: i64,
+    db_state: State<'_, DatabaseService>,
+) -> Result<Vec<TranscriptRevision>, String> {
+    db_state.transcripts()
+        .revisions(transcription_id)
+        .await
+        .map_err(|e| format!("Failed to load transcript history: {}", e))
+}
+
+/// Get the words an edit changed
+#[tauri::command]
+pub async fn get_revision_diff(
+    revision_id: i64,
+    db_state: State<'_, DatabaseService>,
+) -> Result<Vec<TextChange>, String> {
+    let revision = db_state.transcripts()
+        .revision(revision_id)
+        .await
+        .map_err(|e| format!("Failed to load revision: {}", e))?
+        .ok_or_else(|| format!("Revision {} not found", revision_id))?;
+
+    Ok(meeting::revision_diff(&revision))
+}
+
+/// Find transcripts containing every word of a query
+#[tauri::command]
+pub async fn search_transcripts(
+    query: String,
+    db_state: State<'_, DatabaseService>,
+) -> Result<Vec<Transcript>, String> {
+    db_state.transcripts()
+        .search(&query)
+        .await
+        .map_err(|e| format!("Failed to search transcripts: {}", e))
+}
+
+/// Get the voices heard in a meeting
+#[tauri::command]
+pub async fn get_meeting_speakers(
+    meeting_id: i64,
+    db_state: State<'_, DatabaseService>,
+) -> Result<Vec<MeetingSpeaker>, String> {
+    db_state.speakers()
+        .meeting_speakers(meeting_id)
+        .await
+        .map_err(|e| format!("Failed to load meeting speakers: {}", e))
+}
+
+/// Name a voice heard in a meeting so it is recognised from now on
+#[tauri::command]
+pub async fn name_meeting_speaker(
+    meeting_speaker_id: i64,
+    name: String,
+    db_state: State<'_, DatabaseService>,
+) -> Result<Speaker, String> {
+    meeting::name_meeting_speaker(&db_state, meeting_speaker_id, &name)
+        .await
+        .map_err(|e| {
+            error!("Failed to name meeting speaker: {}", e);
+            format!("Failed to name meeting speaker: {}", e)
+        })
+}
+
+/// Rename a known speaker in every meeting they were heard in
+#[tauri::command]
+pub async fn rename_speaker(
+    speaker_id: i64,
+    name: String,
+    db_state: State<'_, DatabaseService>,
+) -> Result<Speaker, String> {
+    meeting::rename_speaker(&db_state, speaker_id, &name)
+        .await
+        .map_err(|e| {
+            error!("Failed to rename speaker: {}", e);
+            format!("Failed to rename speaker: {}", e)
+        })
+}
+
+/// Get every known speaker, most recently heard first
+#[tauri::command]
+pub async fn list_speakers(
+    db_state: State<'_, DatabaseService>,
+) -> Result<Vec<Speaker>, String> {
+    db_state.speakers()
+        .list()
+        .await
+        .map_err(|e| format!("Failed to load speakers: {}", e))
+}
+
+/// Teach transcription a term, for every meeting, a project or one meeting
+#[tauri::command]
+pub async fn add_vocabulary_term(
+    term: VocabularyTerm,
+    scope: VocabularyScope,
+    db_state: State<'_, DatabaseService>,
+) -> Result<i64, String> {
+    info!("Adding vocabulary term {:?} ({:?})", term.term, scope);
+
+    db_state.vocabulary()
+        .add(&term, &scope)
+        .await
+        .map_err(|e| {
+            error!("Failed to add vocabulary term: {}", e);
+            format!("Failed to add vocabulary term: {}", e)
+        })
+}
+
+/// Get the terms added to a scope
+#[tauri::command]
+pub async fn list_vocabulary(
+    scope: VocabularyScope,
+    db_state: State<'_, DatabaseService>,
+) -> Result<Vec<StoredVocabularyTerm>, String> {
+    db_state.vocabulary()
+        .list(&scope)
+        .await
+        .map_err(|e| format!("Failed to load vocabulary: {}", e))
+}
+
+/// Remove a vocabulary term
+#[tauri::command]
+pub async fn delete_vocabulary_term(
+    term_id: i64,
+    db_state: State<'_, DatabaseService>,
+) -> Result<(), String> {
+    db_state.vocabulary()
+        .delete(term_id)
+        .await
+        .map_err(|e| format!("Failed to delete vocabulary term: {}", e))
+}
+
+/// File a meeting under a project, so the project's vocabulary applies to it
+#[tauri::command]
+pub async fn set_meeting_project(
+    meeting_id: i64,
+    project: Option<String>,
+    db_state: State<'_, DatabaseService>,
+) -> Result<(), String> {
+    db_state.meetings()
+        .set_project(meeting_id, project.as_deref())
+        .await
+        .map_err(|e| format!("Failed to set meeting project: {}", e))
+}
+
+/// Prime live transcription with a meeting's vocabulary, title and attendees
+///
+/// Call before recording starts; the service picks it up for its next session.
+#[tauri::command]
+pub async fn apply_meeting_vocabulary(
+    meeting_id: i64,
+    db_state: State<'_, DatabaseService>,
+    transcription_state: State<'_, TranscriptionServiceState>,
+) -> Result<Vocabulary, String> {
+    let meeting = db_state.meetings()
+        .get(meeting_id)
+        .await
+        .map_err(|e| format!("Failed to load meeting: {}", e))?
+        .ok_or_else(|| format!("Meeting {} not found", meeting_id))?;
+    let terms = db_state.vocabulary()
+        .for_meeting(meeting_id)
+        .await
+        .map_err(|e| format!("Failed to load vocabulary: {}", e))?;
+    let vocabulary = meeting.vocabulary(terms);
+
+    info!(
+        "Applying vocabulary of meeting {}: {} terms, {} participants",
+        meeting_id, vocabulary.terms.len(), vocabulary.participants.len()
+    );
+
+    let mut transcription_service_guard = transcription_state.lock()
+        .map_err(|e| format!("Failed to acquire transcription service lock: {}", e))?;
+    if let Some(service) = transcription_service_guard.as_mut() {
+        service.set_vocabulary(vocabulary.clone());
+    }
+
+    Ok(vocabulary)
+}
+
+/// Open the model registry in the configured models directory
+fn model_registry() -> Result<(ModelRegistry, AIConfig), String> {
+    let config = AppConfig::load()
+        .and_then(|config| config.validate().map(|_| config))
+        .map_err(|e| format!("Failed to load configuration: {}", e))?;
+    let registry = ModelRegistry::open(&config.ai.models.directory)
+        .map_err(|e| format!("Failed to open model registry: {}", e))?;
+    Ok((registry, config.ai))
+}
+
+/// Get the installed speech and embedding models
+#[tauri::command]
+pub async fn list_models() -> Result<Vec<InstalledModel>, String> {
+    let (registry, _) = model_registry()?;
+    Ok(registry.models().to_vec())
+}
+
+/// Import a model file, or every model in a directory, into the registry
+///
+/// Published checksums next to the files are checked before anything is
+/// installed.
+#[tauri::command]
+pub async fn import_models(path: String) -> Result<Vec<InstalledModel>, String> {
+    info!("Importing models from {}", path);
+
+    // Copying and hashing multi-gigabyte files blocks
+    tokio::task::spawn_blocking(move || {
+        let (mut registry, _) = model_registry()?;
+        registry.import(std::path::Path::new(&path)).map_err(|e| {
+            error!("Failed to import models: {}", e);
+            format!("Failed to import models: {}", e)
+        })
+    })
+    .await
+    .map_err(|e| format!("Model import failed: {}", e))?
+}
+
+/// Download a model file from the configured mirror, checking it against
+/// `sha256` when given
+#[tauri::command]
+pub async fn download_model(file: String, sha256: Option<String>) -> Result<InstalledModel, String> {
+    info!("Downloading model {}", file);
+
+    tokio::task::spawn_blocking(move || {
+        let (mut registry, ai) = model_registry()?;
+        let base_url = ai.models.download_url.ok_or("Model downloads are not enabled")?;
+        let downloader = HttpDownloader::new(&base_url).map_err(|e| e.to_string())?;
+        registry.download(&downloader, &file, sha256.as_deref()).map_err(|e| {
+            error!("Failed to download model: {}", e);
+            format!("Failed to download model: {}", e)
+        })
+    })
+    .await
+    .map_err(|e| format!("Model download failed: {}", e))?
+}
+
+/// Check an installed model still matches the SHA-256 it was installed with
+#[tauri::command]
+pub async fn verify_model(model_id: String) -> Result<InstalledModel, String> {
+    tokio::task::spawn_blocking(move || {
+        let (registry, _) = model_registry()?;
+        registry.verify(&model_id)
+            .cloned()
+            .map_err(|e| format!("Model verification failed: {}", e))
+    })
+    .await
+    .map_err(|e| format!("Model verification failed: {}", e))?
+}
+
+/// Uninstall a model and delete its file
+#[tauri::command]
+pub async fn remove_model(model_id: String) -> Result<InstalledModel, String> {
+    let (mut registry, _) = model_registry()?;
+    registry.remove(&model_id)
+        .map_err(|e| format!("Failed to remove model: {}", e))
+}
+
+/// Get the disk space taken by installed models and left on the disk
+#[tauri::command]
+pub async fn get_model_disk_usage() -> Result<DiskUsage, String> {
+    let (registry, _) = model_registry()?;
+    Ok(registry.disk_usage())
+}
+
+/// The installed model of `kind` best suited to this machine and the
+/// configured languages
+#[tauri::command]
+pub async fn recommend_model(kind: ModelKind) -> Result<Option<InstalledModel>, String> {
+    let (registry, ai) = model_registry()?;
+    let languages: Vec<String> = ai.transcription.language.iter()
+        .chain(&ai.transcription.candidate_languages)
+        .cloned()
+        .collect();
+
+    Ok(registry.best_for(kind, &languages, &Hardware::detect()).cloned())
+}
+
+/// Start the background job queue with the transcription service's engine
+///
+/// Jobs interrupted when the app last stopped resume from their last
+/// checkpoint. Queue changes are emitted as `transcription_job` events.
+#[tauri::command]
+pub async fn start_transcription_queue(
+    db_state: State<'_, DatabaseService>,
+    transcription_state: State<'_, TranscriptionServiceState>,
+    queue_state: State<'_, TranscriptionQueueState>,
+    app_handle: AppHandle,
+) -> Result<(), String> {
+    let config = AppConfig::load()
+        .and_then(|config| config.validate().map(|_| config))
+        .map_err(|e| format!("Failed to load configuration: {}", e))?;
+    let engine = transcription_state.lock()
+        .map_err(|e| format!("Failed to acquire transcription service lock: {}", e))?
+        .as_ref()
+        .map(|service| service.engine())
+        .ok_or("Transcription service not initialized")?;
+
+    let mut queue_guard = queue_state.lock()
+        .map_err(|e| format!("Failed to acquire transcription queue lock: {}", e))?;
+    if queue_guard.is_some() {
+        return Ok(());
+    }
+
+    info!("Starting transcription job queue");
+    let queue = TranscriptionQueue::new(db_state.inner().clone(), engine, config.capture_config(), config.ai.jobs)
+        .with_archive(config.storage.archive);
+    let mut events = queue.subscribe();
+    tokio::spawn(async move {
+        loop {
+            let event: JobEvent = match events.recv().await {
+                Ok(event) => event,
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                    error!("Skipped {} transcription job events", skipped);
+                    continue;
+                }
+                Err(_) => break,
+            };
+
+            if let Err(e) = app_handle.emit("transcription_job", &event) {
+                error!("Failed to emit transcription job event: {}", e);
+            }
+        }
+    });
+    queue.start();
+    *queue_guard = Some(queue);
+
+    Ok(())
+}
+
+/// Queue a meeting's recording for transcription
+#[tauri::command]
+pub async fn enqueue_transcription(
+    meeting_id: i64,
+    priority: JobPriority,
+    queue_state: State<'_, TranscriptionQueueState>,
+) -> Result<TranscriptionJob, String> {
+    let queue = transcription_queue(&queue_state)?;
+    queue.enqueue(meeting_id, priority)
+        .await
+        .map_err(|e| {
+            error!("Failed to queue transcription: {}", e);
+            format!("Failed to queue transcription: {}", e)
+        })
+}
+
+/// Cancel a transcription job; false if it had already finished
+#[tauri::command]
+pub async fn cancel_transcription_job(
+    job_id: i64,
+    queue_state: State<'_, TranscriptionQueueState>,
+) -> Result<bool, String> {
+    let queue = transcription_queue(&queue_state)?;
+    queue.cancel(job_id)
+        .await
+        .map_err(|e| format!("Failed to cancel transcription job: {}", e))
+}
+
+/// Get every transcription job, unfinished ones first
+#[tauri::command]
+pub async fn list_transcription_jobs(
+    db_state: State<'_, DatabaseService>,
+) -> Result<Vec<TranscriptionJob>, String> {
+    db_state.jobs()
+        .list()
+        .await
+        .map_err(|e| format!("Failed to load transcription jobs: {}", e))
+}
+
+/// Queue meetings to be transcribed again with the current model and
+/// settings, each into a new version of its transcript
+#[tauri::command]
+pub async fn retranscribe_meetings(
+    meeting_ids: Vec<i64>,
+    db_state: State<'_, DatabaseService>,
+    queue_state: State<'_, TranscriptionQueueState>,
+) -> Result<RetranscriptionBatch, String> {
+    let queue = transcription_queue(&queue_state)?;
+    meeting::retranscribe_meetings(&db_state, &queue, &meeting_ids)
+        .await
+        .map_err(|e| {
+            error!("Failed to queue re-transcription: {}", e);
+            format!("Failed to queue re-transcription: {}", e)
+        })
+}
+
+/// Get every version of a meeting's transcript, oldest first
+#[tauri::command]
+pub async fn get_transcript_versions(
+    meeting_id: i64,
+    db_state: State<'_, DatabaseService>,
+) -> Result<Vec<Transcript>, String> {
+    db_state.transcripts()
+        .for_meeting(meeting_id)
+        .await
+        .map_err(|e| format!("Failed to load transcript versions: {}", e))
+}
+
+/// Compare two versions of a meeting's transcript
+#[tauri::command]
+pub async fn compare_transcript_versions(
+    before_id: i64,
+    after_id: i64,
+    db_state: State<'_, DatabaseService>,
+) -> Result<VersionComparison, String> {
+    meeting::compare_versions(&db_state, before_id, after_id)
+        .await
+        .map_err(|e| format!("Failed to compare transcript versions: {}", e))
+}
+
+pub(super) fn transcription_queue(queue_state: &TranscriptionQueueState) -> Result<TranscriptionQueue, String> {
+    queue_state.lock()
+        .map_err(|e| format!("Failed to acquire transcription queue lock: {}", e))?
+        .clone()
+        .ok_or_else(|| "Transcription queue not started".to_string())
+}
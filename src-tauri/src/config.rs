@@ -2,7 +2,12 @@
 
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
+use crate::audio::{self, ArchiveSettings, ChannelMap, QualityThresholds, ValidationConfig, WorkerConfig};
 use crate::error::{AppError, AppResult};
+use crate::meeting::JobQueueConfig;
+use crate::transcription::{
+    CaptionConfig, DiarizationConfig, FormattingConfig, ModelsConfig, RoutingConfig, TranscriptionConfig,
+};
 
 /// Main application configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -16,6 +21,9 @@ pub struct AppConfig {
     /// Database configuration
     pub database: DatabaseConfig,
     
+    /// Recording storage settings
+    pub storage: StorageConfig,
+    
     /// AI/ML model settings
     pub ai: AIConfig,
     
@@ -45,6 +53,26 @@ pub struct AudioConfig {
     
     /// Preferred audio device name (None for system default)
     pub preferred_device: Option<String>,
+    
+    /// How the device's input channels become capture tracks
+    #[serde(default)]
+    pub channel_map: ChannelMap,
+    
+    /// Levels below which live recording quality is warned about
+    #[serde(default)]
+    pub quality_thresholds: QualityThresholds,
+    
+    /// Second input device (e.g. a loopback) captured as an extra track
+    #[serde(default)]
+    pub secondary_device: Option<String>,
+    
+    /// Thresholds for buffer validation and live quality events
+    #[serde(default)]
+    pub validation: ValidationConfig,
+    
+    /// Scheduling and CPU budget of the audio processing thread
+    #[serde(default)]
+    pub worker: WorkerConfig,
 }
 
 /// Database configuration
@@ -60,20 +88,63 @@ pub struct DatabaseConfig {
     pub enable_wal: bool,
 }
 
+/// Recording storage configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StorageConfig {
+    /// Directory recordings and their archives are written to
+    pub recordings_dir: PathBuf,
+    
+    /// Opus archiving of recordings, and whether originals are kept
+    pub archive: ArchiveSettings,
+}
+
 /// AI/ML configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AIConfig {
-    /// Local Whisper model path
+    /// Local Whisper model path (whisper.cpp GGML file), used when no
+    /// speech model is installed in the model registry
     pub whisper_model_path: PathBuf,
     
     /// Whisper model size (tiny, base, small, medium, large)
     pub whisper_model_size: String,
     
-    /// Enable cloud API fallback
+    /// CPU threads per Whisper transcription (0 for automatic)
+    #[serde(default)]
+    pub whisper_threads: u32,
+    
+    /// Send low-confidence or unsupported-language audio to a cloud API
     pub enable_cloud_fallback: bool,
     
     /// OpenAI API key (optional)
     pub openai_api_key: Option<String>,
+    
+    /// Chunking and live streaming of transcription
+    #[serde(default)]
+    pub transcription: TranscriptionConfig,
+    
+    /// When and how much audio goes to the cloud API
+    #[serde(default)]
+    pub cloud_routing: RoutingConfig,
+    
+    /// How voices are grouped into speakers and recognised across meetings
+    #[serde(default)]
+    pub diarization: DiarizationConfig,
+    
+    /// How stored transcripts are punctuated, cleaned up and split into sentences
+    #[serde(default)]
+    pub formatting: FormattingConfig,
+    
+    /// Installed models, which one to use and where downloads come from
+    #[serde(default)]
+    pub models: ModelsConfig,
+    
+    /// Chunking and retries of background transcription jobs
+    #[serde(default)]
+    pub jobs: JobQueueConfig,
+    
+    /// How exported subtitles are cut into cues
+    #[serde(default)]
+    pub captions: CaptionConfig,
 }
 
 /// Security configuration
@@ -102,17 +173,34 @@ impl Default for AppConfig {
                 buffer_size: 1024,
                 channels: 1,         // Mono for speech recognition
                 preferred_device: None,
+                channel_map: ChannelMap::Average,
+                quality_thresholds: QualityThresholds::default(),
+                secondary_device: None,
+                validation: ValidationConfig::default(),
+                worker: WorkerConfig::default(),  // 20ms frames, half of each as budget
             },
             database: DatabaseConfig {
                 path: PathBuf::from("meetings.db"),
                 max_connections: 10,
                 enable_wal: true,
             },
+            storage: StorageConfig {
+                recordings_dir: PathBuf::from("recordings"),
+                archive: ArchiveSettings::default(),  // 24 kbps Opus, originals kept
+            },
             ai: AIConfig {
-                whisper_model_path: PathBuf::from("models/whisper-base.onnx"),
+                whisper_model_path: PathBuf::from("models/ggml-base.bin"),
                 whisper_model_size: "base".to_string(),
+                whisper_threads: 0,
                 enable_cloud_fallback: false,
                 openai_api_key: None,
+                transcription: TranscriptionConfig::default(),
+                cloud_routing: RoutingConfig::default(),
+                diarization: DiarizationConfig::default(),
+                formatting: FormattingConfig::default(),
+                models: ModelsConfig::default(),
+                jobs: JobQueueConfig::default(),
+                captions: CaptionConfig::default(),
             },
             security: SecurityConfig {
                 enable_encryption: true,
@@ -151,8 +239,52 @@ impl AppConfig {
             return Err(AppError::config("Maximum connections must be greater than 0"));
         }
         
+        self.storage.archive.validate()
+            .map_err(|e| AppError::config(e.to_string()))?;
+        
+        self.audio.validation.validate()
+            .map_err(|e| AppError::config(e.to_string()))?;
+        
+        self.audio.worker.validate()
+            .map_err(|e| AppError::config(e.to_string()))?;
+        
+        self.ai.transcription.validate()
+            .map_err(|e| AppError::config(e.to_string()))?;
+        
+        self.ai.cloud_routing.validate()
+            .map_err(|e| AppError::config(e.to_string()))?;
+        
+        self.ai.diarization.validate()
+            .map_err(|e| AppError::config(e.to_string()))?;
+        
+        self.ai.formatting.validate()
+            .map_err(|e| AppError::config(e.to_string()))?;
+        
+        self.ai.models.validate()
+            .map_err(|e| AppError::config(e.to_string()))?;
+        
+        self.ai.jobs.validate()?;
+        
+        self.ai.captions.validate()
+            .map_err(|e| AppError::config(e.to_string()))?;
+        
         Ok(())
     }
+    
+    /// Capture settings for the audio service
+    pub fn capture_config(&self) -> audio::AudioConfig {
+        audio::AudioConfig {
+            sample_rate: self.audio.sample_rate,
+            channels: self.audio.channels,
+            buffer_size: self.audio.buffer_size as usize,
+            channel_map: self.audio.channel_map.clone(),
+            quality_thresholds: self.audio.quality_thresholds,
+            secondary_device: self.audio.secondary_device.clone(),
+            validation: self.audio.validation,
+            worker: self.audio.worker,
+            ..audio::AudioConfig::default()
+        }
+    }
 }
 
 #[cfg(test)]
@@ -1,6 +1,6 @@
 #[cfg(test)]
 mod tests {
-    use super::*;
+    use super::super::*;
 
     #[test]
     fn test_default_config_creation() {
@@ -84,4 +84,53 @@ mod tests {
             panic!("Expected Config error");
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_config_validation_rejects_invalid_settings() {
+        // Each case breaks one setting of an otherwise valid config
+        type Invalidate = fn(&mut AppConfig);
+        let cases: Vec<(&str, Invalidate)> = vec![
+            ("inverted validation thresholds", |c| {
+                c.audio.validation.min_sample_rate = 48000;
+                c.audio.validation.max_sample_rate = 16000;
+            }),
+            ("streaming step longer than the window", |c| {
+                c.ai.transcription.streaming.step_ms = 5000.0;
+                c.ai.transcription.streaming.max_window_ms = 4000.0;
+            }),
+            ("speaker threshold out of range", |c| c.ai.diarization.cluster_threshold = 2.0),
+            ("sentence segments too short", |c| c.ai.formatting.max_segment_ms = 500.0),
+            ("non-HTTP model download URL", |c| {
+                c.ai.models.download_url = Some("ftp://models.example.com".to_string())
+            }),
+            ("no job attempts", |c| c.ai.jobs.max_attempts = 0),
+            ("oversized caption lines", |c| c.ai.captions.max_line_chars = 500),
+        ];
+        
+        for (name, invalidate) in cases {
+            let mut config = AppConfig::default();
+            invalidate(&mut config);
+            assert!(matches!(config.validate(), Err(AppError::Config { .. })), "{} was accepted", name);
+        }
+    }
+
+    #[test]
+    fn test_capture_config_carries_validation_thresholds() {
+        // Given
+        let mut config = AppConfig::default();
+        config.audio.validation.clipping_threshold = 0.8;
+        config.audio.channel_map = ChannelMap::split(&[0, 1]);
+        config.audio.quality_thresholds.min_snr_db = 20.0;
+        config.audio.secondary_device = Some("Loopback".to_string());
+        
+        // When
+        let capture = config.capture_config();
+        
+        // Then
+        assert_eq!(capture.sample_rate, config.audio.sample_rate);
+        assert_eq!(capture.validation.clipping_threshold, 0.8);
+        assert_eq!(capture.channel_map, ChannelMap::split(&[0, 1]));
+        assert_eq!(capture.quality_thresholds.min_snr_db, 20.0);
+        assert_eq!(capture.secondary_device.as_deref(), Some("Loopback"));
+    }
+}
@@ -1,8 +1,8 @@
+pub mod audio;
+pub mod meeting;
+pub mod storage;
+pub mod transcription;
 // Disable these modules temporarily for basic testing
-// pub mod audio;
-// pub mod transcription;
-// pub mod storage;
-// pub mod meeting;
 // pub mod ai;
 // pub mod security;
 // pub mod integrations;
@@ -11,18 +11,89 @@ pub mod config;
 pub mod commands;
 pub mod error;
 
+use tauri::Manager;
 
-// Learn more about Tauri commands at https://tauri.app/v1/guides/features/command
-#[tauri::command]
-fn greet(name: &str) -> String {
-    format!("Hello, {}! You've been greeted from Rust!", name)
+use crate::commands::{AudioServiceState, TranscriptionQueueState, TranscriptionServiceState};
+use crate::config::AppConfig;
+use crate::storage::DatabaseService;
+
+/// Every command the frontend can invoke
+fn invoke_handler() -> impl Fn(tauri::ipc::Invoke) -> bool + Send + Sync + 'static {
+    tauri::generate_handler![
+        commands::app::get_app_version,
+        commands::app::get_app_info,
+        commands::app::health_check,
+        commands::audio::init_audio_service,
+        commands::audio::get_audio_input_devices,
+        commands::audio::start_audio_capture,
+        commands::audio::stop_audio_capture,
+        commands::audio::get_audio_capture_status,
+        commands::audio::get_audio_levels,
+        commands::audio::get_audio_stats,
+        commands::audio::get_audio_quality_report,
+        commands::audio::get_waveform_peaks,
+        commands::audio::save_waveform_peaks,
+        commands::audio::load_waveform_peaks,
+        commands::audio::set_audio_device,
+        commands::audio::get_audio_config,
+        commands::audio::set_audio_config,
+        commands::audio::import_audio_file,
+        commands::audio::archive_meeting_recording,
+        commands::audio::get_live_archive_summary,
+        commands::audio::refresh_audio_devices,
+        commands::transcription::init_transcription_service,
+        commands::transcription::get_transcription_stats,
+        commands::transcription::set_meeting_languages,
+        commands::transcription::get_language_switches,
+        commands::transcription::get_words_between,
+        commands::transcription::diarize_meeting,
+        commands::transcription::format_transcript,
+        commands::transcription::export_captions,
+        commands::transcription::get_transcript_segments,
+        commands::transcription::edit_transcript,
+        commands::transcription::get_transcript_history,
+        commands::transcription::get_revision_diff,
+        commands::transcription::search_transcripts,
+        commands::transcription::get_meeting_speakers,
+        commands::transcription::name_meeting_speaker,
+        commands::transcription::rename_speaker,
+        commands::transcription::list_speakers,
+        commands::transcription::add_vocabulary_term,
+        commands::transcription::list_vocabulary,
+        commands::transcription::delete_vocabulary_term,
+        commands::transcription::set_meeting_project,
+        commands::transcription::apply_meeting_vocabulary,
+        commands::transcription::list_models,
+        commands::transcription::import_models,
+        commands::transcription::download_model,
+        commands::transcription::verify_model,
+        commands::transcription::remove_model,
+        commands::transcription::get_model_disk_usage,
+        commands::transcription::recommend_model,
+        commands::transcription::start_transcription_queue,
+        commands::transcription::enqueue_transcription,
+        commands::transcription::cancel_transcription_job,
+        commands::transcription::list_transcription_jobs,
+        commands::transcription::retranscribe_meetings,
+        commands::transcription::get_transcript_versions,
+        commands::transcription::compare_transcript_versions,
+    ]
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_shell::init())
-        .invoke_handler(tauri::generate_handler![greet])
+        .manage(AudioServiceState::default())
+        .manage(TranscriptionServiceState::default())
+        .manage(TranscriptionQueueState::default())
+        .setup(|app| {
+            let config = AppConfig::load()?;
+            let db = tauri::async_runtime::block_on(DatabaseService::connect(&config.database))?;
+            app.manage(db);
+            Ok(())
+        })
+        .invoke_handler(invoke_handler())
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
-}
\ No newline at end of file
+}
@@ -0,0 +1,84 @@
+//! Archiving finished meeting recordings
+
+use std::path::PathBuf;
+use tracing::{error, info};
+
+use crate::audio::{archive_recording, ArchiveSettings, ArchiveSummary, AudioConfig};
+use crate::error::{AppError, AppResult};
+use crate::storage::DatabaseService;
+
+/// Compress a finished meeting's recording to Opus and record the archive
+///
+/// The original is removed, and forgotten by the meeting, unless `settings`
+/// keeps it. Meetings that are already archived are left alone.
+pub async fn archive_meeting_audio(
+    db: &DatabaseService,
+    meeting_id: i64,
+    config: AudioConfig,
+    settings: ArchiveSettings,
+) -> AppResult<ArchiveSummary> {
+    let meetings = db.meetings();
+    let meeting = meetings
+        .get(meeting_id)
+        .await?
+        .ok_or_else(|| AppError::database(format!("Meeting {} not found", meeting_id)))?;
+
+    if meeting.archive_file_path.is_some() {
+        return Err(AppError::audio(format!("Meeting {} is already archived", meeting_id)));
+    }
+    let source = meeting
+        .audio_file_path
+        .map(PathBuf::from)
+        .ok_or_else(|| AppError::audio(format!("Meeting {} has no recording", meeting_id)))?;
+
+    info!("Archiving recording of meeting {}: {}", meeting_id, source.display());
+
+    let summary = tokio::task::spawn_blocking(move || archive_recording(&source, &config, &settings))
+        .await
+        .map_err(|e| AppError::internal(format!("Archive task failed: {}", e)))?
+        .map_err(|e| {
+            error!("Failed to archive meeting {}: {}", meeting_id, e);
+            AppError::audio(e.to_string())
+        })?;
+
+    meetings
+        .set_archive(meeting_id, &summary.archive_path.to_string_lossy(), summary.original_kept)
+        .await?;
+
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::test_wav::write_wav;
+    use crate::audio::decode_archive;
+    use crate::storage::NewMeeting;
+
+    #[tokio::test]
+    async fn test_archive_replaces_recording_when_original_dropped() {
+        let path = std::env::temp_dir().join(format!("retro-{}.wav", uuid::Uuid::new_v4()));
+        write_wav(&path, 16000, 1, &vec![0.1; 16000 * 2]);
+
+        let db = DatabaseService::in_memory().await.unwrap();
+        let mut new_meeting = NewMeeting::recording("Retro");
+        new_meeting.audio_file_path = Some(path.to_string_lossy().into_owned());
+        let id = db.meetings().create(&new_meeting).await.unwrap();
+
+        let settings = ArchiveSettings { keep_original: false, ..Default::default() };
+        let summary = archive_meeting_audio(&db, id, AudioConfig::default(), settings).await.unwrap();
+
+        let meeting = db.meetings().get(id).await.unwrap().unwrap();
+        assert!(meeting.audio_file_path.is_none());
+        assert_eq!(meeting.playback_path(), Some(summary.archive_path.to_string_lossy().as_ref()));
+        assert!(!path.exists());
+
+        let decoded = decode_archive(&summary.archive_path).unwrap();
+        assert!((decoded.duration_ms() - 2000.0).abs() < 1.0);
+
+        // A second run has nothing left to do
+        assert!(archive_meeting_audio(&db, id, AudioConfig::default(), settings).await.is_err());
+
+        std::fs::remove_file(&summary.archive_path).ok();
+    }
+}
@@ -0,0 +1,75 @@
+//! Captions for a meeting's recording
+
+use std::collections::HashMap;
+
+use crate::error::{AppError, AppResult};
+use crate::storage::DatabaseService;
+use crate::transcription::{CaptionConfig, CaptionFormat, CaptionSegment, CaptionWriter};
+
+/// Write a stored transcript as captions, its segments named after the
+/// meeting speakers they were attributed to
+pub async fn export_captions(
+    db: &DatabaseService,
+    transcription_id: i64,
+    format: CaptionFormat,
+    config: CaptionConfig,
+) -> AppResult<String> {
+    config.validate().map_err(|e| AppError::config(e.to_string()))?;
+
+    let transcripts = db.transcripts();
+    let transcript = transcripts
+        .get(transcription_id)
+        .await?
+        .ok_or_else(|| AppError::database(format!("Transcript {} not found", transcription_id)))?;
+    let labels: HashMap<i64, String> = db
+        .speakers()
+        .meeting_speakers(transcript.meeting_id)
+        .await?
+        .into_iter()
+        .map(|speaker| (speaker.id, speaker.label))
+        .collect();
+
+    let segments: Vec<CaptionSegment> = transcripts
+        .segments(transcription_id)
+        .await?
+        .into_iter()
+        .map(|stored| CaptionSegment {
+            speaker: stored.meeting_speaker_id.and_then(|id| labels.get(&id).cloned()),
+            segment: stored.segment,
+        })
+        .collect();
+
+    Ok(CaptionWriter::new(config).write(format, &segments))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::NewMeeting;
+    use crate::transcription::TranscriptSegment;
+
+    #[tokio::test]
+    async fn test_stored_transcript_exports_with_offset() {
+        let db = DatabaseService::in_memory().await.unwrap();
+        let meeting_id = db.meetings().create(&NewMeeting::recording("Retro")).await.unwrap();
+        let segment = TranscriptSegment {
+            text: "what went well".to_string(),
+            start_ms: 2000.0,
+            end_ms: 3500.0,
+            confidence: 0.9,
+            language: "en".to_string(),
+            model: "mock".to_string(),
+            words: Vec::new(),
+            speaker_hint: None,
+            original_text: None,
+        };
+        let id = db.transcripts().create(meeting_id, &[segment], "mock", 0).await.unwrap();
+
+        let config = CaptionConfig { offset_ms: 1500.0, ..Default::default() };
+        let srt = export_captions(&db, id, CaptionFormat::Srt, config).await.unwrap();
+        assert_eq!(srt, "1\n00:00:03,500 --> 00:00:05,000\nwhat went well\n");
+
+        let invalid = CaptionConfig { max_lines: 0, ..Default::default() };
+        assert!(export_captions(&db, id, CaptionFormat::WebVtt, invalid).await.is_err());
+    }
+}
@@ -0,0 +1,360 @@
+//! Correcting transcripts by hand
+//!
+//! Every edit is stored as a revision holding the segments it touched,
+//! before and after, so the history of a transcript can be shown word by
+//! word and any edit undone. Undoing is itself a revision, and only works
+//! while the segments are as the edit left them.
+
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::error::{AppError, AppResult};
+use crate::storage::{DatabaseService, SegmentEdit, StoredSegment, TranscriptRevision};
+use crate::transcription::{spread_words, TranscriptSegment};
+
+/// A stretch of a revision's text, as a word-level diff
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TextChange {
+    Same { text: String },
+    Added { text: String },
+    Removed { text: String },
+}
+
+/// Apply an edit to a transcript and record it
+pub async fn edit_transcript(
+    db: &DatabaseService,
+    transcription_id: i64,
+    edit: SegmentEdit,
+    author: &str,
+) -> AppResult<TranscriptRevision> {
+    let author = author.trim();
+    if author.is_empty() {
+        return Err(AppError::config("Edits need an author"));
+    }
+
+    let transcripts = db.transcripts();
+    let transcript = transcripts
+        .get(transcription_id)
+        .await?
+        .ok_or_else(|| AppError::database(format!("Transcript {} not found", transcription_id)))?;
+
+    let (before, after) = match &edit {
+        SegmentEdit::Text { segment_id, text } => {
+            let segment = segment_of(db, transcription_id, *segment_id).await?;
+            let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+            if text.is_empty() {
+                return Err(AppError::config("Segment text cannot be empty; merge the segment instead"));
+            }
+            let mut edited = segment.clone();
+            edited.segment.words = spread_words(&segment.segment, &text);
+            edited.segment.text = text;
+            edited.is_edited = true;
+            (vec![segment], vec![edited])
+        }
+        SegmentEdit::Speaker { segment_id, meeting_speaker_id } => {
+            let segment = segment_of(db, transcription_id, *segment_id).await?;
+            let speaker_id = match meeting_speaker_id {
+                Some(id) => {
+                    let (heard, _) = db
+                        .speakers()
+                        .meeting_speaker(*id)
+                        .await?
+                        .filter(|(heard, _)| heard.meeting_id == transcript.meeting_id)
+                        .ok_or_else(|| AppError::database(format!("No speaker {} in this meeting", id)))?;
+                    heard.speaker_id
+                }
+                None => None,
+            };
+            let mut edited = segment.clone();
+            edited.meeting_speaker_id = *meeting_speaker_id;
+            edited.speaker_id = speaker_id;
+            edited.is_edited = true;
+            (vec![segment], vec![edited])
+        }
+        SegmentEdit::Split { segment_id, offset } => {
+            let segment = segment_of(db, transcription_id, *segment_id).await?;
+            let (first, second) = split_segment(&segment, *offset)?;
+            (vec![segment], vec![first, second])
+        }
+        SegmentEdit::Merge { segment_id } => {
+            let segments = transcripts.segments(transcription_id).await?;
+            let position = segments
+                .iter()
+                .position(|s| s.id == *segment_id)
+                .ok_or_else(|| AppError::database(format!("Segment {} not found in transcript", segment_id)))?;
+            let (first, second) = match &segments[position..] {
+                [first, second, ..] => (first.clone(), second.clone()),
+                _ => return Err(AppError::config("The last segment has nothing to merge with")),
+            };
+            let merged = merge_segments(&first, &second);
+            (vec![first, second], vec![merged])
+        }
+        SegmentEdit::Revert { revision_id } => {
+            let revision = transcripts
+                .revision(*revision_id)
+                .await?
+                .filter(|r| r.transcription_id == transcription_id)
+                .ok_or_else(|| AppError::database(format!("Revision {} not found in transcript", revision_id)))?;
+            if let Some(by) = revision.reverted_by {
+                return Err(AppError::config(format!("Revision {} was already undone by {}", revision_id, by)));
+            }
+
+            let mut current = Vec::with_capacity(revision.after.len());
+            for left in &revision.after {
+                match transcripts.segment(left.id).await? {
+                    Some(segment) if unchanged(&segment, left) => current.push(segment),
+                    _ => {
+                        return Err(AppError::config(format!(
+                            "Revision {} can't be undone: its segments have been edited since",
+                            revision_id
+                        )))
+                    }
+                }
+            }
+            (current, revision.before)
+        }
+    };
+
+    let revision = transcripts.revise(transcription_id, author, &edit, &before, after).await?;
+    info!("{} edited transcript {} (revision {})", author, transcription_id, revision.id);
+    Ok(revision)
+}
+
+/// Word-level diff of the text a revision changed
+pub fn revision_diff(revision: &TranscriptRevision) -> Vec<TextChange> {
+    let words = |segments: &[StoredSegment]| -> Vec<String> {
+        let mut segments: Vec<&StoredSegment> = segments.iter().collect();
+        segments.sort_by(|a, b| a.segment.start_ms.total_cmp(&b.segment.start_ms));
+        segments.iter().flat_map(|s| s.segment.text.split_whitespace().map(str::to_string)).collect()
+    };
+    diff_words(&words(&revision.before), &words(&revision.after))
+}
+
+/// Shortest edit between two word sequences, with runs of the same kind joined
+pub(super) fn diff_words(before: &[String], after: &[String]) -> Vec<TextChange> {
+    // Longest common subsequence of every pair of suffixes
+    let mut common = vec![vec![0usize; after.len() + 1]; before.len() + 1];
+    for i in (0..before.len()).rev() {
+        for j in (0..after.len()).rev() {
+            common[i][j] = if before[i] == after[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let mut changes: Vec<TextChange> = Vec::new();
+    let mut push = |change: TextChange| {
+        use TextChange::*;
+        match (changes.last_mut(), change) {
+            (Some(Same { text }), Same { text: word })
+            | (Some(Added { text }), Added { text: word })
+            | (Some(Removed { text }), Removed { text: word }) => {
+                text.push(' ');
+                text.push_str(&word);
+            }
+            (_, change) => changes.push(change),
+        }
+    };
+    let (mut i, mut j) = (0, 0);
+    while i < before.len() || j < after.len() {
+        if i < before.len() && j < after.len() && before[i] == after[j] {
+            push(TextChange::Same { text: before[i].clone() });
+            i += 1;
+            j += 1;
+        } else if i < before.len() && (j == after.len() || common[i + 1][j] >= common[i][j + 1]) {
+            push(TextChange::Removed { text: before[i].clone() });
+            i += 1;
+        } else {
+            push(TextChange::Added { text: after[j].clone() });
+            j += 1;
+        }
+    }
+    changes
+}
+
+async fn segment_of(db: &DatabaseService, transcription_id: i64, segment_id: i64) -> AppResult<StoredSegment> {
+    db.transcripts()
+        .segment(segment_id)
+        .await?
+        .filter(|s| s.transcription_id == transcription_id)
+        .ok_or_else(|| AppError::database(format!("Segment {} not found in transcript", segment_id)))
+}
+
+/// Whether a segment is still as a revision left it
+fn unchanged(current: &StoredSegment, recorded: &StoredSegment) -> bool {
+    let close = |a: f64, b: f64| (a - b).abs() < 0.01;
+    current.id == recorded.id
+        && current.speaker_id == recorded.speaker_id
+        && current.meeting_speaker_id == recorded.meeting_speaker_id
+        && current.segment.text == recorded.segment.text
+        && close(current.segment.start_ms, recorded.segment.start_ms)
+        && close(current.segment.end_ms, recorded.segment.end_ms)
+}
+
+/// Split a segment before the word at character `offset`
+///
+/// The first part keeps the segment's id; the second is new.
+fn split_segment(segment: &StoredSegment, offset: usize) -> AppResult<(StoredSegment, StoredSegment)> {
+    let text = &segment.segment.text;
+    // Character offset at which each word starts
+    let mut starts = Vec::new();
+    let mut in_word = false;
+    for (i, c) in text.chars().enumerate() {
+        if !c.is_whitespace() && !in_word {
+            starts.push(i);
+        }
+        in_word = !c.is_whitespace();
+    }
+    let at_word = starts.iter().rposition(|&start| start <= offset).unwrap_or(0);
+    let words: Vec<&str> = text.split_whitespace().collect();
+    if at_word == 0 || at_word >= words.len() {
+        return Err(AppError::config("A segment can only be split between two of its words"));
+    }
+
+    let timed = spread_words(&segment.segment, text);
+    let split_ms = timed[at_word].start_ms;
+    let mut first = segment.clone();
+    first.segment.text = words[..at_word].join(" ");
+    first.segment.end_ms = split_ms;
+    first.segment.words = timed[..at_word].to_vec();
+    first.segment.original_text = None;
+    first.is_edited = true;
+
+    let mut second = first.clone();
+    second.id = 0;
+    second.segment.text = words[at_word..].join(" ");
+    second.segment.start_ms = split_ms;
+    second.segment.end_ms = segment.segment.end_ms;
+    second.segment.words = timed[at_word..].to_vec();
+    Ok((first, second))
+}
+
+/// Join a segment with the one after it; the result keeps the first's id and speaker
+fn merge_segments(first: &StoredSegment, second: &StoredSegment) -> StoredSegment {
+    let (a, b) = (&first.segment, &second.segment);
+    let weight = |s: &TranscriptSegment| s.duration_ms().max(1.0) as f32;
+
+    let mut merged = first.clone();
+    merged.segment.text = format!("{} {}", a.text, b.text);
+    merged.segment.end_ms = a.end_ms.max(b.end_ms);
+    merged.segment.confidence = (a.confidence * weight(a) + b.confidence * weight(b)) / (weight(a) + weight(b));
+    merged.segment.words = spread_words(&first.segment, &a.text).into_iter().chain(spread_words(&second.segment, &b.text)).collect();
+    merged.segment.original_text = match (&a.original_text, &b.original_text) {
+        (None, None) => None,
+        (x, y) => Some(format!(
+            "{} {}",
+            x.as_deref().unwrap_or(&a.text),
+            y.as_deref().unwrap_or(&b.text)
+        )),
+    };
+    merged.is_edited = true;
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::NewMeeting;
+
+    fn segment(text: &str, start_ms: f64, end_ms: f64) -> TranscriptSegment {
+        TranscriptSegment {
+            text: text.to_string(),
+            start_ms,
+            end_ms,
+            confidence: 0.8,
+            language: "en".to_string(),
+            model: "mock".to_string(),
+            words: Vec::new(),
+            speaker_hint: None,
+            original_text: None,
+        }
+    }
+
+    async fn transcript(db: &DatabaseService) -> i64 {
+        let meeting_id = db.meetings().create(&NewMeeting::recording("Design review")).await.unwrap();
+        let segments = vec![
+            segment("we should ship the cooper netties change", 0.0, 3000.0),
+            segment("next week", 3000.0, 4000.0),
+            segment("any objections", 5000.0, 6000.0),
+        ];
+        db.transcripts().create(meeting_id, &segments, "mock", 0).await.unwrap()
+    }
+
+    fn texts(segments: &[StoredSegment]) -> Vec<&str> {
+        segments.iter().map(|s| s.segment.text.as_str()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_edits_are_recorded_and_searchable() {
+        let db = DatabaseService::in_memory().await.unwrap();
+        let id = transcript(&db).await;
+        let segments = db.transcripts().segments(id).await.unwrap();
+
+        let edit = SegmentEdit::Text { segment_id: segments[0].id, text: "we should ship the Kubernetes change".to_string() };
+        let revision = edit_transcript(&db, id, edit, "Ana").await.unwrap();
+        assert_eq!(revision.author, "Ana");
+        assert_eq!(
+            revision_diff(&revision),
+            vec![
+                TextChange::Same { text: "we should ship the".to_string() },
+                TextChange::Removed { text: "cooper netties".to_string() },
+                TextChange::Added { text: "Kubernetes".to_string() },
+                TextChange::Same { text: "change".to_string() },
+            ]
+        );
+
+        let edited = db.transcripts().segment(segments[0].id).await.unwrap().unwrap();
+        assert!(edited.is_edited);
+        assert_eq!(edited.segment.words.len(), 6);
+        assert_eq!(db.transcripts().search("kubernetes").await.unwrap().len(), 1);
+        assert!(db.transcripts().search("netties").await.unwrap().is_empty());
+        assert_eq!(db.transcripts().get(id).await.unwrap().unwrap().content, "we should ship the Kubernetes change next week any objections");
+
+        assert!(edit_transcript(&db, id, SegmentEdit::Text { segment_id: segments[1].id, text: " ".to_string() }, "Ana").await.is_err());
+        assert!(edit_transcript(&db, id, SegmentEdit::Merge { segment_id: segments[2].id }, "Ana").await.is_err());
+        assert!(edit_transcript(&db, id, SegmentEdit::Merge { segment_id: segments[0].id }, " ").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_split_merge_and_revert() {
+        let db = DatabaseService::in_memory().await.unwrap();
+        let id = transcript(&db).await;
+        let transcripts = db.transcripts();
+        let segments = transcripts.segments(id).await.unwrap();
+
+        // Merge "next week" into the sentence it belongs to
+        let merge = edit_transcript(&db, id, SegmentEdit::Merge { segment_id: segments[0].id }, "Ana").await.unwrap();
+        let merged = transcripts.segments(id).await.unwrap();
+        assert_eq!(texts(&merged), ["we should ship the cooper netties change next week", "any objections"]);
+        assert_eq!(merged[0].segment.end_ms, 4000.0);
+        assert_eq!(merged[0].segment.words.len(), 9);
+
+        // Split it again after "ship", mid-word offsets snap to the word's start
+        let split = SegmentEdit::Split { segment_id: merged[0].id, offset: 17 };
+        let split = edit_transcript(&db, id, split, "Bruno").await.unwrap();
+        let parts = transcripts.segments(id).await.unwrap();
+        assert_eq!(texts(&parts), ["we should ship", "the cooper netties change next week", "any objections"]);
+        assert_eq!(parts[0].segment.end_ms, parts[1].segment.start_ms);
+        assert_eq!(split.after.len(), 2);
+        assert!(edit_transcript(&db, id, SegmentEdit::Split { segment_id: parts[0].id, offset: 1 }, "Bruno").await.is_err());
+
+        // The merge can't be undone until the split that followed it is
+        assert!(edit_transcript(&db, id, SegmentEdit::Revert { revision_id: merge.id }, "Ana").await.is_err());
+        edit_transcript(&db, id, SegmentEdit::Revert { revision_id: split.id }, "Ana").await.unwrap();
+        let undo = edit_transcript(&db, id, SegmentEdit::Revert { revision_id: merge.id }, "Ana").await.unwrap();
+        let restored = transcripts.segments(id).await.unwrap();
+        assert_eq!(texts(&restored), texts(&segments));
+        assert_eq!(restored[0].id, segments[0].id);
+        assert!(!restored[0].is_edited);
+        assert_eq!(undo.edit, SegmentEdit::Revert { revision_id: merge.id });
+
+        let history = transcripts.revisions(id).await.unwrap();
+        assert_eq!(history.len(), 4);
+        assert_eq!(history[0].reverted_by, Some(undo.id));
+        assert!(edit_transcript(&db, id, SegmentEdit::Revert { revision_id: merge.id }, "Ana").await.is_err());
+        assert_eq!(transcripts.search("next week").await.unwrap().len(), 1);
+    }
+}
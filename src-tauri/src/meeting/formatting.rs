@@ -0,0 +1,154 @@
+//! Formatting stored transcripts for reading
+
+use std::time::Instant;
+use tracing::info;
+
+use crate::error::{AppError, AppResult};
+use crate::storage::{DatabaseService, StoredSegment, Transcript};
+use crate::transcription::{FormattingConfig, TranscriptFormatter, TranscriptSegment};
+
+/// Store a formatted version of a transcript
+///
+/// Formatting always starts from what the engines wrote: a transcript that
+/// was itself formatted is formatted again from its source. The source is
+/// never changed, so formatting can be redone with other settings or
+/// dropped altogether.
+pub async fn format_transcript(
+    db: &DatabaseService,
+    transcription_id: i64,
+    config: FormattingConfig,
+) -> AppResult<Transcript> {
+    config.validate().map_err(|e| AppError::config(e.to_string()))?;
+
+    let transcripts = db.transcripts();
+    let not_found = |id| AppError::database(format!("Transcript {} not found", id));
+    let mut source = transcripts.get(transcription_id).await?.ok_or_else(|| not_found(transcription_id))?;
+    if let Some(id) = source.source_transcription_id {
+        source = transcripts.get(id).await?.ok_or_else(|| not_found(id))?;
+    }
+
+    let started = Instant::now();
+    let stored = transcripts.segments(source.id).await?;
+    let formatter = TranscriptFormatter::new(config);
+
+    // Sentences never run from one speaker into the next
+    let mut formatted = Vec::new();
+    for turn in stored.chunk_by(|a, b| a.speaker_id == b.speaker_id && a.meeting_speaker_id == b.meeting_speaker_id) {
+        let segments: Vec<TranscriptSegment> = turn.iter().map(|s| s.segment.clone()).collect();
+        formatted.extend(formatter.format(&segments).into_iter().map(|segment| StoredSegment {
+            id: 0,
+            transcription_id: 0,
+            speaker_id: turn[0].speaker_id,
+            meeting_speaker_id: turn[0].meeting_speaker_id,
+            is_edited: turn
+                .iter()
+                .any(|s| s.is_edited && s.segment.start_ms < segment.end_ms && s.segment.end_ms > segment.start_ms),
+            segment,
+        }));
+    }
+
+    info!(
+        "Formatted transcript {} of meeting {}: {} segments into {}",
+        source.id, source.meeting_id, stored.len(), formatted.len()
+    );
+
+    let id = transcripts
+        .create_formatted(&source, &formatted, started.elapsed().as_millis() as i64)
+        .await?;
+    transcripts.get(id).await?.ok_or_else(|| not_found(id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::NewMeeting;
+
+    fn segment(text: &str, start_ms: f64, end_ms: f64) -> TranscriptSegment {
+        TranscriptSegment {
+            text: text.to_string(),
+            start_ms,
+            end_ms,
+            confidence: 0.8,
+            language: "en".to_string(),
+            model: "mock".to_string(),
+            words: Vec::new(),
+            speaker_hint: None,
+            original_text: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_formatting_keeps_the_source_transcript() {
+        let db = DatabaseService::in_memory().await.unwrap();
+        let meeting_id = db.meetings().create(&NewMeeting::recording("Budget review")).await.unwrap();
+        let raw = vec![
+            segment("um we spent twelve thousand dollars", 0.0, 2000.0),
+            segment("on hosting", 2000.0, 2600.0),
+            segment("is that too much", 4000.0, 5000.0),
+        ];
+        let raw_id = db.transcripts().create(meeting_id, &raw, "mock", 10).await.unwrap();
+
+        let config = FormattingConfig { remove_fillers: true, ..Default::default() };
+        let formatted = format_transcript(&db, raw_id, config).await.unwrap();
+        assert_eq!(formatted.source_transcription_id, Some(raw_id));
+        assert_eq!(formatted.content, "We spent $12,000 on hosting. Is that too much?");
+
+        let segments = db.transcripts().segments(formatted.id).await.unwrap();
+        assert_eq!(segments.len(), 2);
+        assert_eq!(segments[0].segment.original_text.as_deref(), Some("um we spent twelve thousand dollars on hosting"));
+        assert_eq!(segments[0].segment.words[2].text, "$12,000");
+
+        // Formatting again starts over from the engine's text
+        let again = format_transcript(&db, formatted.id, FormattingConfig::default()).await.unwrap();
+        assert_eq!(again.source_transcription_id, Some(raw_id));
+        assert!(again.content.starts_with("Um we spent"));
+
+        let source = db.transcripts().segments(raw_id).await.unwrap();
+        assert_eq!(source[0].segment.text, "um we spent twelve thousand dollars");
+        assert_eq!(source[0].segment.original_text, None);
+    }
+
+    #[tokio::test]
+    async fn test_formatting_keeps_speakers_and_edits() {
+        use crate::meeting::edit_transcript;
+        use crate::storage::SegmentEdit;
+        use crate::transcription::diarization::SpeakerEmbedding;
+        use crate::transcription::{Diarization, SpeakerCluster};
+
+        let db = DatabaseService::in_memory().await.unwrap();
+        let meeting_id = db.meetings().create(&NewMeeting::recording("Release")).await.unwrap();
+        let raw = vec![
+            segment("so we ship it", 0.0, 1500.0),
+            segment("on thursday", 1500.0, 2500.0),
+            segment("sounds good", 2500.0, 3500.0),
+        ];
+        let raw_id = db.transcripts().create(meeting_id, &raw, "mock", 10).await.unwrap();
+        let stored = db.transcripts().segments(raw_id).await.unwrap();
+        let segment_ids: Vec<i64> = stored.iter().map(|s| s.id).collect();
+
+        let cluster = |label: &str| SpeakerCluster {
+            label: label.to_string(),
+            embedding: SpeakerEmbedding::new(vec![1.0, 0.0]),
+            speech_ms: 1000.0,
+            segments: 1,
+            profile: None,
+        };
+        let diarization = Diarization {
+            speakers: vec![cluster("Speaker 1"), cluster("Speaker 2")],
+            assignments: vec![Some(0), Some(0), Some(1)],
+        };
+        let voices = db.speakers().replace_meeting_speakers(meeting_id, &segment_ids, &diarization).await.unwrap();
+        let edit = SegmentEdit::Text { segment_id: stored[2].id, text: "sounds great".to_string() };
+        edit_transcript(&db, raw_id, edit, "sam").await.unwrap();
+
+        let formatted = format_transcript(&db, raw_id, FormattingConfig::default()).await.unwrap();
+        let segments = db.transcripts().segments(formatted.id).await.unwrap();
+
+        // The answer is its own sentence, still the second voice's and still edited
+        let texts: Vec<_> = segments.iter().map(|s| s.segment.text.as_str()).collect();
+        assert_eq!(texts, ["So we ship it on thursday.", "Sounds great."]);
+        assert_eq!(segments[0].meeting_speaker_id, Some(voices[0].id));
+        assert_eq!(segments[1].meeting_speaker_id, Some(voices[1].id));
+        assert_eq!((segments[0].is_edited, segments[1].is_edited), (false, true));
+    }
+}
@@ -0,0 +1,204 @@
+//! Turning existing recordings into meetings
+
+use std::path::Path;
+use std::time::SystemTime;
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use serde::Serialize;
+use tokio::sync::mpsc;
+use tracing::{error, info};
+
+use crate::audio::{AudioChunk, AudioError, AudioFileImporter, ImportProgress, ImportSummary};
+use crate::error::{AppError, AppResult};
+use crate::storage::{DatabaseService, MeetingStatus, NewMeeting};
+
+/// Progress of a meeting import, as sent to the frontend
+#[derive(Debug, Clone, Serialize)]
+pub struct MeetingImportProgress {
+    pub meeting_id: i64,
+    #[serde(flatten)]
+    pub progress: ImportProgress,
+}
+
+/// Result of importing a recording
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportedMeeting {
+    pub meeting_id: i64,
+    pub summary: ImportSummary,
+}
+
+/// Create a meeting from the recording at `path`
+///
+/// Decoded chunks go to `chunks`, if given, with offsets from the start of
+/// the file; without it the recording is only checked and measured, ready to
+/// be queued for transcription. The meeting ends when the file was last written and
+/// lasts as long as the audio, so its timeline matches the recording. If the
+/// import fails the half-created meeting is removed again.
+pub async fn import_recording(
+    db: &DatabaseService,
+    importer: AudioFileImporter,
+    path: &Path,
+    title: Option<String>,
+    chunks: Option<mpsc::Sender<AudioChunk>>,
+    progress: mpsc::UnboundedSender<MeetingImportProgress>,
+) -> AppResult<ImportedMeeting> {
+    if !AudioFileImporter::is_supported(path) {
+        return Err(AppError::audio(format!("Unsupported audio file: {}", path.display())));
+    }
+
+    let recorded_at = std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .map(DateTime::<Utc>::from)
+        .unwrap_or_else(|_| DateTime::<Utc>::from(SystemTime::now()));
+
+    let meetings = db.meetings();
+    let meeting_id = meetings
+        .create(&NewMeeting {
+            title: title.unwrap_or_else(|| default_title(path)),
+            start_time: recorded_at,
+            calendar_event_id: None,
+            audio_file_path: Some(path.to_string_lossy().into_owned()),
+            participants: Vec::new(),
+            status: MeetingStatus::Recording,
+        })
+        .await?;
+
+    info!("Importing {} as meeting {}", path.display(), meeting_id);
+
+    let decode_path = path.to_path_buf();
+    let result = tokio::task::spawn_blocking(move || {
+        importer.import(
+            &decode_path,
+            |chunk| match &chunks {
+                Some(chunks) => chunks.blocking_send(chunk).map_err(|_| AudioError::Internal {
+                    message: "Chunk receiver closed during import".to_string(),
+                }),
+                None => Ok(()),
+            },
+            |update| {
+                let _ = progress.send(MeetingImportProgress { meeting_id, progress: update });
+            },
+        )
+    })
+    .await
+    .map_err(|e| AppError::internal(format!("Import task failed: {}", e)))?;
+
+    let summary = match result {
+        Ok(summary) => summary,
+        Err(e) => {
+            error!("Failed to import {}: {}", path.display(), e);
+            meetings.delete(meeting_id).await?;
+            return Err(AppError::audio(e.to_string()));
+        }
+    };
+
+    let duration = ChronoDuration::milliseconds(summary.duration_ms.round() as i64);
+    meetings.set_timeline(meeting_id, recorded_at - duration, recorded_at).await?;
+
+    Ok(ImportedMeeting { meeting_id, summary })
+}
+
+fn default_title(path: &Path) -> String {
+    path.file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "Imported recording".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::test_wav::write_wav;
+    use std::sync::Arc;
+    use crate::audio::AudioConfig;
+    use crate::meeting::{JobOutcome, JobQueueConfig, TranscriptionQueue};
+    use crate::storage::JobPriority;
+    use crate::transcription::MockEngine;
+
+    #[tokio::test]
+    async fn test_import_recording_creates_matching_meeting() {
+        let path = std::env::temp_dir().join(format!("standup-{}.wav", uuid::Uuid::new_v4()));
+        write_wav(&path, 48000, 1, &vec![0.2; 48000 * 4]);
+
+        let db = DatabaseService::in_memory().await.unwrap();
+        let importer = AudioFileImporter::new(AudioConfig::default())
+            .with_chunk_duration(std::time::Duration::from_secs(1));
+        let (chunk_tx, mut chunk_rx) = mpsc::channel(16);
+        let (progress_tx, mut progress_rx) = mpsc::unbounded_channel();
+
+        let imported = import_recording(&db, importer, &path, None, Some(chunk_tx), progress_tx)
+            .await
+            .unwrap();
+        std::fs::remove_file(&path).ok();
+
+        let mut offsets = Vec::new();
+        while let Some(chunk) = chunk_rx.recv().await {
+            offsets.push(chunk.start_ms);
+        }
+        assert_eq!(offsets, vec![0.0, 1000.0, 2000.0, 3000.0]);
+
+        let mut last_progress = None;
+        while let Ok(update) = progress_rx.try_recv() {
+            assert_eq!(update.meeting_id, imported.meeting_id);
+            last_progress = Some(update.progress);
+        }
+        assert_eq!(last_progress.unwrap().fraction(), Some(1.0));
+
+        let meeting = db.meetings().get(imported.meeting_id).await.unwrap().unwrap();
+        assert!(meeting.title.starts_with("standup-"));
+        assert_eq!(meeting.status, MeetingStatus::Completed);
+        assert_eq!(meeting.duration_seconds, Some(4));
+        assert_eq!(meeting.end_time.unwrap() - meeting.start_time, ChronoDuration::seconds(4));
+    }
+
+    #[tokio::test]
+    async fn test_imported_recording_can_be_queued_for_transcription() {
+        let path = std::env::temp_dir().join(format!("review-{}.wav", uuid::Uuid::new_v4()));
+        write_wav(&path, 16000, 1, &vec![0.2; 16000 * 3]);
+
+        let db = DatabaseService::in_memory().await.unwrap();
+        let (progress_tx, _progress_rx) = mpsc::unbounded_channel();
+        let imported = import_recording(&db, AudioFileImporter::new(AudioConfig::default()), &path, None, None, progress_tx)
+            .await
+            .unwrap();
+
+        let queue = TranscriptionQueue::new(
+            db.clone(),
+            Arc::new(MockEngine::new()),
+            AudioConfig::default(),
+            JobQueueConfig::default(),
+        );
+        let job = queue.enqueue(imported.meeting_id, JobPriority::Normal).await.unwrap();
+        let outcome = queue.run_next().await.unwrap();
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(outcome, Some((job.id, JobOutcome::Completed)));
+        let transcript = db.transcripts().latest(imported.meeting_id).await.unwrap().unwrap();
+        assert!(!db.transcripts().segments(transcript.id).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_failed_import_removes_meeting() {
+        let path = std::env::temp_dir().join(format!("broken-{}.wav", uuid::Uuid::new_v4()));
+        std::fs::write(&path, b"not really a wav file").unwrap();
+
+        let db = DatabaseService::in_memory().await.unwrap();
+        let (progress_tx, _progress_rx) = mpsc::unbounded_channel();
+
+        let result = import_recording(
+            &db,
+            AudioFileImporter::new(AudioConfig::default()),
+            &path,
+            Some("Broken".to_string()),
+            None,
+            progress_tx,
+        )
+        .await;
+        std::fs::remove_file(&path).ok();
+
+        assert!(result.is_err());
+        let remaining: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM meetings")
+            .fetch_one(db.pool())
+            .await
+            .unwrap();
+        assert_eq!(remaining, 0);
+    }
+}
@@ -0,0 +1,580 @@
+//! The persistent transcription job queue
+//!
+//! Recordings are transcribed by one worker, a chunk at a time, most urgent
+//! job first. Every finished chunk is checkpointed in the database, so a job
+//! interrupted by a crash, a shutdown or a more urgent job carries on from
+//! where it stopped: the file is decoded from the start again, but only
+//! chunks without a checkpoint reach the engine. A failed chunk sends the
+//! job back to the queue with exponential backoff until it has failed
+//! `max_attempts` times. With archiving after the session, a recording is
+//! compressed once its first transcript has been stored.
+
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::{broadcast, mpsc, Notify};
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+
+use crate::audio::{AudioChunk, AudioConfig, AudioError, AudioFileImporter, ArchiveMode, ArchiveSettings};
+use crate::error::{AppError, AppResult};
+use crate::meeting::archive::archive_meeting_audio;
+use crate::meeting::retranscribe::carry_edits;
+use crate::storage::{DatabaseService, JobPriority, JobStatus, NewTranscriptionJob, TranscriptionJob};
+use crate::transcription::{session_engine, transcribe_with, TranscriptionEngine, TranscriptionOptions};
+
+/// Longest the worker sleeps before looking at the queue again
+const IDLE_POLL: Duration = Duration::from_secs(60);
+
+/// How jobs are chunked and retried
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct JobQueueConfig {
+    /// Length of the chunks recordings are cut into and checkpointed by
+    pub chunk_ms: f64,
+    /// Failed attempts before a job is given up on
+    pub max_attempts: u32,
+    /// Wait before the first retry; doubles with every further failure
+    pub retry_base_secs: u64,
+    /// Longest wait between retries
+    pub retry_max_secs: u64,
+}
+
+impl Default for JobQueueConfig {
+    fn default() -> Self {
+        Self {
+            chunk_ms: 30_000.0,
+            max_attempts: 5,
+            retry_base_secs: 10,
+            retry_max_secs: 900,
+        }
+    }
+}
+
+impl JobQueueConfig {
+    /// Check the settings are usable
+    pub fn validate(&self) -> AppResult<()> {
+        if !(1000.0..=60_000.0).contains(&self.chunk_ms) {
+            return Err(AppError::config(format!(
+                "Job chunk length of {}ms is out of range (1-60s)", self.chunk_ms
+            )));
+        }
+        if self.max_attempts == 0 {
+            return Err(AppError::config("Transcription jobs need at least one attempt"));
+        }
+        if self.retry_max_secs < self.retry_base_secs {
+            return Err(AppError::config("Longest retry wait is shorter than the first"));
+        }
+        Ok(())
+    }
+
+    /// Wait before retrying a job that has failed `attempts` times
+    pub fn retry_delay(&self, attempts: u32) -> Duration {
+        let doublings = attempts.saturating_sub(1).min(32);
+        let secs = self.retry_base_secs.saturating_mul(1u64 << doublings);
+        Duration::from_secs(secs.min(self.retry_max_secs))
+    }
+}
+
+/// A change in the job queue, as sent to the frontend
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum JobEvent {
+    Queued { job: Box<TranscriptionJob> },
+    /// The worker picked the job up, skipping chunks done in earlier runs
+    Started { job_id: i64, meeting_id: i64, resumed_chunks: usize },
+    Progress {
+        job_id: i64,
+        meeting_id: i64,
+        completed_chunks: i64,
+        /// End of the last chunk transcribed
+        processed_ms: f64,
+        total_ms: Option<f64>,
+    },
+    /// Back in the queue to make way for a more urgent job
+    Paused { job_id: i64 },
+    Retrying { job_id: i64, attempts: u32, error: String, retry_at: DateTime<Utc> },
+    Completed { job_id: i64, meeting_id: i64, transcription_id: i64 },
+    Failed { job_id: i64, error: String },
+    Cancelled { job_id: i64 },
+}
+
+/// How a run of a job ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobOutcome {
+    Completed,
+    Paused,
+    Retrying,
+    Failed,
+    Cancelled,
+}
+
+/// Queues recordings and transcribes them in the background
+#[derive(Clone)]
+pub struct TranscriptionQueue {
+    db: DatabaseService,
+    engine: Arc<dyn TranscriptionEngine>,
+    audio: AudioConfig,
+    config: JobQueueConfig,
+    archive: ArchiveSettings,
+    events: broadcast::Sender<JobEvent>,
+    wake: Arc<Notify>,
+}
+
+impl TranscriptionQueue {
+    /// Queue decoding to `audio`'s format and transcribing with `engine`
+    pub fn new(db: DatabaseService, engine: Arc<dyn TranscriptionEngine>, audio: AudioConfig, config: JobQueueConfig) -> Self {
+        let (events, _) = broadcast::channel(256);
+        Self {
+            db,
+            engine,
+            audio,
+            config,
+            archive: ArchiveSettings { mode: ArchiveMode::Disabled, ..Default::default() },
+            events,
+            wake: Arc::new(Notify::new()),
+        }
+    }
+
+    /// Archive recordings as `settings` say once they are transcribed
+    ///
+    /// Only `ArchiveMode::AfterSession` applies here; live archives are
+    /// written by capture.
+    pub fn with_archive(mut self, settings: ArchiveSettings) -> Self {
+        self.archive = settings;
+        self
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<JobEvent> {
+        self.events.subscribe()
+    }
+
+    /// Queue a meeting's recording with its language settings and vocabulary
+    pub async fn enqueue(&self, meeting_id: i64, priority: JobPriority) -> AppResult<TranscriptionJob> {
+        let job = self.prepare(meeting_id, priority).await?;
+        self.submit(job).await
+    }
+
+    /// The job that would transcribe a meeting's recording as things are set
+    /// up now, ready to adjust and submit
+    pub async fn prepare(&self, meeting_id: i64, priority: JobPriority) -> AppResult<NewTranscriptionJob> {
+        let meeting = self.db.meetings()
+            .get(meeting_id)
+            .await?
+            .ok_or_else(|| AppError::database(format!("Meeting {} not found", meeting_id)))?;
+        let audio_path = meeting.playback_path()
+            .ok_or_else(|| AppError::audio(format!("Meeting {} has no recording", meeting_id)))?
+            .to_string();
+        let terms = self.db.vocabulary().for_meeting(meeting_id).await?;
+
+        Ok(NewTranscriptionJob {
+            meeting_id,
+            priority,
+            audio_path,
+            chunk_ms: self.config.chunk_ms,
+            options: TranscriptionOptions {
+                vocabulary: meeting.vocabulary(terms),
+                ..meeting.transcription_options()
+            },
+            replaces_transcription_id: None,
+        })
+    }
+
+    /// Queue a job as given
+    pub async fn submit(&self, job: NewTranscriptionJob) -> AppResult<TranscriptionJob> {
+        let jobs = self.db.jobs();
+        let id = jobs.enqueue(&job).await?;
+        let job = jobs.get(id)
+            .await?
+            .ok_or_else(|| AppError::database(format!("Transcription job {} not found", id)))?;
+
+        info!("Queued transcription job {} for meeting {} ({:?})", job.id, job.meeting_id, job.priority);
+        let _ = self.events.send(JobEvent::Queued { job: Box::new(job.clone()) });
+        self.wake.notify_one();
+        Ok(job)
+    }
+
+    /// Cancel a job; returns false if it had already finished
+    ///
+    /// A running job stops once its current chunk is done.
+    pub async fn cancel(&self, job_id: i64) -> AppResult<bool> {
+        let jobs = self.db.jobs();
+        let was_running = jobs.get(job_id).await?.is_some_and(|job| job.status == JobStatus::Running);
+        if !jobs.cancel(job_id).await? {
+            return Ok(false);
+        }
+
+        // The worker tidies up after a running job itself
+        if !was_running {
+            jobs.discard_checkpoints(job_id).await?;
+            let _ = self.events.send(JobEvent::Cancelled { job_id });
+        }
+        info!("Cancelled transcription job {}", job_id);
+        Ok(true)
+    }
+
+    /// Start the worker
+    ///
+    /// Jobs left running when the app last stopped are queued again first.
+    pub fn start(&self) -> JoinHandle<()> {
+        let queue = self.clone();
+        tokio::spawn(async move {
+            match queue.db.jobs().requeue_interrupted().await {
+                Ok(0) => {}
+                Ok(interrupted) => info!("Resuming {} interrupted transcription jobs", interrupted),
+                Err(e) => error!("Failed to requeue interrupted transcription jobs: {}", e),
+            }
+
+            loop {
+                match queue.run_next().await {
+                    Ok(Some(_)) => continue,
+                    Ok(None) => {}
+                    Err(e) => error!("Transcription job queue failed: {}", e),
+                }
+
+                let wait = queue.idle_wait().await;
+                tokio::select! {
+                    _ = queue.wake.notified() => {}
+                    _ = tokio::time::sleep(wait) => {}
+                }
+            }
+        })
+    }
+
+    /// Run the most urgent job that is due, if there is one, until it
+    /// completes, fails, is cancelled or makes way for a more urgent job
+    pub async fn run_next(&self) -> AppResult<Option<(i64, JobOutcome)>> {
+        let Some(job) = self.db.jobs().claim_next(Utc::now()).await? else {
+            return Ok(None);
+        };
+        let outcome = match self.run(&job).await {
+            Ok(outcome) => outcome,
+            // Never leave the job running: the queue's own errors are
+            // retried like a failed chunk
+            Err(e) => self.failed(&job, e.to_string()).await?,
+        };
+        Ok(Some((job.id, outcome)))
+    }
+
+    async fn run(&self, job: &TranscriptionJob) -> AppResult<JobOutcome> {
+        let jobs = self.db.jobs();
+        let done: HashSet<usize> = jobs.checkpoints(job.id).await?.iter().map(|c| c.chunk_index).collect();
+        info!(
+            "Transcribing meeting {} (job {}, {} chunks already done)",
+            job.meeting_id, job.id, done.len()
+        );
+        let _ = self.events.send(JobEvent::Started {
+            job_id: job.id,
+            meeting_id: job.meeting_id,
+            resumed_chunks: done.len(),
+        });
+
+        // Each job draws on a cloud budget of its own
+        let engine = session_engine(&self.engine);
+        let (chunk_tx, mut chunk_rx) = mpsc::channel::<AudioChunk>(2);
+        let (progress_tx, mut progress_rx) = mpsc::unbounded_channel();
+        let importer = AudioFileImporter::new(self.audio.clone())
+            .with_chunk_duration(Duration::from_secs_f64(job.chunk_ms / 1000.0));
+        let path = PathBuf::from(&job.audio_path);
+        let decoder = tokio::task::spawn_blocking(move || {
+            importer.import(
+                &path,
+                |chunk| {
+                    if done.contains(&chunk.index) {
+                        return Ok(());
+                    }
+                    chunk_tx.blocking_send(chunk).map_err(|_| AudioError::Internal {
+                        message: "Transcription job stopped".to_string(),
+                    })
+                },
+                |progress| {
+                    let _ = progress_tx.send(progress);
+                },
+            )
+        });
+
+        let mut total_ms = job.total_ms;
+        let mut interrupted = None;
+        let mut failure = None;
+        while let Some(chunk) = chunk_rx.recv().await {
+            if let Some(outcome) = self.interruption(job).await? {
+                interrupted = Some(outcome);
+                break;
+            }
+
+            let index = chunk.index;
+            let processed_ms = chunk.start_ms + chunk.buffer.duration_ms();
+            let started = Instant::now();
+            let segments = match transcribe_with(Arc::clone(&engine), chunk, job.options.clone()).await {
+                Ok(segments) => segments,
+                Err(e) => {
+                    failure = Some(format!("Chunk {} failed: {}", index, e));
+                    break;
+                }
+            };
+            let completed_chunks = jobs
+                .checkpoint(job.id, index, &segments, started.elapsed().as_secs_f64() * 1000.0)
+                .await?;
+
+            while let Ok(progress) = progress_rx.try_recv() {
+                if total_ms.is_none() && progress.total_ms.is_some() {
+                    total_ms = progress.total_ms;
+                    jobs.set_total_ms(job.id, progress.total_ms.unwrap_or_default()).await?;
+                }
+            }
+            let _ = self.events.send(JobEvent::Progress {
+                job_id: job.id,
+                meeting_id: job.meeting_id,
+                completed_chunks,
+                processed_ms,
+                total_ms,
+            });
+        }
+        drop(chunk_rx);
+        let decoded = decoder
+            .await
+            .map_err(|e| AppError::internal(format!("Decoding task failed: {}", e)))?;
+
+        match (interrupted, failure, decoded) {
+            (Some(JobOutcome::Paused), _, _) => {
+                jobs.release(job.id).await?;
+                info!("Paused transcription job {} for a more urgent one", job.id);
+                let _ = self.events.send(JobEvent::Paused { job_id: job.id });
+                Ok(JobOutcome::Paused)
+            }
+            (Some(_), _, _) => self.cancelled(job).await,
+            (None, Some(error), _) => self.failed(job, error).await,
+            (None, None, Err(e)) => self.failed(job, e.to_string()).await,
+            (None, None, Ok(_)) => self.complete(job).await,
+        }
+    }
+
+    /// Why the job should stop before its next chunk, if it should
+    async fn interruption(&self, job: &TranscriptionJob) -> AppResult<Option<JobOutcome>> {
+        let jobs = self.db.jobs();
+        let status = jobs.get(job.id).await?.map(|job| job.status);
+        if status != Some(JobStatus::Running) {
+            return Ok(Some(JobOutcome::Cancelled));
+        }
+        if jobs.has_due_above(job.priority, Utc::now()).await? {
+            return Ok(Some(JobOutcome::Paused));
+        }
+        Ok(None)
+    }
+
+    /// Stitch the checkpoints into the meeting's transcript, or into a new
+    /// version of the transcript a re-transcription replaces
+    async fn complete(&self, job: &TranscriptionJob) -> AppResult<JobOutcome> {
+        let jobs = self.db.jobs();
+        if self.interruption(job).await? == Some(JobOutcome::Cancelled) {
+            return self.cancelled(job).await;
+        }
+
+        let checkpoints = jobs.checkpoints(job.id).await?;
+        let processing_ms: f64 = checkpoints.iter().map(|c| c.processing_ms).sum();
+        let segments: Vec<_> = checkpoints.into_iter().flat_map(|c| c.segments).collect();
+        let transcripts = self.db.transcripts();
+        let previous = match job.replaces_transcription_id {
+            Some(id) => transcripts.get(id).await?,
+            None => None,
+        };
+        let transcription_id = match previous {
+            Some(previous) => {
+                let segments = carry_edits(&transcripts.segments(previous.id).await?, &segments);
+                transcripts
+                    .create_version(&previous, &segments, self.engine.name(), processing_ms.round() as i64)
+                    .await?
+            }
+            None => {
+                transcripts
+                    .create(job.meeting_id, &segments, self.engine.name(), processing_ms.round() as i64)
+                    .await?
+            }
+        };
+        jobs.complete(job.id, transcription_id).await?;
+
+        info!("Transcription job {} finished: {} segments", job.id, segments.len());
+        let _ = self.events.send(JobEvent::Completed {
+            job_id: job.id,
+            meeting_id: job.meeting_id,
+            transcription_id,
+        });
+
+        if self.archive.mode == ArchiveMode::AfterSession && job.replaces_transcription_id.is_none() {
+            self.archive_recording(job.meeting_id).await;
+        }
+        Ok(JobOutcome::Completed)
+    }
+
+    /// Compress a meeting's recording now that its session is finalized
+    async fn archive_recording(&self, meeting_id: i64) {
+        match self.db.meetings().get(meeting_id).await {
+            Ok(Some(meeting)) if meeting.archive_file_path.is_none() && meeting.audio_file_path.is_some() => {}
+            _ => return,
+        }
+        if let Err(e) = archive_meeting_audio(&self.db, meeting_id, self.audio.clone(), self.archive).await {
+            warn!("Recording of meeting {} was not archived: {}", meeting_id, e);
+        }
+    }
+
+    async fn cancelled(&self, job: &TranscriptionJob) -> AppResult<JobOutcome> {
+        self.db.jobs().discard_checkpoints(job.id).await?;
+        let _ = self.events.send(JobEvent::Cancelled { job_id: job.id });
+        Ok(JobOutcome::Cancelled)
+    }
+
+    /// Queue the job for a retry after a backoff, or give up on it
+    async fn failed(&self, job: &TranscriptionJob, error: String) -> AppResult<JobOutcome> {
+        let attempts = job.attempts + 1;
+        if attempts >= self.config.max_attempts {
+            error!("Transcription job {} failed after {} attempts: {}", job.id, attempts, error);
+            self.db.jobs().record_failure(job.id, &error, None).await?;
+            let _ = self.events.send(JobEvent::Failed { job_id: job.id, error });
+            return Ok(JobOutcome::Failed);
+        }
+
+        let delay = self.config.retry_delay(attempts);
+        let retry_at = Utc::now() + chrono::Duration::from_std(delay).unwrap_or_else(|_| chrono::Duration::zero());
+        warn!("Transcription job {} failed, retrying in {:?}: {}", job.id, delay, error);
+        self.db.jobs().record_failure(job.id, &error, Some(retry_at)).await?;
+        let _ = self.events.send(JobEvent::Retrying { job_id: job.id, attempts, error, retry_at });
+        Ok(JobOutcome::Retrying)
+    }
+
+    /// How long the worker can sleep before a retry falls due
+    async fn idle_wait(&self) -> Duration {
+        match self.db.jobs().next_retry().await {
+            Ok(Some(due)) => (due - Utc::now()).to_std().unwrap_or(Duration::ZERO).min(IDLE_POLL),
+            _ => IDLE_POLL,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::test_wav::write_wav;
+    use crate::storage::NewMeeting;
+    use crate::transcription::MockEngine;
+
+    async fn meeting_with_recording(db: &DatabaseService, seconds: usize) -> (i64, PathBuf) {
+        let path = std::env::temp_dir().join(format!("job-{}.wav", uuid::Uuid::new_v4()));
+        write_wav(&path, 16000, 1, &vec![0.2; 16000 * seconds]);
+        let meeting_id = db.meetings()
+            .create(&NewMeeting {
+                audio_file_path: Some(path.to_string_lossy().into_owned()),
+                ..NewMeeting::recording("Quarterly planning")
+            })
+            .await
+            .unwrap();
+        (meeting_id, path)
+    }
+
+    fn queue(db: &DatabaseService, engine: Arc<MockEngine>) -> TranscriptionQueue {
+        let config = JobQueueConfig { chunk_ms: 1000.0, retry_base_secs: 0, ..Default::default() };
+        TranscriptionQueue::new(db.clone(), engine, AudioConfig::default(), config)
+    }
+
+    #[tokio::test]
+    async fn test_failed_job_resumes_from_its_checkpoints() {
+        let db = DatabaseService::in_memory().await.unwrap();
+        let (meeting_id, path) = meeting_with_recording(&db, 4).await;
+
+        let flaky = queue(&db, Arc::new(MockEngine::new().failing_on(&[2])));
+        let job = flaky.enqueue(meeting_id, JobPriority::Normal).await.unwrap();
+        assert_eq!(flaky.run_next().await.unwrap(), Some((job.id, JobOutcome::Retrying)));
+
+        let waiting = db.jobs().get(job.id).await.unwrap().unwrap();
+        assert_eq!((waiting.status, waiting.attempts, waiting.completed_chunks), (JobStatus::Queued, 1, 2));
+        assert_eq!(waiting.progress(), Some(0.5));
+        assert!(waiting.last_error.unwrap().contains("Chunk 2"));
+
+        // As after a restart: a new worker picks the job up where it stopped
+        let engine = Arc::new(MockEngine::new());
+        let healthy = queue(&db, engine.clone());
+        let mut events = healthy.subscribe();
+        assert_eq!(healthy.run_next().await.unwrap(), Some((job.id, JobOutcome::Completed)));
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(engine.calls(), 2);
+        assert!(matches!(events.recv().await.unwrap(), JobEvent::Started { resumed_chunks: 2, .. }));
+        let finished = db.jobs().get(job.id).await.unwrap().unwrap();
+        assert_eq!(finished.status, JobStatus::Completed);
+        assert!(db.jobs().checkpoints(job.id).await.unwrap().is_empty());
+
+        let segments = db.transcripts().segments(finished.transcription_id.unwrap()).await.unwrap();
+        let starts: Vec<f64> = segments.iter().map(|s| s.segment.start_ms).collect();
+        assert_eq!(starts, vec![0.0, 1000.0, 2000.0, 3000.0]);
+    }
+
+    #[tokio::test]
+    async fn test_database_error_mid_job_is_retried_not_left_running() {
+        let db = DatabaseService::in_memory().await.unwrap();
+        let (meeting_id, path) = meeting_with_recording(&db, 2).await;
+        let queue = queue(&db, Arc::new(MockEngine::new()));
+        let job = queue.enqueue(meeting_id, JobPriority::Normal).await.unwrap();
+
+        // Checkpoints can no longer be read or written
+        sqlx::query("DROP TABLE transcription_job_chunks").execute(db.pool()).await.unwrap();
+        assert_eq!(queue.run_next().await.unwrap(), Some((job.id, JobOutcome::Retrying)));
+        std::fs::remove_file(&path).ok();
+
+        let waiting = db.jobs().get(job.id).await.unwrap().unwrap();
+        assert_eq!((waiting.status, waiting.attempts), (JobStatus::Queued, 1));
+        assert!(waiting.last_error.unwrap().contains("transcription_job_chunks"));
+    }
+
+    #[tokio::test]
+    async fn test_live_job_preempts_backlog_and_cancelled_jobs_never_run() {
+        let db = DatabaseService::in_memory().await.unwrap();
+        let (old_meeting, old_path) = meeting_with_recording(&db, 4).await;
+        let (live_meeting, live_path) = meeting_with_recording(&db, 2).await;
+        let (dropped_meeting, dropped_path) = meeting_with_recording(&db, 2).await;
+
+        let queue = queue(&db, Arc::new(MockEngine::new().with_latency(Duration::from_millis(20))));
+        let mut events = queue.subscribe();
+        let backlog = queue.enqueue(old_meeting, JobPriority::Backlog).await.unwrap();
+        let dropped = queue.enqueue(dropped_meeting, JobPriority::Backlog).await.unwrap();
+        assert!(queue.cancel(dropped.id).await.unwrap());
+        assert!(!queue.cancel(dropped.id).await.unwrap());
+
+        let worker = queue.clone();
+        let running = tokio::spawn(async move { worker.run_next().await.unwrap() });
+        while !matches!(events.recv().await.unwrap(), JobEvent::Progress { .. }) {}
+        let live = queue.enqueue(live_meeting, JobPriority::Live).await.unwrap();
+
+        assert_eq!(running.await.unwrap(), Some((backlog.id, JobOutcome::Paused)));
+        assert_eq!(queue.run_next().await.unwrap(), Some((live.id, JobOutcome::Completed)));
+        assert_eq!(queue.run_next().await.unwrap(), Some((backlog.id, JobOutcome::Completed)));
+        assert_eq!(queue.run_next().await.unwrap(), None);
+        for path in [old_path, live_path, dropped_path] {
+            std::fs::remove_file(path).ok();
+        }
+
+        assert_eq!(db.jobs().get(dropped.id).await.unwrap().unwrap().status, JobStatus::Cancelled);
+        assert_eq!(db.transcripts().for_meeting(old_meeting).await.unwrap().len(), 1);
+        assert!(db.transcripts().for_meeting(dropped_meeting).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_recording_is_archived_once_its_session_is_transcribed() {
+        let db = DatabaseService::in_memory().await.unwrap();
+        let (meeting_id, path) = meeting_with_recording(&db, 2).await;
+
+        let settings = ArchiveSettings { mode: ArchiveMode::AfterSession, ..Default::default() };
+        let queue = queue(&db, Arc::new(MockEngine::new())).with_archive(settings);
+        let job = queue.enqueue(meeting_id, JobPriority::Normal).await.unwrap();
+        assert_eq!(queue.run_next().await.unwrap(), Some((job.id, JobOutcome::Completed)));
+
+        let meeting = db.meetings().get(meeting_id).await.unwrap().unwrap();
+        let archive = PathBuf::from(meeting.archive_file_path.unwrap());
+        assert!(archive.exists());
+        assert!(path.exists());
+        std::fs::remove_file(&path).ok();
+        std::fs::remove_file(&archive).ok();
+    }
+}
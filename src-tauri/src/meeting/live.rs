@@ -0,0 +1,188 @@
+//! Live capture sessions stored as meetings
+
+use chrono::{Local, Utc};
+use tracing::info;
+
+use crate::audio::ArchiveSummary;
+use crate::error::AppResult;
+use crate::storage::{DatabaseService, NewMeeting};
+use crate::transcription::LiveTranscript;
+
+/// Create the meeting a live capture records into
+///
+/// The meeting starts now and stays `Recording` until `finish_live_meeting`.
+pub async fn start_live_meeting(db: &DatabaseService, title: Option<String>) -> AppResult<i64> {
+    let title = title.unwrap_or_else(|| format!("Meeting {}", Local::now().format("%Y-%m-%d %H:%M")));
+    let meeting_id = db.meetings().create(&NewMeeting::recording(title)).await?;
+
+    info!("Recording live meeting {}", meeting_id);
+    Ok(meeting_id)
+}
+
+/// Complete a live meeting and store what its session finalized
+///
+/// `archive` is the Opus file written while recording, if any; it becomes
+/// the meeting's recording. Returns the id of the stored transcript, or
+/// `None` if nothing was transcribed live.
+pub async fn finish_live_meeting(
+    db: &DatabaseService,
+    meeting_id: i64,
+    transcript: Option<&LiveTranscript>,
+    archive: Option<&ArchiveSummary>,
+) -> AppResult<Option<i64>> {
+    let meetings = db.meetings();
+    meetings.complete(meeting_id, Utc::now()).await?;
+    if let Some(archive) = archive {
+        meetings
+            .set_archive(meeting_id, &archive.archive_path.to_string_lossy(), archive.original_kept)
+            .await?;
+    }
+
+    let Some(transcript) = transcript.filter(|transcript| !transcript.segments.is_empty()) else {
+        return Ok(None);
+    };
+    let transcription_id = db
+        .transcripts()
+        .create(
+            meeting_id,
+            &transcript.segments,
+            &transcript.engine,
+            transcript.stats.processing_ms.round() as i64,
+        )
+        .await?;
+
+    info!(
+        "Stored {} live segments for meeting {}",
+        transcript.segments.len(),
+        meeting_id
+    );
+    Ok(Some(transcription_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use tokio::sync::broadcast;
+    use crate::audio::{AudioBuffer, TrackAudio};
+    use crate::storage::MeetingStatus;
+    use crate::transcription::{MockEngine, SpeakerHint, TranscriptSegment, TranscriptionService};
+
+    fn segment(text: &str, start_ms: f64) -> TranscriptSegment {
+        TranscriptSegment {
+            text: text.to_string(),
+            start_ms,
+            end_ms: start_ms + 1000.0,
+            confidence: 0.9,
+            language: "en".to_string(),
+            model: "mock".to_string(),
+            words: Vec::new(),
+            speaker_hint: None,
+            original_text: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_live_session_is_stored_as_a_meeting() {
+        let db = DatabaseService::in_memory().await.unwrap();
+        let meeting_id = start_live_meeting(&db, Some("Standup".to_string())).await.unwrap();
+        assert_eq!(db.meetings().get(meeting_id).await.unwrap().unwrap().status, MeetingStatus::Recording);
+
+        let live = LiveTranscript {
+            segments: vec![segment("morning all", 0.0), segment("shall we start", 1000.0)],
+            engine: "mock".to_string(),
+            ..Default::default()
+        };
+        let transcription_id = finish_live_meeting(&db, meeting_id, Some(&live), None).await.unwrap().unwrap();
+
+        let meeting = db.meetings().get(meeting_id).await.unwrap().unwrap();
+        assert_eq!(meeting.title, "Standup");
+        assert_eq!(meeting.status, MeetingStatus::Completed);
+        assert!(meeting.end_time.is_some());
+
+        let stored: Vec<_> = db
+            .transcripts()
+            .segments(transcription_id)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|s| s.segment.text)
+            .collect();
+        assert_eq!(stored, vec!["morning all", "shall we start"]);
+    }
+
+    #[tokio::test]
+    async fn test_live_session_stores_track_attribution() {
+        let db = DatabaseService::in_memory().await.unwrap();
+        let meeting_id = start_live_meeting(&db, None).await.unwrap();
+        let service = TranscriptionService::new(Arc::new(MockEngine::new()));
+
+        // Mic, then the far end, one second each
+        let (track_tx, track_rx) = broadcast::channel(16);
+        let attribution = service
+            .attribute_tracks(track_rx, vec![SpeakerHint::Me, SpeakerHint::Others])
+            .unwrap();
+        for (mic, system) in [(0.2, 0.0), (0.01, 0.2)] {
+            for (track, level) in [(0, mic), (1, system)] {
+                track_tx.send(TrackAudio { track, buffer: AudioBuffer::new(vec![level; 16000], 16000, 1) }).unwrap();
+            }
+        }
+        drop(track_tx);
+        attribution.await.unwrap();
+
+        let (audio_tx, audio_rx) = broadcast::channel(64);
+        let handle = service.stream_capture(audio_rx);
+        for _ in 0..20 {
+            audio_tx.send(AudioBuffer::new(vec![0.2; 9600], 48000, 2)).unwrap();
+        }
+        drop(audio_tx);
+        let live = handle.await.unwrap();
+
+        let transcription_id = finish_live_meeting(&db, meeting_id, Some(&live), None).await.unwrap().unwrap();
+
+        let hints: Vec<_> = db
+            .transcripts()
+            .segments(transcription_id)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|s| s.segment.speaker_hint)
+            .collect();
+        assert_eq!(hints, vec![Some(SpeakerHint::Me), Some(SpeakerHint::Others)]);
+    }
+
+    #[tokio::test]
+    async fn test_silent_live_session_completes_without_a_transcript() {
+        let db = DatabaseService::in_memory().await.unwrap();
+        let meeting_id = start_live_meeting(&db, None).await.unwrap();
+
+        let stored = finish_live_meeting(&db, meeting_id, Some(&LiveTranscript::default()), None).await.unwrap();
+
+        assert_eq!(stored, None);
+        assert!(db.transcripts().latest(meeting_id).await.unwrap().is_none());
+        let meeting = db.meetings().get(meeting_id).await.unwrap().unwrap();
+        assert!(meeting.title.starts_with("Meeting "));
+        assert_eq!(meeting.status, MeetingStatus::Completed);
+    }
+
+    #[tokio::test]
+    async fn test_live_archive_becomes_the_meeting_recording() {
+        let db = DatabaseService::in_memory().await.unwrap();
+        let meeting_id = start_live_meeting(&db, None).await.unwrap();
+
+        let archive = ArchiveSummary {
+            archive_path: std::path::PathBuf::from("recordings/meeting.opus"),
+            duration_ms: 2000.0,
+            sample_rate: 16000,
+            channels: 1,
+            bitrate_bps: 24_000,
+            original_bytes: 0,
+            archive_bytes: 6000,
+            original_kept: true,
+        };
+        finish_live_meeting(&db, meeting_id, None, Some(&archive)).await.unwrap();
+
+        let meeting = db.meetings().get(meeting_id).await.unwrap().unwrap();
+        assert_eq!(meeting.playback_path(), Some("recordings/meeting.opus"));
+    }
+}
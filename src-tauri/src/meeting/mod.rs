@@ -1,7 +1,26 @@
 //! Meeting detection and management
 
-// TODO: Implement meeting modules
-// This is a placeholder for the meeting domain
+pub mod archive;
+pub mod captions;
+pub mod editing;
+pub mod formatting;
+pub mod import;
+pub mod jobs;
+pub mod live;
+pub mod retranscribe;
+pub mod speakers;
+
+pub use archive::archive_meeting_audio;
+pub use captions::export_captions;
+pub use editing::{edit_transcript, revision_diff, TextChange};
+pub use formatting::format_transcript;
+pub use import::{import_recording, ImportedMeeting, MeetingImportProgress};
+pub use jobs::{JobEvent, JobOutcome, JobQueueConfig, TranscriptionQueue};
+pub use live::{finish_live_meeting, start_live_meeting};
+pub use retranscribe::{
+    compare_versions, retranscribe_meetings, ComparedSection, RetranscriptionBatch, SkippedMeeting, VersionComparison,
+};
+pub use speakers::{diarize_meeting, name_meeting_speaker, rename_speaker};
 
 /// Meeting service placeholder
 pub struct MeetingService;
@@ -11,4 +30,4 @@ impl MeetingService {
     pub fn new() -> Self {
         Self
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,388 @@
+//! Re-transcribing recorded meetings
+//!
+//! After a model upgrade or a vocabulary fix, past meetings can be run
+//! through the current pipeline again. Each meeting becomes a backlog job
+//! whose result is a new version of the meeting's transcript, linked to the
+//! version it replaces. Hand edits carry over wherever an edited segment
+//! still lines up in time with one the engine produced; comparing the two
+//! versions shows what the engine now hears differently and which edits
+//! could not be kept.
+
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::error::{AppError, AppResult};
+use crate::meeting::editing::diff_words;
+use crate::meeting::{TextChange, TranscriptionQueue};
+use crate::storage::{DatabaseService, JobPriority, StoredSegment, Transcript, TranscriptionJob};
+use crate::transcription::{spread_words, TranscriptSegment};
+
+/// Share of two segments' combined span they must have in common to count
+/// as the same stretch of speech
+const MIN_ALIGNMENT: f64 = 0.7;
+
+/// Largest word-by-word comparison, in before × after words; longer
+/// stretches are shown as replaced outright
+const MAX_DIFF_CELLS: usize = 4_000_000;
+
+/// Meetings queued for re-transcription, and those that weren't
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RetranscriptionBatch {
+    pub queued: Vec<TranscriptionJob>,
+    pub skipped: Vec<SkippedMeeting>,
+}
+
+/// A meeting left out of a batch, and why
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SkippedMeeting {
+    pub meeting_id: i64,
+    pub reason: String,
+}
+
+/// Two versions of a meeting's transcript side by side
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VersionComparison {
+    pub before: Transcript,
+    pub after: Transcript,
+    /// The meeting's timeline, a stretch of speech at a time
+    pub sections: Vec<ComparedSection>,
+    pub words_added: usize,
+    pub words_removed: usize,
+    /// Edited segments of `before` that `after` still has
+    pub edits_kept: usize,
+    /// Edited segments of `before` that didn't line up with `after`
+    pub edits_lost: Vec<StoredSegment>,
+}
+
+/// Word-level changes over a stretch of the timeline both versions agree
+/// on the bounds of
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ComparedSection {
+    pub start_ms: f64,
+    pub end_ms: f64,
+    pub changes: Vec<TextChange>,
+}
+
+/// Queue meetings to be transcribed again with the current engine, language
+/// settings and vocabulary
+///
+/// Meetings without a recording, and those already waiting for a job, are
+/// skipped. The rest run at backlog priority, behind live meetings.
+pub async fn retranscribe_meetings(
+    db: &DatabaseService,
+    queue: &TranscriptionQueue,
+    meeting_ids: &[i64],
+) -> AppResult<RetranscriptionBatch> {
+    let mut batch = RetranscriptionBatch { queued: Vec::new(), skipped: Vec::new() };
+
+    for &meeting_id in meeting_ids {
+        let skip = |reason: String| SkippedMeeting { meeting_id, reason };
+        if db.jobs().for_meeting(meeting_id).await?.iter().any(|job| !job.status.is_finished()) {
+            batch.skipped.push(skip("A transcription job is already queued".to_string()));
+            continue;
+        }
+        let mut job = match queue.prepare(meeting_id, JobPriority::Backlog).await {
+            Ok(job) => job,
+            Err(e) => {
+                batch.skipped.push(skip(e.to_string()));
+                continue;
+            }
+        };
+
+        job.replaces_transcription_id = db.transcripts().latest(meeting_id).await?.map(|t| t.id);
+        batch.queued.push(queue.submit(job).await?);
+    }
+
+    info!(
+        "Queued {} meetings for re-transcription, skipped {}",
+        batch.queued.len(),
+        batch.skipped.len()
+    );
+    Ok(batch)
+}
+
+/// Segments for a new version of a transcript, with what was done by hand to
+/// `previous` carried over
+///
+/// Each segment of `previous` is matched to at most one new segment covering
+/// much the same time, edited segments first. A match takes the old
+/// segment's speaker, and if it was edited, its text too; the engine's new
+/// text is kept as the original. Edits without a match are dropped.
+pub fn carry_edits(previous: &[StoredSegment], segments: &[TranscriptSegment]) -> Vec<StoredSegment> {
+    let mut carried: Vec<StoredSegment> = segments
+        .iter()
+        .map(|segment| StoredSegment {
+            id: 0,
+            transcription_id: 0,
+            speaker_id: None,
+            meeting_speaker_id: None,
+            segment: segment.clone(),
+            is_edited: false,
+        })
+        .collect();
+    let mut matched = vec![false; carried.len()];
+
+    let mut previous: Vec<&StoredSegment> = previous.iter().collect();
+    previous.sort_by_key(|old| !old.is_edited);
+    for old in previous {
+        let best = carried
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !matched[*i])
+            .map(|(i, new)| (i, alignment(&old.segment, &new.segment)))
+            .filter(|(_, share)| *share >= MIN_ALIGNMENT)
+            .max_by(|a, b| a.1.total_cmp(&b.1));
+        let Some((i, _)) = best else {
+            continue;
+        };
+        matched[i] = true;
+
+        let new = &mut carried[i];
+        new.speaker_id = old.speaker_id;
+        new.meeting_speaker_id = old.meeting_speaker_id;
+        if old.is_edited {
+            if new.segment.text != old.segment.text {
+                new.segment.words = spread_words(&new.segment, &old.segment.text);
+                new.segment.original_text = Some(std::mem::replace(&mut new.segment.text, old.segment.text.clone()));
+            }
+            new.is_edited = true;
+        }
+    }
+    carried
+}
+
+/// Compare two versions of a meeting's transcript
+pub async fn compare_versions(db: &DatabaseService, before_id: i64, after_id: i64) -> AppResult<VersionComparison> {
+    let transcripts = db.transcripts();
+    let not_found = |id| AppError::database(format!("Transcript {} not found", id));
+    let before = transcripts.get(before_id).await?.ok_or_else(|| not_found(before_id))?;
+    let after = transcripts.get(after_id).await?.ok_or_else(|| not_found(after_id))?;
+    if before.meeting_id != after.meeting_id {
+        return Err(AppError::config("Only transcripts of the same meeting can be compared"));
+    }
+
+    let old = transcripts.segments(before.id).await?;
+    let new = transcripts.segments(after.id).await?;
+    let sections = compare_segments(&old, &new);
+    let count = |text: &str| text.split_whitespace().count();
+    let words_added = sections
+        .iter()
+        .flat_map(|s| &s.changes)
+        .map(|change| match change {
+            TextChange::Added { text } => count(text),
+            _ => 0,
+        })
+        .sum();
+    let words_removed = sections
+        .iter()
+        .flat_map(|s| &s.changes)
+        .map(|change| match change {
+            TextChange::Removed { text } => count(text),
+            _ => 0,
+        })
+        .sum();
+
+    let (kept, lost): (Vec<&StoredSegment>, Vec<&StoredSegment>) = old.iter().filter(|s| s.is_edited).partition(|edit| {
+        new.iter().any(|s| {
+            s.is_edited && s.segment.text == edit.segment.text && alignment(&s.segment, &edit.segment) > 0.0
+        })
+    });
+
+    Ok(VersionComparison {
+        before,
+        after,
+        sections,
+        words_added,
+        words_removed,
+        edits_kept: kept.len(),
+        edits_lost: lost.into_iter().cloned().collect(),
+    })
+}
+
+/// Diff two sets of segments a stretch of speech at a time
+///
+/// A stretch ends wherever neither version has a segment running on, so
+/// segments that were only cut differently still compare word by word.
+fn compare_segments(before: &[StoredSegment], after: &[StoredSegment]) -> Vec<ComparedSection> {
+    let mut timeline: Vec<(bool, &TranscriptSegment)> = before
+        .iter()
+        .map(|s| (false, &s.segment))
+        .chain(after.iter().map(|s| (true, &s.segment)))
+        .collect();
+    timeline.sort_by(|a, b| a.1.start_ms.total_cmp(&b.1.start_ms));
+
+    let mut sections = Vec::new();
+    let mut words: (Vec<String>, Vec<String>) = (Vec::new(), Vec::new());
+    let mut span: Option<(f64, f64)> = None;
+    let mut close = |span: (f64, f64), words: &mut (Vec<String>, Vec<String>)| {
+        let (old, new) = std::mem::take(words);
+        sections.push(ComparedSection { start_ms: span.0, end_ms: span.1, changes: diff_section(&old, &new) });
+    };
+
+    for (is_after, segment) in timeline {
+        match span {
+            Some((start, end)) if segment.start_ms < end => span = Some((start, end.max(segment.end_ms))),
+            Some(finished) => {
+                close(finished, &mut words);
+                span = Some((segment.start_ms, segment.end_ms));
+            }
+            None => span = Some((segment.start_ms, segment.end_ms)),
+        }
+        let side = if is_after { &mut words.1 } else { &mut words.0 };
+        side.extend(segment.text.split_whitespace().map(str::to_string));
+    }
+    if let Some(finished) = span {
+        close(finished, &mut words);
+    }
+    sections
+}
+
+fn diff_section(before: &[String], after: &[String]) -> Vec<TextChange> {
+    if before.len().saturating_mul(after.len()) <= MAX_DIFF_CELLS {
+        return diff_words(before, after);
+    }
+    let mut changes = Vec::new();
+    if !before.is_empty() {
+        changes.push(TextChange::Removed { text: before.join(" ") });
+    }
+    if !after.is_empty() {
+        changes.push(TextChange::Added { text: after.join(" ") });
+    }
+    changes
+}
+
+/// Time two segments share, as a share of the time either covers
+fn alignment(a: &TranscriptSegment, b: &TranscriptSegment) -> f64 {
+    let shared = a.end_ms.min(b.end_ms) - a.start_ms.max(b.start_ms);
+    let covered = a.end_ms.max(b.end_ms) - a.start_ms.min(b.start_ms);
+    if shared <= 0.0 || covered <= 0.0 {
+        return 0.0;
+    }
+    shared / covered
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::audio::test_wav::write_wav;
+    use crate::audio::AudioConfig;
+    use crate::meeting::{edit_transcript, format_transcript, JobOutcome, JobQueueConfig};
+    use crate::storage::{NewMeeting, SegmentEdit};
+    use crate::transcription::{FormattingConfig, MockEngine};
+
+    fn segment(text: &str, start_ms: f64, end_ms: f64) -> TranscriptSegment {
+        TranscriptSegment {
+            text: text.to_string(),
+            start_ms,
+            end_ms,
+            confidence: 0.8,
+            language: "en".to_string(),
+            model: "mock".to_string(),
+            words: Vec::new(),
+            speaker_hint: None,
+            original_text: None,
+        }
+    }
+
+    fn stored(text: &str, start_ms: f64, end_ms: f64, speaker: i64, is_edited: bool) -> StoredSegment {
+        StoredSegment {
+            id: 0,
+            transcription_id: 1,
+            speaker_id: None,
+            meeting_speaker_id: Some(speaker),
+            segment: segment(text, start_ms, end_ms),
+            is_edited,
+        }
+    }
+
+    #[test]
+    fn test_edits_follow_segments_that_still_line_up() {
+        let previous = vec![
+            stored("Kubernetes rollout", 0.0, 2000.0, 1, true),
+            stored("next week", 2000.0, 3000.0, 2, false),
+            stored("any objections", 3000.0, 4000.0, 1, true),
+        ];
+        // The new model hears the first stretch a little later and joins the last two
+        let segments = vec![
+            segment("cooper netties roll out", 150.0, 2100.0),
+            segment("next weak any objections", 2100.0, 4000.0),
+        ];
+
+        let carried = carry_edits(&previous, &segments);
+
+        assert_eq!(carried[0].segment.text, "Kubernetes rollout");
+        assert_eq!(carried[0].segment.original_text.as_deref(), Some("cooper netties roll out"));
+        assert_eq!(carried[0].segment.words.len(), 2);
+        assert!(carried[0].is_edited);
+        assert_eq!(carried[0].meeting_speaker_id, Some(1));
+        // Neither old segment covers enough of the joined one to claim it
+        assert_eq!(carried[1].segment.text, "next weak any objections");
+        assert!(!carried[1].is_edited);
+        assert_eq!(carried[1].meeting_speaker_id, None);
+    }
+
+    #[tokio::test]
+    async fn test_retranscribed_meeting_gets_a_new_version_with_its_edits() {
+        let db = DatabaseService::in_memory().await.unwrap();
+        let path = std::env::temp_dir().join(format!("retranscribe-{}.wav", uuid::Uuid::new_v4()));
+        write_wav(&path, 16000, 1, &vec![0.2; 16000 * 4]);
+        let meeting_id = db.meetings()
+            .create(&NewMeeting {
+                audio_file_path: Some(path.to_string_lossy().into_owned()),
+                ..NewMeeting::recording("Sprint review")
+            })
+            .await
+            .unwrap();
+        let unrecorded = db.meetings().create(&NewMeeting::recording("Hallway chat")).await.unwrap();
+
+        let queue = |engine: MockEngine| {
+            let config = JobQueueConfig { chunk_ms: 1000.0, ..Default::default() };
+            TranscriptionQueue::new(db.clone(), Arc::new(engine), AudioConfig::default(), config)
+        };
+        let first = queue(MockEngine::new());
+        first.enqueue(meeting_id, JobPriority::Normal).await.unwrap();
+        first.run_next().await.unwrap();
+        let original = db.transcripts().for_meeting(meeting_id).await.unwrap().remove(0);
+        let segments = db.transcripts().segments(original.id).await.unwrap();
+        let edit = SegmentEdit::Text { segment_id: segments[1].id, text: "Bravo Two".to_string() };
+        edit_transcript(&db, original.id, edit, "sam").await.unwrap();
+        // A formatted copy is not a version to replace
+        format_transcript(&db, original.id, FormattingConfig::default()).await.unwrap();
+
+        // The upgraded model hears the last word differently
+        let upgraded = queue(MockEngine::new().with_script(&["alpha", "bravo", "charlie", "dealt a"]));
+        let batch = retranscribe_meetings(&db, &upgraded, &[meeting_id, unrecorded]).await.unwrap();
+        assert_eq!(batch.queued.len(), 1);
+        assert_eq!(batch.queued[0].replaces_transcription_id, Some(original.id));
+        assert_eq!(batch.skipped[0].meeting_id, unrecorded);
+        let again = retranscribe_meetings(&db, &upgraded, &[meeting_id]).await.unwrap();
+        assert!(again.queued.is_empty());
+
+        let job_id = batch.queued[0].id;
+        assert_eq!(upgraded.run_next().await.unwrap(), Some((job_id, JobOutcome::Completed)));
+        std::fs::remove_file(PathBuf::from(&path)).ok();
+
+        let versions = db.transcripts().for_meeting(meeting_id).await.unwrap();
+        assert_eq!(versions.len(), 3);
+        assert_eq!(versions[2].previous_transcription_id, Some(original.id));
+        assert_eq!(versions[2].content, "alpha Bravo Two charlie dealt a");
+        // The version replaced is left as it was
+        assert_eq!(db.transcripts().get(original.id).await.unwrap().unwrap().content, "alpha Bravo Two charlie delta");
+
+        let comparison = compare_versions(&db, original.id, versions[2].id).await.unwrap();
+        assert_eq!(comparison.sections.len(), 4);
+        assert_eq!(
+            comparison.sections[3].changes,
+            vec![
+                TextChange::Removed { text: "delta".to_string() },
+                TextChange::Added { text: "dealt a".to_string() },
+            ]
+        );
+        assert_eq!((comparison.words_added, comparison.words_removed), (2, 1));
+        assert_eq!(comparison.edits_kept, 1);
+        assert!(comparison.edits_lost.is_empty());
+    }
+}
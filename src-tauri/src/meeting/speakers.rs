@@ -0,0 +1,226 @@
+//! Working out who spoke in a meeting
+
+use std::path::PathBuf;
+use tracing::{error, info};
+
+use crate::audio::{AudioConfig, AudioFileImporter};
+use crate::error::{AppError, AppResult};
+use crate::storage::{DatabaseService, MeetingSpeaker, Speaker};
+use crate::transcription::{DiarizationConfig, Diarizer, TranscriptSegment};
+
+/// Attribute the segments of a meeting's latest transcript to speakers
+///
+/// The recording is decoded again to embed each segment's voice. Voices
+/// matching an enrolled profile are labelled with that person's name; the
+/// rest are "Speaker N" until named. Any earlier diarization of the meeting
+/// is replaced.
+pub async fn diarize_meeting(
+    db: &DatabaseService,
+    meeting_id: i64,
+    config: AudioConfig,
+    diarization: DiarizationConfig,
+) -> AppResult<Vec<MeetingSpeaker>> {
+    diarization.validate().map_err(|e| AppError::config(e.to_string()))?;
+
+    let meeting = db
+        .meetings()
+        .get(meeting_id)
+        .await?
+        .ok_or_else(|| AppError::database(format!("Meeting {} not found", meeting_id)))?;
+    let path = meeting
+        .playback_path()
+        .map(PathBuf::from)
+        .ok_or_else(|| AppError::audio(format!("Meeting {} has no recording", meeting_id)))?;
+
+    let transcripts = db.transcripts();
+    let transcript = transcripts
+        .latest(meeting_id)
+        .await?
+        .ok_or_else(|| AppError::database(format!("Meeting {} has no transcript", meeting_id)))?;
+    let stored = transcripts.segments(transcript.id).await?;
+    let segment_ids: Vec<i64> = stored.iter().map(|s| s.id).collect();
+    let segments: Vec<TranscriptSegment> = stored.into_iter().map(|s| s.segment).collect();
+    let profiles = db.speakers().profiles().await?;
+
+    info!("Diarizing {} segments of meeting {}", segments.len(), meeting_id);
+
+    let diarizer = Diarizer::new(diarization);
+    let mut embedder = diarizer.embedder(&segments, config.sample_rate);
+    let importer = AudioFileImporter::new(config);
+    let embeddings = tokio::task::spawn_blocking(move || {
+        importer
+            .import(&path, |chunk| { embedder.push(&chunk); Ok(()) }, |_| {})
+            .map(|_| embedder.finish())
+    })
+    .await
+    .map_err(|e| AppError::internal(format!("Diarization task failed: {}", e)))?
+    .map_err(|e| {
+        error!("Failed to decode recording of meeting {}: {}", meeting_id, e);
+        AppError::audio(e.to_string())
+    })?;
+
+    let result = diarizer.diarize(&segments, embeddings, &profiles);
+    db.speakers().replace_meeting_speakers(meeting_id, &segment_ids, &result).await
+}
+
+/// Name a voice heard in a meeting
+///
+/// The name is looked up: a known person learns this voice into their
+/// profile, and a new name enrolls a new speaker. A voice that was linked
+/// to someone else moves to the named speaker; nobody is renamed. Either way
+/// the voice is recognised in later meetings.
+pub async fn name_meeting_speaker(db: &DatabaseService, meeting_speaker_id: i64, name: &str) -> AppResult<Speaker> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(AppError::config("Speaker name cannot be empty"));
+    }
+
+    let speakers = db.speakers();
+    let (meeting_speaker, voice) = speakers
+        .meeting_speaker(meeting_speaker_id)
+        .await?
+        .ok_or_else(|| AppError::database(format!("Meeting speaker {} not found", meeting_speaker_id)))?;
+
+    let named = speakers.find_by_name(name).await?;
+    let speaker_id = match named {
+        // Already theirs: the profile has learned this voice
+        Some(speaker) if meeting_speaker.speaker_id == Some(speaker.id) => speaker.id,
+        Some(speaker) => match speaker.voice_profile() {
+            Some(mut profile) => {
+                profile.absorb(&voice);
+                speakers.update_profile(&profile).await?;
+                profile.speaker_id
+            }
+            None => speakers.create(name, &voice).await?,
+        },
+        None => speakers.create(name, &voice).await?,
+    };
+    speakers.link_meeting_speaker(meeting_speaker_id, speaker_id, name).await?;
+
+    info!("Meeting speaker {} is now speaker {} ({})", meeting_speaker_id, speaker_id, name);
+
+    speakers
+        .get(speaker_id)
+        .await?
+        .ok_or_else(|| AppError::database(format!("Speaker {} not found", speaker_id)))
+}
+
+/// Rename a known speaker, in every meeting they were heard in
+pub async fn rename_speaker(db: &DatabaseService, speaker_id: i64, name: &str) -> AppResult<Speaker> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(AppError::config("Speaker name cannot be empty"));
+    }
+
+    let speakers = db.speakers();
+    speakers.rename(speaker_id, name).await?;
+
+    info!("Speaker {} renamed to {}", speaker_id, name);
+
+    speakers
+        .get(speaker_id)
+        .await?
+        .ok_or_else(|| AppError::database(format!("Speaker {} not found", speaker_id)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::test_wav::write_wav;
+    use crate::storage::NewMeeting;
+    use crate::transcription::diarization::test_voices::conversation;
+
+    const TURNS: &[(usize, f32)] = &[(0, 2.5), (1, 2.0), (0, 1.5), (2, 3.0), (1, 1.5), (2, 2.0)];
+
+    async fn recorded_meeting(db: &DatabaseService, title: &str, seed: u64) -> (i64, PathBuf) {
+        let (samples, segments) = conversation(TURNS, 16000, seed);
+        let path = std::env::temp_dir().join(format!("{}-{}.wav", title, uuid::Uuid::new_v4()));
+        write_wav(&path, 16000, 1, &samples);
+
+        let mut new_meeting = NewMeeting::recording(title);
+        new_meeting.audio_file_path = Some(path.to_string_lossy().into_owned());
+        let id = db.meetings().create(&new_meeting).await.unwrap();
+        db.transcripts().create(id, &segments, "mock", 0).await.unwrap();
+        (id, path)
+    }
+
+    #[tokio::test]
+    async fn test_named_speaker_is_recognised_in_a_later_meeting() {
+        let db = DatabaseService::in_memory().await.unwrap();
+        let config = AudioConfig { sample_rate: 16000, channels: 1, ..Default::default() };
+
+        let (first, first_path) = recorded_meeting(&db, "kickoff", 1).await;
+        let voices = diarize_meeting(&db, first, config.clone(), DiarizationConfig::default()).await.unwrap();
+        assert_eq!(voices.iter().map(|v| v.label.as_str()).collect::<Vec<_>>(), ["Speaker 1", "Speaker 2", "Speaker 3"]);
+
+        let ana = name_meeting_speaker(&db, voices[0].id, "Ana").await.unwrap();
+        assert_eq!(ana.total_meetings, 1);
+        assert!(ana.voice_fingerprint.is_some());
+
+        let transcript = db.transcripts().latest(first).await.unwrap().unwrap();
+        let segments = db.transcripts().segments(transcript.id).await.unwrap();
+        assert_eq!(segments[2].speaker_id, Some(ana.id));
+        assert_eq!(segments[1].meeting_speaker_id, Some(voices[1].id));
+
+        let (second, second_path) = recorded_meeting(&db, "follow-up", 100).await;
+        let voices = diarize_meeting(&db, second, config, DiarizationConfig::default()).await.unwrap();
+        assert_eq!(voices[0].label, "Ana");
+        assert_eq!(voices[0].speaker_id, Some(ana.id));
+        assert_eq!(voices[1].speaker_id, None);
+
+        let ana = db.speakers().get(ana.id).await.unwrap().unwrap();
+        assert_eq!(ana.total_meetings, 2);
+
+        std::fs::remove_file(first_path).ok();
+        std::fs::remove_file(second_path).ok();
+    }
+
+    #[tokio::test]
+    async fn test_naming_an_existing_person_refines_their_profile() {
+        let db = DatabaseService::in_memory().await.unwrap();
+        let config = AudioConfig { sample_rate: 16000, channels: 1, ..Default::default() };
+
+        let (first, first_path) = recorded_meeting(&db, "planning", 1).await;
+        let voices = diarize_meeting(&db, first, config, DiarizationConfig::default()).await.unwrap();
+        let bruno = name_meeting_speaker(&db, voices[1].id, "Bruno").await.unwrap();
+        let again = name_meeting_speaker(&db, voices[2].id, " bruno ").await.unwrap();
+
+        assert_eq!(again.id, bruno.id);
+        assert_eq!(again.fingerprint_samples, 2);
+        assert!(name_meeting_speaker(&db, voices[0].id, "  ").await.is_err());
+
+        std::fs::remove_file(first_path).ok();
+    }
+
+    #[tokio::test]
+    async fn test_naming_a_linked_voice_moves_it_to_the_named_speaker() {
+        let db = DatabaseService::in_memory().await.unwrap();
+        let config = AudioConfig { sample_rate: 16000, channels: 1, ..Default::default() };
+
+        let (first, first_path) = recorded_meeting(&db, "standup", 1).await;
+        let voices = diarize_meeting(&db, first, config, DiarizationConfig::default()).await.unwrap();
+        let ana = name_meeting_speaker(&db, voices[0].id, "Ana").await.unwrap();
+        let bruno = name_meeting_speaker(&db, voices[1].id, "Bruno").await.unwrap();
+
+        // The second voice was Ana too: Bruno keeps his name
+        let moved = name_meeting_speaker(&db, voices[1].id, "Ana").await.unwrap();
+        assert_eq!(moved.id, ana.id);
+        assert_eq!(moved.fingerprint_samples, 2);
+        assert_eq!(db.speakers().get(bruno.id).await.unwrap().unwrap().name.as_deref(), Some("Bruno"));
+        let voices = db.speakers().meeting_speakers(first).await.unwrap();
+        assert_eq!((voices[1].speaker_id, voices[1].label.as_str()), (Some(ana.id), "Ana"));
+
+        // Naming it again changes nothing
+        let same = name_meeting_speaker(&db, voices[1].id, "ana").await.unwrap();
+        assert_eq!(same.fingerprint_samples, 2);
+
+        // Renaming is asked for explicitly, and follows the speaker everywhere
+        let renamed = rename_speaker(&db, ana.id, "Ana Lima").await.unwrap();
+        assert_eq!(renamed.name.as_deref(), Some("Ana Lima"));
+        let voices = db.speakers().meeting_speakers(first).await.unwrap();
+        assert_eq!(voices[0].label, "Ana Lima");
+        assert!(rename_speaker(&db, ana.id, " ").await.is_err());
+
+        std::fs::remove_file(first_path).ok();
+    }
+}
@@ -0,0 +1,93 @@
+//! Database connection and setup
+
+use std::str::FromStr;
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions, SqliteSynchronous};
+use sqlx::SqlitePool;
+use tracing::info;
+
+use crate::config::DatabaseConfig;
+use crate::error::AppResult;
+use super::migrations;
+use super::repositories::{
+    JobRepository, MeetingRepository, SpeakerRepository, TranscriptRepository, VocabularyRepository,
+};
+
+/// Owns the SQLite connection pool and hands out repositories
+#[derive(Debug, Clone)]
+pub struct DatabaseService {
+    pool: SqlitePool,
+}
+
+impl DatabaseService {
+    /// Open (or create) the database described by `config` and bring its schema up to date
+    pub async fn connect(config: &DatabaseConfig) -> AppResult<Self> {
+        let journal_mode = if config.enable_wal {
+            SqliteJournalMode::Wal
+        } else {
+            SqliteJournalMode::Delete
+        };
+
+        let options = SqliteConnectOptions::new()
+            .filename(&config.path)
+            .create_if_missing(true)
+            .journal_mode(journal_mode)
+            .synchronous(SqliteSynchronous::Normal)
+            .foreign_keys(true);
+
+        let pool = SqlitePoolOptions::new()
+            .max_connections(config.max_connections)
+            .connect_with(options)
+            .await?;
+
+        info!("Opened database at {}", config.path.display());
+        Self::with_pool(pool).await
+    }
+
+    /// Open a private in-memory database, mainly for tests
+    pub async fn in_memory() -> AppResult<Self> {
+        let options = SqliteConnectOptions::from_str("sqlite::memory:")?.foreign_keys(true);
+
+        // Every connection to :memory: is a separate database, so keep exactly one
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect_with(options)
+            .await?;
+
+        Self::with_pool(pool).await
+    }
+
+    async fn with_pool(pool: SqlitePool) -> AppResult<Self> {
+        migrations::run(&pool).await?;
+        Ok(Self { pool })
+    }
+
+    /// Underlying connection pool
+    pub fn pool(&self) -> &SqlitePool {
+        &self.pool
+    }
+
+    /// Repository for meeting records
+    pub fn meetings(&self) -> MeetingRepository {
+        MeetingRepository::new(self.pool.clone())
+    }
+
+    /// Repository for transcripts and their segments
+    pub fn transcripts(&self) -> TranscriptRepository {
+        TranscriptRepository::new(self.pool.clone())
+    }
+
+    /// Repository for speakers and the voices heard in each meeting
+    pub fn speakers(&self) -> SpeakerRepository {
+        SpeakerRepository::new(self.pool.clone())
+    }
+
+    /// Repository for the user's vocabulary terms
+    pub fn vocabulary(&self) -> VocabularyRepository {
+        VocabularyRepository::new(self.pool.clone())
+    }
+
+    /// Repository for the transcription job queue
+    pub fn jobs(&self) -> JobRepository {
+        JobRepository::new(self.pool.clone())
+    }
+}
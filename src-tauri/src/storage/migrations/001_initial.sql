@@ -0,0 +1,73 @@
+-- Core entities
+CREATE TABLE meetings (
+    id INTEGER PRIMARY KEY,
+    title TEXT NOT NULL,
+    start_time DATETIME NOT NULL,
+    end_time DATETIME,
+    duration_seconds INTEGER,
+    calendar_event_id TEXT,
+    audio_file_path TEXT,
+    participants TEXT, -- JSON array
+    status TEXT CHECK(status IN ('scheduled', 'recording', 'completed', 'archived')),
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE transcriptions (
+    id INTEGER PRIMARY KEY,
+    meeting_id INTEGER REFERENCES meetings(id),
+    content TEXT NOT NULL,
+    language TEXT DEFAULT 'en',
+    confidence REAL,
+    model_used TEXT,
+    processing_time_ms INTEGER,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE transcription_segments (
+    id INTEGER PRIMARY KEY,
+    transcription_id INTEGER REFERENCES transcriptions(id),
+    speaker_id INTEGER,
+    text TEXT NOT NULL,
+    start_timestamp REAL,
+    end_timestamp REAL,
+    confidence REAL,
+    is_edited BOOLEAN DEFAULT FALSE
+);
+
+CREATE TABLE summaries (
+    id INTEGER PRIMARY KEY,
+    meeting_id INTEGER REFERENCES meetings(id),
+    content TEXT NOT NULL,
+    template_name TEXT,
+    api_provider TEXT,
+    token_count INTEGER,
+    cost_usd REAL,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE speakers (
+    id INTEGER PRIMARY KEY,
+    name TEXT,
+    email TEXT,
+    voice_fingerprint BLOB,
+    color_hex TEXT,
+    total_meetings INTEGER DEFAULT 0,
+    last_seen DATETIME,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Full-text search indexes
+CREATE VIRTUAL TABLE meetings_fts USING fts5(
+    title, participants, content='meetings'
+);
+
+CREATE VIRTUAL TABLE transcriptions_fts USING fts5(
+    content, content='transcriptions'
+);
+
+-- Performance indexes
+CREATE INDEX idx_meetings_start_time ON meetings(start_time DESC);
+CREATE INDEX idx_meetings_status ON meetings(status);
+CREATE INDEX idx_segments_speaker ON transcription_segments(speaker_id);
+CREATE INDEX idx_segments_timestamp ON transcription_segments(start_timestamp);
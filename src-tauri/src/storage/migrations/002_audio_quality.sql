@@ -0,0 +1,2 @@
+-- Per-meeting audio quality report, stored as JSON
+ALTER TABLE meetings ADD COLUMN audio_quality TEXT;
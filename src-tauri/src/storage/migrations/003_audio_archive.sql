@@ -0,0 +1,3 @@
+-- Compressed Opus copy of the recording; audio_file_path is cleared when the
+-- original is not kept
+ALTER TABLE meetings ADD COLUMN archive_file_path TEXT;
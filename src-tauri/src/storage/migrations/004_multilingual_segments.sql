@@ -0,0 +1,14 @@
+-- Language is decided per segment; transcriptions.language keeps the
+-- dominant one
+ALTER TABLE transcription_segments ADD COLUMN language TEXT;
+
+-- Model that produced each segment, which may differ within a transcript
+-- when low-confidence audio is routed to the cloud
+ALTER TABLE transcription_segments ADD COLUMN model_used TEXT;
+
+-- Per-meeting language settings: a pinned language, or the candidates
+-- detection may choose from (JSON array)
+ALTER TABLE meetings ADD COLUMN pinned_language TEXT;
+ALTER TABLE meetings ADD COLUMN candidate_languages TEXT;
+
+CREATE INDEX idx_segments_transcription ON transcription_segments(transcription_id, start_timestamp);
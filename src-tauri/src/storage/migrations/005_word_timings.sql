@@ -0,0 +1,13 @@
+-- Per-word timing within a segment, for click-to-seek and exact quotes
+CREATE TABLE transcription_words (
+    id INTEGER PRIMARY KEY,
+    segment_id INTEGER REFERENCES transcription_segments(id),
+    transcription_id INTEGER REFERENCES transcriptions(id),
+    word TEXT NOT NULL,
+    start_timestamp REAL NOT NULL,
+    end_timestamp REAL NOT NULL,
+    confidence REAL
+);
+
+CREATE INDEX idx_words_transcription ON transcription_words(transcription_id, start_timestamp);
+CREATE INDEX idx_words_segment ON transcription_words(segment_id);
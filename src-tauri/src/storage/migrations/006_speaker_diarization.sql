@@ -0,0 +1,22 @@
+-- Voices found in a meeting by diarization. One named by the user, or
+-- recognised from an earlier meeting, points at its `speakers` profile
+CREATE TABLE meeting_speakers (
+    id INTEGER PRIMARY KEY,
+    meeting_id INTEGER REFERENCES meetings(id),
+    label TEXT NOT NULL,
+    speaker_id INTEGER REFERENCES speakers(id),
+    embedding BLOB NOT NULL,
+    speech_ms REAL,
+    similarity REAL,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+-- The meeting speaker each segment was attributed to; speaker_id follows
+-- once that speaker is known
+ALTER TABLE transcription_segments ADD COLUMN meeting_speaker_id INTEGER REFERENCES meeting_speakers(id);
+
+-- Meeting speakers averaged into voice_fingerprint
+ALTER TABLE speakers ADD COLUMN fingerprint_samples INTEGER DEFAULT 0;
+
+CREATE INDEX idx_meeting_speakers_meeting ON meeting_speakers(meeting_id);
+CREATE INDEX idx_segments_meeting_speaker ON transcription_segments(meeting_speaker_id);
@@ -0,0 +1,5 @@
+-- Capture track each segment was heard on: 'me' for the local microphone,
+-- 'others' for system audio
+ALTER TABLE transcription_segments ADD COLUMN speaker_hint TEXT;
+
+CREATE INDEX idx_segments_speaker_hint ON transcription_segments(transcription_id, speaker_hint);
@@ -0,0 +1,17 @@
+-- Project a meeting belongs to; its vocabulary applies to the meeting
+ALTER TABLE meetings ADD COLUMN project TEXT;
+
+-- Terms transcription is biased towards. A term with neither a project nor
+-- a meeting applies everywhere.
+CREATE TABLE vocabulary_terms (
+    id INTEGER PRIMARY KEY,
+    term TEXT NOT NULL,
+    variants TEXT NOT NULL DEFAULT '[]', -- JSON array of misrecognitions
+    project TEXT,
+    meeting_id INTEGER REFERENCES meetings(id) ON DELETE CASCADE,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    CHECK (project IS NULL OR meeting_id IS NULL)
+);
+
+CREATE INDEX idx_vocabulary_project ON vocabulary_terms(project);
+CREATE INDEX idx_vocabulary_meeting ON vocabulary_terms(meeting_id);
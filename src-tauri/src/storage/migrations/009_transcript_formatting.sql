@@ -0,0 +1,6 @@
+-- Text as the engine produced it, for segments formatting has changed
+ALTER TABLE transcription_segments ADD COLUMN original_text TEXT;
+
+-- A formatted transcript points at the transcript it was made from, which
+-- is kept as it was
+ALTER TABLE transcriptions ADD COLUMN source_transcription_id INTEGER REFERENCES transcriptions(id);
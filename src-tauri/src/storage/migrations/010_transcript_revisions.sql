@@ -0,0 +1,14 @@
+-- Hand edits to transcripts. Each revision keeps the segments it touched
+-- as they were before and after (JSON), enough to show and undo the edit
+CREATE TABLE transcript_revisions (
+    id INTEGER PRIMARY KEY,
+    transcription_id INTEGER NOT NULL REFERENCES transcriptions(id),
+    author TEXT NOT NULL,
+    edit TEXT NOT NULL, -- JSON
+    before_segments TEXT NOT NULL,
+    after_segments TEXT NOT NULL,
+    reverted_by INTEGER REFERENCES transcript_revisions(id),
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_revisions_transcription ON transcript_revisions(transcription_id, id);
@@ -0,0 +1,32 @@
+-- Recordings waiting to be transcribed, or being transcribed. Higher
+-- priorities run first; a failed job waits until run_after to be retried
+CREATE TABLE transcription_jobs (
+    id INTEGER PRIMARY KEY,
+    meeting_id INTEGER NOT NULL REFERENCES meetings(id) ON DELETE CASCADE,
+    priority INTEGER NOT NULL DEFAULT 1,
+    status TEXT NOT NULL DEFAULT 'queued',
+    audio_path TEXT NOT NULL,
+    chunk_ms REAL NOT NULL,
+    options TEXT NOT NULL DEFAULT '{}', -- JSON transcription options
+    total_ms REAL,
+    completed_chunks INTEGER NOT NULL DEFAULT 0,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    last_error TEXT,
+    run_after DATETIME,
+    transcription_id INTEGER REFERENCES transcriptions(id),
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Segments of each chunk a job has finished, so an interrupted job
+-- resumes after its last checkpoint
+CREATE TABLE transcription_job_chunks (
+    job_id INTEGER NOT NULL REFERENCES transcription_jobs(id) ON DELETE CASCADE,
+    chunk_index INTEGER NOT NULL,
+    segments TEXT NOT NULL, -- JSON
+    processing_ms REAL NOT NULL,
+    PRIMARY KEY (job_id, chunk_index)
+);
+
+CREATE INDEX idx_jobs_runnable ON transcription_jobs(status, priority DESC, id);
+CREATE INDEX idx_jobs_meeting ON transcription_jobs(meeting_id);
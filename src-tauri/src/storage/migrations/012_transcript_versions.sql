@@ -0,0 +1,5 @@
+-- Re-transcribing a meeting adds a new version of its transcript. Versions
+-- point at the one they replaced, and jobs remember which version that is
+-- so hand edits can be carried over when they finish
+ALTER TABLE transcriptions ADD COLUMN previous_transcription_id INTEGER REFERENCES transcriptions(id);
+ALTER TABLE transcription_jobs ADD COLUMN replaces_transcription_id INTEGER REFERENCES transcriptions(id);
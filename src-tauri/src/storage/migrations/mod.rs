@@ -0,0 +1,112 @@
+//! Embedded schema migrations
+//!
+//! Migrations are applied in order and recorded in `schema_migrations`, so
+//! each one runs exactly once per database.
+
+use sqlx::SqlitePool;
+use tracing::info;
+
+use crate::error::AppResult;
+
+/// A numbered schema change
+pub struct Migration {
+    pub version: i64,
+    pub name: &'static str,
+    pub sql: &'static str,
+}
+
+/// Every migration, in the order it must be applied
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "initial",
+        sql: include_str!("001_initial.sql"),
+    },
+    Migration {
+        version: 2,
+        name: "audio_quality",
+        sql: include_str!("002_audio_quality.sql"),
+    },
+    Migration {
+        version: 3,
+        name: "audio_archive",
+        sql: include_str!("003_audio_archive.sql"),
+    },
+    Migration {
+        version: 4,
+        name: "multilingual_segments",
+        sql: include_str!("004_multilingual_segments.sql"),
+    },
+    Migration {
+        version: 5,
+        name: "word_timings",
+        sql: include_str!("005_word_timings.sql"),
+    },
+    Migration {
+        version: 6,
+        name: "speaker_diarization",
+        sql: include_str!("006_speaker_diarization.sql"),
+    },
+    Migration {
+        version: 7,
+        name: "speaker_hints",
+        sql: include_str!("007_speaker_hints.sql"),
+    },
+    Migration {
+        version: 8,
+        name: "vocabulary",
+        sql: include_str!("008_vocabulary.sql"),
+    },
+    Migration {
+        version: 9,
+        name: "transcript_formatting",
+        sql: include_str!("009_transcript_formatting.sql"),
+    },
+    Migration {
+        version: 10,
+        name: "transcript_revisions",
+        sql: include_str!("010_transcript_revisions.sql"),
+    },
+    Migration {
+        version: 11,
+        name: "transcription_jobs",
+        sql: include_str!("011_transcription_jobs.sql"),
+    },
+    Migration {
+        version: 12,
+        name: "transcript_versions",
+        sql: include_str!("012_transcript_versions.sql"),
+    },
+];
+
+/// Apply every migration the database hasn't seen yet
+pub async fn run(pool: &SqlitePool) -> AppResult<()> {
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS schema_migrations (
+            version INTEGER PRIMARY KEY,
+            name TEXT NOT NULL,
+            applied_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        )",
+    )
+    .execute(pool)
+    .await?;
+
+    let current: i64 = sqlx::query_scalar("SELECT COALESCE(MAX(version), 0) FROM schema_migrations")
+        .fetch_one(pool)
+        .await?;
+
+    for migration in MIGRATIONS.iter().filter(|m| m.version > current) {
+        info!("Applying database migration {} ({})", migration.version, migration.name);
+
+        let mut tx = pool.begin().await?;
+        sqlx::query(migration.sql).execute(&mut *tx).await?;
+        sqlx::query("INSERT INTO schema_migrations (version, name) VALUES (?, ?)")
+            .bind(migration.version)
+            .bind(migration.name)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+    }
+
+    Ok(())
+}
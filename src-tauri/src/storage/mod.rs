@@ -1,14 +1,19 @@
 //! Data storage and database operations
 
-// TODO: Implement storage modules
-// This is a placeholder for the storage domain
+pub mod database;
+pub mod migrations;
+pub mod models;
+pub mod repositories;
 
-/// Database service placeholder
-pub struct DatabaseService;
+pub use database::DatabaseService;
+pub use models::{
+    JobCheckpoint, JobPriority, JobStatus, Meeting, MeetingSpeaker, MeetingStatus, NewMeeting, NewTranscriptionJob,
+    SegmentEdit, Speaker, StoredSegment, StoredVocabularyTerm, StoredWord, Transcript, TranscriptRevision,
+    TranscriptionJob, VocabularyScope,
+};
+pub use repositories::{
+    JobRepository, MeetingRepository, SpeakerRepository, TranscriptRepository, VocabularyRepository,
+};
 
-impl DatabaseService {
-    /// Create a new database service
-    pub fn new() -> Self {
-        Self
-    }
-}
\ No newline at end of file
+#[cfg(test)]
+mod tests;
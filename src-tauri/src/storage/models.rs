@@ -0,0 +1,384 @@
+//! Database models
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::audio::AudioQualityReport;
+use crate::error::{AppError, AppResult};
+use crate::transcription::diarization::{SpeakerEmbedding, VoiceProfile};
+use crate::transcription::{TranscriptSegment, TranscriptionOptions, Vocabulary, VocabularyTerm, WordTiming};
+
+/// Lifecycle state of a meeting record
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MeetingStatus {
+    Scheduled,
+    Recording,
+    Completed,
+    Archived,
+}
+
+impl MeetingStatus {
+    /// Value stored in the `status` column
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Scheduled => "scheduled",
+            Self::Recording => "recording",
+            Self::Completed => "completed",
+            Self::Archived => "archived",
+        }
+    }
+
+    /// Parse a `status` column value
+    pub fn parse(value: &str) -> AppResult<Self> {
+        match value {
+            "scheduled" => Ok(Self::Scheduled),
+            "recording" => Ok(Self::Recording),
+            "completed" => Ok(Self::Completed),
+            "archived" => Ok(Self::Archived),
+            other => Err(AppError::database(format!("Unknown meeting status: {}", other))),
+        }
+    }
+}
+
+/// A stored meeting
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Meeting {
+    pub id: i64,
+    pub title: String,
+    pub start_time: DateTime<Utc>,
+    pub end_time: Option<DateTime<Utc>>,
+    pub duration_seconds: Option<i64>,
+    pub calendar_event_id: Option<String>,
+    pub audio_file_path: Option<String>,
+    /// Opus archive of the recording
+    pub archive_file_path: Option<String>,
+    pub participants: Vec<String>,
+    pub status: MeetingStatus,
+    pub audio_quality: Option<AudioQualityReport>,
+    /// Language every chunk is transcribed in, if pinned
+    pub pinned_language: Option<String>,
+    /// Languages detection may choose from; empty allows any
+    pub candidate_languages: Vec<String>,
+    /// Project the meeting belongs to, whose vocabulary it uses
+    pub project: Option<String>,
+}
+
+impl Meeting {
+    /// Transcription options carrying the meeting's language settings
+    pub fn transcription_options(&self) -> TranscriptionOptions {
+        TranscriptionOptions {
+            language: self.pinned_language.clone(),
+            candidate_languages: self.candidate_languages.clone(),
+            ..Default::default()
+        }
+    }
+
+    /// Vocabulary for transcribing the meeting: `terms` plus what its title
+    /// and participants say about it
+    pub fn vocabulary(&self, terms: Vec<VocabularyTerm>) -> Vocabulary {
+        Vocabulary::for_meeting(terms, &self.title, &self.participants)
+    }
+
+    /// The recording to play back or re-transcribe: the original if kept, else the archive
+    pub fn playback_path(&self) -> Option<&str> {
+        self.audio_file_path.as_deref().or(self.archive_file_path.as_deref())
+    }
+}
+
+/// Fields needed to create a meeting
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewMeeting {
+    pub title: String,
+    pub start_time: DateTime<Utc>,
+    pub calendar_event_id: Option<String>,
+    pub audio_file_path: Option<String>,
+    pub participants: Vec<String>,
+    pub status: MeetingStatus,
+}
+
+impl NewMeeting {
+    /// A meeting that starts recording now
+    pub fn recording(title: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            start_time: Utc::now(),
+            calendar_event_id: None,
+            audio_file_path: None,
+            participants: Vec::new(),
+            status: MeetingStatus::Recording,
+        }
+    }
+}
+
+/// A stored transcript of a meeting
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Transcript {
+    pub id: i64,
+    pub meeting_id: i64,
+    pub content: String,
+    /// Language spoken for the longest time
+    pub language: Option<String>,
+    pub confidence: Option<f32>,
+    pub model_used: Option<String>,
+    pub processing_time_ms: Option<i64>,
+    pub created_at: DateTime<Utc>,
+    /// Transcript this one was formatted from
+    pub source_transcription_id: Option<i64>,
+    /// Version this one replaced when the meeting was re-transcribed
+    pub previous_transcription_id: Option<i64>,
+}
+
+/// A stored transcript segment
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StoredSegment {
+    pub id: i64,
+    pub transcription_id: i64,
+    pub speaker_id: Option<i64>,
+    /// Voice the segment was attributed to by diarization
+    pub meeting_speaker_id: Option<i64>,
+    #[serde(flatten)]
+    pub segment: TranscriptSegment,
+    pub is_edited: bool,
+}
+
+/// A change made to a transcript by hand
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SegmentEdit {
+    /// Replace a segment's text
+    Text { segment_id: i64, text: String },
+    /// Attribute a segment to another voice heard in the meeting, or to none
+    Speaker { segment_id: i64, meeting_speaker_id: Option<i64> },
+    /// Split a segment before the word at character `offset` of its text
+    Split { segment_id: i64, offset: usize },
+    /// Join a segment with the one after it
+    Merge { segment_id: i64 },
+    /// Undo an earlier revision
+    Revert { revision_id: i64 },
+}
+
+/// A recorded edit of a transcript
+///
+/// The segments it touched are kept as they were before and after, which
+/// is enough to show the change and to undo it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TranscriptRevision {
+    pub id: i64,
+    pub transcription_id: i64,
+    pub author: String,
+    pub edit: SegmentEdit,
+    pub before: Vec<StoredSegment>,
+    pub after: Vec<StoredSegment>,
+    /// Later revision that undid this one
+    pub reverted_by: Option<i64>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A stored word and the segment it belongs to
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StoredWord {
+    pub id: i64,
+    pub segment_id: i64,
+    #[serde(flatten)]
+    pub word: WordTiming,
+}
+
+/// A person who speaks in meetings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Speaker {
+    pub id: i64,
+    pub name: Option<String>,
+    pub email: Option<String>,
+    pub color_hex: Option<String>,
+    pub total_meetings: i64,
+    pub last_seen: Option<DateTime<Utc>>,
+    /// Voice learned from the meetings the speaker was named in
+    pub voice_fingerprint: Option<SpeakerEmbedding>,
+    pub fingerprint_samples: u32,
+}
+
+impl Speaker {
+    /// Profile to recognise this speaker by, once they have a name and a voice
+    pub fn voice_profile(&self) -> Option<VoiceProfile> {
+        Some(VoiceProfile {
+            speaker_id: self.id,
+            name: self.name.clone()?,
+            embedding: self.voice_fingerprint.clone()?,
+            samples: self.fingerprint_samples,
+        })
+    }
+}
+
+/// A voice heard in one meeting
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MeetingSpeaker {
+    pub id: i64,
+    pub meeting_id: i64,
+    /// The speaker's name, or "Speaker N" until they are known
+    pub label: String,
+    /// Profile the voice was recognised as or named to
+    pub speaker_id: Option<i64>,
+    pub speech_ms: f64,
+    /// How closely the voice matched its profile when recognised
+    pub similarity: Option<f32>,
+}
+
+/// Where a vocabulary term applies
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum VocabularyScope {
+    /// Every meeting
+    Global,
+    /// Meetings filed under a project
+    Project { name: String },
+    /// A single meeting
+    Meeting { meeting_id: i64 },
+}
+
+/// A vocabulary term the user has added
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StoredVocabularyTerm {
+    pub id: i64,
+    pub scope: VocabularyScope,
+    #[serde(flatten)]
+    pub term: VocabularyTerm,
+}
+
+/// How soon a transcription job should run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobPriority {
+    /// Old recordings and re-transcriptions
+    Backlog,
+    /// Recordings the user is waiting for, e.g. a fresh import
+    Normal,
+    /// The meeting happening now or just finished
+    Live,
+}
+
+impl JobPriority {
+    /// Value stored in the `priority` column; higher runs first
+    pub fn rank(&self) -> i64 {
+        match self {
+            Self::Backlog => 0,
+            Self::Normal => 1,
+            Self::Live => 2,
+        }
+    }
+
+    /// Parse a `priority` column value
+    pub fn from_rank(rank: i64) -> AppResult<Self> {
+        match rank {
+            0 => Ok(Self::Backlog),
+            1 => Ok(Self::Normal),
+            2 => Ok(Self::Live),
+            other => Err(AppError::database(format!("Unknown job priority: {}", other))),
+        }
+    }
+}
+
+/// Where a transcription job is in its life
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    /// Waiting for the worker, possibly until a retry is due
+    Queued,
+    Running,
+    Completed,
+    /// Gave up after too many attempts
+    Failed,
+    Cancelled,
+}
+
+impl JobStatus {
+    /// Value stored in the `status` column
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Queued => "queued",
+            Self::Running => "running",
+            Self::Completed => "completed",
+            Self::Failed => "failed",
+            Self::Cancelled => "cancelled",
+        }
+    }
+
+    /// Parse a `status` column value
+    pub fn parse(value: &str) -> AppResult<Self> {
+        match value {
+            "queued" => Ok(Self::Queued),
+            "running" => Ok(Self::Running),
+            "completed" => Ok(Self::Completed),
+            "failed" => Ok(Self::Failed),
+            "cancelled" => Ok(Self::Cancelled),
+            other => Err(AppError::database(format!("Unknown job status: {}", other))),
+        }
+    }
+
+    /// Whether the job is done with, one way or another
+    pub fn is_finished(&self) -> bool {
+        matches!(self, Self::Completed | Self::Failed | Self::Cancelled)
+    }
+}
+
+/// A recording queued for transcription
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TranscriptionJob {
+    pub id: i64,
+    pub meeting_id: i64,
+    pub priority: JobPriority,
+    pub status: JobStatus,
+    pub audio_path: String,
+    /// Length of the chunks the recording is cut into; fixed for the job so
+    /// checkpoints still line up when it resumes
+    pub chunk_ms: f64,
+    pub options: TranscriptionOptions,
+    /// Length of the recording, once decoding has started
+    pub total_ms: Option<f64>,
+    /// Chunks transcribed and checkpointed so far
+    pub completed_chunks: i64,
+    /// Failed attempts so far
+    pub attempts: u32,
+    pub last_error: Option<String>,
+    /// A failed job isn't retried before this
+    pub run_after: Option<DateTime<Utc>>,
+    /// Transcript the job produced
+    pub transcription_id: Option<i64>,
+    /// Version of the meeting's transcript a re-transcription replaces
+    pub replaces_transcription_id: Option<i64>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl TranscriptionJob {
+    /// Completed fraction, if the recording's length is known
+    pub fn progress(&self) -> Option<f32> {
+        if self.status == JobStatus::Completed {
+            return Some(1.0);
+        }
+        self.total_ms
+            .filter(|&total| total > 0.0)
+            .map(|total| (self.completed_chunks as f64 * self.chunk_ms / total).min(1.0) as f32)
+    }
+}
+
+/// Fields needed to queue a transcription job
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NewTranscriptionJob {
+    pub meeting_id: i64,
+    pub priority: JobPriority,
+    pub audio_path: String,
+    pub chunk_ms: f64,
+    pub options: TranscriptionOptions,
+    /// Set to re-transcribe the meeting: the result becomes a new version of
+    /// this transcript, with its hand edits carried over
+    pub replaces_transcription_id: Option<i64>,
+}
+
+/// The segments of one chunk a job has finished
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JobCheckpoint {
+    pub chunk_index: usize,
+    pub segments: Vec<TranscriptSegment>,
+    pub processing_ms: f64,
+}
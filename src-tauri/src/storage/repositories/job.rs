@@ -0,0 +1,329 @@
+//! Transcription job repository
+
+use chrono::{DateTime, Utc};
+use sqlx::{Row, SqlitePool};
+use sqlx::sqlite::SqliteRow;
+
+use crate::error::{AppError, AppResult};
+use crate::storage::models::{JobCheckpoint, JobPriority, JobStatus, NewTranscriptionJob, TranscriptionJob};
+use crate::transcription::TranscriptSegment;
+
+const JOB_COLUMNS: &str = "id, meeting_id, priority, status, audio_path, chunk_ms, options, total_ms, completed_chunks,
+                           attempts, last_error, run_after, transcription_id, replaces_transcription_id,
+                           created_at, updated_at";
+
+/// Reads and writes the transcription job queue and its checkpoints
+#[derive(Debug, Clone)]
+pub struct JobRepository {
+    pool: SqlitePool,
+}
+
+impl JobRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// Queue a job and return its id
+    pub async fn enqueue(&self, job: &NewTranscriptionJob) -> AppResult<i64> {
+        let options = serde_json::to_string(&job.options)
+            .map_err(|e| AppError::database(format!("Failed to encode job options: {}", e)))?;
+
+        let result = sqlx::query(
+            "INSERT INTO transcription_jobs (meeting_id, priority, audio_path, chunk_ms, options, replaces_transcription_id)
+             VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind(job.meeting_id)
+        .bind(job.priority.rank())
+        .bind(&job.audio_path)
+        .bind(job.chunk_ms)
+        .bind(options)
+        .bind(job.replaces_transcription_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.last_insert_rowid())
+    }
+
+    pub async fn get(&self, id: i64) -> AppResult<Option<TranscriptionJob>> {
+        let row = sqlx::query(&format!("SELECT {} FROM transcription_jobs WHERE id = ?", JOB_COLUMNS))
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        row.map(|row| Self::from_row(&row)).transpose()
+    }
+
+    /// Every job, unfinished ones first in the order they will run, then
+    /// the most recently finished
+    pub async fn list(&self) -> AppResult<Vec<TranscriptionJob>> {
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM transcription_jobs
+             ORDER BY status IN ('completed', 'failed', 'cancelled'), status != 'running', priority DESC,
+                      CASE WHEN status IN ('completed', 'failed', 'cancelled') THEN -id ELSE id END",
+            JOB_COLUMNS
+        ))
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(Self::from_row).collect()
+    }
+
+    /// Jobs for one meeting, oldest first
+    pub async fn for_meeting(&self, meeting_id: i64) -> AppResult<Vec<TranscriptionJob>> {
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM transcription_jobs WHERE meeting_id = ? ORDER BY id",
+            JOB_COLUMNS
+        ))
+        .bind(meeting_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(Self::from_row).collect()
+    }
+
+    /// Mark the highest-priority job that is due as running and return it
+    pub async fn claim_next(&self, now: DateTime<Utc>) -> AppResult<Option<TranscriptionJob>> {
+        let row = sqlx::query(&format!(
+            "UPDATE transcription_jobs SET status = 'running', updated_at = CURRENT_TIMESTAMP
+             WHERE id = (
+                 SELECT id FROM transcription_jobs
+                 WHERE status = 'queued' AND (run_after IS NULL OR run_after <= ?)
+                 ORDER BY priority DESC, id LIMIT 1
+             )
+             RETURNING {}",
+            JOB_COLUMNS
+        ))
+        .bind(now)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.map(|row| Self::from_row(&row)).transpose()
+    }
+
+    /// Whether a job more urgent than `priority` is due
+    pub async fn has_due_above(&self, priority: JobPriority, now: DateTime<Utc>) -> AppResult<bool> {
+        let due: bool = sqlx::query_scalar(
+            "SELECT EXISTS (
+                 SELECT 1 FROM transcription_jobs
+                 WHERE status = 'queued' AND priority > ? AND (run_after IS NULL OR run_after <= ?)
+             )",
+        )
+        .bind(priority.rank())
+        .bind(now)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(due)
+    }
+
+    /// When the earliest waiting retry is due
+    pub async fn next_retry(&self) -> AppResult<Option<DateTime<Utc>>> {
+        let due: Option<DateTime<Utc>> = sqlx::query_scalar(
+            "SELECT MIN(run_after) FROM transcription_jobs WHERE status = 'queued' AND run_after IS NOT NULL",
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(due)
+    }
+
+    /// Put jobs left running by a crash or shutdown back in the queue;
+    /// returns how many there were
+    pub async fn requeue_interrupted(&self) -> AppResult<u64> {
+        let result = sqlx::query(
+            "UPDATE transcription_jobs SET status = 'queued', updated_at = CURRENT_TIMESTAMP WHERE status = 'running'",
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Record the length of a job's recording
+    pub async fn set_total_ms(&self, id: i64, total_ms: f64) -> AppResult<()> {
+        sqlx::query("UPDATE transcription_jobs SET total_ms = ?, updated_at = CURRENT_TIMESTAMP WHERE id = ?")
+            .bind(total_ms)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Store the segments of a finished chunk and return the job's
+    /// completed chunk count
+    pub async fn checkpoint(
+        &self,
+        id: i64,
+        chunk_index: usize,
+        segments: &[TranscriptSegment],
+        processing_ms: f64,
+    ) -> AppResult<i64> {
+        let json = serde_json::to_string(segments)
+            .map_err(|e| AppError::database(format!("Failed to encode checkpoint: {}", e)))?;
+
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(
+            "INSERT OR REPLACE INTO transcription_job_chunks (job_id, chunk_index, segments, processing_ms)
+             VALUES (?, ?, ?, ?)",
+        )
+        .bind(id)
+        .bind(chunk_index as i64)
+        .bind(json)
+        .bind(processing_ms)
+        .execute(&mut *tx)
+        .await?;
+
+        let completed: i64 = sqlx::query_scalar(
+            "UPDATE transcription_jobs
+             SET completed_chunks = (SELECT COUNT(*) FROM transcription_job_chunks WHERE job_id = ?),
+                 updated_at = CURRENT_TIMESTAMP
+             WHERE id = ?
+             RETURNING completed_chunks",
+        )
+        .bind(id)
+        .bind(id)
+        .fetch_one(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        Ok(completed)
+    }
+
+    /// A job's finished chunks, in order
+    pub async fn checkpoints(&self, id: i64) -> AppResult<Vec<JobCheckpoint>> {
+        let rows = sqlx::query(
+            "SELECT chunk_index, segments, processing_ms FROM transcription_job_chunks
+             WHERE job_id = ? ORDER BY chunk_index",
+        )
+        .bind(id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter()
+            .map(|row| {
+                let segments: String = row.try_get("segments")?;
+                let chunk_index: i64 = row.try_get("chunk_index")?;
+                Ok(JobCheckpoint {
+                    chunk_index: chunk_index as usize,
+                    segments: serde_json::from_str(&segments)
+                        .map_err(|e| AppError::database(format!("Invalid checkpoint JSON: {}", e)))?,
+                    processing_ms: row.try_get("processing_ms")?,
+                })
+            })
+            .collect()
+    }
+
+    /// Mark a job done with the transcript it produced; its checkpoints are
+    /// no longer needed
+    pub async fn complete(&self, id: i64, transcription_id: i64) -> AppResult<()> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(
+            "UPDATE transcription_jobs
+             SET status = 'completed', transcription_id = ?, last_error = NULL, run_after = NULL,
+                 updated_at = CURRENT_TIMESTAMP
+             WHERE id = ?",
+        )
+        .bind(transcription_id)
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query("DELETE FROM transcription_job_chunks WHERE job_id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Count a failed attempt: the job is retried from `retry_at`, or given
+    /// up on if that's `None`. Its checkpoints are kept either way.
+    pub async fn record_failure(&self, id: i64, error: &str, retry_at: Option<DateTime<Utc>>) -> AppResult<()> {
+        let status = if retry_at.is_some() { JobStatus::Queued } else { JobStatus::Failed };
+        sqlx::query(
+            "UPDATE transcription_jobs
+             SET status = ?, attempts = attempts + 1, last_error = ?, run_after = ?, updated_at = CURRENT_TIMESTAMP
+             WHERE id = ? AND status = 'running'",
+        )
+        .bind(status.as_str())
+        .bind(error)
+        .bind(retry_at)
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Put a running job back in the queue to make way for a more urgent one
+    pub async fn release(&self, id: i64) -> AppResult<()> {
+        sqlx::query(
+            "UPDATE transcription_jobs SET status = 'queued', updated_at = CURRENT_TIMESTAMP
+             WHERE id = ? AND status = 'running'",
+        )
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Cancel a job that hasn't finished; returns false if it already had
+    ///
+    /// A running job stops at its next chunk.
+    pub async fn cancel(&self, id: i64) -> AppResult<bool> {
+        let Some(job) = self.get(id).await? else {
+            return Err(AppError::database(format!("Transcription job {} not found", id)));
+        };
+        if job.status.is_finished() {
+            return Ok(false);
+        }
+
+        let result = sqlx::query(
+            "UPDATE transcription_jobs SET status = 'cancelled', updated_at = CURRENT_TIMESTAMP
+             WHERE id = ? AND status IN ('queued', 'running')",
+        )
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Drop the checkpoints of a cancelled job
+    pub async fn discard_checkpoints(&self, id: i64) -> AppResult<()> {
+        sqlx::query("DELETE FROM transcription_job_chunks WHERE job_id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    fn from_row(row: &SqliteRow) -> AppResult<TranscriptionJob> {
+        let status: String = row.try_get("status")?;
+        let options: String = row.try_get("options")?;
+        let attempts: i64 = row.try_get("attempts")?;
+
+        Ok(TranscriptionJob {
+            id: row.try_get("id")?,
+            meeting_id: row.try_get("meeting_id")?,
+            priority: JobPriority::from_rank(row.try_get("priority")?)?,
+            status: JobStatus::parse(&status)?,
+            audio_path: row.try_get("audio_path")?,
+            chunk_ms: row.try_get("chunk_ms")?,
+            options: serde_json::from_str(&options)
+                .map_err(|e| AppError::database(format!("Invalid job options JSON: {}", e)))?,
+            total_ms: row.try_get("total_ms")?,
+            completed_chunks: row.try_get("completed_chunks")?,
+            attempts: attempts as u32,
+            last_error: row.try_get("last_error")?,
+            run_after: row.try_get("run_after")?,
+            transcription_id: row.try_get("transcription_id")?,
+            replaces_transcription_id: row.try_get("replaces_transcription_id")?,
+            created_at: row.try_get("created_at")?,
+            updated_at: row.try_get("updated_at")?,
+        })
+    }
+}
@@ -0,0 +1,231 @@
+//! Meeting repository
+
+use chrono::{DateTime, Utc};
+use sqlx::{Row, SqlitePool};
+use sqlx::sqlite::SqliteRow;
+
+use crate::audio::AudioQualityReport;
+use crate::error::{AppError, AppResult};
+use crate::storage::models::{Meeting, MeetingStatus, NewMeeting};
+
+/// Reads and writes meeting records
+#[derive(Debug, Clone)]
+pub struct MeetingRepository {
+    pool: SqlitePool,
+}
+
+impl MeetingRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// Insert a meeting and return its id
+    pub async fn create(&self, meeting: &NewMeeting) -> AppResult<i64> {
+        let participants = serde_json::to_string(&meeting.participants)
+            .map_err(|e| AppError::database(format!("Failed to encode participants: {}", e)))?;
+
+        let result = sqlx::query(
+            "INSERT INTO meetings (title, start_time, calendar_event_id, audio_file_path, participants, status)
+             VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind(&meeting.title)
+        .bind(meeting.start_time)
+        .bind(&meeting.calendar_event_id)
+        .bind(&meeting.audio_file_path)
+        .bind(participants)
+        .bind(meeting.status.as_str())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.last_insert_rowid())
+    }
+
+    /// Load a meeting by id
+    pub async fn get(&self, id: i64) -> AppResult<Option<Meeting>> {
+        let row = sqlx::query(
+            "SELECT id, title, start_time, end_time, duration_seconds, calendar_event_id,
+                    audio_file_path, archive_file_path, participants, status, audio_quality,
+                    pinned_language, candidate_languages, project
+             FROM meetings WHERE id = ?",
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.map(|row| Self::from_row(&row)).transpose()
+    }
+
+    /// Mark a meeting as finished recording
+    pub async fn complete(&self, id: i64, end_time: DateTime<Utc>) -> AppResult<()> {
+        sqlx::query(
+            "UPDATE meetings
+             SET end_time = ?,
+                 duration_seconds = CAST(strftime('%s', ?) - strftime('%s', start_time) AS INTEGER),
+                 status = ?,
+                 updated_at = CURRENT_TIMESTAMP
+             WHERE id = ?",
+        )
+        .bind(end_time)
+        .bind(end_time)
+        .bind(MeetingStatus::Completed.as_str())
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Set a finished meeting's start and end, e.g. from an imported recording
+    pub async fn set_timeline(&self, id: i64, start_time: DateTime<Utc>, end_time: DateTime<Utc>) -> AppResult<()> {
+        sqlx::query(
+            "UPDATE meetings
+             SET start_time = ?, end_time = ?, duration_seconds = ?, status = ?,
+                 updated_at = CURRENT_TIMESTAMP
+             WHERE id = ?",
+        )
+        .bind(start_time)
+        .bind(end_time)
+        .bind((end_time - start_time).num_seconds())
+        .bind(MeetingStatus::Completed.as_str())
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Remove a meeting
+    pub async fn delete(&self, id: i64) -> AppResult<()> {
+        sqlx::query("DELETE FROM meetings WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Attach the recording's audio quality report to a meeting
+    pub async fn set_audio_quality(&self, id: i64, report: &AudioQualityReport) -> AppResult<()> {
+        let json = serde_json::to_string(report)
+            .map_err(|e| AppError::database(format!("Failed to encode audio quality report: {}", e)))?;
+
+        let result = sqlx::query("UPDATE meetings SET audio_quality = ?, updated_at = CURRENT_TIMESTAMP WHERE id = ?")
+            .bind(json)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::database(format!("Meeting {} not found", id)));
+        }
+
+        Ok(())
+    }
+
+    /// Record a meeting's Opus archive, forgetting the original unless it was kept
+    pub async fn set_archive(&self, id: i64, archive_path: &str, original_kept: bool) -> AppResult<()> {
+        let result = sqlx::query(
+            "UPDATE meetings
+             SET archive_file_path = ?,
+                 audio_file_path = CASE WHEN ? THEN audio_file_path ELSE NULL END,
+                 updated_at = CURRENT_TIMESTAMP
+             WHERE id = ?",
+        )
+        .bind(archive_path)
+        .bind(original_kept)
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::database(format!("Meeting {} not found", id)));
+        }
+
+        Ok(())
+    }
+
+    /// Pin a meeting's language, or restrict detection to `candidates`
+    pub async fn set_languages(&self, id: i64, pinned: Option<&str>, candidates: &[String]) -> AppResult<()> {
+        let candidates = serde_json::to_string(candidates)
+            .map_err(|e| AppError::database(format!("Failed to encode candidate languages: {}", e)))?;
+
+        let result = sqlx::query(
+            "UPDATE meetings SET pinned_language = ?, candidate_languages = ?, updated_at = CURRENT_TIMESTAMP
+             WHERE id = ?",
+        )
+        .bind(pinned)
+        .bind(candidates)
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::database(format!("Meeting {} not found", id)));
+        }
+
+        Ok(())
+    }
+
+    /// File a meeting under a project, or under none
+    pub async fn set_project(&self, id: i64, project: Option<&str>) -> AppResult<()> {
+        let project = project.map(str::trim).filter(|p| !p.is_empty());
+        let result = sqlx::query("UPDATE meetings SET project = ?, updated_at = CURRENT_TIMESTAMP WHERE id = ?")
+            .bind(project)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::database(format!("Meeting {} not found", id)));
+        }
+
+        Ok(())
+    }
+
+    /// Audio quality report attached to a meeting, if any
+    pub async fn audio_quality(&self, id: i64) -> AppResult<Option<AudioQualityReport>> {
+        let json: Option<Option<String>> = sqlx::query_scalar("SELECT audio_quality FROM meetings WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        json.flatten().map(|json| decode_quality(&json)).transpose()
+    }
+
+    fn from_row(row: &SqliteRow) -> AppResult<Meeting> {
+        let participants: Option<String> = row.try_get("participants")?;
+        let status: Option<String> = row.try_get("status")?;
+        let audio_quality: Option<String> = row.try_get("audio_quality")?;
+        let candidate_languages: Option<String> = row.try_get("candidate_languages")?;
+
+        Ok(Meeting {
+            id: row.try_get("id")?,
+            title: row.try_get("title")?,
+            start_time: row.try_get("start_time")?,
+            end_time: row.try_get("end_time")?,
+            duration_seconds: row.try_get("duration_seconds")?,
+            calendar_event_id: row.try_get("calendar_event_id")?,
+            audio_file_path: row.try_get("audio_file_path")?,
+            archive_file_path: row.try_get("archive_file_path")?,
+            participants: match participants {
+                Some(json) => serde_json::from_str(&json)
+                    .map_err(|e| AppError::database(format!("Invalid participants JSON: {}", e)))?,
+                None => Vec::new(),
+            },
+            status: MeetingStatus::parse(status.as_deref().unwrap_or("scheduled"))?,
+            audio_quality: audio_quality.map(|json| decode_quality(&json)).transpose()?,
+            pinned_language: row.try_get("pinned_language")?,
+            candidate_languages: match candidate_languages {
+                Some(json) => serde_json::from_str(&json)
+                    .map_err(|e| AppError::database(format!("Invalid candidate languages JSON: {}", e)))?,
+                None => Vec::new(),
+            },
+            project: row.try_get("project")?,
+        })
+    }
+}
+
+fn decode_quality(json: &str) -> AppResult<AudioQualityReport> {
+    serde_json::from_str(json)
+        .map_err(|e| AppError::database(format!("Invalid audio quality report: {}", e)))
+}
@@ -0,0 +1,13 @@
+//! Data access layer
+
+pub mod job;
+pub mod meeting;
+pub mod speaker;
+pub mod transcript;
+pub mod vocabulary;
+
+pub use job::JobRepository;
+pub use meeting::MeetingRepository;
+pub use speaker::SpeakerRepository;
+pub use transcript::TranscriptRepository;
+pub use vocabulary::VocabularyRepository;
@@ -0,0 +1,314 @@
+//! Speaker repository
+
+use std::collections::HashSet;
+use sqlx::{Row, SqlitePool};
+use sqlx::sqlite::SqliteRow;
+
+use crate::error::{AppError, AppResult};
+use crate::storage::models::{MeetingSpeaker, Speaker};
+use crate::transcription::diarization::{Diarization, SpeakerEmbedding, VoiceProfile};
+
+/// Reads and writes speakers, their voice profiles and the voices heard in
+/// each meeting
+#[derive(Debug, Clone)]
+pub struct SpeakerRepository {
+    pool: SqlitePool,
+}
+
+impl SpeakerRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// Enroll a named speaker with their first voice sample, returning the id
+    pub async fn create(&self, name: &str, voice: &SpeakerEmbedding) -> AppResult<i64> {
+        let result = sqlx::query(
+            "INSERT INTO speakers (name, voice_fingerprint, fingerprint_samples) VALUES (?, ?, 1)",
+        )
+        .bind(name)
+        .bind(voice.to_bytes())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.last_insert_rowid())
+    }
+
+    /// Load a speaker by id
+    pub async fn get(&self, id: i64) -> AppResult<Option<Speaker>> {
+        let row = sqlx::query(
+            "SELECT id, name, email, color_hex, total_meetings, last_seen, voice_fingerprint, fingerprint_samples
+             FROM speakers WHERE id = ?",
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.map(|row| Self::speaker_from_row(&row)).transpose()
+    }
+
+    /// Find a speaker by name, ignoring case
+    pub async fn find_by_name(&self, name: &str) -> AppResult<Option<Speaker>> {
+        let row = sqlx::query(
+            "SELECT id, name, email, color_hex, total_meetings, last_seen, voice_fingerprint, fingerprint_samples
+             FROM speakers WHERE name = ? COLLATE NOCASE ORDER BY id LIMIT 1",
+        )
+        .bind(name.trim())
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.map(|row| Self::speaker_from_row(&row)).transpose()
+    }
+
+    /// All speakers, most recently heard first
+    pub async fn list(&self) -> AppResult<Vec<Speaker>> {
+        let rows = sqlx::query(
+            "SELECT id, name, email, color_hex, total_meetings, last_seen, voice_fingerprint, fingerprint_samples
+             FROM speakers ORDER BY last_seen DESC, id",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(Self::speaker_from_row).collect()
+    }
+
+    /// Voice profiles of every named speaker with a fingerprint
+    pub async fn profiles(&self) -> AppResult<Vec<VoiceProfile>> {
+        Ok(self.list().await?.iter().filter_map(Speaker::voice_profile).collect())
+    }
+
+    /// Store a refined voice profile
+    pub async fn update_profile(&self, profile: &VoiceProfile) -> AppResult<()> {
+        let result = sqlx::query(
+            "UPDATE speakers SET voice_fingerprint = ?, fingerprint_samples = ? WHERE id = ?",
+        )
+        .bind(profile.embedding.to_bytes())
+        .bind(profile.samples)
+        .bind(profile.speaker_id)
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::database(format!("Speaker {} not found", profile.speaker_id)));
+        }
+
+        Ok(())
+    }
+
+    /// Rename a speaker, along with every meeting speaker linked to them
+    pub async fn rename(&self, id: i64, name: &str) -> AppResult<()> {
+        let mut tx = self.pool.begin().await?;
+        let result = sqlx::query("UPDATE speakers SET name = ? WHERE id = ?")
+            .bind(name)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::database(format!("Speaker {} not found", id)));
+        }
+
+        sqlx::query("UPDATE meeting_speakers SET label = ? WHERE speaker_id = ?")
+            .bind(name)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Replace a meeting's diarization
+    ///
+    /// `segment_ids` are the stored ids of the segments `diarization` was run
+    /// on, in the same order. Recognised speakers are linked straight away
+    /// and counted as having attended.
+    pub async fn replace_meeting_speakers(
+        &self,
+        meeting_id: i64,
+        segment_ids: &[i64],
+        diarization: &Diarization,
+    ) -> AppResult<Vec<MeetingSpeaker>> {
+        let mut tx = self.pool.begin().await?;
+
+        let attended: HashSet<i64> = sqlx::query_scalar(
+            "SELECT speaker_id FROM meeting_speakers WHERE meeting_id = ? AND speaker_id IS NOT NULL",
+        )
+        .bind(meeting_id)
+        .fetch_all(&mut *tx)
+        .await?
+        .into_iter()
+        .collect();
+
+        sqlx::query(
+            "UPDATE transcription_segments SET meeting_speaker_id = NULL, speaker_id = NULL
+             WHERE meeting_speaker_id IN (SELECT id FROM meeting_speakers WHERE meeting_id = ?)",
+        )
+        .bind(meeting_id)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query("DELETE FROM meeting_speakers WHERE meeting_id = ?")
+            .bind(meeting_id)
+            .execute(&mut *tx)
+            .await?;
+
+        let mut stored = Vec::with_capacity(diarization.speakers.len());
+        for speaker in &diarization.speakers {
+            let speaker_id = speaker.profile.as_ref().map(|p| p.speaker_id);
+            let similarity = speaker.profile.as_ref().map(|p| p.similarity);
+            let id = sqlx::query(
+                "INSERT INTO meeting_speakers (meeting_id, label, speaker_id, embedding, speech_ms, similarity)
+                 VALUES (?, ?, ?, ?, ?, ?)",
+            )
+            .bind(meeting_id)
+            .bind(&speaker.label)
+            .bind(speaker_id)
+            .bind(speaker.embedding.to_bytes())
+            .bind(speaker.speech_ms)
+            .bind(similarity)
+            .execute(&mut *tx)
+            .await?
+            .last_insert_rowid();
+
+            if let Some(speaker_id) = speaker_id.filter(|id| !attended.contains(id)) {
+                Self::count_attendance(&mut tx, speaker_id).await?;
+            }
+
+            stored.push(MeetingSpeaker {
+                id,
+                meeting_id,
+                label: speaker.label.clone(),
+                speaker_id,
+                speech_ms: speaker.speech_ms,
+                similarity,
+            });
+        }
+
+        for (segment_id, assignment) in segment_ids.iter().zip(&diarization.assignments) {
+            let Some(speaker) = assignment.map(|index| &stored[index]) else {
+                continue;
+            };
+            sqlx::query("UPDATE transcription_segments SET meeting_speaker_id = ?, speaker_id = ? WHERE id = ?")
+                .bind(speaker.id)
+                .bind(speaker.speaker_id)
+                .bind(segment_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        tx.commit().await?;
+        Ok(stored)
+    }
+
+    /// Voices heard in a meeting, in order of first appearance
+    pub async fn meeting_speakers(&self, meeting_id: i64) -> AppResult<Vec<MeetingSpeaker>> {
+        let rows = sqlx::query(
+            "SELECT id, meeting_id, label, speaker_id, speech_ms, similarity
+             FROM meeting_speakers WHERE meeting_id = ? ORDER BY id",
+        )
+        .bind(meeting_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(Self::meeting_speaker_from_row).collect()
+    }
+
+    /// Load a meeting speaker and the voice they were heard with
+    pub async fn meeting_speaker(&self, id: i64) -> AppResult<Option<(MeetingSpeaker, SpeakerEmbedding)>> {
+        let row = sqlx::query(
+            "SELECT id, meeting_id, label, speaker_id, speech_ms, similarity, embedding
+             FROM meeting_speakers WHERE id = ?",
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.map(|row| {
+            let bytes: Vec<u8> = row.try_get("embedding")?;
+            let embedding = SpeakerEmbedding::from_bytes(&bytes)
+                .ok_or_else(|| AppError::database(format!("Invalid voice embedding for meeting speaker {}", id)))?;
+            Ok((Self::meeting_speaker_from_row(&row)?, embedding))
+        })
+        .transpose()
+    }
+
+    /// Attribute a meeting speaker, and their segments, to `speaker_id`
+    ///
+    /// The speaker counts as having attended the meeting once, however many
+    /// of its voices are theirs.
+    pub async fn link_meeting_speaker(&self, id: i64, speaker_id: i64, label: &str) -> AppResult<()> {
+        let mut tx = self.pool.begin().await?;
+        let meeting_id: Option<i64> = sqlx::query_scalar("SELECT meeting_id FROM meeting_speakers WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&mut *tx)
+            .await?;
+        let Some(meeting_id) = meeting_id else {
+            return Err(AppError::database(format!("Meeting speaker {} not found", id)));
+        };
+        let attended: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM meeting_speakers WHERE meeting_id = ? AND speaker_id = ?",
+        )
+        .bind(meeting_id)
+        .bind(speaker_id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        sqlx::query("UPDATE meeting_speakers SET speaker_id = ?, label = ?, similarity = NULL WHERE id = ?")
+            .bind(speaker_id)
+            .bind(label)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("UPDATE transcription_segments SET speaker_id = ? WHERE meeting_speaker_id = ?")
+            .bind(speaker_id)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        if attended == 0 {
+            Self::count_attendance(&mut tx, speaker_id).await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn count_attendance(tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>, speaker_id: i64) -> AppResult<()> {
+        sqlx::query(
+            "UPDATE speakers SET total_meetings = COALESCE(total_meetings, 0) + 1, last_seen = CURRENT_TIMESTAMP
+             WHERE id = ?",
+        )
+        .bind(speaker_id)
+        .execute(&mut **tx)
+        .await?;
+        Ok(())
+    }
+
+    fn speaker_from_row(row: &SqliteRow) -> AppResult<Speaker> {
+        let fingerprint: Option<Vec<u8>> = row.try_get("voice_fingerprint")?;
+        let total_meetings: Option<i64> = row.try_get("total_meetings")?;
+        let samples: Option<i64> = row.try_get("fingerprint_samples")?;
+
+        Ok(Speaker {
+            id: row.try_get("id")?,
+            name: row.try_get("name")?,
+            email: row.try_get("email")?,
+            color_hex: row.try_get("color_hex")?,
+            total_meetings: total_meetings.unwrap_or(0),
+            last_seen: row.try_get("last_seen")?,
+            voice_fingerprint: fingerprint.as_deref().and_then(SpeakerEmbedding::from_bytes),
+            fingerprint_samples: samples.unwrap_or(0).max(0) as u32,
+        })
+    }
+
+    fn meeting_speaker_from_row(row: &SqliteRow) -> AppResult<MeetingSpeaker> {
+        let speech_ms: Option<f64> = row.try_get("speech_ms")?;
+
+        Ok(MeetingSpeaker {
+            id: row.try_get("id")?,
+            meeting_id: row.try_get("meeting_id")?,
+            label: row.try_get("label")?,
+            speaker_id: row.try_get("speaker_id")?,
+            speech_ms: speech_ms.unwrap_or_default(),
+            similarity: row.try_get("similarity")?,
+        })
+    }
+}
@@ -0,0 +1,597 @@
+//! Transcript repository
+
+use std::collections::HashMap;
+use sqlx::{Row, SqliteConnection, SqlitePool};
+use sqlx::sqlite::SqliteRow;
+
+use crate::error::{AppError, AppResult};
+use crate::storage::models::{SegmentEdit, StoredSegment, StoredWord, Transcript, TranscriptRevision};
+use crate::transcription::language::dominant_language;
+use crate::transcription::{language_switches, LanguageSwitch, SpeakerHint, TranscriptSegment, WordTiming};
+
+/// Reads and writes transcripts, their segments and word timings
+///
+/// Segment and word timestamps are stored in seconds on the meeting timeline.
+#[derive(Debug, Clone)]
+pub struct TranscriptRepository {
+    pool: SqlitePool,
+}
+
+impl TranscriptRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// Store a meeting's transcript and its segments, returning the transcript id
+    pub async fn create(
+        &self,
+        meeting_id: i64,
+        segments: &[TranscriptSegment],
+        model_used: &str,
+        processing_time_ms: i64,
+    ) -> AppResult<i64> {
+        let segments = unattributed(segments);
+        self.insert(meeting_id, None, None, &segments, model_used, processing_time_ms).await
+    }
+
+    /// Store a formatted version of `source`, which is left as it is
+    ///
+    /// The segments keep their speakers and edited flags.
+    pub async fn create_formatted(
+        &self,
+        source: &Transcript,
+        segments: &[StoredSegment],
+        processing_time_ms: i64,
+    ) -> AppResult<i64> {
+        let model_used = source.model_used.as_deref().unwrap_or_default();
+        self.insert(source.meeting_id, Some(source.id), None, segments, model_used, processing_time_ms).await
+    }
+
+    /// Store a new version of `previous` from re-transcribing its meeting
+    ///
+    /// The segments keep their speakers and edited flags; `previous` is left
+    /// as it is.
+    pub async fn create_version(
+        &self,
+        previous: &Transcript,
+        segments: &[StoredSegment],
+        model_used: &str,
+        processing_time_ms: i64,
+    ) -> AppResult<i64> {
+        self.insert(previous.meeting_id, None, Some(previous.id), segments, model_used, processing_time_ms).await
+    }
+
+    async fn insert(
+        &self,
+        meeting_id: i64,
+        source_transcription_id: Option<i64>,
+        previous_transcription_id: Option<i64>,
+        segments: &[StoredSegment],
+        model_used: &str,
+        processing_time_ms: i64,
+    ) -> AppResult<i64> {
+        let content = segments.iter().map(|s| s.segment.text.as_str()).collect::<Vec<_>>().join(" ");
+        let confidence = (!segments.is_empty())
+            .then(|| segments.iter().map(|s| s.segment.confidence).sum::<f32>() / segments.len() as f32);
+        let language = dominant_language(&segments.iter().map(|s| s.segment.clone()).collect::<Vec<_>>());
+
+        let mut tx = self.pool.begin().await?;
+        let transcription_id = sqlx::query(
+            "INSERT INTO transcriptions
+                (meeting_id, content, language, confidence, model_used, processing_time_ms, source_transcription_id,
+                 previous_transcription_id)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(meeting_id)
+        .bind(&content)
+        .bind(language)
+        .bind(confidence)
+        .bind(model_used)
+        .bind(processing_time_ms)
+        .bind(source_transcription_id)
+        .bind(previous_transcription_id)
+        .execute(&mut *tx)
+        .await?
+        .last_insert_rowid();
+
+        for segment in segments {
+            let stored = StoredSegment { transcription_id, ..segment.clone() };
+            insert_segment(&mut tx, &stored).await?;
+        }
+
+        sqlx::query("INSERT INTO transcriptions_fts (rowid, content) VALUES (?, ?)")
+            .bind(transcription_id)
+            .bind(&content)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(transcription_id)
+    }
+
+    /// Load a transcript by id
+    pub async fn get(&self, id: i64) -> AppResult<Option<Transcript>> {
+        let row = sqlx::query(
+            "SELECT id, meeting_id, content, language, confidence, model_used, processing_time_ms, created_at,
+                    source_transcription_id, previous_transcription_id
+             FROM transcriptions WHERE id = ?",
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.map(|row| Self::transcript_from_row(&row)).transpose()
+    }
+
+    /// A meeting's transcripts, oldest first
+    pub async fn for_meeting(&self, meeting_id: i64) -> AppResult<Vec<Transcript>> {
+        let rows = sqlx::query(
+            "SELECT id, meeting_id, content, language, confidence, model_used, processing_time_ms, created_at,
+                    source_transcription_id, previous_transcription_id
+             FROM transcriptions WHERE meeting_id = ? ORDER BY id",
+        )
+        .bind(meeting_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(Self::transcript_from_row).collect()
+    }
+
+    /// A meeting's newest transcript from an engine, passing over formatted
+    /// copies; re-transcriptions count as newer versions
+    pub async fn latest(&self, meeting_id: i64) -> AppResult<Option<Transcript>> {
+        let row = sqlx::query(
+            "SELECT id, meeting_id, content, language, confidence, model_used, processing_time_ms, created_at,
+                    source_transcription_id, previous_transcription_id
+             FROM transcriptions WHERE meeting_id = ? AND source_transcription_id IS NULL
+             ORDER BY id DESC LIMIT 1",
+        )
+        .bind(meeting_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.map(|row| Self::transcript_from_row(&row)).transpose()
+    }
+
+    /// A transcript's segments in timeline order, with their words
+    pub async fn segments(&self, transcription_id: i64) -> AppResult<Vec<StoredSegment>> {
+        let rows = sqlx::query(
+            "SELECT id, transcription_id, speaker_id, meeting_speaker_id, text, start_timestamp, end_timestamp, confidence,
+                    language, model_used, speaker_hint, original_text, is_edited
+             FROM transcription_segments
+             WHERE transcription_id = ?
+             ORDER BY start_timestamp, id",
+        )
+        .bind(transcription_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut segments = rows.iter().map(Self::segment_from_row).collect::<AppResult<Vec<_>>>()?;
+        self.attach_words(transcription_id, &mut segments).await?;
+        Ok(segments)
+    }
+
+    /// Load a segment, with its words, by id
+    pub async fn segment(&self, id: i64) -> AppResult<Option<StoredSegment>> {
+        let row = sqlx::query(
+            "SELECT id, transcription_id, speaker_id, meeting_speaker_id, text, start_timestamp, end_timestamp, confidence,
+                    language, model_used, speaker_hint, original_text, is_edited
+             FROM transcription_segments WHERE id = ?",
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+        let Some(row) = row else {
+            return Ok(None);
+        };
+
+        let mut segment = Self::segment_from_row(&row)?;
+        let words = sqlx::query(
+            "SELECT id, segment_id, word, start_timestamp, end_timestamp, confidence
+             FROM transcription_words WHERE segment_id = ? ORDER BY start_timestamp, id",
+        )
+        .bind(id)
+        .fetch_all(&self.pool)
+        .await?;
+        segment.segment.words = words
+            .iter()
+            .map(|row| Self::word_from_row(row).map(|stored| stored.word))
+            .collect::<AppResult<_>>()?;
+        Ok(Some(segment))
+    }
+
+    /// A transcript's segments spoken in `language`
+    pub async fn segments_in_language(&self, transcription_id: i64, language: &str) -> AppResult<Vec<StoredSegment>> {
+        let rows = sqlx::query(
+            "SELECT id, transcription_id, speaker_id, meeting_speaker_id, text, start_timestamp, end_timestamp, confidence,
+                    language, model_used, speaker_hint, original_text, is_edited
+             FROM transcription_segments
+             WHERE transcription_id = ? AND language = ?
+             ORDER BY start_timestamp, id",
+        )
+        .bind(transcription_id)
+        .bind(language)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut segments = rows.iter().map(Self::segment_from_row).collect::<AppResult<Vec<_>>>()?;
+        self.attach_words(transcription_id, &mut segments).await?;
+        Ok(segments)
+    }
+
+    /// Words of a transcript spoken at least partly between `start_ms` and
+    /// `end_ms`, in timeline order
+    pub async fn words_between(&self, transcription_id: i64, start_ms: f64, end_ms: f64) -> AppResult<Vec<StoredWord>> {
+        let rows = sqlx::query(
+            "SELECT id, segment_id, word, start_timestamp, end_timestamp, confidence
+             FROM transcription_words
+             WHERE transcription_id = ? AND start_timestamp < ? AND end_timestamp > ?
+             ORDER BY start_timestamp, id",
+        )
+        .bind(transcription_id)
+        .bind(end_ms / 1000.0)
+        .bind(start_ms / 1000.0)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(Self::word_from_row).collect()
+    }
+
+    /// Transcripts whose text matches every word of `query`, best match first
+    pub async fn search(&self, query: &str) -> AppResult<Vec<Transcript>> {
+        // Quote each word so user input is never read as FTS syntax
+        let query = query
+            .split_whitespace()
+            .map(|word| format!("\"{}\"", word.replace('"', "\"\"")))
+            .collect::<Vec<_>>()
+            .join(" ");
+        if query.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let rows = sqlx::query(
+            "SELECT t.id, t.meeting_id, t.content, t.language, t.confidence, t.model_used, t.processing_time_ms,
+                    t.created_at, t.source_transcription_id, t.previous_transcription_id
+             FROM transcriptions_fts
+             JOIN transcriptions t ON t.id = transcriptions_fts.rowid
+             WHERE transcriptions_fts MATCH ?
+             ORDER BY rank",
+        )
+        .bind(query)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(Self::transcript_from_row).collect()
+    }
+
+    /// Replace the segments `before` with `after` and record it as a revision
+    ///
+    /// Segments in `after` with the id of one in `before` update it; the
+    /// rest are added with new ids. Segments of `before` left out of `after`
+    /// are deleted. The transcript's text and search index follow the edit.
+    pub async fn revise(
+        &self,
+        transcription_id: i64,
+        author: &str,
+        edit: &SegmentEdit,
+        before: &[StoredSegment],
+        mut after: Vec<StoredSegment>,
+    ) -> AppResult<TranscriptRevision> {
+        let mut tx = self.pool.begin().await?;
+
+        for removed in before.iter().filter(|b| !after.iter().any(|a| a.id == b.id)) {
+            sqlx::query("DELETE FROM transcription_words WHERE segment_id = ?")
+                .bind(removed.id)
+                .execute(&mut *tx)
+                .await?;
+            sqlx::query("DELETE FROM transcription_segments WHERE id = ?")
+                .bind(removed.id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        for segment in &mut after {
+            segment.transcription_id = transcription_id;
+            if before.iter().any(|b| b.id == segment.id) {
+                update_segment(&mut tx, segment).await?;
+            } else {
+                segment.id = insert_segment(&mut tx, segment).await?;
+            }
+        }
+
+        // Keep the transcript's text, and the search index built from it, current
+        let previous: String = sqlx::query_scalar("SELECT content FROM transcriptions WHERE id = ?")
+            .bind(transcription_id)
+            .fetch_one(&mut *tx)
+            .await?;
+        let texts: Vec<String> = sqlx::query_scalar(
+            "SELECT text FROM transcription_segments WHERE transcription_id = ? ORDER BY start_timestamp, id",
+        )
+        .bind(transcription_id)
+        .fetch_all(&mut *tx)
+        .await?;
+        let content = texts.join(" ");
+        sqlx::query("UPDATE transcriptions SET content = ? WHERE id = ?")
+            .bind(&content)
+            .bind(transcription_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("INSERT INTO transcriptions_fts (transcriptions_fts, rowid, content) VALUES ('delete', ?, ?)")
+            .bind(transcription_id)
+            .bind(previous)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("INSERT INTO transcriptions_fts (rowid, content) VALUES (?, ?)")
+            .bind(transcription_id)
+            .bind(&content)
+            .execute(&mut *tx)
+            .await?;
+
+        let encode = |value: Result<String, serde_json::Error>| {
+            value.map_err(|e| AppError::database(format!("Failed to encode revision: {}", e)))
+        };
+        let revision_id = sqlx::query(
+            "INSERT INTO transcript_revisions (transcription_id, author, edit, before_segments, after_segments)
+             VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(transcription_id)
+        .bind(author)
+        .bind(encode(serde_json::to_string(edit))?)
+        .bind(encode(serde_json::to_string(before))?)
+        .bind(encode(serde_json::to_string(&after))?)
+        .execute(&mut *tx)
+        .await?
+        .last_insert_rowid();
+        if let SegmentEdit::Revert { revision_id: reverted } = edit {
+            sqlx::query("UPDATE transcript_revisions SET reverted_by = ? WHERE id = ?")
+                .bind(revision_id)
+                .bind(reverted)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        tx.commit().await?;
+        self.revision(revision_id)
+            .await?
+            .ok_or_else(|| AppError::database(format!("Revision {} not found", revision_id)))
+    }
+
+    /// Load a revision by id
+    pub async fn revision(&self, id: i64) -> AppResult<Option<TranscriptRevision>> {
+        let row = sqlx::query(
+            "SELECT id, transcription_id, author, edit, before_segments, after_segments, reverted_by, created_at
+             FROM transcript_revisions WHERE id = ?",
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.map(|row| Self::revision_from_row(&row)).transpose()
+    }
+
+    /// Every edit made to a transcript, oldest first
+    pub async fn revisions(&self, transcription_id: i64) -> AppResult<Vec<TranscriptRevision>> {
+        let rows = sqlx::query(
+            "SELECT id, transcription_id, author, edit, before_segments, after_segments, reverted_by, created_at
+             FROM transcript_revisions WHERE transcription_id = ? ORDER BY id",
+        )
+        .bind(transcription_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(Self::revision_from_row).collect()
+    }
+
+    /// Points in a transcript where the spoken language changes
+    pub async fn language_switches(&self, transcription_id: i64) -> AppResult<Vec<LanguageSwitch>> {
+        let segments: Vec<TranscriptSegment> = self.segments(transcription_id)
+            .await?
+            .into_iter()
+            .map(|stored| stored.segment)
+            .collect();
+
+        Ok(language_switches(&segments))
+    }
+
+    /// Fill in each segment's words from one query over the transcript
+    async fn attach_words(&self, transcription_id: i64, segments: &mut [StoredSegment]) -> AppResult<()> {
+        let rows = sqlx::query(
+            "SELECT id, segment_id, word, start_timestamp, end_timestamp, confidence
+             FROM transcription_words
+             WHERE transcription_id = ?
+             ORDER BY start_timestamp, id",
+        )
+        .bind(transcription_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut words: HashMap<i64, Vec<WordTiming>> = HashMap::new();
+        for row in &rows {
+            let stored = Self::word_from_row(row)?;
+            words.entry(stored.segment_id).or_default().push(stored.word);
+        }
+        for segment in segments {
+            segment.segment.words = words.remove(&segment.id).unwrap_or_default();
+        }
+
+        Ok(())
+    }
+
+    fn transcript_from_row(row: &SqliteRow) -> AppResult<Transcript> {
+        Ok(Transcript {
+            id: row.try_get("id")?,
+            meeting_id: row.try_get("meeting_id")?,
+            content: row.try_get("content")?,
+            language: row.try_get("language")?,
+            confidence: row.try_get("confidence")?,
+            model_used: row.try_get("model_used")?,
+            processing_time_ms: row.try_get("processing_time_ms")?,
+            created_at: row.try_get("created_at")?,
+            source_transcription_id: row.try_get("source_transcription_id")?,
+            previous_transcription_id: row.try_get("previous_transcription_id")?,
+        })
+    }
+
+    fn segment_from_row(row: &SqliteRow) -> AppResult<StoredSegment> {
+        let start: Option<f64> = row.try_get("start_timestamp")?;
+        let end: Option<f64> = row.try_get("end_timestamp")?;
+        let confidence: Option<f32> = row.try_get("confidence")?;
+        let language: Option<String> = row.try_get("language")?;
+        let model: Option<String> = row.try_get("model_used")?;
+        let speaker_hint: Option<String> = row.try_get("speaker_hint")?;
+        let is_edited: Option<bool> = row.try_get("is_edited")?;
+
+        Ok(StoredSegment {
+            id: row.try_get("id")?,
+            transcription_id: row.try_get("transcription_id")?,
+            speaker_id: row.try_get("speaker_id")?,
+            meeting_speaker_id: row.try_get("meeting_speaker_id")?,
+            segment: TranscriptSegment {
+                text: row.try_get("text")?,
+                start_ms: start.unwrap_or_default() * 1000.0,
+                end_ms: end.unwrap_or_default() * 1000.0,
+                confidence: confidence.unwrap_or_default(),
+                language: language.unwrap_or_default(),
+                model: model.unwrap_or_default(),
+                words: Vec::new(),
+                speaker_hint: speaker_hint.as_deref().and_then(SpeakerHint::parse),
+                original_text: row.try_get("original_text")?,
+            },
+            is_edited: is_edited.unwrap_or(false),
+        })
+    }
+
+    fn revision_from_row(row: &SqliteRow) -> AppResult<TranscriptRevision> {
+        fn decode<T: serde::de::DeserializeOwned>(row: &SqliteRow, column: &str) -> AppResult<T> {
+            let json: String = row.try_get(column)?;
+            serde_json::from_str(&json)
+                .map_err(|e| AppError::database(format!("Invalid revision {}: {}", column, e)))
+        }
+
+        Ok(TranscriptRevision {
+            id: row.try_get("id")?,
+            transcription_id: row.try_get("transcription_id")?,
+            author: row.try_get("author")?,
+            edit: decode(row, "edit")?,
+            before: decode(row, "before_segments")?,
+            after: decode(row, "after_segments")?,
+            reverted_by: row.try_get("reverted_by")?,
+            created_at: row.try_get("created_at")?,
+        })
+    }
+
+    fn word_from_row(row: &SqliteRow) -> AppResult<StoredWord> {
+        let start: f64 = row.try_get("start_timestamp")?;
+        let end: f64 = row.try_get("end_timestamp")?;
+        let confidence: Option<f32> = row.try_get("confidence")?;
+
+        Ok(StoredWord {
+            id: row.try_get("id")?,
+            segment_id: row.try_get("segment_id")?,
+            word: WordTiming {
+                text: row.try_get("word")?,
+                start_ms: start * 1000.0,
+                end_ms: end * 1000.0,
+                confidence: confidence.unwrap_or_default(),
+            },
+        })
+    }
+}
+
+/// Segments as the engine produced them: no speakers yet, nothing edited
+fn unattributed(segments: &[TranscriptSegment]) -> Vec<StoredSegment> {
+    segments
+        .iter()
+        .map(|segment| StoredSegment {
+            id: 0,
+            transcription_id: 0,
+            speaker_id: None,
+            meeting_speaker_id: None,
+            segment: segment.clone(),
+            is_edited: false,
+        })
+        .collect()
+}
+
+/// Insert a segment and its words, returning the segment's id
+async fn insert_segment(conn: &mut SqliteConnection, stored: &StoredSegment) -> AppResult<i64> {
+    let segment = &stored.segment;
+    let segment_id = sqlx::query(
+        "INSERT INTO transcription_segments
+            (transcription_id, speaker_id, meeting_speaker_id, text, start_timestamp, end_timestamp, confidence,
+             language, model_used, speaker_hint, original_text, is_edited)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(stored.transcription_id)
+    .bind(stored.speaker_id)
+    .bind(stored.meeting_speaker_id)
+    .bind(&segment.text)
+    .bind(segment.start_ms / 1000.0)
+    .bind(segment.end_ms / 1000.0)
+    .bind(segment.confidence)
+    .bind(&segment.language)
+    .bind(&segment.model)
+    .bind(segment.speaker_hint.map(|hint| hint.as_str()))
+    .bind(&segment.original_text)
+    .bind(stored.is_edited)
+    .execute(&mut *conn)
+    .await?
+    .last_insert_rowid();
+
+    insert_words(conn, stored.transcription_id, segment_id, &segment.words).await?;
+    Ok(segment_id)
+}
+
+/// Overwrite a segment and its words
+async fn update_segment(conn: &mut SqliteConnection, stored: &StoredSegment) -> AppResult<()> {
+    let segment = &stored.segment;
+    sqlx::query(
+        "UPDATE transcription_segments
+         SET speaker_id = ?, meeting_speaker_id = ?, text = ?, start_timestamp = ?, end_timestamp = ?,
+             confidence = ?, language = ?, model_used = ?, speaker_hint = ?, original_text = ?, is_edited = ?
+         WHERE id = ?",
+    )
+    .bind(stored.speaker_id)
+    .bind(stored.meeting_speaker_id)
+    .bind(&segment.text)
+    .bind(segment.start_ms / 1000.0)
+    .bind(segment.end_ms / 1000.0)
+    .bind(segment.confidence)
+    .bind(&segment.language)
+    .bind(&segment.model)
+    .bind(segment.speaker_hint.map(|hint| hint.as_str()))
+    .bind(&segment.original_text)
+    .bind(stored.is_edited)
+    .bind(stored.id)
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query("DELETE FROM transcription_words WHERE segment_id = ?")
+        .bind(stored.id)
+        .execute(&mut *conn)
+        .await?;
+    insert_words(conn, stored.transcription_id, stored.id, &segment.words).await
+}
+
+async fn insert_words(
+    conn: &mut SqliteConnection,
+    transcription_id: i64,
+    segment_id: i64,
+    words: &[WordTiming],
+) -> AppResult<()> {
+    for word in words {
+        sqlx::query(
+            "INSERT INTO transcription_words
+                (segment_id, transcription_id, word, start_timestamp, end_timestamp, confidence)
+             VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind(segment_id)
+        .bind(transcription_id)
+        .bind(&word.text)
+        .bind(word.start_ms / 1000.0)
+        .bind(word.end_ms / 1000.0)
+        .bind(word.confidence)
+        .execute(&mut *conn)
+        .await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,129 @@
+//! Vocabulary repository
+
+use sqlx::{Row, SqlitePool};
+use sqlx::sqlite::SqliteRow;
+
+use crate::error::{AppError, AppResult};
+use crate::storage::models::{StoredVocabularyTerm, VocabularyScope};
+use crate::transcription::VocabularyTerm;
+
+/// Reads and writes the terms users teach transcription
+#[derive(Debug, Clone)]
+pub struct VocabularyRepository {
+    pool: SqlitePool,
+}
+
+impl VocabularyRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// Add a term to a scope and return its id
+    pub async fn add(&self, term: &VocabularyTerm, scope: &VocabularyScope) -> AppResult<i64> {
+        let spelling = term.term.trim();
+        if spelling.is_empty() {
+            return Err(AppError::config("Vocabulary term cannot be empty"));
+        }
+        let variants: Vec<&str> = term.variants.iter().map(|v| v.trim()).filter(|v| !v.is_empty()).collect();
+        let variants = serde_json::to_string(&variants)
+            .map_err(|e| AppError::database(format!("Failed to encode term variants: {}", e)))?;
+        let (project, meeting_id) = scope_columns(scope)?;
+
+        let result = sqlx::query("INSERT INTO vocabulary_terms (term, variants, project, meeting_id) VALUES (?, ?, ?, ?)")
+            .bind(spelling)
+            .bind(variants)
+            .bind(project)
+            .bind(meeting_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.last_insert_rowid())
+    }
+
+    /// Terms added to exactly this scope, oldest first
+    pub async fn list(&self, scope: &VocabularyScope) -> AppResult<Vec<StoredVocabularyTerm>> {
+        let (project, meeting_id) = scope_columns(scope)?;
+        let rows = sqlx::query(
+            "SELECT id, term, variants, project, meeting_id FROM vocabulary_terms
+             WHERE project IS ? AND meeting_id IS ? ORDER BY id",
+        )
+        .bind(project)
+        .bind(meeting_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(Self::from_row).collect()
+    }
+
+    /// Remove a term
+    pub async fn delete(&self, id: i64) -> AppResult<()> {
+        let result = sqlx::query("DELETE FROM vocabulary_terms WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::database(format!("Vocabulary term {} not found", id)));
+        }
+
+        Ok(())
+    }
+
+    /// Every term that applies to a meeting: its own, its project's, then
+    /// the global ones
+    pub async fn for_meeting(&self, meeting_id: i64) -> AppResult<Vec<VocabularyTerm>> {
+        let rows = sqlx::query(
+            "SELECT v.id, v.term, v.variants, v.project, v.meeting_id FROM vocabulary_terms v
+             LEFT JOIN meetings m ON m.id = ?
+             WHERE v.meeting_id = ?
+                OR (v.meeting_id IS NULL AND v.project = m.project)
+                OR (v.meeting_id IS NULL AND v.project IS NULL)
+             ORDER BY v.meeting_id IS NULL, v.project IS NULL, v.id",
+        )
+        .bind(meeting_id)
+        .bind(meeting_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter()
+            .map(|row| Self::from_row(row).map(|stored| stored.term))
+            .collect()
+    }
+
+    fn from_row(row: &SqliteRow) -> AppResult<StoredVocabularyTerm> {
+        let variants: String = row.try_get("variants")?;
+        let project: Option<String> = row.try_get("project")?;
+        let meeting_id: Option<i64> = row.try_get("meeting_id")?;
+
+        let scope = match (meeting_id, project) {
+            (Some(meeting_id), _) => VocabularyScope::Meeting { meeting_id },
+            (None, Some(name)) => VocabularyScope::Project { name },
+            (None, None) => VocabularyScope::Global,
+        };
+
+        Ok(StoredVocabularyTerm {
+            id: row.try_get("id")?,
+            scope,
+            term: VocabularyTerm {
+                term: row.try_get("term")?,
+                variants: serde_json::from_str(&variants)
+                    .map_err(|e| AppError::database(format!("Invalid term variants JSON: {}", e)))?,
+            },
+        })
+    }
+}
+
+/// The project and meeting columns a scope is stored as
+fn scope_columns(scope: &VocabularyScope) -> AppResult<(Option<&str>, Option<i64>)> {
+    match scope {
+        VocabularyScope::Global => Ok((None, None)),
+        VocabularyScope::Project { name } => {
+            let name = name.trim();
+            if name.is_empty() {
+                return Err(AppError::config("Project name cannot be empty"));
+            }
+            Ok((Some(name), None))
+        }
+        VocabularyScope::Meeting { meeting_id } => Ok((None, Some(*meeting_id))),
+    }
+}
@@ -0,0 +1,317 @@
+//! Storage tests against an in-memory database
+
+use super::*;
+use crate::audio::{AudioQualityReport, QualityWarning};
+
+fn sample_report() -> AudioQualityReport {
+    AudioQualityReport {
+        duration_ms: 60_000.0,
+        noise_floor_dbfs: Some(-58.0),
+        speech_level_dbfs: Some(-22.0),
+        snr_db: Some(36.0),
+        speech_ratio: 0.6,
+        clipped_samples: 12,
+        clipping_ratio: 0.0000125,
+        dropouts: 1,
+        buffer_overruns: 0,
+        buffer_underruns: 3,
+        integrated_loudness_lufs: Some(-24.5),
+        warnings: vec![QualityWarning::Dropouts { count: 1 }],
+    }
+}
+
+#[tokio::test]
+async fn test_migrations_are_idempotent() {
+    let db = DatabaseService::in_memory().await.unwrap();
+    migrations::run(db.pool()).await.unwrap();
+
+    let applied: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM schema_migrations")
+        .fetch_one(db.pool())
+        .await
+        .unwrap();
+    assert_eq!(applied, migrations::MIGRATIONS.len() as i64);
+}
+
+#[tokio::test]
+async fn test_create_and_get_meeting() {
+    let db = DatabaseService::in_memory().await.unwrap();
+    let meetings = db.meetings();
+
+    let mut new_meeting = NewMeeting::recording("Weekly sync");
+    new_meeting.participants = vec!["ana@example.com".to_string()];
+    let id = meetings.create(&new_meeting).await.unwrap();
+
+    let meeting = meetings.get(id).await.unwrap().unwrap();
+    assert_eq!(meeting.title, "Weekly sync");
+    assert_eq!(meeting.status, MeetingStatus::Recording);
+    assert_eq!(meeting.participants, vec!["ana@example.com".to_string()]);
+    assert!(meeting.audio_quality.is_none());
+
+    meetings.complete(id, new_meeting.start_time + chrono::Duration::seconds(90)).await.unwrap();
+    let meeting = meetings.get(id).await.unwrap().unwrap();
+    assert_eq!(meeting.status, MeetingStatus::Completed);
+    assert_eq!(meeting.duration_seconds, Some(90));
+}
+
+#[tokio::test]
+async fn test_attach_audio_quality_report() {
+    let db = DatabaseService::in_memory().await.unwrap();
+    let meetings = db.meetings();
+    let id = meetings.create(&NewMeeting::recording("Design review")).await.unwrap();
+
+    meetings.set_audio_quality(id, &sample_report()).await.unwrap();
+
+    assert_eq!(meetings.audio_quality(id).await.unwrap(), Some(sample_report()));
+    assert_eq!(meetings.get(id).await.unwrap().unwrap().audio_quality, Some(sample_report()));
+    assert!(meetings.set_audio_quality(id + 1, &sample_report()).await.is_err());
+}
+
+fn segment(start_ms: f64, text: &str, language: &str, model: &str) -> crate::transcription::TranscriptSegment {
+    crate::transcription::TranscriptSegment {
+        text: text.to_string(),
+        start_ms,
+        end_ms: start_ms + 1500.0,
+        confidence: 0.8,
+        language: language.to_string(),
+        model: model.to_string(),
+        words: Vec::new(),
+        speaker_hint: None,
+        original_text: None,
+    }
+}
+
+#[tokio::test]
+async fn test_meeting_language_settings() {
+    let db = DatabaseService::in_memory().await.unwrap();
+    let meetings = db.meetings();
+    let id = meetings.create(&NewMeeting::recording("Sprint planning")).await.unwrap();
+    assert!(meetings.get(id).await.unwrap().unwrap().candidate_languages.is_empty());
+
+    let candidates = vec!["en".to_string(), "pt".to_string(), "es".to_string()];
+    meetings.set_languages(id, None, &candidates).await.unwrap();
+
+    let options = meetings.get(id).await.unwrap().unwrap().transcription_options();
+    assert_eq!(options.language, None);
+    assert_eq!(options.candidate_languages, candidates);
+}
+
+#[tokio::test]
+async fn test_transcript_segments_keep_their_language() {
+    let db = DatabaseService::in_memory().await.unwrap();
+    let meeting_id = db.meetings().create(&NewMeeting::recording("Standup")).await.unwrap();
+    let transcripts = db.transcripts();
+
+    let segments = vec![
+        segment(0.0, "Good morning", "en", "whisper-base"),
+        segment(1500.0, "everyone", "en", "whisper-base"),
+        segment(3000.0, "Bom dia", "pt", "cloud-whisper-1"),
+        segment(4500.0, "Buenos días", "es", "whisper-base"),
+    ];
+    let mut segments = segments;
+    segments[2].speaker_hint = Some(crate::transcription::SpeakerHint::Others);
+    let id = transcripts.create(meeting_id, &segments, "whisper-base", 1200).await.unwrap();
+
+    let transcript = transcripts.get(id).await.unwrap().unwrap();
+    assert_eq!(transcript.language.as_deref(), Some("en"));
+    assert_eq!(transcript.content, "Good morning everyone Bom dia Buenos días");
+    assert_eq!(transcripts.for_meeting(meeting_id).await.unwrap().len(), 1);
+
+    // A formatted copy is newer, but not what the engine heard
+    let source = transcripts.get(id).await.unwrap().unwrap();
+    let stored = transcripts.segments(id).await.unwrap();
+    let formatted = transcripts.create_formatted(&source, &stored, 5).await.unwrap();
+    assert_eq!(transcripts.for_meeting(meeting_id).await.unwrap().last().map(|t| t.id), Some(formatted));
+    assert_eq!(transcripts.latest(meeting_id).await.unwrap().map(|t| t.id), Some(id));
+
+    let stored = transcripts.segments(id).await.unwrap();
+    assert_eq!(stored.iter().map(|s| s.segment.clone()).collect::<Vec<_>>(), segments);
+    assert_eq!(stored[2].segment.model, "cloud-whisper-1");
+
+    let portuguese = transcripts.segments_in_language(id, "pt").await.unwrap();
+    assert_eq!(portuguese.len(), 1);
+    assert_eq!(portuguese[0].segment.text, "Bom dia");
+
+    let switches = transcripts.language_switches(id).await.unwrap();
+    let points: Vec<_> = switches.iter().map(|s| (s.at_ms, s.to.as_str())).collect();
+    assert_eq!(points, vec![(3000.0, "pt"), (4500.0, "es")]);
+}
+
+fn word(text: &str, start_ms: f64, end_ms: f64) -> crate::transcription::WordTiming {
+    crate::transcription::WordTiming { text: text.to_string(), start_ms, end_ms, confidence: 0.75 }
+}
+
+#[tokio::test]
+async fn test_word_timings_are_queryable_by_time() {
+    let db = DatabaseService::in_memory().await.unwrap();
+    let meeting_id = db.meetings().create(&NewMeeting::recording("Retro")).await.unwrap();
+    let transcripts = db.transcripts();
+
+    let mut first = segment(0.0, "Good morning", "en", "whisper-base");
+    first.words = vec![word("Good", 0.0, 500.0), word("morning", 500.0, 1250.0)];
+    let mut second = segment(1500.0, "everyone", "en", "whisper-base");
+    second.words = vec![word("everyone", 1500.0, 2250.0)];
+    let id = transcripts.create(meeting_id, &[first.clone(), second], "whisper-base", 800).await.unwrap();
+
+    let stored = transcripts.segments(id).await.unwrap();
+    assert_eq!(stored[0].segment, first);
+    assert_eq!(stored[1].segment.words.len(), 1);
+
+    // Words overlapping the range, even partly, are included
+    let words = transcripts.words_between(id, 1000.0, 1750.0).await.unwrap();
+    let texts: Vec<_> = words.iter().map(|w| w.word.text.as_str()).collect();
+    assert_eq!(texts, vec!["morning", "everyone"]);
+    assert_eq!(words[1].segment_id, stored[1].id);
+    assert!(transcripts.words_between(id, 2500.0, 3000.0).await.unwrap().is_empty());
+}
+
+fn voice(values: &[f32]) -> crate::transcription::diarization::SpeakerEmbedding {
+    crate::transcription::diarization::SpeakerEmbedding::new(values.to_vec())
+}
+
+#[tokio::test]
+async fn test_meeting_speakers_link_segments_to_people() {
+    use crate::transcription::diarization::ProfileMatch;
+    use crate::transcription::{Diarization, SpeakerCluster};
+
+    let db = DatabaseService::in_memory().await.unwrap();
+    let meeting_id = db.meetings().create(&NewMeeting::recording("Sync")).await.unwrap();
+    let segments = vec![
+        segment(0.0, "Hi", "en", "whisper-base"),
+        segment(1500.0, "Hello", "en", "whisper-base"),
+        segment(3000.0, "Hm", "en", "whisper-base"),
+    ];
+    let transcription_id = db.transcripts().create(meeting_id, &segments, "whisper-base", 10).await.unwrap();
+    let segment_ids: Vec<i64> = db.transcripts().segments(transcription_id).await.unwrap().iter().map(|s| s.id).collect();
+
+    let speakers = db.speakers();
+    let ana = speakers.create("Ana", &voice(&[1.0, 0.0])).await.unwrap();
+    let cluster = |label: &str, values: &[f32], profile: Option<ProfileMatch>| SpeakerCluster {
+        label: label.to_string(),
+        embedding: voice(values),
+        speech_ms: 1500.0,
+        segments: 1,
+        profile,
+    };
+    let diarization = Diarization {
+        speakers: vec![
+            cluster("Ana", &[0.95, 0.05], Some(ProfileMatch { speaker_id: ana, name: "Ana".to_string(), similarity: 0.97 })),
+            cluster("Speaker 2", &[0.0, 1.0], None),
+        ],
+        assignments: vec![Some(0), Some(1), None],
+    };
+
+    let stored = speakers.replace_meeting_speakers(meeting_id, &segment_ids, &diarization).await.unwrap();
+    assert_eq!(speakers.meeting_speakers(meeting_id).await.unwrap(), stored);
+    assert_eq!(speakers.get(ana).await.unwrap().unwrap().total_meetings, 1);
+
+    let attributed = db.transcripts().segments(transcription_id).await.unwrap();
+    assert_eq!(attributed[0].speaker_id, Some(ana));
+    assert_eq!(attributed[1].meeting_speaker_id, Some(stored[1].id));
+    assert_eq!(attributed[1].speaker_id, None);
+    assert_eq!(attributed[2].meeting_speaker_id, None);
+
+    // Rediarizing doesn't count the meeting twice
+    speakers.replace_meeting_speakers(meeting_id, &segment_ids, &diarization).await.unwrap();
+    assert_eq!(speakers.get(ana).await.unwrap().unwrap().total_meetings, 1);
+
+    let (second, embedding) = speakers.meeting_speaker(stored[1].id).await.unwrap().unwrap();
+    assert_eq!(embedding, voice(&[0.0, 1.0]));
+    let bruno = speakers.create("Bruno", &embedding).await.unwrap();
+    speakers.link_meeting_speaker(second.id, bruno, "Bruno").await.unwrap();
+    speakers.rename(bruno, "Bruno Silva").await.unwrap();
+
+    assert_eq!(db.transcripts().segments(transcription_id).await.unwrap()[1].speaker_id, Some(bruno));
+    assert_eq!(speakers.meeting_speakers(meeting_id).await.unwrap()[1].label, "Bruno Silva");
+    assert_eq!(speakers.find_by_name("bruno silva").await.unwrap().unwrap().id, bruno);
+    let names: Vec<String> = speakers.profiles().await.unwrap().into_iter().map(|p| p.name).collect();
+    assert_eq!(names.len(), 2);
+}
+
+#[tokio::test]
+async fn test_meeting_vocabulary_layers_scopes() {
+    use crate::transcription::VocabularyTerm;
+
+    let db = DatabaseService::in_memory().await.unwrap();
+    let vocabulary = db.vocabulary();
+    let mut new_meeting = NewMeeting::recording("Platform sync");
+    new_meeting.participants = vec!["ana.silva@example.com".to_string()];
+    let id = db.meetings().create(&new_meeting).await.unwrap();
+    let other = db.meetings().create(&NewMeeting::recording("Retro")).await.unwrap();
+    db.meetings().set_project(id, Some(" Atlas ")).await.unwrap();
+
+    let global = VocabularyScope::Global;
+    let atlas = VocabularyScope::Project { name: "Atlas".to_string() };
+    vocabulary.add(&VocabularyTerm::new("MeetingMind"), &global).await.unwrap();
+    vocabulary
+        .add(&VocabularyTerm::new("Kubernetes").with_variants(&["cooper netties", " "]), &atlas)
+        .await
+        .unwrap();
+    vocabulary.add(&VocabularyTerm::new("OKR"), &VocabularyScope::Meeting { meeting_id: id }).await.unwrap();
+    let stray = vocabulary.add(&VocabularyTerm::new("Retro"), &VocabularyScope::Meeting { meeting_id: other }).await.unwrap();
+    assert!(vocabulary.add(&VocabularyTerm::new("  "), &global).await.is_err());
+
+    let stored = vocabulary.list(&atlas).await.unwrap();
+    assert_eq!(stored.len(), 1);
+    assert_eq!(stored[0].scope, atlas);
+    assert_eq!(stored[0].term.variants, vec!["cooper netties"]);
+
+    let meeting = db.meetings().get(id).await.unwrap().unwrap();
+    assert_eq!(meeting.project.as_deref(), Some("Atlas"));
+    let terms = vocabulary.for_meeting(id).await.unwrap();
+    let spellings: Vec<_> = terms.iter().map(|t| t.term.as_str()).collect();
+    assert_eq!(spellings, ["OKR", "Kubernetes", "MeetingMind"]);
+    assert_eq!(
+        meeting.vocabulary(terms).prompt().unwrap(),
+        "Platform sync. With Ana Silva. OKR, Kubernetes, MeetingMind."
+    );
+
+    vocabulary.delete(stray).await.unwrap();
+    assert!(vocabulary.delete(stray).await.is_err());
+    let spellings: Vec<_> = vocabulary.for_meeting(other).await.unwrap().into_iter().map(|t| t.term).collect();
+    assert_eq!(spellings, ["MeetingMind"]);
+}
+
+#[tokio::test]
+async fn test_job_queue_claims_by_priority_and_requeues_interrupted_jobs() {
+    let db = DatabaseService::in_memory().await.unwrap();
+    let meeting_id = db.meetings().create(&NewMeeting::recording("Backlog")).await.unwrap();
+    let jobs = db.jobs();
+    let job = |priority| NewTranscriptionJob {
+        meeting_id,
+        priority,
+        audio_path: "recordings/backlog.wav".to_string(),
+        chunk_ms: 30_000.0,
+        options: Default::default(),
+        replaces_transcription_id: None,
+    };
+    let backlog = jobs.enqueue(&job(JobPriority::Backlog)).await.unwrap();
+    let live = jobs.enqueue(&job(JobPriority::Live)).await.unwrap();
+    let now = chrono::Utc::now();
+
+    let claimed = jobs.claim_next(now).await.unwrap().unwrap();
+    assert_eq!((claimed.id, claimed.status), (live, JobStatus::Running));
+    // Nothing queued outranks the backlog job once the live one is running
+    assert!(!jobs.has_due_above(JobPriority::Backlog, now).await.unwrap());
+
+    // A retry isn't due before its time
+    let claimed = jobs.claim_next(now).await.unwrap().unwrap();
+    assert_eq!(claimed.id, backlog);
+    let retry_at = now + chrono::Duration::seconds(30);
+    jobs.record_failure(backlog, "engine crashed", Some(retry_at)).await.unwrap();
+    assert!(jobs.claim_next(now).await.unwrap().is_none());
+    assert_eq!(jobs.next_retry().await.unwrap(), Some(retry_at));
+    assert_eq!(jobs.claim_next(retry_at).await.unwrap().unwrap().attempts, 1);
+
+    // Jobs running when the app stopped go back in the queue, checkpoints kept
+    jobs.checkpoint(live, 0, &[], 12.0).await.unwrap();
+    assert_eq!(jobs.requeue_interrupted().await.unwrap(), 2);
+    let resumed = jobs.get(live).await.unwrap().unwrap();
+    assert_eq!((resumed.status, resumed.completed_chunks), (JobStatus::Queued, 1));
+    assert_eq!(jobs.checkpoints(live).await.unwrap().len(), 1);
+
+    assert!(jobs.cancel(live).await.unwrap());
+    assert!(!jobs.cancel(live).await.unwrap());
+    assert!(jobs.cancel(404).await.is_err());
+    let listed: Vec<_> = jobs.list().await.unwrap().into_iter().map(|job| job.id).collect();
+    assert_eq!(listed, vec![backlog, live]);
+}
@@ -0,0 +1,245 @@
+//! Word-level timing
+//!
+//! Engines that time individual tokens build words with `push_token`. For
+//! those that only time segments, `align_words` places each word on the
+//! segment's audio: the voiced stretches of the segment are shared out
+//! between its words in proportion to their length, so pauses fall between
+//! words rather than inside them. That is cruder than an acoustic model but
+//! close enough to seek to a word or quote a phrase. Text changed after
+//! decoding is timed with `spread_words`, which keeps the old timings where
+//! the words still line up.
+
+use crate::audio::AudioChunk;
+
+use super::types::{TranscriptSegment, WordTiming};
+
+/// Length of the energy frames words are placed on
+const FRAME_MS: f64 = 10.0;
+
+/// Frames quieter than this share of the segment's loudest frame count as pauses
+const VOICED_RATIO: f32 = 0.1;
+
+/// Add a decoded token to `words`, starting a new word at leading whitespace
+///
+/// Subword tokens extend the word before them; a word's confidence is that
+/// of its least certain token.
+pub fn push_token(words: &mut Vec<WordTiming>, text: &str, start_ms: f64, end_ms: f64, probability: f32) {
+    let starts_word = text.starts_with(char::is_whitespace);
+    let text = text.trim();
+    if text.is_empty() {
+        return;
+    }
+
+    match words.last_mut() {
+        Some(word) if !starts_word => {
+            word.text.push_str(text);
+            word.end_ms = word.end_ms.max(end_ms);
+            word.confidence = word.confidence.min(probability);
+        }
+        _ => words.push(WordTiming {
+            text: text.to_string(),
+            start_ms,
+            end_ms: end_ms.max(start_ms),
+            confidence: probability,
+        }),
+    }
+}
+
+/// Derive word timings for `segment` from the audio of `chunk`
+pub fn align_words(chunk: &AudioChunk, segment: &TranscriptSegment) -> Vec<WordTiming> {
+    let words: Vec<&str> = segment.text.split_whitespace().collect();
+    if words.is_empty() {
+        return Vec::new();
+    }
+
+    let voiced = voiced_frames(chunk, segment);
+    let total = voiced.iter().filter(|&&v| v).count();
+    let lengths: Vec<usize> = words.iter().map(|word| word.chars().count().max(1)).collect();
+    let characters: usize = lengths.iter().sum();
+
+    let mut timings = Vec::with_capacity(words.len());
+    let mut before = 0;
+    for (word, length) in words.into_iter().zip(lengths) {
+        let start = before as f64 / characters as f64;
+        before += length;
+        let end = before as f64 / characters as f64;
+
+        let (start_ms, end_ms) = if total == 0 {
+            // Nothing to go on; spread the words evenly
+            (
+                segment.start_ms + start * segment.duration_ms(),
+                segment.start_ms + end * segment.duration_ms(),
+            )
+        } else {
+            (
+                frame_time(segment.start_ms, &voiced, start * total as f64, true),
+                frame_time(segment.start_ms, &voiced, end * total as f64, false),
+            )
+        };
+
+        let start_ms = start_ms.clamp(segment.start_ms, segment.end_ms.max(segment.start_ms));
+        timings.push(WordTiming {
+            text: word.to_string(),
+            start_ms,
+            end_ms: end_ms.clamp(start_ms, segment.end_ms.max(start_ms)),
+            confidence: segment.confidence,
+        });
+    }
+
+    timings
+}
+
+/// Words of `text` timed on `segment`
+///
+/// When `text` has as many words as the segment's timings only their
+/// spelling changed, so the timings stay; otherwise the words are spread
+/// over the segment by length.
+pub fn spread_words(segment: &TranscriptSegment, text: &str) -> Vec<WordTiming> {
+    let words: Vec<&str> = text.split_whitespace().collect();
+    if segment.words.len() == words.len() {
+        return segment
+            .words
+            .iter()
+            .zip(words)
+            .map(|(timing, word)| WordTiming { text: word.to_string(), ..timing.clone() })
+            .collect();
+    }
+
+    let characters: usize = words.iter().map(|w| w.chars().count()).sum::<usize>().max(1);
+    let mut before = 0;
+    words
+        .into_iter()
+        .map(|word| {
+            let start = before as f64 / characters as f64;
+            before += word.chars().count();
+            let end = before as f64 / characters as f64;
+            WordTiming {
+                text: word.to_string(),
+                start_ms: segment.start_ms + start * segment.duration_ms(),
+                end_ms: segment.start_ms + end * segment.duration_ms(),
+                confidence: segment.confidence,
+            }
+        })
+        .collect()
+}
+
+/// Align every segment the engine didn't time word by word
+pub fn fill_word_timings(chunk: &AudioChunk, segments: &mut [TranscriptSegment]) {
+    for segment in segments.iter_mut().filter(|s| s.words.is_empty()) {
+        segment.words = align_words(chunk, segment);
+    }
+}
+
+/// Whether each frame of the segment's audio holds speech
+fn voiced_frames(chunk: &AudioChunk, segment: &TranscriptSegment) -> Vec<bool> {
+    let buffer = &chunk.buffer;
+    let channels = buffer.channels.max(1) as usize;
+    let rate = buffer.sample_rate as f64;
+    let frame_len = ((FRAME_MS / 1000.0 * rate) as usize).max(1);
+    let frames = buffer.samples.len() / channels;
+    let offset = |ms: f64| (((ms - chunk.start_ms).max(0.0) / 1000.0 * rate) as usize).min(frames);
+
+    let from = offset(segment.start_ms);
+    let to = offset(segment.end_ms).max(from);
+    let levels: Vec<f32> = buffer.samples[from * channels..to * channels]
+        .chunks(frame_len * channels)
+        .map(|frame| (frame.iter().map(|s| s * s).sum::<f32>() / frame.len() as f32).sqrt())
+        .collect();
+
+    let loudest = levels.iter().copied().fold(0.0f32, f32::max);
+    levels.into_iter().map(|level| loudest > 0.0 && level >= loudest * VOICED_RATIO).collect()
+}
+
+/// Time at which `target` voiced frames have elapsed
+///
+/// A start lands at the beginning of the next voiced frame and an end at
+/// the close of the last one, so neither runs into a pause.
+fn frame_time(segment_start_ms: f64, voiced: &[bool], target: f64, is_start: bool) -> f64 {
+    let mut elapsed = 0.0;
+    for (i, _) in voiced.iter().enumerate().filter(|(_, &v)| v) {
+        let reached = if is_start { elapsed + 1.0 > target } else { elapsed + 1.0 >= target };
+        if reached {
+            return segment_start_ms + (i as f64 + (target - elapsed).clamp(0.0, 1.0)) * FRAME_MS;
+        }
+        elapsed += 1.0;
+    }
+    segment_start_ms + voiced.len() as f64 * FRAME_MS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::AudioBuffer;
+
+    fn segment(text: &str, start_ms: f64, end_ms: f64) -> TranscriptSegment {
+        TranscriptSegment {
+            text: text.to_string(),
+            start_ms,
+            end_ms,
+            confidence: 0.8,
+            language: "en".to_string(),
+            model: "mock".to_string(),
+            words: Vec::new(),
+            speaker_hint: None,
+            original_text: None,
+        }
+    }
+
+    #[test]
+    fn test_subword_tokens_join_into_words() {
+        let mut words = Vec::new();
+        push_token(&mut words, " Good", 0.0, 200.0, 0.9);
+        push_token(&mut words, " morn", 200.0, 350.0, 0.8);
+        push_token(&mut words, "ing", 350.0, 500.0, 0.6);
+        push_token(&mut words, ",", 500.0, 510.0, 0.95);
+        push_token(&mut words, " ", 510.0, 520.0, 0.1);
+
+        assert_eq!(words.len(), 2);
+        assert_eq!(words[1].text, "morning,");
+        assert_eq!((words[1].start_ms, words[1].end_ms), (200.0, 510.0));
+        assert_eq!(words[1].confidence, 0.6);
+    }
+
+    #[test]
+    fn test_words_avoid_the_pause_between_them() {
+        // One second of speech, half a second of silence, one second of speech
+        let mut samples = vec![0.3f32; 16000];
+        samples.extend(vec![0.0; 8000]);
+        samples.extend(vec![0.3; 16000]);
+        let chunk = AudioChunk { index: 0, start_ms: 10_000.0, buffer: AudioBuffer::new(samples, 16000, 1) };
+
+        let words = align_words(&chunk, &segment("hello there", 10_000.0, 12_500.0));
+        assert_eq!(words.len(), 2);
+        assert_eq!(words[0].start_ms, 10_000.0);
+        assert!((words[0].end_ms - 11_000.0).abs() < FRAME_MS, "{:?}", words[0]);
+        assert!((words[1].start_ms - 11_500.0).abs() < FRAME_MS, "{:?}", words[1]);
+        assert_eq!(words[1].end_ms, 12_500.0);
+    }
+
+    #[test]
+    fn test_silent_audio_spreads_words_evenly() {
+        let chunk = AudioChunk { index: 0, start_ms: 0.0, buffer: AudioBuffer::new(vec![0.0; 16000], 16000, 1) };
+        let mut segments = vec![segment("ab cd", 0.0, 1000.0)];
+        fill_word_timings(&chunk, &mut segments);
+
+        let spans: Vec<_> = segments[0].words.iter().map(|w| (w.start_ms, w.end_ms)).collect();
+        assert_eq!(spans, vec![(0.0, 500.0), (500.0, 1000.0)]);
+    }
+
+    #[test]
+    fn test_respelled_words_keep_their_timings() {
+        let chunk = AudioChunk { index: 0, start_ms: 0.0, buffer: AudioBuffer::new(vec![0.0; 16000], 16000, 1) };
+        let mut segments = vec![segment("gonna ship", 0.0, 1000.0)];
+        fill_word_timings(&chunk, &mut segments);
+        segments[0].words[0].end_ms = 300.0;
+
+        let respelled = spread_words(&segments[0], "going ship");
+        assert_eq!(respelled[0].text, "going");
+        assert_eq!(respelled[0].end_ms, 300.0);
+
+        // A different number of words no longer lines up, so they are spread by length
+        let rewritten = spread_words(&segments[0], "go to ship");
+        let spans: Vec<_> = rewritten.iter().map(|w| (w.start_ms, w.end_ms)).collect();
+        assert_eq!(spans, vec![(0.0, 250.0), (250.0, 500.0), (500.0, 1000.0)]);
+    }
+}
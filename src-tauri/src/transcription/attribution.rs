@@ -0,0 +1,261 @@
+//! Telling the local speaker from everyone else by capture track
+//!
+//! With the microphone and system audio captured as separate tracks, whoever
+//! is loud on a track is, to a first approximation, that track's speaker.
+//! Each track's level is kept in 10 ms frames on the meeting timeline. A
+//! segment goes to the role whose tracks were dominant for most of its
+//! frames; frames where both sides talk at a similar level count for
+//! neither. This works before (and alongside) diarization, which can then
+//! split "Others" into individual voices.
+
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+
+use crate::audio::AudioBuffer;
+
+use super::types::{SpeakerHint, TranscriptSegment, TranscriptionError, TranscriptionResult};
+
+/// Length of the frames track levels are measured over
+const FRAME_MS: f64 = 10.0;
+
+/// When a track counts as active and when it drowns out the others
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AttributionConfig {
+    /// RMS level a track must reach for its frame to count as speech
+    pub activity_threshold: f32,
+    /// How much louder than every other active track a track must be to
+    /// claim a frame both are active in
+    pub dominance_db: f32,
+}
+
+impl Default for AttributionConfig {
+    fn default() -> Self {
+        Self {
+            activity_threshold: 0.01,
+            dominance_db: 6.0,
+        }
+    }
+}
+
+impl AttributionConfig {
+    /// Check the settings are usable
+    pub fn validate(&self) -> TranscriptionResult<()> {
+        if !(0.0..1.0).contains(&self.activity_threshold) {
+            return Err(TranscriptionError::InvalidConfig {
+                details: format!("Track activity threshold {} must be between 0 and 1", self.activity_threshold),
+            });
+        }
+        if !(0.0..=60.0).contains(&self.dominance_db) {
+            return Err(TranscriptionError::InvalidConfig {
+                details: format!("Track dominance of {}dB is out of range (0-60dB)", self.dominance_db),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Speaker role of each capture track
+///
+/// With a second capture device, its track (always the last) is system
+/// audio and every track of the primary device is the microphone. Without
+/// one, a multi-track channel map is taken to carry the microphone on its
+/// first track and system audio (e.g. an interface's loopback) on the rest.
+pub fn track_roles(track_count: usize, has_secondary_device: bool) -> Vec<SpeakerHint> {
+    (0..track_count)
+        .map(|track| {
+            let others = if has_secondary_device { track + 1 == track_count } else { track > 0 };
+            if others { SpeakerHint::Others } else { SpeakerHint::Me }
+        })
+        .collect()
+}
+
+/// Level history of one track
+#[derive(Debug, Clone, Default)]
+struct TrackLevels {
+    /// RMS of each complete frame, from the start of capture
+    frames: Vec<f32>,
+    sum_sq: f64,
+    pending: usize,
+}
+
+/// Works out which capture track each segment was spoken on
+#[derive(Debug, Clone)]
+pub struct TrackAttributor {
+    config: AttributionConfig,
+    roles: Vec<SpeakerHint>,
+    tracks: Vec<TrackLevels>,
+    /// Samples per frame, once the tracks' sample rate is known
+    frame_len: Option<usize>,
+}
+
+impl TrackAttributor {
+    /// An attributor for tracks with the given roles, in track order
+    pub fn new(roles: Vec<SpeakerHint>, config: AttributionConfig) -> Self {
+        let tracks = vec![TrackLevels::default(); roles.len()];
+        Self { config, roles, tracks, frame_len: None }
+    }
+
+    /// Whether the tracks can tell the roles apart at all
+    pub fn distinguishes_roles(&self) -> bool {
+        self.roles.contains(&SpeakerHint::Me) && self.roles.contains(&SpeakerHint::Others)
+    }
+
+    /// Add the next stretch of a track's audio
+    ///
+    /// Every track is expected to start at the same moment and run on the
+    /// same clock, as capture tracks do. Audio for unknown tracks is ignored.
+    pub fn push(&mut self, track: usize, buffer: &AudioBuffer) {
+        let frame_len = *self
+            .frame_len
+            .get_or_insert(((buffer.sample_rate as f64 * FRAME_MS / 1000.0).round() as usize).max(1));
+        let Some(levels) = self.tracks.get_mut(track) else {
+            return;
+        };
+
+        let channels = buffer.channels.max(1) as usize;
+        for frame in buffer.samples.chunks_exact(channels) {
+            let sample = frame.iter().sum::<f32>() / channels as f32;
+            levels.sum_sq += (sample as f64).powi(2);
+            levels.pending += 1;
+            if levels.pending == frame_len {
+                levels.frames.push((levels.sum_sq / frame_len as f64).sqrt() as f32);
+                levels.sum_sq = 0.0;
+                levels.pending = 0;
+            }
+        }
+    }
+
+    /// Move every track past audio that never arrived
+    ///
+    /// The gap counts as silence on all tracks, so it is attributed to no one
+    /// and later audio keeps its place on the timeline.
+    pub fn skip(&mut self, gap_ms: f64) {
+        let frames = (gap_ms.max(0.0) / FRAME_MS).round() as usize;
+        for levels in &mut self.tracks {
+            levels.frames.resize(levels.frames.len() + frames, 0.0);
+        }
+    }
+
+    /// The role that dominated `start_ms..end_ms`, if any did
+    pub fn hint(&self, start_ms: f64, end_ms: f64) -> Option<SpeakerHint> {
+        let first = (start_ms.max(0.0) / FRAME_MS).floor() as usize;
+        let last = (end_ms.max(0.0) / FRAME_MS).ceil() as usize;
+        let dominance = 10f32.powf(self.config.dominance_db / 20.0);
+
+        let mut votes: HashMap<SpeakerHint, usize> = HashMap::new();
+        for frame in first..last {
+            let mut loudest: Option<(usize, f32)> = None;
+            let mut runner_up = 0.0f32;
+            for (track, levels) in self.tracks.iter().enumerate() {
+                let level = levels.frames.get(frame).copied().unwrap_or(0.0);
+                if level < self.config.activity_threshold {
+                    continue;
+                }
+                match loudest {
+                    Some((_, top)) if level <= top => runner_up = runner_up.max(level),
+                    _ => {
+                        runner_up = runner_up.max(loudest.map_or(0.0, |(_, top)| top));
+                        loudest = Some((track, level));
+                    }
+                }
+            }
+
+            let Some((track, level)) = loudest else { continue };
+            if runner_up > 0.0 && level < runner_up * dominance {
+                continue;
+            }
+            *votes.entry(self.roles[track]).or_default() += 1;
+        }
+
+        let me = votes.get(&SpeakerHint::Me).copied().unwrap_or(0);
+        let others = votes.get(&SpeakerHint::Others).copied().unwrap_or(0);
+        match me.cmp(&others) {
+            std::cmp::Ordering::Greater => Some(SpeakerHint::Me),
+            std::cmp::Ordering::Less => Some(SpeakerHint::Others),
+            std::cmp::Ordering::Equal => None,
+        }
+    }
+
+    /// Set the hint of every segment that doesn't have one yet
+    pub fn attribute(&self, segments: &mut [TranscriptSegment]) {
+        for segment in segments.iter_mut().filter(|s| s.speaker_hint.is_none()) {
+            segment.speaker_hint = self.hint(segment.start_ms, segment.end_ms);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// One second of a tone at `level` RMS, or silence
+    fn second(level: f32) -> AudioBuffer {
+        let samples = (0..16000)
+            .map(|i| level * std::f32::consts::SQRT_2 * (i as f32 * 0.1).sin())
+            .collect();
+        AudioBuffer::new(samples, 16000, 1)
+    }
+
+    fn attributor(mic: &[f32], system: &[f32]) -> TrackAttributor {
+        let mut attributor = TrackAttributor::new(track_roles(2, true), AttributionConfig::default());
+        for (track, levels) in [mic, system].iter().enumerate() {
+            for &level in levels.iter() {
+                attributor.push(track, &second(level));
+            }
+        }
+        attributor
+    }
+
+    #[test]
+    fn test_each_track_claims_the_speech_only_it_carries() {
+        let attributor = attributor(&[0.2, 0.0, 0.0], &[0.0, 0.1, 0.0]);
+
+        assert_eq!(attributor.hint(0.0, 1000.0), Some(SpeakerHint::Me));
+        assert_eq!(attributor.hint(1000.0, 2000.0), Some(SpeakerHint::Others));
+        assert_eq!(attributor.hint(2000.0, 3000.0), None);
+        // Mostly the remote side, with a little of the local speaker
+        assert_eq!(attributor.hint(800.0, 2000.0), Some(SpeakerHint::Others));
+    }
+
+    #[test]
+    fn test_louder_track_wins_when_both_are_active() {
+        // Far-end speech leaking into the mic is well below the system track
+        let attributor = attributor(&[0.02, 0.2, 0.1], &[0.2, 0.02, 0.08]);
+
+        assert_eq!(attributor.hint(0.0, 1000.0), Some(SpeakerHint::Others));
+        assert_eq!(attributor.hint(1000.0, 2000.0), Some(SpeakerHint::Me));
+        // Talking over each other at similar levels decides nothing
+        assert_eq!(attributor.hint(2000.0, 3000.0), None);
+    }
+
+    #[test]
+    fn test_attribute_keeps_existing_hints() {
+        let attributor = attributor(&[0.2], &[0.0]);
+        let segment = |hint| TranscriptSegment {
+            text: "hello".to_string(),
+            start_ms: 0.0,
+            end_ms: 1000.0,
+            confidence: 0.9,
+            language: "en".to_string(),
+            model: "mock".to_string(),
+            words: Vec::new(),
+            speaker_hint: hint,
+            original_text: None,
+        };
+        let mut segments = vec![segment(None), segment(Some(SpeakerHint::Others))];
+        attributor.attribute(&mut segments);
+
+        assert_eq!(segments[0].speaker_hint, Some(SpeakerHint::Me));
+        assert_eq!(segments[1].speaker_hint, Some(SpeakerHint::Others));
+    }
+
+    #[test]
+    fn test_track_roles() {
+        use SpeakerHint::*;
+        assert_eq!(track_roles(3, true), vec![Me, Me, Others]);
+        assert_eq!(track_roles(2, false), vec![Me, Others]);
+        assert!(!TrackAttributor::new(track_roles(1, false), AttributionConfig::default()).distinguishes_roles());
+        assert!(AttributionConfig { dominance_db: -1.0, ..Default::default() }.validate().is_err());
+    }
+}
@@ -0,0 +1,432 @@
+//! Subtitles and captions for recordings
+//!
+//! `CaptionWriter` cuts timed segments into cues short enough to read, at
+//! most `max_lines` lines of `max_line_chars` characters, each shown for
+//! `min_duration_ms` to `max_duration_ms`. It writes them as SRT, WebVTT or
+//! TTML. A cue never spans two segments, so it has one speaker at most.
+//! WebVTT names the speaker in a voice tag and TTML as an agent. SRT has no
+//! markup for it, so the name goes in front of the text where the speaker
+//! changes. Word timings decide where a long segment is cut; segments
+//! without them are timed by the length of their words.
+
+use serde::{Deserialize, Serialize};
+
+use super::alignment::spread_words;
+use super::language::dominant_language;
+use super::types::{TranscriptSegment, TranscriptionError, TranscriptionResult, WordTiming};
+
+/// File format captions are written in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CaptionFormat {
+    Srt,
+    WebVtt,
+    Ttml,
+}
+
+impl CaptionFormat {
+    /// Usual file extension, without the dot
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Srt => "srt",
+            Self::WebVtt => "vtt",
+            Self::Ttml => "ttml",
+        }
+    }
+}
+
+/// How transcripts are cut into cues
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CaptionConfig {
+    /// Longest line, in characters
+    pub max_line_chars: usize,
+    /// Lines shown at once
+    pub max_lines: usize,
+    /// Shortest time a cue stays up, unless the next one starts sooner
+    pub min_duration_ms: f64,
+    /// Longest time a cue stays up before the rest of its segment follows
+    /// in another
+    pub max_duration_ms: f64,
+    /// Shift applied to every cue, e.g. when the video starts before or
+    /// after the recording; cues that end up wholly before zero are dropped
+    pub offset_ms: f64,
+}
+
+impl Default for CaptionConfig {
+    fn default() -> Self {
+        Self {
+            max_line_chars: 42,
+            max_lines: 2,
+            min_duration_ms: 1000.0,
+            max_duration_ms: 7000.0,
+            offset_ms: 0.0,
+        }
+    }
+}
+
+impl CaptionConfig {
+    /// Check the settings are usable
+    pub fn validate(&self) -> TranscriptionResult<()> {
+        let invalid = |details: String| Err(TranscriptionError::InvalidConfig { details });
+        if !(10..=120).contains(&self.max_line_chars) {
+            return invalid(format!("Caption lines of {} characters are out of range (10-120)", self.max_line_chars));
+        }
+        if !(1..=3).contains(&self.max_lines) {
+            return invalid(format!("Captions of {} lines are out of range (1-3)", self.max_lines));
+        }
+        if self.max_duration_ms < 1000.0 {
+            return invalid(format!("Captions must be allowed at least 1000ms, not {}ms", self.max_duration_ms));
+        }
+        if !(0.0..=self.max_duration_ms).contains(&self.min_duration_ms) {
+            return invalid(format!(
+                "Shortest caption of {}ms must be between 0 and the longest ({}ms)",
+                self.min_duration_ms, self.max_duration_ms
+            ));
+        }
+        if !self.offset_ms.is_finite() {
+            return invalid("Caption offset must be a number".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// A segment to caption and who spoke it
+#[derive(Debug, Clone, PartialEq)]
+pub struct CaptionSegment {
+    pub speaker: Option<String>,
+    pub segment: TranscriptSegment,
+}
+
+/// Text shown on screen for a while
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cue {
+    pub start_ms: f64,
+    pub end_ms: f64,
+    pub speaker: Option<String>,
+    pub lines: Vec<String>,
+}
+
+/// Writes segments as captions
+#[derive(Debug, Clone)]
+pub struct CaptionWriter {
+    config: CaptionConfig,
+}
+
+impl CaptionWriter {
+    pub fn new(config: CaptionConfig) -> Self {
+        Self { config }
+    }
+
+    /// The captions for `segments` as a file in `format`
+    pub fn write(&self, format: CaptionFormat, segments: &[CaptionSegment]) -> String {
+        let cues = self.cues(segments, format == CaptionFormat::Srt);
+        match format {
+            CaptionFormat::Srt => srt(&cues),
+            CaptionFormat::WebVtt => webvtt(&cues),
+            CaptionFormat::Ttml => {
+                let spoken: Vec<TranscriptSegment> = segments.iter().map(|s| s.segment.clone()).collect();
+                ttml(&cues, dominant_language(&spoken).as_deref().unwrap_or_default())
+            }
+        }
+    }
+
+    /// Cut segments into cues, in timeline order and shifted by the offset
+    ///
+    /// With `label_turns`, the first cue of each speaker's turn starts with
+    /// their name.
+    pub fn cues(&self, segments: &[CaptionSegment], label_turns: bool) -> Vec<Cue> {
+        let mut ordered: Vec<&CaptionSegment> = segments.iter().collect();
+        ordered.sort_by(|a, b| a.segment.start_ms.total_cmp(&b.segment.start_ms));
+
+        let mut cues = Vec::new();
+        let mut previous_speaker = None;
+        for spoken in ordered {
+            let mut words = spread_words(&spoken.segment, &spoken.segment.text);
+            let Some(first) = words.first().cloned() else {
+                continue;
+            };
+            let speaker = spoken.speaker.as_deref();
+            if let Some(name) = speaker.filter(|_| label_turns && speaker != previous_speaker) {
+                words.insert(0, WordTiming { text: format!("{}:", name), end_ms: first.start_ms, ..first });
+            }
+            previous_speaker = speaker;
+
+            let mut current: Vec<WordTiming> = Vec::new();
+            for word in words {
+                if let Some(start) = current.first().map(|w| w.start_ms) {
+                    let mut texts: Vec<&str> = current.iter().map(|w| w.text.as_str()).collect();
+                    texts.push(&word.text);
+                    if wrap(&texts, self.config.max_line_chars).len() > self.config.max_lines
+                        || word.end_ms - start > self.config.max_duration_ms
+                    {
+                        cues.push(self.cue(&current, spoken.speaker.clone()));
+                        current.clear();
+                    }
+                }
+                current.push(word);
+            }
+            if !current.is_empty() {
+                cues.push(self.cue(&current, spoken.speaker.clone()));
+            }
+        }
+
+        // Keep short cues up long enough to read, without covering the next
+        for i in 0..cues.len() {
+            let wanted = cues[i].start_ms + self.config.min_duration_ms;
+            if cues[i].end_ms < wanted {
+                let next = cues.get(i + 1).map_or(f64::INFINITY, |next| next.start_ms);
+                cues[i].end_ms = wanted.min(next).max(cues[i].end_ms);
+            }
+        }
+
+        let offset = self.config.offset_ms;
+        cues.into_iter()
+            .filter(|cue| cue.end_ms + offset > 0.0)
+            .map(|cue| Cue {
+                start_ms: (cue.start_ms + offset).max(0.0),
+                end_ms: cue.end_ms + offset,
+                ..cue
+            })
+            .collect()
+    }
+
+    fn cue(&self, words: &[WordTiming], speaker: Option<String>) -> Cue {
+        let texts: Vec<&str> = words.iter().map(|w| w.text.as_str()).collect();
+        Cue {
+            start_ms: words.first().map_or(0.0, |w| w.start_ms),
+            end_ms: words.last().map_or(0.0, |w| w.end_ms),
+            speaker,
+            lines: balanced(&texts, self.config.max_line_chars),
+        }
+    }
+}
+
+/// Fill lines of at most `width` characters; a longer word gets a line of its own
+fn wrap(words: &[&str], width: usize) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for word in words {
+        match lines.last_mut() {
+            Some(line) if line.chars().count() + 1 + word.chars().count() <= width => {
+                line.push(' ');
+                line.push_str(word);
+            }
+            _ => lines.push(word.to_string()),
+        }
+    }
+    lines
+}
+
+/// Lines as even in length as they can be without needing more of them
+/// than filling each up to `width` would
+fn balanced(words: &[&str], width: usize) -> Vec<String> {
+    let lines = wrap(words, width);
+    (1..width)
+        .map(|narrower| wrap(words, narrower))
+        .find(|narrower| narrower.len() <= lines.len())
+        .unwrap_or(lines)
+}
+
+/// `HH:MM:SS` and milliseconds after `separator`
+fn timestamp(ms: f64, separator: char) -> String {
+    let ms = ms.max(0.0).round() as u64;
+    format!(
+        "{:02}:{:02}:{:02}{}{:03}",
+        ms / 3_600_000,
+        ms / 60_000 % 60,
+        ms / 1000 % 60,
+        separator,
+        ms % 1000
+    )
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+fn srt(cues: &[Cue]) -> String {
+    let mut out = String::new();
+    for (i, cue) in cues.iter().enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        out.push_str(&format!(
+            "{}\n{} --> {}\n{}\n",
+            i + 1,
+            timestamp(cue.start_ms, ','),
+            timestamp(cue.end_ms, ','),
+            cue.lines.join("\n")
+        ));
+    }
+    out
+}
+
+fn webvtt(cues: &[Cue]) -> String {
+    let mut out = String::from("WEBVTT\n");
+    for (i, cue) in cues.iter().enumerate() {
+        let voice = cue.speaker.as_deref().map(|name| format!("<v {}>", escape(name))).unwrap_or_default();
+        let lines: Vec<String> = cue.lines.iter().map(|line| escape(line)).collect();
+        out.push_str(&format!(
+            "\n{}\n{} --> {}\n{}{}\n",
+            i + 1,
+            timestamp(cue.start_ms, '.'),
+            timestamp(cue.end_ms, '.'),
+            voice,
+            lines.join("\n")
+        ));
+    }
+    out
+}
+
+fn ttml(cues: &[Cue], language: &str) -> String {
+    let mut speakers: Vec<&str> = Vec::new();
+    for name in cues.iter().filter_map(|cue| cue.speaker.as_deref()) {
+        if !speakers.contains(&name) {
+            speakers.push(name);
+        }
+    }
+    let agent = |name: &str| speakers.iter().position(|s| *s == name).map(|i| format!("speaker{}", i + 1));
+
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str(&format!(
+        "<tt xmlns=\"http://www.w3.org/ns/ttml\" xmlns:ttm=\"http://www.w3.org/ns/ttml#metadata\" xml:lang=\"{}\">\n",
+        escape(language)
+    ));
+    if !speakers.is_empty() {
+        out.push_str("  <head>\n    <metadata>\n");
+        for name in &speakers {
+            out.push_str(&format!(
+                "      <ttm:agent type=\"person\" xml:id=\"{}\">\n",
+                agent(name).unwrap_or_default()
+            ));
+            out.push_str(&format!("        <ttm:name type=\"full\">{}</ttm:name>\n", escape(name)));
+            out.push_str("      </ttm:agent>\n");
+        }
+        out.push_str("    </metadata>\n  </head>\n");
+    }
+    out.push_str("  <body>\n    <div>\n");
+    for cue in cues {
+        let speaker = cue
+            .speaker
+            .as_deref()
+            .and_then(agent)
+            .map(|id| format!(" ttm:agent=\"{}\"", id))
+            .unwrap_or_default();
+        let lines: Vec<String> = cue.lines.iter().map(|line| escape(line)).collect();
+        out.push_str(&format!(
+            "      <p begin=\"{}\" end=\"{}\"{}>{}</p>\n",
+            timestamp(cue.start_ms, '.'),
+            timestamp(cue.end_ms, '.'),
+            speaker,
+            lines.join("<br/>")
+        ));
+    }
+    out.push_str("    </div>\n  </body>\n</tt>\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    /// A segment whose words are spread evenly from `start_ms`, `word_ms` each
+    fn spoken(speaker: Option<&str>, text: &str, start_ms: f64, word_ms: f64) -> CaptionSegment {
+        let words: Vec<WordTiming> = text
+            .split_whitespace()
+            .enumerate()
+            .map(|(i, word)| WordTiming {
+                text: word.to_string(),
+                start_ms: start_ms + i as f64 * word_ms,
+                end_ms: start_ms + (i + 1) as f64 * word_ms,
+                confidence: 0.9,
+            })
+            .collect();
+        CaptionSegment {
+            speaker: speaker.map(str::to_string),
+            segment: TranscriptSegment {
+                text: text.to_string(),
+                start_ms,
+                end_ms: words.last().map_or(start_ms, |w| w.end_ms),
+                confidence: 0.9,
+                language: "en".to_string(),
+                model: "mock".to_string(),
+                words,
+                speaker_hint: None,
+                original_text: None,
+            },
+        }
+    }
+
+    fn standup() -> Vec<CaptionSegment> {
+        vec![
+            spoken(Some("Alice"), "Morning everyone, let's keep this short.", 500.0, 300.0),
+            spoken(
+                Some("Alice"),
+                "The importer is done and the archive job now runs nightly, so storage should stop growing \
+                 as fast as it did last month.",
+                3000.0,
+                350.0,
+            ),
+            spoken(Some("Bob <ops>"), "Great.", 12500.0, 200.0),
+            spoken(None, "Q&A is on Friday.", 14000.0, 400.0),
+        ]
+    }
+
+    /// Compare with `tests/fixtures/captions/{name}`; run with
+    /// `UPDATE_GOLDEN=1` to write the file instead
+    fn assert_golden(name: &str, actual: &str) {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/captions").join(name);
+        if std::env::var_os("UPDATE_GOLDEN").is_some() {
+            std::fs::write(&path, actual).unwrap();
+            return;
+        }
+        let expected = std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+        assert_eq!(actual, expected, "{} differs; rerun with UPDATE_GOLDEN=1 if the change is intended", name);
+    }
+
+    #[test]
+    fn test_srt_matches_golden_file() {
+        let writer = CaptionWriter::new(CaptionConfig::default());
+        assert_golden("standup.srt", &writer.write(CaptionFormat::Srt, &standup()));
+    }
+
+    #[test]
+    fn test_webvtt_matches_golden_file() {
+        let writer = CaptionWriter::new(CaptionConfig::default());
+        assert_golden("standup.vtt", &writer.write(CaptionFormat::WebVtt, &standup()));
+    }
+
+    #[test]
+    fn test_ttml_matches_golden_file() {
+        let writer = CaptionWriter::new(CaptionConfig::default());
+        assert_golden("standup.ttml", &writer.write(CaptionFormat::Ttml, &standup()));
+    }
+
+    #[test]
+    fn test_cues_respect_line_and_duration_limits_and_offset() {
+        let config = CaptionConfig {
+            max_line_chars: 20,
+            max_lines: 1,
+            max_duration_ms: 2000.0,
+            offset_ms: -1000.0,
+            ..Default::default()
+        };
+        let cues = CaptionWriter::new(config.clone()).cues(&standup(), false);
+
+        assert!(cues.iter().all(|cue| cue.lines.len() == 1 && cue.lines[0].chars().count() <= 20));
+        assert!(cues.iter().all(|cue| cue.end_ms - cue.start_ms <= config.max_duration_ms));
+        // The first cue started at 500ms, so it's cut short at zero
+        assert_eq!((cues[0].start_ms, cues[0].lines[0].as_str()), (0.0, "Morning everyone,"));
+        // "Great." is stretched to the minimum duration
+        let great = cues.iter().find(|cue| cue.lines[0] == "Great.").unwrap();
+        assert_eq!((great.start_ms, great.end_ms), (11500.0, 12500.0));
+    }
+
+    #[test]
+    fn test_invalid_caption_config_is_rejected() {
+        assert!(CaptionConfig { max_lines: 0, ..Default::default() }.validate().is_err());
+        assert!(CaptionConfig { min_duration_ms: 9000.0, ..Default::default() }.validate().is_err());
+        assert!(CaptionConfig::default().validate().is_ok());
+    }
+}
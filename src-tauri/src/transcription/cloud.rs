@@ -0,0 +1,369 @@
+//! Cloud speech-to-text through an OpenAI-compatible API
+//!
+//! Audio is uploaded as 16-bit WAV to `{base_url}/audio/transcriptions` and
+//! the `verbose_json` response is turned back into timeline segments, with
+//! word timings when the API returns them. Only used when the user has opted
+//! into cloud fallback.
+
+use std::time::Duration;
+use reqwest::blocking::{multipart, Client};
+use serde::Deserialize;
+use tracing::debug;
+
+use crate::audio::AudioChunk;
+
+use super::engine::TranscriptionEngine;
+use super::types::{
+    TranscriptSegment, TranscriptionError, TranscriptionOptions, TranscriptionResult, WordTiming,
+};
+
+/// Where and how to reach the cloud API
+#[derive(Debug, Clone, PartialEq)]
+pub struct CloudConfig {
+    /// API root, e.g. "https://api.openai.com/v1"
+    pub base_url: String,
+    pub api_key: String,
+    /// Model requested from the API, e.g. "whisper-1"
+    pub model: String,
+    pub timeout: Duration,
+}
+
+#[derive(Debug, Deserialize)]
+struct VerboseTranscription {
+    #[serde(default)]
+    language: Option<String>,
+    #[serde(default)]
+    text: String,
+    #[serde(default)]
+    segments: Vec<VerboseSegment>,
+    #[serde(default)]
+    words: Vec<VerboseWord>,
+}
+
+#[derive(Debug, Deserialize)]
+struct VerboseSegment {
+    start: f64,
+    end: f64,
+    text: String,
+    #[serde(default)]
+    avg_logprob: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct VerboseWord {
+    word: String,
+    start: f64,
+    end: f64,
+}
+
+/// Transcribes chunks with a remote `/audio/transcriptions` endpoint
+pub struct CloudEngine {
+    client: Client,
+    endpoint: String,
+    api_key: String,
+    model: String,
+    name: String,
+}
+
+impl CloudEngine {
+    pub fn new(config: &CloudConfig) -> TranscriptionResult<Self> {
+        let client = Client::builder()
+            .timeout(config.timeout)
+            .build()
+            .map_err(|e| TranscriptionError::Engine {
+                message: format!("Failed to create HTTP client: {}", e),
+            })?;
+
+        Ok(Self {
+            client,
+            endpoint: format!("{}/audio/transcriptions", config.base_url.trim_end_matches('/')),
+            api_key: config.api_key.clone(),
+            model: config.model.clone(),
+            name: format!("cloud-{}", config.model),
+        })
+    }
+
+    fn request(&self, chunk: &AudioChunk, options: &TranscriptionOptions) -> TranscriptionResult<VerboseTranscription> {
+        let audio = encode_wav(&chunk.buffer.samples, chunk.buffer.sample_rate);
+        let file = multipart::Part::bytes(audio)
+            .file_name("audio.wav")
+            .mime_str("audio/wav")
+            .map_err(|e| TranscriptionError::Internal { message: e.to_string() })?;
+
+        let mut form = multipart::Form::new()
+            .part("file", file)
+            .text("model", self.model.clone())
+            .text("response_format", "verbose_json")
+            .text("timestamp_granularities[]", "segment")
+            .text("timestamp_granularities[]", "word");
+        if let Some(language) = &options.language {
+            form = form.text("language", language.clone());
+        }
+        if let Some(prompt) = options.vocabulary.prompt() {
+            form = form.text("prompt", prompt);
+        }
+
+        let response = self.client
+            .post(&self.endpoint)
+            .bearer_auth(&self.api_key)
+            .multipart(form)
+            .send()
+            .map_err(|e| TranscriptionError::Engine {
+                message: format!("Cloud transcription request failed: {}", e),
+            })?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().unwrap_or_default();
+            return Err(TranscriptionError::Engine {
+                message: format!(
+                    "Cloud transcription returned {}: {}",
+                    status,
+                    body.chars().take(200).collect::<String>()
+                ),
+            });
+        }
+
+        response.json().map_err(|e| TranscriptionError::Engine {
+            message: format!("Unexpected cloud transcription response: {}", e),
+        })
+    }
+}
+
+impl TranscriptionEngine for CloudEngine {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn supports_prompt(&self) -> bool {
+        true
+    }
+
+    fn transcribe(
+        &self,
+        chunk: &AudioChunk,
+        options: &TranscriptionOptions,
+    ) -> TranscriptionResult<Vec<TranscriptSegment>> {
+        let response = self.request(chunk, options)?;
+        let language = options.language.clone()
+            .or_else(|| response.language.as_deref().map(language_code))
+            .unwrap_or_default();
+        debug!("Cloud returned {} segments for chunk {}", response.segments.len(), chunk.index);
+
+        // Responses without segments still carry the text for the whole clip
+        if response.segments.is_empty() {
+            let text = response.text.trim();
+            return Ok((!text.is_empty()).then(|| TranscriptSegment {
+                text: text.to_string(),
+                start_ms: chunk.start_ms,
+                end_ms: chunk.start_ms + chunk.buffer.duration_ms(),
+                confidence: 1.0,
+                language: language.clone(),
+                model: self.name.clone(),
+                words: timeline_words(chunk, &response.words, 1.0),
+                speaker_hint: None,
+                original_text: None,
+            }).into_iter().collect());
+        }
+
+        // Words belong to the segment they start in
+        let mut words = response.words.as_slice();
+        let count = response.segments.len();
+        Ok(response.segments
+            .iter()
+            .enumerate()
+            .map(|(i, segment)| {
+                let confidence = segment.avg_logprob.map_or(1.0, |p| p.exp().clamp(0.0, 1.0) as f32);
+                let taken = if i + 1 == count {
+                    words.len()
+                } else {
+                    words.iter().take_while(|word| word.start < segment.end).count()
+                };
+                let (own, rest) = words.split_at(taken);
+                words = rest;
+
+                TranscriptSegment {
+                    text: segment.text.trim().to_string(),
+                    start_ms: chunk.start_ms + segment.start * 1000.0,
+                    end_ms: chunk.start_ms + segment.end.max(segment.start) * 1000.0,
+                    confidence,
+                    language: language.clone(),
+                    model: self.name.clone(),
+                    words: timeline_words(chunk, own, confidence),
+                    speaker_hint: None,
+                    original_text: None,
+                }
+            })
+            .collect())
+    }
+}
+
+/// Response words placed on the meeting timeline
+///
+/// The API doesn't score words, so each takes its segment's confidence.
+fn timeline_words(chunk: &AudioChunk, words: &[VerboseWord], confidence: f32) -> Vec<WordTiming> {
+    words
+        .iter()
+        .filter(|word| !word.word.trim().is_empty())
+        .map(|word| WordTiming {
+            text: word.word.trim().to_string(),
+            start_ms: chunk.start_ms + word.start * 1000.0,
+            end_ms: chunk.start_ms + word.end.max(word.start) * 1000.0,
+            confidence,
+        })
+        .collect()
+}
+
+/// ISO 639-1 code for a language name as reported by the API
+fn language_code(name: &str) -> String {
+    let name = name.trim().to_lowercase();
+    let code = match name.as_str() {
+        "english" => "en",
+        "german" => "de",
+        "french" => "fr",
+        "spanish" => "es",
+        "italian" => "it",
+        "portuguese" => "pt",
+        "dutch" => "nl",
+        "polish" => "pl",
+        "russian" => "ru",
+        "ukrainian" => "uk",
+        "turkish" => "tr",
+        "arabic" => "ar",
+        "hindi" => "hi",
+        "chinese" => "zh",
+        "japanese" => "ja",
+        "korean" => "ko",
+        "swedish" => "sv",
+        _ => return name,
+    };
+    code.to_string()
+}
+
+/// Encode mono samples as a 16-bit PCM WAV file
+fn encode_wav(samples: &[f32], sample_rate: u32) -> Vec<u8> {
+    let data_len = (samples.len() * 2) as u32;
+    let mut wav = Vec::with_capacity(44 + data_len as usize);
+
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+    wav.extend_from_slice(b"WAVEfmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes());
+    wav.extend_from_slice(&1u16.to_le_bytes()); // PCM
+    wav.extend_from_slice(&1u16.to_le_bytes()); // mono
+    wav.extend_from_slice(&sample_rate.to_le_bytes());
+    wav.extend_from_slice(&(sample_rate * 2).to_le_bytes());
+    wav.extend_from_slice(&2u16.to_le_bytes());
+    wav.extend_from_slice(&16u16.to_le_bytes());
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&data_len.to_le_bytes());
+    for sample in samples {
+        let value = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+        wav.extend_from_slice(&value.to_le_bytes());
+    }
+
+    wav
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::AudioBuffer;
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn config(server: &MockServer) -> CloudConfig {
+        CloudConfig {
+            base_url: format!("{}/v1", server.uri()),
+            api_key: "test-key".to_string(),
+            model: "whisper-1".to_string(),
+            timeout: Duration::from_secs(5),
+        }
+    }
+
+    fn chunk() -> AudioChunk {
+        AudioChunk { index: 3, start_ms: 5000.0, buffer: AudioBuffer::new(vec![0.1; 32000], 16000, 1) }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_segments_are_placed_on_the_timeline() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/audio/transcriptions"))
+            .and(header("authorization", "Bearer test-key"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "language": "german",
+                "text": "Guten Morgen zusammen",
+                "segments": [
+                    { "start": 0.0, "end": 0.8, "text": " Guten Morgen", "avg_logprob": -0.1 },
+                    { "start": 0.8, "end": 1.6, "text": " zusammen", "avg_logprob": -0.4 }
+                ],
+                "words": [
+                    { "word": "Guten", "start": 0.0, "end": 0.4 },
+                    { "word": "Morgen", "start": 0.4, "end": 0.8 },
+                    { "word": "zusammen", "start": 0.9, "end": 1.5 }
+                ]
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let config = config(&server);
+        let segments = tokio::task::spawn_blocking(move || {
+            CloudEngine::new(&config)?.transcribe(&chunk(), &TranscriptionOptions::default())
+        })
+        .await
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(segments.len(), 2);
+        assert_eq!(segments[0].text, "Guten Morgen");
+        assert_eq!(segments[0].start_ms, 5000.0);
+        assert_eq!(segments[1].end_ms, 6600.0);
+        assert_eq!(segments[1].language, "de");
+        assert_eq!(segments[1].model, "cloud-whisper-1");
+        assert!(segments[0].confidence > segments[1].confidence);
+
+        // Word timings are split between the segments they were spoken in
+        let words: Vec<_> = segments[0].words.iter().map(|w| (w.text.as_str(), w.start_ms)).collect();
+        assert_eq!(words, vec![("Guten", 5000.0), ("Morgen", 5400.0)]);
+        assert_eq!(segments[1].words.len(), 1);
+        assert_eq!(segments[1].words[0].end_ms, 6500.0);
+        assert_eq!(segments[1].words[0].confidence, segments[1].confidence);
+
+        // The upload is a WAV file of the chunk
+        let requests = server.received_requests().await.unwrap();
+        let body = String::from_utf8_lossy(&requests[0].body);
+        assert!(body.contains("RIFF") && body.contains("verbose_json") && body.contains("whisper-1"));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_api_errors_are_reported() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(429).set_body_string("rate limited"))
+            .mount(&server)
+            .await;
+
+        let config = config(&server);
+        let result = tokio::task::spawn_blocking(move || {
+            CloudEngine::new(&config)?.transcribe(&chunk(), &TranscriptionOptions::default())
+        })
+        .await
+        .unwrap();
+
+        match result {
+            Err(TranscriptionError::Engine { message }) => assert!(message.contains("429"), "{}", message),
+            other => panic!("expected an engine error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_wav_header_describes_samples() {
+        let wav = encode_wav(&[0.0, 1.0, -1.0], 16000);
+        assert_eq!(wav.len(), 44 + 6);
+        assert_eq!(&wav[0..4], b"RIFF");
+        assert_eq!(u32::from_le_bytes(wav[24..28].try_into().unwrap()), 16000);
+        assert_eq!(i16::from_le_bytes([wav[46], wav[47]]), i16::MAX);
+    }
+}
@@ -0,0 +1,130 @@
+//! Grouping a meeting's segment embeddings into speakers
+//!
+//! Agglomerative clustering on cosine similarity: every embedding starts as
+//! its own cluster and the two closest clusters, compared by their weighted
+//! centroids, are merged until no pair is similar enough. A speaker limit
+//! keeps merging past the threshold until it is met.
+
+use super::embedding::SpeakerEmbedding;
+
+/// A cluster's running centroid
+struct Cluster {
+    sum: Vec<f64>,
+    weight: f64,
+    centroid: SpeakerEmbedding,
+}
+
+impl Cluster {
+    fn new(embedding: &SpeakerEmbedding, weight: f64) -> Self {
+        let weight = weight.max(f64::EPSILON);
+        Self {
+            sum: embedding.values().iter().map(|&v| v as f64 * weight).collect(),
+            weight,
+            centroid: embedding.clone(),
+        }
+    }
+
+    fn absorb(&mut self, other: Cluster) {
+        for (total, value) in self.sum.iter_mut().zip(other.sum) {
+            *total += value;
+        }
+        self.weight += other.weight;
+        self.centroid = SpeakerEmbedding::new(self.sum.iter().map(|&v| v as f32).collect());
+    }
+}
+
+/// Cluster label for each `(embedding, weight)`, numbered from 0 in order of
+/// first appearance
+///
+/// Weights are typically speech duration, so long turns shape a centroid
+/// more than short interjections. `max_clusters` of 0 means no limit.
+pub fn cluster_embeddings(items: &[(SpeakerEmbedding, f64)], threshold: f32, max_clusters: usize) -> Vec<usize> {
+    let n = items.len();
+    let mut clusters: Vec<Option<Cluster>> = items.iter().map(|(e, w)| Some(Cluster::new(e, *w))).collect();
+    let mut owner: Vec<usize> = (0..n).collect();
+    let mut live = n;
+
+    // Similarity of every live pair, upper triangle only
+    let mut similarity = vec![f32::NEG_INFINITY; n * n];
+    for i in 0..n {
+        for j in i + 1..n {
+            similarity[i * n + j] = items[i].0.similarity(&items[j].0);
+        }
+    }
+
+    while live > 1 {
+        let mut best: Option<(usize, usize, f32)> = None;
+        for i in (0..n).filter(|&i| clusters[i].is_some()) {
+            for j in (i + 1..n).filter(|&j| clusters[j].is_some()) {
+                let s = similarity[i * n + j];
+                if best.is_none_or(|(_, _, b)| s > b) {
+                    best = Some((i, j, s));
+                }
+            }
+        }
+
+        let Some((keep, gone, s)) = best else { break };
+        let over_limit = max_clusters > 0 && live > max_clusters;
+        if s < threshold && !over_limit {
+            break;
+        }
+
+        let absorbed = clusters[gone].take().expect("live cluster");
+        let kept = clusters[keep].as_mut().expect("live cluster");
+        kept.absorb(absorbed);
+        for o in owner.iter_mut().filter(|o| **o == gone) {
+            *o = keep;
+        }
+        live -= 1;
+
+        let centroid = kept.centroid.clone();
+        for other in (0..n).filter(|&o| o != keep) {
+            if let Some(cluster) = &clusters[other] {
+                let (a, b) = if other < keep { (other, keep) } else { (keep, other) };
+                similarity[a * n + b] = centroid.similarity(&cluster.centroid);
+            }
+        }
+    }
+
+    // Renumber by first appearance
+    let mut numbering: Vec<Option<usize>> = vec![None; n];
+    let mut next = 0;
+    owner
+        .into_iter()
+        .map(|o| {
+            *numbering[o].get_or_insert_with(|| {
+                next += 1;
+                next - 1
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(values: &[f32]) -> (SpeakerEmbedding, f64) {
+        (SpeakerEmbedding::new(values.to_vec()), 1.0)
+    }
+
+    #[test]
+    fn test_similar_embeddings_share_a_label() {
+        let items = vec![
+            item(&[1.0, 0.05, 0.0]),
+            item(&[0.0, 1.0, 0.1]),
+            item(&[0.95, 0.1, 0.0]),
+            item(&[0.05, 0.9, 0.0]),
+            item(&[0.0, 0.0, 1.0]),
+        ];
+        assert_eq!(cluster_embeddings(&items, 0.9, 0), vec![0, 1, 0, 1, 2]);
+    }
+
+    #[test]
+    fn test_speaker_limit_forces_merges() {
+        let items = vec![item(&[1.0, 0.0, 0.0]), item(&[0.0, 1.0, 0.0]), item(&[0.0, 0.0, 1.0])];
+        let labels = cluster_embeddings(&items, 0.9, 2);
+        assert_eq!(labels.iter().max(), Some(&1));
+        assert_eq!(cluster_embeddings(&[], 0.9, 0), Vec::<usize>::new());
+    }
+}
@@ -0,0 +1,348 @@
+//! Speaker embeddings from log-mel statistics
+//!
+//! Audio is cut into ~32 ms frames and each voiced frame is reduced to its
+//! log-mel spectrum, with the frame's mean level removed so loudness and
+//! microphone gain don't matter. A segment's embedding is the mean and
+//! spread of those spectra, scaled to unit length: it captures the shape of
+//! a voice (pitch harmonics, formants) rather than what was said.
+
+use std::f32::consts::PI;
+use serde::{Deserialize, Serialize};
+
+use crate::audio::analysis::fft_in_place;
+use crate::audio::AudioChunk;
+
+/// Mel bands per frame
+const MEL_BANDS: usize = 24;
+
+/// Frequency range the mel bands cover
+const MIN_HZ: f32 = 80.0;
+const MAX_HZ: f32 = 7600.0;
+
+/// Frame length in seconds, rounded up to a power of two samples
+const FRAME_SECONDS: f32 = 0.032;
+
+/// Frames quieter than this carry no voice
+const FRAME_SILENCE_RMS: f32 = 0.005;
+
+/// Voiced frames a segment needs for an embedding (about a quarter second)
+const MIN_VOICED_FRAMES: usize = 8;
+
+/// Floor added before taking logs
+const LOG_FLOOR: f32 = 1e-10;
+
+/// Values in an embedding
+pub const EMBEDDING_DIM: usize = MEL_BANDS * 2;
+
+/// Unit-length summary of a voice
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SpeakerEmbedding(Vec<f32>);
+
+impl SpeakerEmbedding {
+    /// Scale `values` to unit length; all-zero input stays zero
+    pub fn new(mut values: Vec<f32>) -> Self {
+        let norm = values.iter().map(|v| v * v).sum::<f32>().sqrt();
+        if norm > f32::EPSILON {
+            values.iter_mut().for_each(|v| *v /= norm);
+        }
+        Self(values)
+    }
+
+    pub fn values(&self) -> &[f32] {
+        &self.0
+    }
+
+    /// Cosine similarity, from -1 (opposite) to 1 (same voice)
+    pub fn similarity(&self, other: &SpeakerEmbedding) -> f32 {
+        self.0.iter().zip(&other.0).map(|(a, b)| a * b).sum()
+    }
+
+    /// Weighted mean direction of `embeddings`
+    pub fn mean<'a>(embeddings: impl IntoIterator<Item = (&'a SpeakerEmbedding, f64)>) -> Option<Self> {
+        let mut sum: Vec<f64> = Vec::new();
+        for (embedding, weight) in embeddings {
+            if sum.is_empty() {
+                sum = vec![0.0; embedding.0.len()];
+            }
+            for (total, value) in sum.iter_mut().zip(&embedding.0) {
+                *total += *value as f64 * weight;
+            }
+        }
+        (!sum.is_empty()).then(|| Self::new(sum.into_iter().map(|v| v as f32).collect()))
+    }
+
+    /// Little-endian `f32`s, as stored in `speakers.voice_fingerprint`
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.0.iter().flat_map(|v| v.to_le_bytes()).collect()
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.is_empty() || !bytes.len().is_multiple_of(4) {
+            return None;
+        }
+        Some(Self(
+            bytes
+                .chunks_exact(4)
+                .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                .collect(),
+        ))
+    }
+}
+
+/// Running log-mel statistics for one segment
+#[derive(Debug, Clone)]
+struct SpanStats {
+    start_ms: f64,
+    end_ms: f64,
+    frames: usize,
+    sum: [f64; MEL_BANDS],
+    sum_sq: [f64; MEL_BANDS],
+}
+
+/// Accumulates embeddings for a set of timeline spans as audio streams past
+///
+/// Chunks must arrive in timeline order; frames are carried across chunk
+/// boundaries, and a gap in the timeline starts framing afresh.
+pub struct SegmentEmbedder {
+    sample_rate: u32,
+    frame_len: usize,
+    /// Per band, the FFT bins it covers and their weights
+    filterbank: Vec<Vec<(usize, f32)>>,
+    window: Vec<f32>,
+    pending: Vec<f32>,
+    pending_start_ms: f64,
+    spans: Vec<SpanStats>,
+    /// Spans before this one have ended
+    first_open: usize,
+    fft_re: Vec<f32>,
+    fft_im: Vec<f32>,
+}
+
+impl SegmentEmbedder {
+    /// Embed the spans `(start_ms, end_ms)`, given in start order, from audio
+    /// at `sample_rate`
+    pub fn new(spans: &[(f64, f64)], sample_rate: u32) -> Self {
+        let sample_rate = sample_rate.max(1);
+        let frame_len = ((sample_rate as f32 * FRAME_SECONDS) as usize).next_power_of_two().max(64);
+
+        Self {
+            sample_rate,
+            frame_len,
+            filterbank: mel_filterbank(frame_len, sample_rate),
+            window: (0..frame_len)
+                .map(|i| 0.5 - 0.5 * (2.0 * PI * i as f32 / (frame_len - 1) as f32).cos())
+                .collect(),
+            pending: Vec::with_capacity(frame_len),
+            pending_start_ms: 0.0,
+            spans: spans
+                .iter()
+                .map(|&(start_ms, end_ms)| SpanStats {
+                    start_ms,
+                    end_ms,
+                    frames: 0,
+                    sum: [0.0; MEL_BANDS],
+                    sum_sq: [0.0; MEL_BANDS],
+                })
+                .collect(),
+            first_open: 0,
+            fft_re: vec![0.0; frame_len],
+            fft_im: vec![0.0; frame_len],
+        }
+    }
+
+    /// Feed the next chunk of the timeline; multi-channel audio is averaged
+    pub fn push(&mut self, chunk: &AudioChunk) {
+        let channels = chunk.buffer.channels.max(1) as usize;
+        let expected_ms = self.pending_start_ms + self.samples_to_ms(self.pending.len());
+        if self.pending.is_empty() || (chunk.start_ms - expected_ms).abs() > self.samples_to_ms(1) {
+            self.pending.clear();
+            self.pending_start_ms = chunk.start_ms;
+        }
+
+        for frame in chunk.buffer.samples.chunks_exact(channels) {
+            self.pending.push(frame.iter().sum::<f32>() / channels as f32);
+            if self.pending.len() == self.frame_len {
+                self.process_frame();
+                self.pending.clear();
+                self.pending_start_ms += self.samples_to_ms(self.frame_len);
+            }
+        }
+    }
+
+    /// Embeddings in span order; `None` where a span had too little voice
+    pub fn finish(self) -> Vec<Option<SpeakerEmbedding>> {
+        self.spans
+            .into_iter()
+            .map(|span| {
+                if span.frames < MIN_VOICED_FRAMES {
+                    return None;
+                }
+                let n = span.frames as f64;
+                let mean = span.sum.map(|sum| sum / n);
+                let spread = std::array::from_fn::<f64, MEL_BANDS, _>(|b| {
+                    (span.sum_sq[b] / n - mean[b] * mean[b]).max(0.0).sqrt()
+                });
+                Some(SpeakerEmbedding::new(
+                    mean.iter().chain(&spread).map(|&v| v as f32).collect(),
+                ))
+            })
+            .collect()
+    }
+
+    fn samples_to_ms(&self, samples: usize) -> f64 {
+        samples as f64 / self.sample_rate as f64 * 1000.0
+    }
+
+    fn process_frame(&mut self) {
+        let center_ms = self.pending_start_ms + self.samples_to_ms(self.frame_len / 2);
+        while self.first_open < self.spans.len() && self.spans[self.first_open].end_ms <= center_ms {
+            self.first_open += 1;
+        }
+        let open = self.spans[self.first_open..]
+            .iter()
+            .take_while(|span| span.start_ms <= center_ms)
+            .any(|span| center_ms < span.end_ms);
+        if !open {
+            return;
+        }
+
+        let power = self.pending.iter().map(|s| s * s).sum::<f32>() / self.frame_len as f32;
+        if power.sqrt() < FRAME_SILENCE_RMS {
+            return;
+        }
+
+        for (i, sample) in self.pending.iter().enumerate() {
+            self.fft_re[i] = sample * self.window[i];
+            self.fft_im[i] = 0.0;
+        }
+        fft_in_place(&mut self.fft_re, &mut self.fft_im);
+
+        let mut bands = [0.0f32; MEL_BANDS];
+        for (band, weights) in bands.iter_mut().zip(&self.filterbank) {
+            let energy: f32 = weights
+                .iter()
+                .map(|&(bin, weight)| weight * (self.fft_re[bin].powi(2) + self.fft_im[bin].powi(2)))
+                .sum();
+            *band = (energy + LOG_FLOOR).ln();
+        }
+        let level = bands.iter().sum::<f32>() / MEL_BANDS as f32;
+
+        for span in self.spans[self.first_open..].iter_mut().take_while(|span| span.start_ms <= center_ms) {
+            if center_ms >= span.end_ms {
+                continue;
+            }
+            span.frames += 1;
+            for (b, band) in bands.iter().enumerate() {
+                let value = (band - level) as f64;
+                span.sum[b] += value;
+                span.sum_sq[b] += value * value;
+            }
+        }
+    }
+}
+
+fn hz_to_mel(hz: f32) -> f32 {
+    2595.0 * (1.0 + hz / 700.0).log10()
+}
+
+fn mel_to_hz(mel: f32) -> f32 {
+    700.0 * (10f32.powf(mel / 2595.0) - 1.0)
+}
+
+/// Triangular mel filters over the bins of a `frame_len` FFT
+fn mel_filterbank(frame_len: usize, sample_rate: u32) -> Vec<Vec<(usize, f32)>> {
+    let nyquist = sample_rate as f32 / 2.0;
+    let (low, high) = (hz_to_mel(MIN_HZ.min(nyquist)), hz_to_mel(MAX_HZ.min(nyquist)));
+    let edges: Vec<f32> = (0..MEL_BANDS + 2)
+        .map(|i| mel_to_hz(low + (high - low) * i as f32 / (MEL_BANDS + 1) as f32))
+        .collect();
+    let bin_hz = sample_rate as f32 / frame_len as f32;
+
+    (0..MEL_BANDS)
+        .map(|band| {
+            let (left, center, right) = (edges[band], edges[band + 1], edges[band + 2]);
+            let weights: Vec<(usize, f32)> = (1..frame_len / 2)
+                .filter_map(|bin| {
+                    let hz = bin as f32 * bin_hz;
+                    let weight = if hz <= left || hz >= right {
+                        0.0
+                    } else if hz <= center {
+                        (hz - left) / (center - left)
+                    } else {
+                        (right - hz) / (right - center)
+                    };
+                    (weight > 0.0).then_some((bin, weight))
+                })
+                .collect();
+
+            // Narrow low bands may fall between bins; use the nearest one
+            if weights.is_empty() {
+                vec![(((center / bin_hz).round() as usize).clamp(1, frame_len / 2 - 1), 1.0)]
+            } else {
+                weights
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::AudioBuffer;
+    use crate::transcription::diarization::test_voices::{Voice, VOICES};
+
+    fn embed(voice: &Voice, seed: u64, gain: f32) -> SpeakerEmbedding {
+        let samples: Vec<f32> = voice.speak(2.0, 16000, seed).into_iter().map(|s| s * gain).collect();
+        let chunk = AudioChunk { index: 0, start_ms: 0.0, buffer: AudioBuffer::new(samples, 16000, 1) };
+        let mut embedder = SegmentEmbedder::new(&[(0.0, 2000.0)], 16000);
+        embedder.push(&chunk);
+        embedder.finish().remove(0).unwrap()
+    }
+
+    #[test]
+    fn test_same_voice_is_closer_than_another_voice() {
+        let first = embed(&VOICES[0], 1, 1.0);
+        let again = embed(&VOICES[0], 2, 0.3);
+        let other = embed(&VOICES[1], 3, 1.0);
+
+        assert!((first.values().iter().map(|v| v * v).sum::<f32>() - 1.0).abs() < 1e-4);
+        assert!(first.similarity(&again) > first.similarity(&other) + 0.1);
+    }
+
+    #[test]
+    fn test_spans_straddling_chunks_match_a_single_chunk() {
+        let samples = VOICES[2].speak(3.0, 16000, 7);
+        let whole = AudioChunk { index: 0, start_ms: 0.0, buffer: AudioBuffer::new(samples.clone(), 16000, 1) };
+        let spans = [(500.0, 2500.0)];
+
+        let mut at_once = SegmentEmbedder::new(&spans, 16000);
+        at_once.push(&whole);
+
+        let mut split = SegmentEmbedder::new(&spans, 16000);
+        for (i, part) in samples.chunks(7000).enumerate() {
+            split.push(&AudioChunk {
+                index: i,
+                start_ms: i as f64 * 7000.0 / 16.0,
+                buffer: AudioBuffer::new(part.to_vec(), 16000, 1),
+            });
+        }
+
+        assert_eq!(at_once.finish(), split.finish());
+    }
+
+    #[test]
+    fn test_silent_span_has_no_embedding() {
+        let chunk = AudioChunk { index: 0, start_ms: 0.0, buffer: AudioBuffer::new(vec![0.0; 16000], 16000, 1) };
+        let mut embedder = SegmentEmbedder::new(&[(0.0, 1000.0)], 16000);
+        embedder.push(&chunk);
+        assert_eq!(embedder.finish(), vec![None]);
+    }
+
+    #[test]
+    fn test_embedding_bytes_round_trip() {
+        let embedding = SpeakerEmbedding::new(vec![3.0, 4.0]);
+        assert_eq!(embedding.values(), &[0.6, 0.8]);
+        assert_eq!(SpeakerEmbedding::from_bytes(&embedding.to_bytes()), Some(embedding));
+        assert_eq!(SpeakerEmbedding::from_bytes(&[1, 2, 3]), None);
+    }
+}
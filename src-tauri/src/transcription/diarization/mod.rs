@@ -0,0 +1,240 @@
+//! Speaker diarization
+//!
+//! Every transcript segment gets a speaker embedding from its audio. The
+//! embeddings of one meeting are clustered into speakers, and each speaker
+//! is compared with the voice profiles enrolled in earlier meetings. Naming
+//! a speaker enrolls (or refines) a profile, so the voice is recognised the
+//! next time it is heard. Segments with too little voice for an embedding
+//! are left without a speaker.
+
+pub mod clustering;
+pub mod embedding;
+pub mod profiles;
+#[cfg(test)]
+pub(crate) mod test_voices;
+
+pub use clustering::cluster_embeddings;
+pub use embedding::{SegmentEmbedder, SpeakerEmbedding, EMBEDDING_DIM};
+pub use profiles::{match_profiles, ProfileMatch, VoiceProfile};
+
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::audio::AudioChunk;
+
+use super::types::{TranscriptSegment, TranscriptionError, TranscriptionResult};
+
+/// How readily segments are grouped and speakers recognised
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DiarizationConfig {
+    /// Clusters at least this similar are the same speaker
+    pub cluster_threshold: f32,
+    /// A speaker at least this similar to a profile is that person
+    pub match_threshold: f32,
+    /// Most speakers expected in a meeting; 0 for no limit
+    pub max_speakers: usize,
+}
+
+impl Default for DiarizationConfig {
+    fn default() -> Self {
+        Self {
+            cluster_threshold: 0.88,
+            match_threshold: 0.9,
+            max_speakers: 0,
+        }
+    }
+}
+
+impl DiarizationConfig {
+    /// Check the settings are usable
+    pub fn validate(&self) -> TranscriptionResult<()> {
+        for threshold in [self.cluster_threshold, self.match_threshold] {
+            if !(-1.0..=1.0).contains(&threshold) {
+                return Err(TranscriptionError::InvalidConfig {
+                    details: format!("Speaker similarity threshold {} must be between -1 and 1", threshold),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// One voice heard in a meeting
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SpeakerCluster {
+    /// The recognised person's name, or "Speaker N"
+    pub label: String,
+    pub embedding: SpeakerEmbedding,
+    pub speech_ms: f64,
+    pub segments: usize,
+    /// Enrolled profile this voice matched
+    pub profile: Option<ProfileMatch>,
+}
+
+/// Who spoke each segment
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Diarization {
+    pub speakers: Vec<SpeakerCluster>,
+    /// Index into `speakers` for each input segment
+    pub assignments: Vec<Option<usize>>,
+}
+
+/// Attributes a meeting's segments to speakers
+#[derive(Debug, Clone, Default)]
+pub struct Diarizer {
+    config: DiarizationConfig,
+}
+
+impl Diarizer {
+    pub fn new(config: DiarizationConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn config(&self) -> &DiarizationConfig {
+        &self.config
+    }
+
+    /// An embedder for `segments`, to be fed the meeting's audio in order
+    pub fn embedder(&self, segments: &[TranscriptSegment], sample_rate: u32) -> SegmentEmbedder {
+        let spans: Vec<(f64, f64)> = segments.iter().map(|s| (s.start_ms, s.end_ms)).collect();
+        SegmentEmbedder::new(&spans, sample_rate)
+    }
+
+    /// Diarize segments whose audio is in `chunks`
+    pub fn diarize_chunks<'a>(
+        &self,
+        chunks: impl IntoIterator<Item = &'a AudioChunk>,
+        segments: &[TranscriptSegment],
+        profiles: &[VoiceProfile],
+    ) -> Diarization {
+        let mut chunks = chunks.into_iter().peekable();
+        let sample_rate = chunks.peek().map_or(16000, |chunk| chunk.buffer.sample_rate);
+        let mut embedder = self.embedder(segments, sample_rate);
+        for chunk in chunks {
+            embedder.push(chunk);
+        }
+        self.diarize(segments, embedder.finish(), profiles)
+    }
+
+    /// Cluster the segments' embeddings and match the clusters to `profiles`
+    ///
+    /// `segments` must be in start order, with one embedding (or `None`) each.
+    pub fn diarize(
+        &self,
+        segments: &[TranscriptSegment],
+        embeddings: Vec<Option<SpeakerEmbedding>>,
+        profiles: &[VoiceProfile],
+    ) -> Diarization {
+        let embedded: Vec<(usize, SpeakerEmbedding, f64)> = embeddings
+            .into_iter()
+            .enumerate()
+            .filter_map(|(i, e)| e.map(|e| (i, e, segments[i].duration_ms())))
+            .collect();
+        let items: Vec<(SpeakerEmbedding, f64)> = embedded.iter().map(|(_, e, w)| (e.clone(), *w)).collect();
+        let labels = cluster_embeddings(&items, self.config.cluster_threshold, self.config.max_speakers);
+        let count = labels.iter().max().map_or(0, |&max| max + 1);
+
+        let mut assignments = vec![None; segments.len()];
+        let mut speakers = Vec::with_capacity(count);
+        for cluster in 0..count {
+            let members: Vec<&(usize, SpeakerEmbedding, f64)> = embedded
+                .iter()
+                .zip(&labels)
+                .filter(|(_, &label)| label == cluster)
+                .map(|(member, _)| member)
+                .collect();
+            for (segment, _, _) in &members {
+                assignments[*segment] = Some(cluster);
+            }
+            let embedding = SpeakerEmbedding::mean(members.iter().map(|(_, e, w)| (e, *w)))
+                .expect("clusters have members");
+
+            speakers.push(SpeakerCluster {
+                label: format!("Speaker {}", cluster + 1),
+                embedding,
+                speech_ms: members.iter().map(|(_, _, w)| w).sum(),
+                segments: members.len(),
+                profile: None,
+            });
+        }
+
+        let centroids: Vec<SpeakerEmbedding> = speakers.iter().map(|s| s.embedding.clone()).collect();
+        for (speaker, matched) in speakers.iter_mut().zip(match_profiles(&centroids, profiles, self.config.match_threshold)) {
+            if let Some(matched) = matched {
+                speaker.label = matched.name.clone();
+                speaker.profile = Some(matched);
+            }
+        }
+
+        info!(
+            "Diarized {} segments into {} speakers, {} recognised",
+            segments.len(),
+            speakers.len(),
+            speakers.iter().filter(|s| s.profile.is_some()).count()
+        );
+        Diarization { speakers, assignments }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::AudioBuffer;
+    use super::test_voices::conversation;
+
+    /// Speaker turns of a three-person meeting
+    const TURNS: &[(usize, f32)] = &[(0, 2.5), (1, 2.0), (0, 1.5), (2, 3.0), (1, 1.5), (2, 2.0), (0, 2.0), (1, 2.5)];
+
+    fn diarize(seed: u64, profiles: &[VoiceProfile]) -> Diarization {
+        let (samples, segments) = conversation(TURNS, 16000, seed);
+        let chunks: Vec<AudioChunk> = samples
+            .chunks(16000 * 5)
+            .enumerate()
+            .map(|(i, part)| AudioChunk {
+                index: i,
+                start_ms: i as f64 * 5000.0,
+                buffer: AudioBuffer::new(part.to_vec(), 16000, 1),
+            })
+            .collect();
+        Diarizer::default().diarize_chunks(&chunks, &segments, profiles)
+    }
+
+    #[test]
+    fn test_turns_are_attributed_to_their_voices() {
+        let diarization = diarize(1, &[]);
+
+        assert_eq!(diarization.speakers.len(), 3);
+        let labels: Vec<usize> = diarization.assignments.iter().map(|a| a.unwrap()).collect();
+        assert_eq!(labels, vec![0, 1, 0, 2, 1, 2, 0, 1]);
+        assert_eq!(diarization.speakers[2].label, "Speaker 3");
+        assert_eq!(diarization.speakers[0].segments, 3);
+    }
+
+    #[test]
+    fn test_named_voices_are_recognised_in_the_next_meeting() {
+        let first = diarize(1, &[]);
+        let profiles: Vec<VoiceProfile> = ["Ana", "Bruno"]
+            .iter()
+            .enumerate()
+            .map(|(i, name)| VoiceProfile {
+                speaker_id: i as i64 + 1,
+                name: name.to_string(),
+                embedding: first.speakers[i].embedding.clone(),
+                samples: 1,
+            })
+            .collect();
+
+        let next = diarize(100, &profiles);
+        let names: Vec<&str> = next.speakers.iter().map(|s| s.label.as_str()).collect();
+        assert_eq!(names, vec!["Ana", "Bruno", "Speaker 3"]);
+        assert!(next.speakers[0].profile.as_ref().unwrap().similarity >= Diarizer::default().config().match_threshold);
+    }
+
+    #[test]
+    fn test_invalid_thresholds_are_rejected() {
+        let config = DiarizationConfig { match_threshold: 1.5, ..Default::default() };
+        assert!(config.validate().is_err());
+        assert!(DiarizationConfig::default().validate().is_ok());
+    }
+}
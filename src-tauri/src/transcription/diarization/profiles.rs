@@ -0,0 +1,104 @@
+//! Enrolled voice profiles and matching meeting speakers against them
+
+use serde::{Deserialize, Serialize};
+
+use super::embedding::SpeakerEmbedding;
+
+/// A named person's voice, learned from the meetings they were named in
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VoiceProfile {
+    pub speaker_id: i64,
+    pub name: String,
+    pub embedding: SpeakerEmbedding,
+    /// Meeting speakers averaged into the embedding
+    pub samples: u32,
+}
+
+impl VoiceProfile {
+    /// Fold another recording of this voice into the profile
+    pub fn absorb(&mut self, embedding: &SpeakerEmbedding) {
+        let previous = self.samples.max(1) as f64;
+        if let Some(mean) = SpeakerEmbedding::mean([(&self.embedding, previous), (embedding, 1.0)]) {
+            self.embedding = mean;
+        }
+        self.samples = self.samples.saturating_add(1);
+    }
+}
+
+/// A meeting speaker recognised as an enrolled person
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProfileMatch {
+    pub speaker_id: i64,
+    pub name: String,
+    pub similarity: f32,
+}
+
+/// Match each embedding to at most one profile, and each profile to at most
+/// one embedding, best pairs first
+///
+/// Two speakers of one meeting are never the same person, so a profile
+/// claimed by a closer speaker isn't offered to the next.
+pub fn match_profiles(embeddings: &[SpeakerEmbedding], profiles: &[VoiceProfile], threshold: f32) -> Vec<Option<ProfileMatch>> {
+    let mut pairs: Vec<(usize, usize, f32)> = embeddings
+        .iter()
+        .enumerate()
+        .flat_map(|(e, embedding)| {
+            profiles
+                .iter()
+                .enumerate()
+                .map(move |(p, profile)| (e, p, embedding.similarity(&profile.embedding)))
+        })
+        .filter(|&(_, _, similarity)| similarity >= threshold)
+        .collect();
+    pairs.sort_by(|a, b| b.2.total_cmp(&a.2));
+
+    let mut matches: Vec<Option<ProfileMatch>> = vec![None; embeddings.len()];
+    let mut claimed = vec![false; profiles.len()];
+    for (e, p, similarity) in pairs {
+        if matches[e].is_some() || claimed[p] {
+            continue;
+        }
+        claimed[p] = true;
+        matches[e] = Some(ProfileMatch {
+            speaker_id: profiles[p].speaker_id,
+            name: profiles[p].name.clone(),
+            similarity,
+        });
+    }
+
+    matches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(id: i64, name: &str, values: &[f32]) -> VoiceProfile {
+        VoiceProfile { speaker_id: id, name: name.to_string(), embedding: SpeakerEmbedding::new(values.to_vec()), samples: 1 }
+    }
+
+    #[test]
+    fn test_each_profile_matches_one_speaker() {
+        let profiles = vec![profile(1, "Ana", &[1.0, 0.0]), profile(2, "Bruno", &[0.0, 1.0])];
+        let embeddings = vec![
+            SpeakerEmbedding::new(vec![0.9, 0.2]),
+            SpeakerEmbedding::new(vec![1.0, 0.05]),
+            SpeakerEmbedding::new(vec![0.1, 1.0]),
+        ];
+
+        let matches = match_profiles(&embeddings, &profiles, 0.8);
+        assert_eq!(matches[0], None);
+        assert_eq!(matches[1].as_ref().map(|m| m.name.as_str()), Some("Ana"));
+        assert_eq!(matches[2].as_ref().map(|m| m.speaker_id), Some(2));
+    }
+
+    #[test]
+    fn test_absorbing_moves_the_profile_towards_the_new_voice() {
+        let mut ana = profile(1, "Ana", &[1.0, 0.0]);
+        let newer = SpeakerEmbedding::new(vec![0.0, 1.0]);
+        ana.absorb(&newer);
+
+        assert_eq!(ana.samples, 2);
+        assert!((ana.embedding.similarity(&newer) - std::f32::consts::FRAC_1_SQRT_2).abs() < 1e-5);
+    }
+}
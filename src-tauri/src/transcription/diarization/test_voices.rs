@@ -0,0 +1,102 @@
+//! Synthetic voices for diarization tests
+//!
+//! Each voice is a train of harmonics on a wavering pitch, shaped by three
+//! formants and chopped into syllable-like bursts. Every utterance shifts the
+//! formants a little, as different vowels would, so no two are identical.
+
+use std::f32::consts::PI;
+
+use crate::transcription::TranscriptSegment;
+
+/// Pitch and vocal-tract resonances of a synthetic speaker
+pub struct Voice {
+    pub f0: f32,
+    /// Formant centres and bandwidths in Hz
+    pub formants: [(f32, f32); 3],
+}
+
+/// Three clearly different speakers
+pub const VOICES: [Voice; 3] = [
+    Voice { f0: 110.0, formants: [(700.0, 130.0), (1220.0, 150.0), (2600.0, 200.0)] },
+    Voice { f0: 215.0, formants: [(400.0, 100.0), (2250.0, 180.0), (3000.0, 250.0)] },
+    Voice { f0: 160.0, formants: [(550.0, 120.0), (900.0, 120.0), (2450.0, 200.0)] },
+];
+
+/// Small deterministic generator so fixtures don't depend on `rand`'s algorithm
+struct Noise(u64);
+
+impl Noise {
+    fn next(&mut self) -> f32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 >> 40) as f32 / (1u64 << 24) as f32 * 2.0 - 1.0
+    }
+}
+
+impl Voice {
+    /// `seconds` of babble at `sample_rate`; `seed` picks the "vowel" and phrasing
+    pub fn speak(&self, seconds: f32, sample_rate: u32, seed: u64) -> Vec<f32> {
+        let mut noise = Noise(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1);
+        let shift = 1.0 + 0.05 * noise.next();
+        let syllable_phase = noise.next() * PI;
+        let rate = sample_rate as f32;
+
+        // Harmonic amplitudes follow the formant envelope
+        let harmonics: Vec<f32> = (1..)
+            .map(|k| k as f32 * self.f0)
+            .take_while(|&hz| hz < rate / 2.0 * 0.9)
+            .map(|hz| {
+                let envelope: f32 = self.formants
+                    .iter()
+                    .map(|&(centre, width)| (-((hz - centre * shift) / width).powi(2)).exp())
+                    .sum();
+                (envelope + 0.02) / (hz / self.f0).sqrt()
+            })
+            .collect();
+        let scale = 0.25 / harmonics.iter().sum::<f32>().max(f32::EPSILON);
+
+        let mut phase = 0.0f32;
+        (0..(seconds * rate) as usize)
+            .map(|i| {
+                let t = i as f32 / rate;
+                let pitch = self.f0 * (1.0 + 0.04 * (2.0 * PI * 0.8 * t + syllable_phase).sin());
+                phase = (phase + 2.0 * PI * pitch / rate) % (2.0 * PI);
+                let voiced: f32 = harmonics
+                    .iter()
+                    .enumerate()
+                    .map(|(k, amplitude)| amplitude * ((k + 1) as f32 * phase).sin())
+                    .sum();
+                let syllables = 0.3 + 0.7 * (2.0 * PI * 3.5 * t + syllable_phase).sin().abs();
+                voiced * scale * syllables * 2.0 + 0.002 * noise.next()
+            })
+            .collect()
+    }
+}
+
+/// A meeting where each `(voice, seconds)` turn follows the last after a
+/// short pause, with a segment per turn
+pub fn conversation(turns: &[(usize, f32)], sample_rate: u32, seed: u64) -> (Vec<f32>, Vec<TranscriptSegment>) {
+    let pause = (0.3 * sample_rate as f32) as usize;
+    let mut samples = Vec::new();
+    let mut segments = Vec::new();
+
+    for (i, &(voice, seconds)) in turns.iter().enumerate() {
+        samples.extend(std::iter::repeat_n(0.0, pause));
+        let start_ms = samples.len() as f64 / sample_rate as f64 * 1000.0;
+        samples.extend(VOICES[voice].speak(seconds, sample_rate, seed + i as u64));
+        segments.push(TranscriptSegment {
+            text: format!("turn {}", i),
+            start_ms,
+            end_ms: samples.len() as f64 / sample_rate as f64 * 1000.0,
+            confidence: 0.9,
+            language: "en".to_string(),
+            model: "mock".to_string(),
+            words: Vec::new(),
+            speaker_hint: None,
+            original_text: None,
+        });
+    }
+
+    (samples, segments)
+}
@@ -0,0 +1,152 @@
+//! The interface speech-to-text backends implement
+
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::info;
+
+use crate::audio::AudioChunk;
+use crate::config::AIConfig;
+
+use super::cloud::{CloudConfig, CloudEngine};
+use super::language::LanguageScore;
+use super::routing::HybridEngine;
+use super::types::{TranscriptSegment, TranscriptionError, TranscriptionOptions, TranscriptionResult};
+
+/// Sample rate chunks are delivered at unless an engine asks otherwise
+pub const DEFAULT_ENGINE_SAMPLE_RATE: u32 = 16000;
+
+/// A speech-to-text backend
+///
+/// `transcribe` is called from a blocking thread and may take as long as the
+/// model needs. Chunks arrive as mono audio at `sample_rate()`; the returned
+/// segments must be on the meeting timeline, i.e. offset by
+/// `chunk.start_ms`, and in order.
+pub trait TranscriptionEngine: Send + Sync {
+    /// Short name recorded as the model used, e.g. "whisper-base"
+    fn name(&self) -> &str;
+
+    /// Sample rate the engine expects
+    fn sample_rate(&self) -> u32 {
+        DEFAULT_ENGINE_SAMPLE_RATE
+    }
+
+    /// A copy of the engine for one meeting
+    ///
+    /// Engines with per-meeting state, such as a cloud budget, return one
+    /// with that state fresh and everything else shared, so meetings
+    /// transcribed side by side don't draw on each other's. The rest return
+    /// `None` and are used as they are.
+    fn new_session(&self) -> Option<Arc<dyn TranscriptionEngine>> {
+        None
+    }
+
+    /// Whether `transcribe` primes the model with `options.vocabulary`
+    ///
+    /// The pipeline corrects the output of engines that don't.
+    fn supports_prompt(&self) -> bool {
+        false
+    }
+
+    /// Score the languages `chunk` may be in, most likely first
+    ///
+    /// Engines that can't identify languages return no scores.
+    fn identify_language(&self, _chunk: &AudioChunk) -> TranscriptionResult<Vec<LanguageScore>> {
+        Ok(Vec::new())
+    }
+
+    /// Transcribe one chunk of audio
+    fn transcribe(
+        &self,
+        chunk: &AudioChunk,
+        options: &TranscriptionOptions,
+    ) -> TranscriptionResult<Vec<TranscriptSegment>>;
+}
+
+/// Load the local engine configured in `config`
+///
+/// The model comes from the model registry, checked against its SHA-256,
+/// unless none is installed there.
+#[cfg(feature = "whisper")]
+pub fn local_engine(config: &AIConfig) -> TranscriptionResult<Arc<dyn TranscriptionEngine>> {
+    let mut whisper = super::whisper::WhisperConfig::from(config);
+    if let Some((model, path)) = super::models::verified_speech_model(config)? {
+        whisper.model_path = path;
+        whisper.model_size = model.variant;
+    }
+    let engine = super::whisper::WhisperEngine::load(&whisper)?;
+    Ok(Arc::new(engine))
+}
+
+/// Load the local engine configured in `config`
+#[cfg(not(feature = "whisper"))]
+pub fn local_engine(config: &AIConfig) -> TranscriptionResult<Arc<dyn TranscriptionEngine>> {
+    Err(super::types::TranscriptionError::ModelUnavailable {
+        model: format!(
+            "{} (built without the `whisper` feature)",
+            config.whisper_model_path.display()
+        ),
+    })
+}
+
+/// `engine` set up for a new meeting, leaving any other meeting's state be
+pub fn session_engine(engine: &Arc<dyn TranscriptionEngine>) -> Arc<dyn TranscriptionEngine> {
+    engine.new_session().unwrap_or_else(|| Arc::clone(engine))
+}
+
+/// Load the engine `config` asks for: the local engine, routed through the
+/// cloud when the user has opted in and provided an API key
+///
+/// Builds without the `whisper` feature have no local engine, so they
+/// transcribe with the cloud API alone, and only if it is set up.
+pub fn configured_engine(config: &AIConfig) -> TranscriptionResult<Arc<dyn TranscriptionEngine>> {
+    let cloud = cloud_engine(config)?;
+    if !cfg!(feature = "whisper") {
+        let cloud = cloud.ok_or_else(|| TranscriptionError::InvalidConfig {
+            details: "this build has no local engine (no `whisper` feature); \
+                      enable cloud fallback and set an API key to transcribe"
+                .to_string(),
+        })?;
+        info!("No local engine in this build, transcribing with {} only", cloud.name());
+        return Ok(Arc::new(cloud));
+    }
+
+    let local = local_engine(config)?;
+    Ok(match cloud {
+        Some(cloud) => Arc::new(HybridEngine::new(local, Arc::new(cloud), config.cloud_routing.clone())),
+        None => local,
+    })
+}
+
+/// The cloud engine, if the user has opted in and provided an API key
+fn cloud_engine(config: &AIConfig) -> TranscriptionResult<Option<CloudEngine>> {
+    let api_key = match (&config.openai_api_key, config.enable_cloud_fallback) {
+        (Some(key), true) if !key.is_empty() => key.clone(),
+        _ => return Ok(None),
+    };
+
+    let routing = &config.cloud_routing;
+    CloudEngine::new(&CloudConfig {
+        base_url: routing.api_base_url.clone(),
+        api_key,
+        model: routing.model.clone(),
+        timeout: Duration::from_secs(routing.timeout_secs),
+    })
+    .map(Some)
+}
+
+#[cfg(all(test, not(feature = "whisper")))]
+mod tests {
+    use super::*;
+    use crate::config::AppConfig;
+
+    #[test]
+    fn test_build_without_whisper_transcribes_in_the_cloud() {
+        let mut config = AppConfig::default().ai;
+        assert!(matches!(configured_engine(&config).err(), Some(TranscriptionError::InvalidConfig { .. })));
+
+        config.enable_cloud_fallback = true;
+        config.openai_api_key = Some("sk-test".to_string());
+        let engine = configured_engine(&config).unwrap();
+        assert_eq!(engine.name(), format!("cloud-{}", config.cloud_routing.model));
+    }
+}
@@ -0,0 +1,778 @@
+//! Making raw transcripts readable
+//!
+//! Engines without punctuation hand back lowercase runs of words, and even
+//! those with it write down every "um" and spell numbers out. The
+//! `TranscriptFormatter` restores sentence punctuation and casing, can drop
+//! fillers, writes numbers, amounts and dates as digits and regroups
+//! segments into sentences. It works on word timings, so formatted segments
+//! stay on the timeline, and each keeps the text it was made from in
+//! `original_text`.
+//!
+//! Sentences end where the engine put a full stop, at a long pause, or where
+//! the speaker or language changes. Casing and punctuation apply to any
+//! language; question detection, "I" and numbers are English only.
+
+use std::ops::Range;
+use serde::{Deserialize, Serialize};
+
+use super::alignment::spread_words;
+use super::types::{TranscriptSegment, TranscriptionError, TranscriptionResult, WordTiming};
+
+/// Which formatting steps run and how sentences are found
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FormattingConfig {
+    /// End sentences with a full stop or question mark where the engine didn't
+    pub punctuation: bool,
+    /// Capitalise the start of each sentence and "I"
+    pub casing: bool,
+    /// Drop hesitations such as "um" and "uh"
+    pub remove_fillers: bool,
+    /// Words dropped as hesitations, lowercase
+    pub fillers: Vec<String>,
+    /// Write numbers, percentages, amounts and dates as digits
+    pub normalize_numbers: bool,
+    /// Regroup segments so that each holds one sentence
+    pub sentence_segments: bool,
+    /// Silence between words that ends a sentence
+    pub sentence_pause_ms: f64,
+    /// Longest a sentence segment may run before it is split
+    pub max_segment_ms: f64,
+}
+
+impl Default for FormattingConfig {
+    fn default() -> Self {
+        Self {
+            punctuation: true,
+            casing: true,
+            remove_fillers: false,
+            fillers: ["um", "uh", "umm", "uhm", "erm", "er", "ah", "hmm", "mm"]
+                .iter()
+                .map(|f| f.to_string())
+                .collect(),
+            normalize_numbers: true,
+            sentence_segments: true,
+            sentence_pause_ms: 800.0,
+            max_segment_ms: 20_000.0,
+        }
+    }
+}
+
+impl FormattingConfig {
+    /// Check the settings are usable
+    pub fn validate(&self) -> TranscriptionResult<()> {
+        if !(100.0..=10_000.0).contains(&self.sentence_pause_ms) {
+            return Err(TranscriptionError::InvalidConfig {
+                details: format!("Sentence pause of {}ms is out of range (100-10000ms)", self.sentence_pause_ms),
+            });
+        }
+        if self.max_segment_ms < 1000.0 {
+            return Err(TranscriptionError::InvalidConfig {
+                details: format!("Segments must be allowed at least 1000ms, not {}ms", self.max_segment_ms),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Words that usually open a question
+const QUESTION_WORDS: &[&str] = &[
+    "what", "why", "how", "who", "whom", "whose", "when", "where", "which", "is", "are", "am", "was", "were", "do",
+    "does", "did", "can", "could", "would", "will", "should", "shall", "have", "has", "may", "might", "isn't",
+    "aren't", "don't", "doesn't", "didn't", "can't", "won't", "shouldn't", "wouldn't", "couldn't",
+];
+
+/// Words a question may be prefixed with, as in "so what do we do"
+const LEAD_INS: &[&str] = &["so", "and", "but", "okay", "ok", "well", "right", "now", "then"];
+
+const MONTHS: &[&str] = &[
+    "january", "february", "march", "april", "may", "june", "july", "august", "september", "october", "november",
+    "december",
+];
+
+const ORDINALS: &[&str] = &[
+    "first", "second", "third", "fourth", "fifth", "sixth", "seventh", "eighth", "ninth", "tenth", "eleventh",
+    "twelfth", "thirteenth", "fourteenth", "fifteenth", "sixteenth", "seventeenth", "eighteenth", "nineteenth",
+    "twentieth",
+];
+
+/// A word on its way through the chain
+#[derive(Debug, Clone)]
+struct Token {
+    text: String,
+    /// What the engine wrote, including any fillers dropped next to the word
+    raw: String,
+    start_ms: f64,
+    end_ms: f64,
+    confidence: f32,
+    /// Index of the segment the word came from
+    source: usize,
+    /// Whether a sentence ends before this word, whatever the punctuation
+    break_before: bool,
+}
+
+/// Runs the formatting chain over a transcript's segments
+#[derive(Debug, Clone, Default)]
+pub struct TranscriptFormatter {
+    config: FormattingConfig,
+}
+
+impl TranscriptFormatter {
+    pub fn new(config: FormattingConfig) -> Self {
+        Self { config }
+    }
+
+    /// Formatted copy of `segments`, which must be in timeline order
+    ///
+    /// Segments whose text changed keep what the engine wrote in
+    /// `original_text`.
+    pub fn format(&self, segments: &[TranscriptSegment]) -> Vec<TranscriptSegment> {
+        let mut tokens = self.tokens(segments);
+        if self.config.remove_fillers {
+            tokens = self.drop_fillers(tokens);
+        }
+        if self.config.normalize_numbers {
+            tokens = normalize_numbers(tokens, segments);
+        }
+
+        let sentences = sentences(&tokens);
+        for sentence in &sentences {
+            let english = is_english(&segments[tokens[sentence.start].source]);
+            if self.config.punctuation {
+                punctuate(&mut tokens[sentence.clone()], english);
+            }
+            if self.config.casing {
+                capitalise(&mut tokens[sentence.clone()], english);
+            }
+        }
+
+        let groups = if self.config.sentence_segments {
+            sentences.into_iter().flat_map(|sentence| self.split_long(&tokens, sentence)).collect()
+        } else {
+            by_source(&tokens)
+        };
+        groups.into_iter().map(|group| build_segment(&tokens[group], segments)).collect()
+    }
+
+    /// Every word of the transcript, timed
+    fn tokens(&self, segments: &[TranscriptSegment]) -> Vec<Token> {
+        let mut tokens: Vec<Token> = Vec::new();
+        for (source, segment) in segments.iter().enumerate() {
+            let switched = source > 0 && {
+                let previous = &segments[source - 1];
+                previous.speaker_hint != segment.speaker_hint || previous.language != segment.language
+            };
+            for (i, word) in spread_words(segment, &segment.text).into_iter().enumerate() {
+                let gap = tokens.last().map_or(0.0, |last| word.start_ms - last.end_ms);
+                tokens.push(Token {
+                    raw: word.text.clone(),
+                    text: word.text,
+                    start_ms: word.start_ms,
+                    end_ms: word.end_ms,
+                    confidence: word.confidence,
+                    source,
+                    break_before: gap >= self.config.sentence_pause_ms || (i == 0 && switched),
+                });
+            }
+        }
+        tokens
+    }
+
+    /// Remove fillers, keeping what they said in a neighbour's raw text
+    fn drop_fillers(&self, tokens: Vec<Token>) -> Vec<Token> {
+        let mut kept: Vec<Token> = Vec::with_capacity(tokens.len());
+        let mut pending: Option<Token> = None;
+        for token in tokens {
+            let (_, core, trailing) = split_punctuation(&token.text);
+            if !self.config.fillers.iter().any(|filler| filler.eq_ignore_ascii_case(core)) {
+                let mut token = token;
+                if let Some(filler) = pending.take() {
+                    match kept.last_mut() {
+                        Some(last) if last.source == filler.source && token.source != filler.source => {
+                            last.raw = format!("{} {}", last.raw, filler.raw);
+                        }
+                        _ => token.raw = format!("{} {}", filler.raw, token.raw),
+                    }
+                    token.break_before |= filler.break_before;
+                }
+                kept.push(token);
+                continue;
+            }
+
+            // A sentence the filler closed now ends at the word before it
+            if trailing.contains(['.', '?', '!']) {
+                if let Some(last) = kept.last_mut().filter(|last| !ends_sentence(&last.text)) {
+                    last.text = format!("{}{}", last.text.trim_end_matches([',', ';', ':']), trailing);
+                }
+            }
+            pending = Some(match pending.take() {
+                Some(previous) => Token {
+                    raw: format!("{} {}", previous.raw, token.raw),
+                    break_before: previous.break_before || token.break_before,
+                    ..previous
+                },
+                None => token,
+            });
+        }
+        if let (Some(filler), Some(last)) = (pending, kept.last_mut()) {
+            last.raw = format!("{} {}", last.raw, filler.raw);
+        }
+        kept
+    }
+
+    /// Split a sentence that runs longer than a segment may
+    fn split_long(&self, tokens: &[Token], sentence: Range<usize>) -> Vec<Range<usize>> {
+        let mut pieces = Vec::new();
+        let mut start = sentence.start;
+        for i in sentence.clone().skip(1) {
+            if tokens[i].end_ms - tokens[start].start_ms > self.config.max_segment_ms {
+                pieces.push(start..i);
+                start = i;
+            }
+        }
+        pieces.push(start..sentence.end);
+        pieces
+    }
+}
+
+/// Ranges of tokens forming sentences
+fn sentences(tokens: &[Token]) -> Vec<Range<usize>> {
+    let mut sentences = Vec::new();
+    let mut start = 0;
+    for i in 1..tokens.len() {
+        if tokens[i].break_before || ends_sentence(&tokens[i - 1].text) {
+            sentences.push(start..i);
+            start = i;
+        }
+    }
+    if start < tokens.len() {
+        sentences.push(start..tokens.len());
+    }
+    sentences
+}
+
+/// Ranges of tokens from the same source segment
+fn by_source(tokens: &[Token]) -> Vec<Range<usize>> {
+    let mut groups = Vec::new();
+    let mut start = 0;
+    for i in 1..tokens.len() {
+        if tokens[i].source != tokens[i - 1].source {
+            groups.push(start..i);
+            start = i;
+        }
+    }
+    if start < tokens.len() {
+        groups.push(start..tokens.len());
+    }
+    groups
+}
+
+fn build_segment(tokens: &[Token], segments: &[TranscriptSegment]) -> TranscriptSegment {
+    let source = &segments[tokens[0].source];
+    let text = tokens.iter().map(|t| t.text.as_str()).collect::<Vec<_>>().join(" ");
+    let raw = raw_text(tokens);
+    TranscriptSegment {
+        start_ms: tokens[0].start_ms,
+        end_ms: tokens[tokens.len() - 1].end_ms,
+        confidence: tokens.iter().map(|t| t.confidence).sum::<f32>() / tokens.len() as f32,
+        language: source.language.clone(),
+        model: source.model.clone(),
+        words: tokens
+            .iter()
+            .map(|t| WordTiming {
+                text: t.text.clone(),
+                start_ms: t.start_ms,
+                end_ms: t.end_ms,
+                confidence: t.confidence,
+            })
+            .collect(),
+        speaker_hint: source.speaker_hint,
+        original_text: (raw != text).then_some(raw),
+        text,
+    }
+}
+
+/// What the engine wrote for `tokens`
+fn raw_text(tokens: &[Token]) -> String {
+    tokens.iter().map(|t| t.raw.as_str()).filter(|raw| !raw.is_empty()).collect::<Vec<_>>().join(" ")
+}
+
+fn is_english(segment: &TranscriptSegment) -> bool {
+    segment.language.is_empty() || segment.language.starts_with("en")
+}
+
+fn ends_sentence(text: &str) -> bool {
+    text.trim_end_matches(['"', '\'', ')', '”', '’']).ends_with(['.', '?', '!', '…'])
+}
+
+/// Close a sentence that has no final punctuation
+fn punctuate(sentence: &mut [Token], english: bool) {
+    let question = english && is_question(sentence);
+    let Some(last) = sentence.last_mut() else { return };
+    if !ends_sentence(&last.text) {
+        last.text = format!("{}{}", last.text.trim_end_matches([',', ';', ':', '-']), if question { '?' } else { '.' });
+    }
+}
+
+/// Whether an English sentence opens like a question
+fn is_question(sentence: &[Token]) -> bool {
+    let words: Vec<String> = sentence.iter().take(2).map(|t| split_punctuation(&t.text).1.to_lowercase()).collect();
+    let opening = match words.as_slice() {
+        [lead_in, next] if LEAD_INS.contains(&lead_in.as_str()) => next,
+        [first, ..] => first,
+        [] => return false,
+    };
+    QUESTION_WORDS.contains(&opening.as_str())
+}
+
+/// Capitalise the sentence's first letter and, in English, "I"
+fn capitalise(sentence: &mut [Token], english: bool) {
+    if let Some(first) = sentence.first_mut() {
+        if let Some((i, c)) = first.text.char_indices().find(|(_, c)| c.is_alphabetic()) {
+            if c.is_lowercase() {
+                first.text = format!("{}{}{}", &first.text[..i], c.to_uppercase(), &first.text[i + c.len_utf8()..]);
+            }
+        }
+    }
+    if english {
+        for token in sentence.iter_mut() {
+            let (leading, core, trailing) = split_punctuation(&token.text);
+            if core == "i" || core.starts_with("i'") || core.starts_with("i’") {
+                token.text = format!("{}I{}{}", leading, &core[1..], trailing);
+            }
+        }
+    }
+}
+
+/// The leading punctuation, word and trailing punctuation of a token
+fn split_punctuation(text: &str) -> (&str, &str, &str) {
+    let is_edge = |c: char| !c.is_alphanumeric();
+    let start = text.len() - text.trim_start_matches(is_edge).len();
+    let end = text.trim_end_matches(is_edge).len().max(start);
+    (&text[..start], &text[start..end], &text[end..])
+}
+
+/// Replace spelled-out numbers in English segments with digits
+fn normalize_numbers(tokens: Vec<Token>, segments: &[TranscriptSegment]) -> Vec<Token> {
+    let tokens = split_hyphenated(tokens, segments);
+    let mut normalized: Vec<Token> = Vec::with_capacity(tokens.len());
+    let mut i = 0;
+    while i < tokens.len() {
+        let run = joinable_run(&tokens[i..]);
+        let words: Vec<String> = tokens[i..i + run].iter().map(|t| split_punctuation(&t.text).1.to_lowercase()).collect();
+        let matched = is_english(&segments[tokens[i].source]).then(|| match_number(&words)).flatten();
+        match matched {
+            Some((consumed, text)) => {
+                let merged = &tokens[i..i + consumed];
+                let (leading, _, _) = split_punctuation(&merged[0].text);
+                let (_, _, trailing) = split_punctuation(&merged[consumed - 1].text);
+                normalized.push(Token {
+                    text: format!("{}{}{}", leading, text, trailing),
+                    raw: raw_text(merged),
+                    start_ms: merged[0].start_ms,
+                    end_ms: merged[consumed - 1].end_ms,
+                    confidence: merged.iter().map(|t| t.confidence).fold(1.0, f32::min),
+                    source: merged[0].source,
+                    break_before: merged[0].break_before,
+                });
+                i += consumed;
+            }
+            None => {
+                normalized.push(tokens[i].clone());
+                i += 1;
+            }
+        }
+    }
+    normalized
+}
+
+/// Split "twenty-five" into its words, sharing out its time
+fn split_hyphenated(tokens: Vec<Token>, segments: &[TranscriptSegment]) -> Vec<Token> {
+    let mut split = Vec::with_capacity(tokens.len());
+    for token in tokens {
+        let (leading, core, trailing) = split_punctuation(&token.text);
+        let parts: Vec<&str> = core.split('-').collect();
+        let numeric = parts.len() > 1
+            && parts.iter().all(|part| number_word(&part.to_lowercase()).is_some() || ordinal_word(&part.to_lowercase()).is_some());
+        if !numeric || !is_english(&segments[token.source]) {
+            split.push(token);
+            continue;
+        }
+
+        let step = (token.end_ms - token.start_ms) / parts.len() as f64;
+        let last = parts.len() - 1;
+        for (i, part) in parts.iter().enumerate() {
+            split.push(Token {
+                text: format!("{}{}{}", if i == 0 { leading } else { "" }, part, if i == last { trailing } else { "" }),
+                // The whole word's raw text goes with its first part
+                raw: if i == 0 { token.raw.clone() } else { String::new() },
+                start_ms: token.start_ms + step * i as f64,
+                end_ms: token.start_ms + step * (i + 1) as f64,
+                break_before: token.break_before && i == 0,
+                ..token.clone()
+            });
+        }
+    }
+    split
+}
+
+/// How many tokens from the first could read as one number: no punctuation
+/// or sentence break between them
+fn joinable_run(tokens: &[Token]) -> usize {
+    let mut run = 1;
+    while run < tokens.len() && run < 16 {
+        let (_, _, trailing) = split_punctuation(&tokens[run - 1].text);
+        let (leading, _, _) = split_punctuation(&tokens[run].text);
+        if !trailing.is_empty() || !leading.is_empty() || tokens[run].break_before {
+            break;
+        }
+        run += 1;
+    }
+    run
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum NumberWord {
+    /// zero to nine
+    Digit(u64),
+    /// ten to nineteen
+    Teen(u64),
+    /// twenty, thirty, ...
+    Tens(u64),
+    Hundred,
+    /// thousand, million, billion
+    Scale(u64),
+}
+
+fn number_word(word: &str) -> Option<NumberWord> {
+    use NumberWord::*;
+    const DIGITS: &[&str] = &["zero", "one", "two", "three", "four", "five", "six", "seven", "eight", "nine"];
+    const TEENS: &[&str] = &[
+        "ten", "eleven", "twelve", "thirteen", "fourteen", "fifteen", "sixteen", "seventeen", "eighteen", "nineteen",
+    ];
+    const TENS: &[&str] = &["twenty", "thirty", "forty", "fifty", "sixty", "seventy", "eighty", "ninety"];
+
+    if let Some(n) = DIGITS.iter().position(|w| *w == word) {
+        return Some(Digit(n as u64));
+    }
+    if let Some(n) = TEENS.iter().position(|w| *w == word) {
+        return Some(Teen(10 + n as u64));
+    }
+    if let Some(n) = TENS.iter().position(|w| *w == word) {
+        return Some(Tens(20 + 10 * n as u64));
+    }
+    match word {
+        "hundred" => Some(Hundred),
+        "thousand" => Some(Scale(1_000)),
+        "million" => Some(Scale(1_000_000)),
+        "billion" => Some(Scale(1_000_000_000)),
+        _ => None,
+    }
+}
+
+/// Value of a single ordinal word, "first" to "twentieth" and "thirtieth"
+fn ordinal_word(word: &str) -> Option<u64> {
+    if word == "thirtieth" {
+        return Some(30);
+    }
+    ORDINALS.iter().position(|w| *w == word).map(|n| n as u64 + 1)
+}
+
+/// A cardinal number at the start of `words`: its value and how many words
+/// it took
+fn cardinal(words: &[String]) -> Option<(u64, usize)> {
+    use NumberWord::*;
+    let (mut total, mut current) = (0u64, 0u64);
+    let mut last: Option<NumberWord> = None;
+    let mut smallest_scale = u64::MAX;
+    let mut consumed = 0;
+    let mut i = 0;
+    while i < words.len() {
+        // "one hundred and five"
+        if words[i] == "and" && matches!(last, Some(Hundred | Scale(_))) {
+            match words.get(i + 1).and_then(|w| number_word(w)) {
+                Some(Digit(d)) if d > 0 => {}
+                Some(Teen(_) | Tens(_)) => {}
+                _ => break,
+            }
+            i += 1;
+            continue;
+        }
+        let Some(word) = number_word(&words[i]) else { break };
+        let fits = match (last, word) {
+            (Some(Digit(0)), _) => false,
+            (None, _) => true,
+            (Some(Tens(_)), Digit(d)) => d > 0,
+            (Some(Hundred | Scale(_)), Digit(d)) => d > 0,
+            (Some(Hundred | Scale(_)), Teen(_) | Tens(_)) => true,
+            (Some(Digit(d)), Hundred) => d > 0 && current < 10,
+            (Some(Teen(_)), Hundred) => current < 100,
+            (Some(Digit(d)), Scale(s)) => d > 0 && s < smallest_scale,
+            (Some(Teen(_) | Tens(_) | Hundred), Scale(s)) => s < smallest_scale,
+            _ => false,
+        };
+        if !fits {
+            break;
+        }
+        match word {
+            Digit(n) | Teen(n) | Tens(n) => current += n,
+            Hundred => current = current.max(1) * 100,
+            Scale(s) => {
+                total += current.max(1) * s;
+                current = 0;
+                smallest_scale = s;
+            }
+        }
+        last = Some(word);
+        i += 1;
+        consumed = i;
+    }
+    (consumed > 0).then_some((total + current, consumed))
+}
+
+/// A year spoken in pairs, "nineteen ninety nine" or "twenty twenty four"
+fn year(words: &[String]) -> Option<(u64, usize)> {
+    let century = match words.first()?.as_str() {
+        "nineteen" => 19,
+        "twenty" => 20,
+        _ => return None,
+    };
+    let (rest, consumed) = cardinal(&words[1..])?;
+    (10..100).contains(&rest).then_some((century * 100 + rest, consumed + 1))
+}
+
+/// An ordinal at the start of `words`, up to the thirty-first
+fn ordinal(words: &[String]) -> Option<(u64, usize)> {
+    let first = words.first()?;
+    if let Some(n) = ordinal_word(first) {
+        return Some((n, 1));
+    }
+    let tens = match first.as_str() {
+        "twenty" => 20,
+        "thirty" => 30,
+        _ => return None,
+    };
+    let unit = ordinal_word(words.get(1)?).filter(|n| *n < 10)?;
+    (tens + unit <= 31).then_some((tens + unit, 2))
+}
+
+/// A number, amount, percentage or date at the start of `words`, written
+/// out, with how many words it took
+fn match_number(words: &[String]) -> Option<(usize, String)> {
+    if let Some(date) = match_date(words) {
+        return Some(date);
+    }
+    if let Some((n, consumed)) = ordinal(words) {
+        if words.get(consumed).map(String::as_str) == Some("of") {
+            if let Some(month) = words.get(consumed + 1).and_then(|w| month_name(w)) {
+                return Some((consumed + 2, format!("{}{} of {}", n, ordinal_suffix(n), month)));
+            }
+        }
+        return (n >= 10).then(|| (consumed, format!("{}{}", n, ordinal_suffix(n))));
+    }
+
+    let year = year(words);
+    let (value, mut consumed) = year.or_else(|| cardinal(words))?;
+    let mut digits = if year.is_some() { value.to_string() } else { group_thousands(value) };
+    let mut decimal = false;
+    if words.get(consumed).map(String::as_str) == Some("point") {
+        let decimals: String = words[consumed + 1..]
+            .iter()
+            .map_while(|w| match number_word(w) {
+                Some(NumberWord::Digit(d)) => char::from_digit(d as u32, 10),
+                _ => None,
+            })
+            .collect();
+        if !decimals.is_empty() {
+            consumed += 1 + decimals.len();
+            digits = format!("{}.{}", digits, decimals);
+            decimal = true;
+        }
+    }
+
+    let next = words.get(consumed).map(String::as_str);
+    if next == Some("percent") {
+        return Some((consumed + 1, format!("{}%", digits)));
+    }
+    if next == Some("per") && words.get(consumed + 1).map(String::as_str) == Some("cent") {
+        return Some((consumed + 2, format!("{}%", digits)));
+    }
+    if let Some(symbol) = next.and_then(currency_symbol) {
+        consumed += 1;
+        if !decimal && words.get(consumed).map(String::as_str) == Some("and") {
+            if let Some((cents, taken)) = cardinal(&words[consumed + 1..]) {
+                if cents < 100 && matches!(words.get(consumed + 1 + taken).map(String::as_str), Some("cents" | "cent" | "pence")) {
+                    return Some((consumed + taken + 2, format!("{}{}.{:02}", symbol, digits, cents)));
+                }
+            }
+        }
+        return Some((consumed, format!("{}{}", symbol, digits)));
+    }
+
+    // Small whole numbers read better as words
+    (decimal || value >= 10).then_some((consumed, digits))
+}
+
+/// "march third", optionally followed by a year
+fn match_date(words: &[String]) -> Option<(usize, String)> {
+    let month = month_name(words.first()?)?;
+    let (day, taken) = ordinal(&words[1..])?;
+    let consumed = 1 + taken;
+    match year(&words[consumed..]).or_else(|| cardinal(&words[consumed..]).filter(|(y, _)| (1900..2100).contains(y))) {
+        Some((year, taken)) => Some((consumed + taken, format!("{} {}, {}", month, day, year))),
+        None => Some((consumed, format!("{} {}", month, day))),
+    }
+}
+
+fn month_name(word: &str) -> Option<String> {
+    MONTHS.contains(&word).then(|| {
+        let mut chars = word.chars();
+        chars.next().map(|c| c.to_uppercase().chain(chars).collect()).unwrap_or_default()
+    })
+}
+
+fn currency_symbol(word: &str) -> Option<&'static str> {
+    match word {
+        "dollar" | "dollars" => Some("$"),
+        "euro" | "euros" => Some("€"),
+        "pound" | "pounds" => Some("£"),
+        _ => None,
+    }
+}
+
+fn ordinal_suffix(n: u64) -> &'static str {
+    match (n % 10, n % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    }
+}
+
+/// "12,000"
+fn group_thousands(value: u64) -> String {
+    let digits = value.to_string();
+    let mut grouped = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(c);
+    }
+    grouped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A segment whose words are spread evenly from `start_ms`, 300ms each
+    fn segment(text: &str, start_ms: f64) -> TranscriptSegment {
+        let words: Vec<WordTiming> = text
+            .split_whitespace()
+            .enumerate()
+            .map(|(i, word)| WordTiming {
+                text: word.to_string(),
+                start_ms: start_ms + i as f64 * 300.0,
+                end_ms: start_ms + (i + 1) as f64 * 300.0,
+                confidence: 0.9,
+            })
+            .collect();
+        TranscriptSegment {
+            text: text.to_string(),
+            start_ms,
+            end_ms: words.last().map_or(start_ms, |w| w.end_ms),
+            confidence: 0.9,
+            language: "en".to_string(),
+            model: "mock".to_string(),
+            words,
+            speaker_hint: None,
+            original_text: None,
+        }
+    }
+
+    fn texts(segments: &[TranscriptSegment]) -> Vec<&str> {
+        segments.iter().map(|s| s.text.as_str()).collect()
+    }
+
+    #[test]
+    fn test_segments_are_regrouped_into_punctuated_sentences() {
+        // A chunk boundary cuts the first sentence; a pause ends it
+        let segments = vec![
+            segment("so what do we ship", 0.0),
+            segment("this week i think", 1500.0),
+            segment("the importer is ready", 4000.0),
+        ];
+        let formatted = TranscriptFormatter::default().format(&segments);
+
+        assert_eq!(texts(&formatted), ["So what do we ship this week I think?", "The importer is ready."]);
+        assert_eq!(formatted[0].start_ms, 0.0);
+        assert_eq!(formatted[0].end_ms, 2700.0);
+        assert_eq!(formatted[0].original_text.as_deref(), Some("so what do we ship this week i think"));
+        assert_eq!(formatted[1].words[0].text, "The");
+    }
+
+    #[test]
+    fn test_engine_punctuation_is_respected() {
+        let segments = vec![segment("Hello, everyone. Let's start", 0.0)];
+        let config = FormattingConfig { sentence_segments: false, ..Default::default() };
+        let formatted = TranscriptFormatter::new(config).format(&segments);
+
+        assert_eq!(texts(&formatted), ["Hello, everyone. Let's start."]);
+        assert_eq!(formatted[0].original_text.as_deref(), Some("Hello, everyone. Let's start"));
+    }
+
+    #[test]
+    fn test_fillers_are_removed_but_kept_in_the_original() {
+        let segments = vec![segment("um so, uh, we should uh merge it", 0.0)];
+        let config = FormattingConfig { remove_fillers: true, ..Default::default() };
+        let formatted = TranscriptFormatter::new(config).format(&segments);
+
+        assert_eq!(texts(&formatted), ["So, we should merge it."]);
+        assert_eq!(formatted[0].original_text.as_deref(), Some("um so, uh, we should uh merge it"));
+        assert_eq!(formatted[0].words.len(), 5);
+        // Off by default
+        assert_eq!(texts(&TranscriptFormatter::default().format(&segments)), ["Um so, uh, we should uh merge it."]);
+    }
+
+    #[test]
+    fn test_numbers_amounts_and_dates_are_written_as_digits() {
+        let cases = [
+            ("we hired three people", "We hired three people."),
+            ("revenue grew twenty-five percent", "Revenue grew 25%."),
+            ("it costs two thousand and fifty dollars", "It costs $2,050."),
+            ("the fee is four dollars and fifty cents", "The fee is $4.50."),
+            ("about one hundred twenty thousand users", "About 120,000 users."),
+            ("latency is two point five seconds", "Latency is 2.5 seconds."),
+            ("launch on march third twenty twenty four", "Launch on March 3, 2024."),
+            ("due the twenty first of june", "Due the 21st of June."),
+            ("we met in nineteen ninety nine", "We met in 1999."),
+            ("call one two three", "Call one two three."),
+        ];
+        for (spoken, written) in cases {
+            let formatted = TranscriptFormatter::default().format(&[segment(spoken, 0.0)]);
+            assert_eq!(texts(&formatted), [written], "{}", spoken);
+        }
+
+        let formatted = TranscriptFormatter::default().format(&[segment("fifty five people", 0.0)]);
+        assert_eq!((formatted[0].words[0].start_ms, formatted[0].words[0].end_ms), (0.0, 600.0));
+    }
+
+    #[test]
+    fn test_sentences_split_at_speaker_changes_and_length() {
+        use crate::transcription::SpeakerHint;
+
+        let mut answer = segment("yes it does", 1000.0);
+        answer.speaker_hint = Some(SpeakerHint::Others);
+        let segments = vec![segment("does it scale", 0.0), answer];
+        assert_eq!(texts(&TranscriptFormatter::default().format(&segments)), ["Does it scale?", "Yes it does."]);
+
+        let long = segment(&["word"; 10].join(" "), 0.0);
+        let config = FormattingConfig { max_segment_ms: 1000.0, ..Default::default() };
+        let formatted = TranscriptFormatter::new(config).format(&[long]);
+        assert_eq!(formatted.len(), 4);
+        assert!(formatted.iter().all(|s| s.duration_ms() <= 1000.0));
+        assert!(FormattingConfig { sentence_pause_ms: 0.0, ..Default::default() }.validate().is_err());
+    }
+}
@@ -0,0 +1,160 @@
+//! Spoken language identification and switch points
+//!
+//! Meetings may move between languages, so the language is decided per chunk
+//! rather than per transcript. A pinned language is always used as is; with a
+//! list of candidates, the engine's language scores for the chunk pick one of
+//! them; with neither, the engine detects the language itself.
+
+use serde::{Deserialize, Serialize};
+
+use crate::audio::AudioChunk;
+
+use super::engine::TranscriptionEngine;
+use super::types::{TranscriptSegment, TranscriptionOptions, TranscriptionResult};
+
+/// How likely a chunk is to be in one language
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LanguageScore {
+    pub language: String,
+    pub probability: f32,
+}
+
+/// Point on the timeline where the spoken language changes
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LanguageSwitch {
+    pub at_ms: f64,
+    pub from: String,
+    pub to: String,
+}
+
+/// Best-scoring language among `candidates`, or overall if there are none
+pub fn choose_language(scores: &[LanguageScore], candidates: &[String]) -> Option<String> {
+    scores
+        .iter()
+        .filter(|score| candidates.is_empty() || candidates.iter().any(|c| c.eq_ignore_ascii_case(&score.language)))
+        .max_by(|a, b| a.probability.total_cmp(&b.probability))
+        .map(|score| score.language.clone())
+}
+
+/// Options to transcribe `chunk` with, its language decided if candidates
+/// were given
+///
+/// Engines that can't score languages transcribe with the options as given.
+pub fn resolve_options(
+    engine: &dyn TranscriptionEngine,
+    chunk: &AudioChunk,
+    options: &TranscriptionOptions,
+) -> TranscriptionResult<TranscriptionOptions> {
+    if options.language.is_some() || options.candidate_languages.is_empty() {
+        return Ok(options.clone());
+    }
+
+    let scores = engine.identify_language(chunk)?;
+    Ok(TranscriptionOptions {
+        language: choose_language(&scores, &options.candidate_languages),
+        ..options.clone()
+    })
+}
+
+/// Where consecutive segments change language
+pub fn language_switches(segments: &[TranscriptSegment]) -> Vec<LanguageSwitch> {
+    let mut switches = Vec::new();
+    let mut current: Option<&str> = None;
+
+    for segment in segments.iter().filter(|s| !s.language.is_empty()) {
+        match current {
+            Some(language) if language != segment.language => switches.push(LanguageSwitch {
+                at_ms: segment.start_ms,
+                from: language.to_string(),
+                to: segment.language.clone(),
+            }),
+            _ => {}
+        }
+        current = Some(&segment.language);
+    }
+
+    switches
+}
+
+/// Language spoken for the longest time
+pub fn dominant_language(segments: &[TranscriptSegment]) -> Option<String> {
+    let mut totals: Vec<(&str, f64)> = Vec::new();
+    for segment in segments.iter().filter(|s| !s.language.is_empty()) {
+        match totals.iter_mut().find(|(language, _)| *language == segment.language) {
+            Some((_, total)) => *total += segment.duration_ms(),
+            None => totals.push((&segment.language, segment.duration_ms())),
+        }
+    }
+
+    totals
+        .into_iter()
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(language, _)| language.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::AudioBuffer;
+    use crate::transcription::MockEngine;
+
+    fn segment(start_ms: f64, language: &str) -> TranscriptSegment {
+        TranscriptSegment {
+            text: "word".to_string(),
+            start_ms,
+            end_ms: start_ms + 1000.0,
+            confidence: 0.9,
+            language: language.to_string(),
+            model: "mock".to_string(),
+            words: Vec::new(),
+            speaker_hint: None,
+            original_text: None,
+        }
+    }
+
+    #[test]
+    fn test_candidates_restrict_the_chosen_language() {
+        let scores = vec![
+            LanguageScore { language: "gl".to_string(), probability: 0.5 },
+            LanguageScore { language: "pt".to_string(), probability: 0.3 },
+            LanguageScore { language: "es".to_string(), probability: 0.2 },
+        ];
+        assert_eq!(choose_language(&scores, &[]), Some("gl".to_string()));
+        assert_eq!(choose_language(&scores, &["es".to_string(), "pt".to_string()]), Some("pt".to_string()));
+        assert_eq!(choose_language(&scores, &["en".to_string()]), None);
+    }
+
+    #[test]
+    fn test_chunk_language_is_identified_among_candidates() {
+        let engine = MockEngine::new().with_language("en").with_language_at(2000.0, "pt");
+        let chunk = AudioChunk { index: 0, start_ms: 1000.0, buffer: AudioBuffer::new(vec![0.2; 48000], 16000, 1) };
+        let options = TranscriptionOptions {
+            candidate_languages: vec!["en".to_string(), "pt".to_string(), "es".to_string()],
+            ..Default::default()
+        };
+
+        let resolved = resolve_options(&engine, &chunk, &options).unwrap();
+        assert_eq!(resolved.language.as_deref(), Some("pt"));
+
+        // A pinned language is never second-guessed
+        let pinned = TranscriptionOptions { language: Some("es".to_string()), ..options };
+        assert_eq!(resolve_options(&engine, &chunk, &pinned).unwrap().language.as_deref(), Some("es"));
+    }
+
+    #[test]
+    fn test_switch_points_and_dominant_language() {
+        let segments = vec![
+            segment(0.0, "en"),
+            segment(1000.0, "en"),
+            segment(2000.0, "pt"),
+            segment(3000.0, ""),
+            segment(4000.0, "es"),
+        ];
+
+        let switches = language_switches(&segments);
+        assert_eq!(switches.len(), 2);
+        assert_eq!(switches[0], LanguageSwitch { at_ms: 2000.0, from: "en".to_string(), to: "pt".to_string() });
+        assert_eq!(switches[1].at_ms, 4000.0);
+        assert_eq!(dominant_language(&segments).as_deref(), Some("en"));
+    }
+}
@@ -0,0 +1,216 @@
+//! Deterministic engine for exercising the pipeline without models
+
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use crate::audio::AudioChunk;
+
+use super::engine::TranscriptionEngine;
+use super::language::LanguageScore;
+use super::types::{
+    TranscriptSegment, TranscriptionError, TranscriptionOptions, TranscriptionResult,
+};
+
+/// RMS below which a window is treated as silence
+const MOCK_SILENCE_RMS: f32 = 0.01;
+
+/// Engine that "recognises" fixed windows of non-silent audio
+///
+/// Each window becomes one segment whose text is a word from the script,
+/// picked by the window's position on the meeting timeline. The same audio
+/// therefore gives the same transcript however it is chunked.
+#[derive(Debug)]
+pub struct MockEngine {
+    window_ms: f64,
+    script: Vec<String>,
+    /// Language spoken from each timeline position on, in order
+    languages: Vec<(f64, String)>,
+    latency: Duration,
+    failing_chunks: HashSet<usize>,
+    calls: AtomicUsize,
+}
+
+impl Default for MockEngine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MockEngine {
+    pub fn new() -> Self {
+        Self {
+            window_ms: 1000.0,
+            script: ["alpha", "bravo", "charlie", "delta", "echo", "foxtrot"]
+                .iter()
+                .map(|word| word.to_string())
+                .collect(),
+            languages: vec![(0.0, "en".to_string())],
+            latency: Duration::ZERO,
+            failing_chunks: HashSet::new(),
+            calls: AtomicUsize::new(0),
+        }
+    }
+
+    /// Length of audio each segment covers
+    pub fn with_window(mut self, window: Duration) -> Self {
+        self.window_ms = window.as_secs_f64() * 1000.0;
+        self
+    }
+
+    /// Words segments are labelled with, cycled along the timeline
+    pub fn with_script(mut self, script: &[&str]) -> Self {
+        self.script = script.iter().map(|word| word.to_string()).collect();
+        self
+    }
+
+    /// Language reported when the request doesn't name one
+    pub fn with_language(mut self, language: &str) -> Self {
+        self.languages = vec![(0.0, language.to_string())];
+        self
+    }
+
+    /// Switch to `language` at `start_ms` on the timeline
+    pub fn with_language_at(mut self, start_ms: f64, language: &str) -> Self {
+        self.languages.push((start_ms, language.to_string()));
+        self.languages.sort_by(|a, b| a.0.total_cmp(&b.0));
+        self
+    }
+
+    /// Language spoken at `at_ms`
+    pub fn language_at(&self, at_ms: f64) -> &str {
+        self.languages
+            .iter()
+            .rev()
+            .find(|(start_ms, _)| *start_ms <= at_ms)
+            .or(self.languages.first())
+            .map_or("", |(_, language)| language.as_str())
+    }
+
+    /// Time each call blocks for, to simulate a slow model
+    pub fn with_latency(mut self, latency: Duration) -> Self {
+        self.latency = latency;
+        self
+    }
+
+    /// Fail chunks with the given indices
+    pub fn failing_on(mut self, chunks: &[usize]) -> Self {
+        self.failing_chunks = chunks.iter().copied().collect();
+        self
+    }
+
+    /// Number of chunks handed to the engine so far
+    pub fn calls(&self) -> usize {
+        self.calls.load(Ordering::Relaxed)
+    }
+
+    /// Text the mock produces for the window starting at `start_ms`
+    pub fn word_at(&self, start_ms: f64) -> &str {
+        if self.script.is_empty() {
+            return "";
+        }
+        let window = (start_ms / self.window_ms).round() as usize;
+        &self.script[window % self.script.len()]
+    }
+}
+
+impl TranscriptionEngine for MockEngine {
+    fn name(&self) -> &str {
+        "mock"
+    }
+
+    /// Scores each language by its share of the chunk
+    fn identify_language(&self, chunk: &AudioChunk) -> TranscriptionResult<Vec<LanguageScore>> {
+        let start = chunk.start_ms;
+        let end = start + chunk.buffer.duration_ms();
+        let mut scores: Vec<LanguageScore> = Vec::new();
+
+        for (i, (from, language)) in self.languages.iter().enumerate() {
+            let until = self.languages.get(i + 1).map_or(f64::INFINITY, |(next, _)| *next);
+            let overlap = until.min(end) - from.max(start);
+            if overlap > 0.0 && end > start {
+                scores.push(LanguageScore {
+                    language: language.clone(),
+                    probability: (overlap / (end - start)) as f32,
+                });
+            }
+        }
+
+        scores.sort_by(|a, b| b.probability.total_cmp(&a.probability));
+        Ok(scores)
+    }
+
+    fn transcribe(
+        &self,
+        chunk: &AudioChunk,
+        options: &TranscriptionOptions,
+    ) -> TranscriptionResult<Vec<TranscriptSegment>> {
+        self.calls.fetch_add(1, Ordering::Relaxed);
+        if !self.latency.is_zero() {
+            std::thread::sleep(self.latency);
+        }
+        if self.failing_chunks.contains(&chunk.index) {
+            return Err(TranscriptionError::Engine {
+                message: format!("mock failure on chunk {}", chunk.index),
+            });
+        }
+
+        let buffer = &chunk.buffer;
+        let rate = buffer.sample_rate.max(1) as f64;
+        let window_len = ((self.window_ms / 1000.0 * rate) as usize).max(1);
+
+        let segments = buffer.samples
+            .chunks(window_len)
+            .enumerate()
+            .filter_map(|(i, window)| {
+                let rms = (window.iter().map(|s| s * s).sum::<f32>() / window.len() as f32).sqrt();
+                if rms < MOCK_SILENCE_RMS {
+                    return None;
+                }
+                let start_ms = chunk.start_ms + (i * window_len) as f64 / rate * 1000.0;
+                Some(TranscriptSegment {
+                    text: self.word_at(start_ms).to_string(),
+                    start_ms,
+                    end_ms: start_ms + window.len() as f64 / rate * 1000.0,
+                    confidence: (0.5 + rms).min(1.0),
+                    language: options.language.clone()
+                        .unwrap_or_else(|| self.language_at(start_ms).to_string()),
+                    model: self.name().to_string(),
+                    words: Vec::new(),
+                    speaker_hint: None,
+                    original_text: None,
+                })
+            })
+            .collect();
+
+        Ok(segments)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::AudioBuffer;
+
+    #[test]
+    fn test_mock_text_depends_only_on_timeline() {
+        let engine = MockEngine::new();
+        let audio = vec![0.2f32; 16000 * 4];
+
+        let whole = AudioChunk { index: 0, start_ms: 0.0, buffer: AudioBuffer::new(audio.clone(), 16000, 1) };
+        let second_half = AudioChunk {
+            index: 1,
+            start_ms: 2000.0,
+            buffer: AudioBuffer::new(audio[32000..].to_vec(), 16000, 1),
+        };
+        let options = TranscriptionOptions::default();
+
+        let whole = engine.transcribe(&whole, &options).unwrap();
+        let half = engine.transcribe(&second_half, &options).unwrap();
+        assert_eq!(whole.len(), 4);
+        assert_eq!(&whole[2..], &half[..]);
+        assert_eq!(half[0].text, "charlie");
+        assert_eq!(half[0].start_ms, 2000.0);
+        assert_eq!(engine.calls(), 2);
+    }
+}
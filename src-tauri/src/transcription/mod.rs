@@ -1,14 +1,61 @@
 //! Transcription and speech-to-text processing
+//!
+//! Engines implement `TranscriptionEngine`; `TranscriptionService` feeds them
+//! chunks from capture or import and publishes the resulting segments. Live
+//! capture can also be streamed, with provisional results revised as more
+//! audio arrives. With cloud fallback enabled, doubtful local results are
+//! re-transcribed by an OpenAI-compatible API within a per-meeting budget.
+//! Segments carry word timings, aligned from the audio when the engine
+//! doesn't provide them. A separate microphone and system-audio track hint
+//! whether the local user or someone else spoke; diarization attributes
+//! segments to individual speakers. A user-managed vocabulary and the
+//! meeting's title and attendees prime engines that take a prompt and
+//! correct the output of those that don't. Stored transcripts can be
+//! formatted into punctuated sentences, keeping what the engine wrote, and
+//! exported as SRT, WebVTT or TTML captions.
+//! Model files are kept in a registry that verifies their checksums before
+//! they are loaded and picks the best one the machine can run.
 
-// TODO: Implement transcription modules
-// This is a placeholder for the transcription domain
+pub mod alignment;
+pub mod attribution;
+pub mod captions;
+pub mod cloud;
+pub mod diarization;
+pub mod engine;
+pub mod formatting;
+pub mod language;
+pub mod mock;
+pub mod models;
+pub mod routing;
+pub mod service;
+pub mod streaming;
+pub mod types;
+pub mod vocabulary;
+#[cfg(feature = "whisper")]
+pub mod whisper;
 
-/// Transcription service placeholder
-pub struct TranscriptionService;
-
-impl TranscriptionService {
-    /// Create a new transcription service
-    pub fn new() -> Self {
-        Self
-    }
-}
\ No newline at end of file
+pub use alignment::{align_words, fill_word_timings, spread_words};
+pub use attribution::{track_roles, AttributionConfig, TrackAttributor};
+pub use captions::{CaptionConfig, CaptionFormat, CaptionSegment, CaptionWriter, Cue};
+pub use cloud::{CloudConfig, CloudEngine};
+pub use diarization::{Diarization, DiarizationConfig, Diarizer, SpeakerCluster};
+pub use engine::{configured_engine, local_engine, session_engine, TranscriptionEngine, DEFAULT_ENGINE_SAMPLE_RATE};
+pub use formatting::{FormattingConfig, TranscriptFormatter};
+pub use language::{language_switches, LanguageScore, LanguageSwitch};
+pub use mock::MockEngine;
+pub use models::{
+    verified_speech_model, DiskUsage, Hardware, HttpDownloader, InstalledModel, ModelDownloader, ModelKind,
+    ModelRegistry, ModelsConfig,
+};
+pub use routing::{CloudBudget, HybridEngine, RoutingConfig, RoutingStats};
+pub use service::{
+    prepare_chunk, transcribe_with, ChunkAssembler, LiveTranscript, TranscriptionConfig, TranscriptionService,
+};
+pub use streaming::{SegmentId, StreamingConfig, StreamingTranscriber, TranscriptEvent};
+pub use types::{
+    SpeakerHint, TranscriptSegment, TranscriptionError, TranscriptionOptions, TranscriptionResult,
+    TranscriptionStats, WordTiming,
+};
+pub use vocabulary::{Vocabulary, VocabularyTerm};
+#[cfg(feature = "whisper")]
+pub use whisper::{WhisperConfig, WhisperEngine};
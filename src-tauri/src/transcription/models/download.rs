@@ -0,0 +1,59 @@
+//! Fetching models from elsewhere
+//!
+//! The registry never downloads anything on its own. A `ModelDownloader`
+//! writes a model's bytes and the registry hashes, verifies and installs
+//! them as it does an imported file; `HttpDownloader` fetches from a mirror
+//! laid out like whisper.cpp's, with one file per model under a base URL.
+
+use std::io::Write;
+use std::time::Duration;
+use reqwest::blocking::Client;
+use tracing::debug;
+
+use crate::transcription::types::{TranscriptionError, TranscriptionResult};
+
+/// A source models can be downloaded from
+pub trait ModelDownloader: Send + Sync {
+    /// Write the model file called `file` (e.g. "ggml-base.en.bin") to
+    /// `destination` and return the bytes written
+    fn download(&self, file: &str, destination: &mut dyn Write) -> TranscriptionResult<u64>;
+}
+
+/// Downloads `{base_url}/{file}` over HTTP(S)
+pub struct HttpDownloader {
+    client: Client,
+    base_url: String,
+}
+
+impl HttpDownloader {
+    /// Connection timeout; the transfer itself may take as long as it needs
+    const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+
+    pub fn new(base_url: &str) -> TranscriptionResult<Self> {
+        let client = Client::builder()
+            .connect_timeout(Self::CONNECT_TIMEOUT)
+            .timeout(None)
+            .build()
+            .map_err(|e| TranscriptionError::Internal {
+                message: format!("Failed to create HTTP client: {}", e),
+            })?;
+
+        Ok(Self {
+            client,
+            base_url: base_url.trim_end_matches('/').to_string(),
+        })
+    }
+}
+
+impl ModelDownloader for HttpDownloader {
+    fn download(&self, file: &str, destination: &mut dyn Write) -> TranscriptionResult<u64> {
+        let url = format!("{}/{}", self.base_url, file);
+        debug!("Downloading model from {}", url);
+
+        let failed = |e: reqwest::Error| TranscriptionError::ModelUnavailable {
+            model: format!("{} ({})", url, e),
+        };
+        let mut response = self.client.get(&url).send().and_then(|r| r.error_for_status()).map_err(failed)?;
+        response.copy_to(destination).map_err(failed)
+    }
+}
@@ -0,0 +1,54 @@
+//! What the machine can run
+
+use serde::{Deserialize, Serialize};
+
+/// Share of physical memory a model may take; the meeting app itself, the
+/// browser and the rest of the desktop need the remainder
+const MEMORY_BUDGET_SHARE: u64 = 2;
+
+/// CPU resources models are chosen for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Hardware {
+    /// Logical cores
+    pub cores: usize,
+    /// Physical memory, when it could be read
+    pub memory_bytes: Option<u64>,
+}
+
+impl Hardware {
+    /// The machine this is running on
+    pub fn detect() -> Self {
+        Self {
+            cores: std::thread::available_parallelism().map(|cores| cores.get()).unwrap_or(1),
+            memory_bytes: physical_memory(),
+        }
+    }
+
+    /// Memory a model may use; unlimited when it isn't known
+    pub fn memory_budget(&self) -> u64 {
+        self.memory_bytes.map_or(u64::MAX, |bytes| bytes / MEMORY_BUDGET_SHARE)
+    }
+
+    /// Largest model size (0 for tiny up to 4 for large) that keeps up with
+    /// live speech on this many cores
+    pub fn max_variant_rank(&self) -> usize {
+        match self.cores {
+            0..=2 => 1,
+            3..=4 => 2,
+            5..=8 => 3,
+            _ => 4,
+        }
+    }
+}
+
+#[cfg(unix)]
+fn physical_memory() -> Option<u64> {
+    // SAFETY: sysconf only reads system information
+    let (pages, page_size) = unsafe { (libc::sysconf(libc::_SC_PHYS_PAGES), libc::sysconf(libc::_SC_PAGESIZE)) };
+    (pages > 0 && page_size > 0).then(|| pages as u64 * page_size as u64)
+}
+
+#[cfg(not(unix))]
+fn physical_memory() -> Option<u64> {
+    None
+}
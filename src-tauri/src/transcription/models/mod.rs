@@ -0,0 +1,251 @@
+//! Installed speech and embedding models
+//!
+//! Models live in one directory next to a `models.json` manifest recording
+//! what each file is: its kind, size, the languages it handles, how its
+//! weights are quantized and its SHA-256. Models are imported from local
+//! files or directories, checked against published checksums when those are
+//! given, and checked again before they are loaded. Downloading is optional:
+//! any `ModelDownloader` can fetch a model into the registry, with the same
+//! verification as an import.
+
+pub mod download;
+pub mod hardware;
+pub mod registry;
+
+pub use download::{HttpDownloader, ModelDownloader};
+pub use hardware::Hardware;
+pub use registry::{DiskUsage, ModelRegistry, ModelUsage};
+
+use std::path::{Path, PathBuf};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::config::AIConfig;
+
+use super::types::{TranscriptionError, TranscriptionResult};
+
+/// File extensions models are recognised by
+pub const MODEL_EXTENSIONS: &[&str] = &["bin", "gguf", "onnx"];
+
+/// What a model is used for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModelKind {
+    /// Speech-to-text, e.g. a whisper.cpp GGML file
+    Speech,
+    /// Speaker embeddings for diarization
+    Embedding,
+}
+
+/// A model file in the registry
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InstalledModel {
+    /// File name without its extension, e.g. "ggml-base.en-q5_1"
+    pub id: String,
+    pub kind: ModelKind,
+    /// File name within the models directory
+    pub file: String,
+    /// Architecture size, e.g. "base" or "large-v3"
+    pub variant: String,
+    pub size_bytes: u64,
+    /// Languages the model was trained for; empty when it is multilingual
+    pub languages: Vec<String>,
+    /// Weight format, e.g. "f16" or "q5_1"
+    pub quantization: String,
+    /// Hex-encoded SHA-256 of the file
+    pub sha256: String,
+    /// Where the model was imported or downloaded from
+    pub source: String,
+    pub installed_at: DateTime<Utc>,
+}
+
+impl InstalledModel {
+    /// Whether the model can transcribe every one of `languages`; an empty
+    /// list means the language is detected, which needs a multilingual model
+    pub fn supports(&self, languages: &[String]) -> bool {
+        self.languages.is_empty()
+            || (!languages.is_empty() && languages.iter().all(|l| self.languages.contains(l)))
+    }
+
+    /// Rough memory needed to run the model: its weights plus a quarter for
+    /// buffers and 192 MiB of decoder state
+    pub fn memory_bytes(&self) -> u64 {
+        self.size_bytes + self.size_bytes / 4 + 192 * 1024 * 1024
+    }
+
+    /// Position of `variant` from smallest (0) to largest
+    pub fn variant_rank(&self) -> usize {
+        ["tiny", "base", "small", "medium", "large"]
+            .iter()
+            .position(|size| self.variant == *size || self.variant.starts_with(&format!("{}-", size)))
+            .unwrap_or(0)
+    }
+
+    /// Bits per weight the quantization keeps, e.g. 5 for "q5_1"
+    pub fn precision_bits(&self) -> u32 {
+        let digits: String = self.quantization.chars().skip(1).take_while(char::is_ascii_digit).collect();
+        digits.parse().unwrap_or(16)
+    }
+}
+
+/// Where models are kept and which one transcription uses
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ModelsConfig {
+    /// Directory holding the models and their manifest
+    pub directory: PathBuf,
+    /// Id of the speech model to use; `None` picks the best installed model
+    /// for this machine
+    pub speech_model: Option<String>,
+    /// Check a model's SHA-256 every time it is loaded
+    pub verify_checksums: bool,
+    /// Base URL models can be downloaded from; `None` disables downloads
+    pub download_url: Option<String>,
+}
+
+impl Default for ModelsConfig {
+    fn default() -> Self {
+        Self {
+            directory: PathBuf::from("models"),
+            speech_model: None,
+            verify_checksums: true,
+            download_url: None,
+        }
+    }
+}
+
+impl ModelsConfig {
+    /// Check the settings are usable
+    pub fn validate(&self) -> TranscriptionResult<()> {
+        if self.directory.as_os_str().is_empty() {
+            return Err(TranscriptionError::InvalidConfig {
+                details: "Models directory cannot be empty".to_string(),
+            });
+        }
+        if self.speech_model.as_deref().is_some_and(|id| id.trim().is_empty()) {
+            return Err(TranscriptionError::InvalidConfig {
+                details: "Speech model id cannot be empty".to_string(),
+            });
+        }
+        if let Some(url) = &self.download_url {
+            if !url.starts_with("https://") && !url.starts_with("http://") {
+                return Err(TranscriptionError::InvalidConfig {
+                    details: format!("Model download URL {} must be http(s)", url),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// What a model's file name says about it
+///
+/// whisper.cpp names its files `ggml-{variant}[.en][-{quantization}].bin`,
+/// e.g. "ggml-large-v3-q5_0.bin"; unquantized GGML weights are f16.
+/// Speaker embedding models are recognised by name or as ONNX files.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModelDescription {
+    pub id: String,
+    pub kind: ModelKind,
+    pub variant: String,
+    pub languages: Vec<String>,
+    pub quantization: String,
+}
+
+impl ModelDescription {
+    /// Describe the model at `path`; `None` if it isn't a model file
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+        if !MODEL_EXTENSIONS.contains(&extension.as_str()) {
+            return None;
+        }
+        let id = path.file_stem()?.to_str()?.to_string();
+        let lower = id.to_lowercase();
+
+        let is_embedding = extension == "onnx"
+            || ["embedding", "ecapa", "wespeaker", "xvector", "speaker"].iter().any(|k| lower.contains(k));
+        let kind = if is_embedding { ModelKind::Embedding } else { ModelKind::Speech };
+
+        let name = lower.strip_prefix("ggml-").unwrap_or(&lower);
+        let (name, quantization) = match name.rsplit_once('-') {
+            Some((rest, suffix)) if is_quantization(suffix) => (rest, suffix.to_string()),
+            _ if extension == "onnx" => (name, "f32".to_string()),
+            _ => (name, "f16".to_string()),
+        };
+        let (variant, languages) = match name.strip_suffix(".en") {
+            Some(variant) => (variant, vec!["en".to_string()]),
+            None => (name, Vec::new()),
+        };
+
+        Some(Self {
+            id,
+            kind,
+            variant: variant.to_string(),
+            languages,
+            quantization,
+        })
+    }
+}
+
+/// Quantization suffixes such as "q5_1", "q4_k", "q8_0", "f16"
+fn is_quantization(suffix: &str) -> bool {
+    match suffix.strip_prefix('q') {
+        Some(rest) => rest.starts_with(|c: char| c.is_ascii_digit()),
+        None => suffix == "f16" || suffix == "f32",
+    }
+}
+
+/// The registry's speech model to load for `config`, with its checksum
+/// verified
+///
+/// Returns `None` when no speech model is registered, in which case the
+/// engine falls back to `whisper_model_path`.
+pub fn verified_speech_model(config: &AIConfig) -> TranscriptionResult<Option<(InstalledModel, PathBuf)>> {
+    let registry = ModelRegistry::open(&config.models.directory)?;
+    let model = match &config.models.speech_model {
+        Some(id) => registry.get(id).ok_or_else(|| TranscriptionError::ModelUnavailable { model: id.clone() })?,
+        None => {
+            let languages: Vec<String> = config.transcription.language.iter()
+                .chain(&config.transcription.candidate_languages)
+                .cloned()
+                .collect();
+            match registry.best_for(ModelKind::Speech, &languages, &Hardware::detect()) {
+                Some(model) => model,
+                None => return Ok(None),
+            }
+        }
+    };
+
+    if config.models.verify_checksums {
+        registry.verify(&model.id)?;
+    } else {
+        warn!("Loading model {} without checking its SHA-256", model.id);
+    }
+    info!("Using {} model {} ({})", model.variant, model.id, model.quantization);
+    Ok(Some((model.clone(), registry.path(model))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_whisper_file_names_are_described() {
+        let model = ModelDescription::from_path(Path::new("/tmp/ggml-large-v3-turbo-q5_0.bin")).unwrap();
+        assert_eq!(model.id, "ggml-large-v3-turbo-q5_0");
+        assert_eq!(model.kind, ModelKind::Speech);
+        assert_eq!(model.variant, "large-v3-turbo");
+        assert!(model.languages.is_empty());
+        assert_eq!(model.quantization, "q5_0");
+
+        let english = ModelDescription::from_path(Path::new("ggml-base.en.bin")).unwrap();
+        assert_eq!((english.variant.as_str(), english.quantization.as_str()), ("base", "f16"));
+        assert_eq!(english.languages, vec!["en".to_string()]);
+
+        let embedding = ModelDescription::from_path(Path::new("wespeaker-resnet34.onnx")).unwrap();
+        assert_eq!((embedding.kind, embedding.quantization.as_str()), (ModelKind::Embedding, "f32"));
+
+        assert_eq!(ModelDescription::from_path(Path::new("notes.txt")), None);
+    }
+}
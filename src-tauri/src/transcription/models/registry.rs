@@ -0,0 +1,620 @@
+//! The models directory and its manifest
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tracing::{info, warn};
+
+use crate::transcription::types::{TranscriptionError, TranscriptionResult};
+
+use super::download::ModelDownloader;
+use super::hardware::Hardware;
+use super::{InstalledModel, ModelDescription, ModelKind};
+
+/// Manifest file within the models directory
+const MANIFEST: &str = "models.json";
+
+/// Checksum lists a directory of models may ship with, in `sha256sum` format
+const CHECKSUM_LISTS: &[&str] = &["SHA256SUMS", "sha256sums.txt"];
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Manifest {
+    models: Vec<InstalledModel>,
+}
+
+/// Space taken by one model
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ModelUsage {
+    pub id: String,
+    pub kind: ModelKind,
+    pub size_bytes: u64,
+    /// Whether the file is still there
+    pub present: bool,
+}
+
+/// Space taken by the models directory
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DiskUsage {
+    pub directory: PathBuf,
+    pub total_bytes: u64,
+    pub speech_bytes: u64,
+    pub embedding_bytes: u64,
+    /// Free space left on the disk, when it could be read
+    pub available_bytes: Option<u64>,
+    pub models: Vec<ModelUsage>,
+}
+
+/// The installed models and the directory they are kept in
+#[derive(Debug, Clone)]
+pub struct ModelRegistry {
+    directory: PathBuf,
+    models: Vec<InstalledModel>,
+}
+
+impl ModelRegistry {
+    /// Read the registry kept in `directory`; a directory without a manifest
+    /// has no models yet
+    pub fn open(directory: impl Into<PathBuf>) -> TranscriptionResult<Self> {
+        let directory = directory.into();
+        let manifest = directory.join(MANIFEST);
+        let models = match fs::read_to_string(&manifest) {
+            Ok(json) => serde_json::from_str::<Manifest>(&json)
+                .map_err(|e| TranscriptionError::Internal {
+                    message: format!("Invalid model manifest {}: {}", manifest.display(), e),
+                })?
+                .models,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(io_error("read", &manifest, e)),
+        };
+
+        Ok(Self { directory, models })
+    }
+
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    pub fn models(&self) -> &[InstalledModel] {
+        &self.models
+    }
+
+    pub fn get(&self, id: &str) -> Option<&InstalledModel> {
+        self.models.iter().find(|model| model.id == id)
+    }
+
+    /// Where a model's file is
+    pub fn path(&self, model: &InstalledModel) -> PathBuf {
+        self.directory.join(&model.file)
+    }
+
+    /// Import a model file, or every model file in a directory
+    ///
+    /// Files are copied into the models directory. Checksums published
+    /// alongside them, in a `SHA256SUMS` list or a `<file>.sha256` file, are
+    /// checked before anything is installed.
+    pub fn import(&mut self, source: &Path) -> TranscriptionResult<Vec<InstalledModel>> {
+        if !source.is_dir() {
+            let expected = sidecar_checksum(source)?;
+            return Ok(vec![self.import_file(source, expected.as_deref())?]);
+        }
+
+        let published = published_checksums(source)?;
+        let mut files: Vec<PathBuf> = fs::read_dir(source)
+            .map_err(|e| io_error("read", source, e))?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.is_file() && ModelDescription::from_path(path).is_some())
+            .collect();
+        if files.is_empty() {
+            return Err(TranscriptionError::ModelUnavailable {
+                model: format!("{} (no .bin, .gguf or .onnx models)", source.display()),
+            });
+        }
+        files.sort();
+
+        files
+            .iter()
+            .map(|file| {
+                let expected = match published.get(&file_name(file)) {
+                    Some(sha256) => Some(sha256.clone()),
+                    None => sidecar_checksum(file)?,
+                };
+                self.import_file(file, expected.as_deref())
+            })
+            .collect()
+    }
+
+    /// Import one model file, checking it against `expected_sha256` if given
+    pub fn import_file(&mut self, source: &Path, expected_sha256: Option<&str>) -> TranscriptionResult<InstalledModel> {
+        let description = describe(source)?;
+        let file = file_name(source);
+        let destination = self.directory.join(&file);
+
+        let already_here = fs::canonicalize(source).ok().is_some_and(|source| {
+            fs::canonicalize(&destination).is_ok_and(|destination| destination == source)
+        });
+        if already_here {
+            let (sha256, size_bytes) = hash_file(source)?;
+            check_checksum(&description.id, expected_sha256, &sha256)?;
+            return self.register(description, file, size_bytes, sha256, source.display().to_string());
+        }
+
+        let mut reader = File::open(source).map_err(|e| io_error("open", source, e))?;
+        self.receive(description, &file, expected_sha256, source.display().to_string(), |writer| {
+            io::copy(&mut reader, writer).map_err(|e| io_error("copy", source, e))
+        })
+    }
+
+    /// Download the model file called `file` with `downloader` and install it
+    pub fn download(
+        &mut self,
+        downloader: &dyn ModelDownloader,
+        file: &str,
+        expected_sha256: Option<&str>,
+    ) -> TranscriptionResult<InstalledModel> {
+        if file.is_empty() || file.contains(['/', '\\']) || file.starts_with('.') {
+            return Err(TranscriptionError::InvalidConfig {
+                details: format!("{} is not a model file name", file),
+            });
+        }
+        let description = describe(Path::new(file))?;
+        self.receive(description, file, expected_sha256, format!("download:{}", file), |writer| {
+            downloader.download(file, writer)
+        })
+    }
+
+    /// Recompute a model's SHA-256 and check it matches the one recorded at
+    /// install
+    pub fn verify(&self, id: &str) -> TranscriptionResult<&InstalledModel> {
+        let model = self.get(id).ok_or_else(|| TranscriptionError::ModelUnavailable { model: id.to_string() })?;
+        let path = self.path(model);
+        if !path.is_file() {
+            return Err(TranscriptionError::ModelUnavailable { model: path.display().to_string() });
+        }
+
+        let (sha256, _) = hash_file(&path)?;
+        check_checksum(&model.id, Some(&model.sha256), &sha256)?;
+        Ok(model)
+    }
+
+    /// Uninstall a model and delete its file
+    pub fn remove(&mut self, id: &str) -> TranscriptionResult<InstalledModel> {
+        let index = self.models.iter().position(|model| model.id == id)
+            .ok_or_else(|| TranscriptionError::ModelUnavailable { model: id.to_string() })?;
+        let model = self.models.remove(index);
+
+        let path = self.path(&model);
+        match fs::remove_file(&path) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::NotFound => warn!("Model file {} was already gone", path.display()),
+            Err(e) => return Err(io_error("delete", &path, e)),
+        }
+        self.save()?;
+
+        info!("Removed model {}", model.id);
+        Ok(model)
+    }
+
+    /// How much disk the installed models take
+    pub fn disk_usage(&self) -> DiskUsage {
+        let models: Vec<ModelUsage> = self
+            .models
+            .iter()
+            .map(|model| {
+                let size = fs::metadata(self.path(model)).ok().filter(|metadata| metadata.is_file()).map(|m| m.len());
+                ModelUsage {
+                    id: model.id.clone(),
+                    kind: model.kind,
+                    size_bytes: size.unwrap_or(0),
+                    present: size.is_some(),
+                }
+            })
+            .collect();
+        let bytes_of = |kind: ModelKind| models.iter().filter(|m| m.kind == kind).map(|m| m.size_bytes).sum::<u64>();
+
+        DiskUsage {
+            directory: self.directory.clone(),
+            total_bytes: models.iter().map(|m| m.size_bytes).sum(),
+            speech_bytes: bytes_of(ModelKind::Speech),
+            embedding_bytes: bytes_of(ModelKind::Embedding),
+            available_bytes: available_space(&self.directory),
+            models,
+        }
+    }
+
+    /// The most capable model of `kind` this machine can run for `languages`
+    ///
+    /// Larger architectures win, then models trained for just the requested
+    /// languages, then finer quantization. Models too big for the memory
+    /// budget or too slow for the cores are passed over; if that leaves
+    /// nothing, the lightest model is used. Without a model for the
+    /// languages, any model of the kind is considered.
+    pub fn best_for(&self, kind: ModelKind, languages: &[String], hardware: &Hardware) -> Option<&InstalledModel> {
+        let of_kind: Vec<&InstalledModel> = self.models.iter().filter(|model| model.kind == kind).collect();
+        let speaking: Vec<&InstalledModel> = of_kind.iter().copied().filter(|model| model.supports(languages)).collect();
+        let candidates = if speaking.is_empty() { of_kind } else { speaking };
+
+        candidates
+            .iter()
+            .copied()
+            .filter(|model| {
+                model.memory_bytes() <= hardware.memory_budget() && model.variant_rank() <= hardware.max_variant_rank()
+            })
+            .max_by_key(|model| (model.variant_rank(), !model.languages.is_empty(), model.precision_bits()))
+            .or_else(|| candidates.iter().copied().min_by_key(|model| model.memory_bytes()))
+    }
+
+    /// Write the bytes `write` produces to a temporary file, check them and
+    /// move them into place
+    fn receive(
+        &mut self,
+        description: ModelDescription,
+        file: &str,
+        expected_sha256: Option<&str>,
+        source: String,
+        write: impl FnOnce(&mut dyn Write) -> TranscriptionResult<u64>,
+    ) -> TranscriptionResult<InstalledModel> {
+        fs::create_dir_all(&self.directory).map_err(|e| io_error("create", &self.directory, e))?;
+        let partial = self.directory.join(format!(".{}.part", file));
+        let destination = self.directory.join(file);
+
+        let received = File::create(&partial)
+            .map_err(|e| io_error("create", &partial, e))
+            .and_then(|out| {
+                let mut writer = HashingWriter::new(out);
+                write(&mut writer)?;
+                writer.flush().map_err(|e| io_error("write", &partial, e))?;
+                let (sha256, size_bytes) = writer.finish();
+                check_checksum(&description.id, expected_sha256, &sha256)?;
+                Ok((sha256, size_bytes))
+            });
+        let (sha256, size_bytes) = match received {
+            Ok(received) => received,
+            Err(e) => {
+                let _ = fs::remove_file(&partial);
+                return Err(e);
+            }
+        };
+
+        if let Some(existing) = self.get(&description.id) {
+            let _ = fs::remove_file(&partial);
+            if existing.sha256 == sha256 && self.path(existing).is_file() {
+                return Ok(existing.clone());
+            }
+            return Err(TranscriptionError::InvalidConfig {
+                details: format!("A different model {} is already installed; remove it first", description.id),
+            });
+        }
+
+        fs::rename(&partial, &destination).map_err(|e| io_error("install", &destination, e))?;
+        self.register(description, file.to_string(), size_bytes, sha256, source)
+    }
+
+    /// Record a model whose file is in place
+    fn register(
+        &mut self,
+        description: ModelDescription,
+        file: String,
+        size_bytes: u64,
+        sha256: String,
+        source: String,
+    ) -> TranscriptionResult<InstalledModel> {
+        if let Some(existing) = self.get(&description.id) {
+            if existing.sha256 == sha256 {
+                return Ok(existing.clone());
+            }
+            return Err(TranscriptionError::InvalidConfig {
+                details: format!("A different model {} is already installed; remove it first", description.id),
+            });
+        }
+
+        let model = InstalledModel {
+            id: description.id,
+            kind: description.kind,
+            file,
+            variant: description.variant,
+            size_bytes,
+            languages: description.languages,
+            quantization: description.quantization,
+            sha256,
+            source,
+            installed_at: Utc::now(),
+        };
+        self.models.push(model.clone());
+        self.save()?;
+
+        info!("Installed {:?} model {} ({} bytes)", model.kind, model.id, model.size_bytes);
+        Ok(model)
+    }
+
+    /// Write the manifest, replacing the old one only once it is complete
+    fn save(&self) -> TranscriptionResult<()> {
+        fs::create_dir_all(&self.directory).map_err(|e| io_error("create", &self.directory, e))?;
+        let manifest = self.directory.join(MANIFEST);
+        let partial = self.directory.join(format!(".{}.part", MANIFEST));
+
+        let json = serde_json::to_string_pretty(&Manifest { models: self.models.clone() })
+            .map_err(|e| TranscriptionError::Internal {
+                message: format!("Failed to encode model manifest: {}", e),
+            })?;
+        fs::write(&partial, json).map_err(|e| io_error("write", &partial, e))?;
+        fs::rename(&partial, &manifest).map_err(|e| io_error("write", &manifest, e))
+    }
+}
+
+/// Passes bytes through while hashing and counting them
+struct HashingWriter<W> {
+    inner: W,
+    hasher: Sha256,
+    bytes: u64,
+}
+
+impl<W: Write> HashingWriter<W> {
+    fn new(inner: W) -> Self {
+        Self { inner, hasher: Sha256::new(), bytes: 0 }
+    }
+
+    /// Hex SHA-256 and length of everything written
+    fn finish(self) -> (String, u64) {
+        (hex(&self.hasher.finalize()), self.bytes)
+    }
+}
+
+impl<W: Write> Write for HashingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.hasher.update(&buf[..written]);
+        self.bytes += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Hex SHA-256 and length of a file
+fn hash_file(path: &Path) -> TranscriptionResult<(String, u64)> {
+    let mut file = File::open(path).map_err(|e| io_error("open", path, e))?;
+    let mut writer = HashingWriter::new(io::sink());
+    let mut buffer = vec![0u8; 1 << 20];
+    loop {
+        let read = file.read(&mut buffer).map_err(|e| io_error("read", path, e))?;
+        if read == 0 {
+            break;
+        }
+        writer.write_all(&buffer[..read]).map_err(|e| io_error("hash", path, e))?;
+    }
+    Ok(writer.finish())
+}
+
+fn check_checksum(model: &str, expected: Option<&str>, actual: &str) -> TranscriptionResult<()> {
+    match expected {
+        Some(expected) if !expected.eq_ignore_ascii_case(actual) => Err(TranscriptionError::ChecksumMismatch {
+            model: model.to_string(),
+            expected: expected.to_lowercase(),
+            actual: actual.to_string(),
+        }),
+        _ => Ok(()),
+    }
+}
+
+fn describe(path: &Path) -> TranscriptionResult<ModelDescription> {
+    ModelDescription::from_path(path).ok_or_else(|| TranscriptionError::ModelUnavailable {
+        model: format!("{} (not a .bin, .gguf or .onnx model)", path.display()),
+    })
+}
+
+/// Checksums listed in a directory's `SHA256SUMS`, by file name
+fn published_checksums(directory: &Path) -> TranscriptionResult<HashMap<String, String>> {
+    let Some(list) = CHECKSUM_LISTS.iter().map(|name| directory.join(name)).find(|path| path.is_file()) else {
+        return Ok(HashMap::new());
+    };
+    let text = fs::read_to_string(&list).map_err(|e| io_error("read", &list, e))?;
+
+    // Lines are "<sha256>  <file>", with a `*` before the file in binary mode
+    Ok(text
+        .lines()
+        .filter_map(|line| line.trim().split_once(char::is_whitespace))
+        .filter(|(sha256, _)| is_sha256(sha256))
+        .map(|(sha256, file)| {
+            let file = file.trim().trim_start_matches('*');
+            (file.rsplit(['/', '\\']).next().unwrap_or(file).to_string(), sha256.to_lowercase())
+        })
+        .collect())
+}
+
+/// The checksum in `<file>.sha256` next to a model, if there is one
+fn sidecar_checksum(model: &Path) -> TranscriptionResult<Option<String>> {
+    let sidecar = PathBuf::from(format!("{}.sha256", model.display()));
+    if !sidecar.is_file() {
+        return Ok(None);
+    }
+    let text = fs::read_to_string(&sidecar).map_err(|e| io_error("read", &sidecar, e))?;
+    match text.split_whitespace().next() {
+        Some(sha256) if is_sha256(sha256) => Ok(Some(sha256.to_lowercase())),
+        _ => Err(TranscriptionError::InvalidConfig {
+            details: format!("{} does not hold a SHA-256", sidecar.display()),
+        }),
+    }
+}
+
+fn is_sha256(text: &str) -> bool {
+    text.len() == 64 && text.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default()
+}
+
+fn io_error(action: &str, path: &Path, error: io::Error) -> TranscriptionError {
+    TranscriptionError::Internal {
+        message: format!("Failed to {} {}: {}", action, path.display(), error),
+    }
+}
+
+/// Free space on the disk holding `directory`
+#[cfg(unix)]
+fn available_space(directory: &Path) -> Option<u64> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = CString::new(directory.as_os_str().as_bytes()).ok()?;
+    // SAFETY: `path` is NUL-terminated and `stats` is only read once
+    // statvfs has filled it in
+    unsafe {
+        let mut stats: libc::statvfs = std::mem::zeroed();
+        (libc::statvfs(path.as_ptr(), &mut stats) == 0).then(|| stats.f_bavail as u64 * stats.f_frsize as u64)
+    }
+}
+
+#[cfg(not(unix))]
+fn available_space(_directory: &Path) -> Option<u64> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GIB: u64 = 1024 * 1024 * 1024;
+
+    fn scratch(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("meeting-mind-models-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn sha256(bytes: &[u8]) -> String {
+        hex(&Sha256::digest(bytes))
+    }
+
+    struct FakeDownloader(Vec<u8>);
+
+    impl ModelDownloader for FakeDownloader {
+        fn download(&self, _file: &str, destination: &mut dyn Write) -> TranscriptionResult<u64> {
+            destination.write_all(&self.0).unwrap();
+            Ok(self.0.len() as u64)
+        }
+    }
+
+    #[test]
+    fn test_import_checks_published_checksums_and_detects_tampering() {
+        let root = scratch("import");
+        let downloads = root.join("downloads");
+        fs::create_dir_all(&downloads).unwrap();
+        fs::write(downloads.join("ggml-base.en.bin"), b"base weights").unwrap();
+        fs::write(downloads.join("ggml-small-q5_1.bin"), b"small weights").unwrap();
+        fs::write(downloads.join("README.md"), b"not a model").unwrap();
+        fs::write(
+            downloads.join("SHA256SUMS"),
+            format!("{}  ggml-base.en.bin\n{} *ggml-small-q5_1.bin\n", sha256(b"base weights"), sha256(b"small weights")),
+        )
+        .unwrap();
+
+        let mut registry = ModelRegistry::open(root.join("models")).unwrap();
+        let imported = registry.import(&downloads).unwrap();
+        let ids: Vec<_> = imported.iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(ids, vec!["ggml-base.en", "ggml-small-q5_1"]);
+        assert_eq!(imported[0].size_bytes, 12);
+        assert_eq!(imported[1].quantization, "q5_1");
+
+        // The manifest survives reopening, and importing again changes nothing
+        let mut registry = ModelRegistry::open(root.join("models")).unwrap();
+        assert_eq!(registry.models().len(), 2);
+        registry.import(&downloads.join("ggml-base.en.bin")).unwrap();
+        assert_eq!(registry.models().len(), 2);
+        assert!(registry.verify("ggml-base.en").is_ok());
+
+        fs::write(registry.path(&imported[0]), b"base weights, edited").unwrap();
+        assert!(matches!(registry.verify("ggml-base.en"), Err(TranscriptionError::ChecksumMismatch { .. })));
+
+        let usage = registry.disk_usage();
+        assert_eq!(usage.total_bytes, 20 + 13);
+        assert_eq!(usage.speech_bytes, usage.total_bytes);
+        assert_eq!(usage.embedding_bytes, 0);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_corrupt_copies_are_never_installed() {
+        let root = scratch("corrupt");
+        fs::write(root.join("ggml-tiny.bin"), b"tiny weights").unwrap();
+        fs::write(root.join("ggml-tiny.bin.sha256"), format!("{}  ggml-tiny.bin\n", sha256(b"other weights"))).unwrap();
+
+        let mut registry = ModelRegistry::open(root.join("models")).unwrap();
+        let result = registry.import(&root.join("ggml-tiny.bin"));
+        assert!(matches!(result, Err(TranscriptionError::ChecksumMismatch { .. })));
+
+        let download = registry.download(&FakeDownloader(b"medium weights".to_vec()), "ggml-medium.bin", Some(&sha256(b"x")));
+        assert!(matches!(download, Err(TranscriptionError::ChecksumMismatch { .. })));
+        assert!(registry.models().is_empty());
+        assert_eq!(fs::read_dir(root.join("models")).unwrap().count(), 0);
+
+        let model = registry
+            .download(&FakeDownloader(b"medium weights".to_vec()), "ggml-medium.bin", Some(&sha256(b"medium weights")))
+            .unwrap();
+        assert_eq!(model.source, "download:ggml-medium.bin");
+        registry.remove(&model.id).unwrap();
+        assert!(!root.join("models/ggml-medium.bin").exists());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_best_model_fits_the_hardware_and_languages() {
+        let model = |id: &str, size_bytes: u64| {
+            let description = ModelDescription::from_path(Path::new(&format!("{}.bin", id))).unwrap();
+            InstalledModel {
+                id: description.id,
+                kind: description.kind,
+                file: format!("{}.bin", id),
+                variant: description.variant,
+                size_bytes,
+                languages: description.languages,
+                quantization: description.quantization,
+                sha256: String::new(),
+                source: String::new(),
+                installed_at: Utc::now(),
+            }
+        };
+        let registry = ModelRegistry {
+            directory: PathBuf::from("models"),
+            models: vec![
+                model("ggml-base", GIB / 7),
+                model("ggml-base.en", GIB / 7),
+                model("ggml-small-q5_1", GIB / 5),
+                model("ggml-small", GIB / 2),
+                model("ggml-large-v3", 3 * GIB),
+            ],
+        };
+        let best = |languages: &[&str], cores, memory_gib: u64| {
+            let languages: Vec<String> = languages.iter().map(|l| l.to_string()).collect();
+            let hardware = Hardware { cores, memory_bytes: Some(memory_gib * GIB) };
+            registry.best_for(ModelKind::Speech, &languages, &hardware).unwrap().id.clone()
+        };
+
+        assert_eq!(best(&[], 16, 32), "ggml-large-v3");
+        // Large needs more memory than half of 4 GiB
+        assert_eq!(best(&[], 16, 4), "ggml-small");
+        // Four cores can't keep up with large or medium
+        assert_eq!(best(&["de"], 4, 32), "ggml-small");
+        // Too little memory for full precision small
+        assert_eq!(best(&[], 4, 1), "ggml-small-q5_1");
+        // English-only models win at the same size when English is asked for
+        assert_eq!(best(&["en"], 2, 32), "ggml-base.en");
+        assert_eq!(best(&[], 2, 32), "ggml-base");
+        // With nothing that fits, the lightest model is used
+        assert_eq!(best(&[], 1, 0), "ggml-base");
+        assert!(registry.best_for(ModelKind::Embedding, &[], &Hardware::detect()).is_none());
+    }
+}
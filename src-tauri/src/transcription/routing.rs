@@ -0,0 +1,417 @@
+//! Hybrid local/cloud transcription
+//!
+//! Every chunk is transcribed locally first. Segments the local model is
+//! unsure of, or in a language it isn't trusted with, are cut out (with a
+//! little padding for context) and sent to the cloud engine; its segments
+//! replace the local ones in that stretch of the timeline. Cloud audio is
+//! metered against a per-session budget, and anything that can't be sent --
+//! budget spent, request failed -- keeps its local transcript.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info, warn};
+
+use crate::audio::{AudioBuffer, AudioChunk};
+
+use super::engine::{session_engine, TranscriptionEngine};
+use super::language::LanguageScore;
+use super::types::{
+    TranscriptSegment, TranscriptionError, TranscriptionOptions, TranscriptionResult,
+};
+
+/// When to send audio to the cloud and how much
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RoutingConfig {
+    /// Local segments below this confidence are re-transcribed in the cloud
+    pub min_confidence: f32,
+    /// Languages the local model is trusted with; empty trusts it with all
+    pub local_languages: Vec<String>,
+    /// Cloud audio allowed per meeting, in seconds
+    pub budget_seconds: f64,
+    /// Audio either side of a routed segment sent along for context
+    pub padding_ms: f64,
+    /// Root of the OpenAI-compatible API
+    pub api_base_url: String,
+    /// Model requested from the API
+    pub model: String,
+    pub timeout_secs: u64,
+}
+
+impl Default for RoutingConfig {
+    fn default() -> Self {
+        Self {
+            min_confidence: 0.6,
+            local_languages: Vec::new(),
+            budget_seconds: 600.0,
+            padding_ms: 250.0,
+            api_base_url: "https://api.openai.com/v1".to_string(),
+            model: "whisper-1".to_string(),
+            timeout_secs: 30,
+        }
+    }
+}
+
+impl RoutingConfig {
+    /// Check the settings are usable
+    pub fn validate(&self) -> TranscriptionResult<()> {
+        if !(0.0..=1.0).contains(&self.min_confidence) {
+            return Err(TranscriptionError::InvalidConfig {
+                details: format!("Routing confidence {} must be between 0 and 1", self.min_confidence),
+            });
+        }
+        if self.budget_seconds < 0.0 || self.padding_ms < 0.0 || self.timeout_secs == 0 {
+            return Err(TranscriptionError::InvalidConfig {
+                details: "Cloud budget, padding and timeout must not be negative or zero".to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    fn trusts_locally(&self, language: &str) -> bool {
+        self.local_languages.is_empty()
+            || self.local_languages.iter().any(|trusted| trusted.eq_ignore_ascii_case(language))
+    }
+}
+
+/// Cloud audio allowance for one meeting
+#[derive(Debug)]
+pub struct CloudBudget {
+    limit_ms: f64,
+    used_ms: Mutex<f64>,
+}
+
+impl CloudBudget {
+    pub fn new(limit_ms: f64) -> Self {
+        Self { limit_ms, used_ms: Mutex::new(0.0) }
+    }
+
+    /// Take `ms` from the budget, if that much is left
+    pub fn try_spend(&self, ms: f64) -> bool {
+        let Ok(mut used) = self.used_ms.lock() else {
+            return false;
+        };
+        if *used + ms > self.limit_ms {
+            return false;
+        }
+        *used += ms;
+        true
+    }
+
+    /// Give back audio that wasn't transcribed after all
+    pub fn refund(&self, ms: f64) {
+        if let Ok(mut used) = self.used_ms.lock() {
+            *used = (*used - ms).max(0.0);
+        }
+    }
+
+    pub fn used_ms(&self) -> f64 {
+        self.used_ms.lock().map(|used| *used).unwrap_or(self.limit_ms)
+    }
+
+    pub fn remaining_ms(&self) -> f64 {
+        (self.limit_ms - self.used_ms()).max(0.0)
+    }
+}
+
+/// Counters for routing decisions
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct RoutingStats {
+    /// Stretches of audio sent to the cloud
+    pub cloud_requests: u64,
+    pub cloud_failures: u64,
+    /// Stretches kept local because the budget was spent
+    pub over_budget: u64,
+}
+
+#[derive(Debug, Default)]
+struct RoutingCounters {
+    cloud_requests: AtomicU64,
+    cloud_failures: AtomicU64,
+    over_budget: AtomicU64,
+}
+
+/// A stretch of the chunk to re-transcribe, on the meeting timeline
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Span {
+    start_ms: f64,
+    end_ms: f64,
+}
+
+/// Engine that routes doubtful local results to a cloud engine
+pub struct HybridEngine {
+    local: Arc<dyn TranscriptionEngine>,
+    cloud: Arc<dyn TranscriptionEngine>,
+    config: RoutingConfig,
+    budget: CloudBudget,
+    /// Shared by every session
+    counters: Arc<RoutingCounters>,
+    name: String,
+}
+
+impl HybridEngine {
+    pub fn new(
+        local: Arc<dyn TranscriptionEngine>,
+        cloud: Arc<dyn TranscriptionEngine>,
+        config: RoutingConfig,
+    ) -> Self {
+        info!(
+            "Routing {} segments below {:.2} confidence to {} ({}s per meeting)",
+            local.name(), config.min_confidence, cloud.name(), config.budget_seconds
+        );
+        Self {
+            name: format!("{}+{}", local.name(), cloud.name()),
+            budget: CloudBudget::new(config.budget_seconds * 1000.0),
+            local,
+            cloud,
+            config,
+            counters: Arc::default(),
+        }
+    }
+
+    pub fn budget(&self) -> &CloudBudget {
+        &self.budget
+    }
+
+    pub fn routing_stats(&self) -> RoutingStats {
+        RoutingStats {
+            cloud_requests: self.counters.cloud_requests.load(Ordering::Relaxed),
+            cloud_failures: self.counters.cloud_failures.load(Ordering::Relaxed),
+            over_budget: self.counters.over_budget.load(Ordering::Relaxed),
+        }
+    }
+
+    fn needs_cloud(&self, segment: &TranscriptSegment) -> bool {
+        segment.confidence < self.config.min_confidence || !self.config.trusts_locally(&segment.language)
+    }
+
+    /// Padded stretches covering the segments to re-transcribe, merged where
+    /// they touch
+    fn spans(&self, chunk: &AudioChunk, segments: &[TranscriptSegment]) -> Vec<Span> {
+        let chunk_end = chunk.start_ms + chunk.buffer.duration_ms();
+        let mut spans: Vec<Span> = Vec::new();
+
+        for segment in segments.iter().filter(|s| self.needs_cloud(s)) {
+            let span = Span {
+                start_ms: (segment.start_ms - self.config.padding_ms).max(chunk.start_ms),
+                end_ms: (segment.end_ms + self.config.padding_ms).min(chunk_end),
+            };
+            match spans.last_mut() {
+                Some(last) if span.start_ms <= last.end_ms => last.end_ms = last.end_ms.max(span.end_ms),
+                _ => spans.push(span),
+            }
+        }
+
+        spans
+    }
+
+    /// Transcribe `span` of `chunk` in the cloud, if the budget allows
+    fn transcribe_span(
+        &self,
+        chunk: &AudioChunk,
+        span: Span,
+        options: &TranscriptionOptions,
+    ) -> Option<Vec<TranscriptSegment>> {
+        let duration_ms = span.end_ms - span.start_ms;
+        if !self.budget.try_spend(duration_ms) {
+            self.counters.over_budget.fetch_add(1, Ordering::Relaxed);
+            debug!("Cloud budget spent, keeping local transcript at {:.0}ms", span.start_ms);
+            return None;
+        }
+        self.counters.cloud_requests.fetch_add(1, Ordering::Relaxed);
+
+        let rate = chunk.buffer.sample_rate as f64;
+        let channels = chunk.buffer.channels.max(1) as usize;
+        let frame = |ms: f64| (((ms - chunk.start_ms) / 1000.0 * rate) as usize * channels).min(chunk.buffer.samples.len());
+        let clip = AudioChunk {
+            index: chunk.index,
+            start_ms: span.start_ms,
+            buffer: AudioBuffer::new(
+                chunk.buffer.samples[frame(span.start_ms)..frame(span.end_ms)].to_vec(),
+                chunk.buffer.sample_rate,
+                chunk.buffer.channels,
+            ),
+        };
+
+        match self.cloud.transcribe(&clip, options) {
+            Ok(segments) => Some(segments),
+            Err(e) => {
+                self.budget.refund(duration_ms);
+                self.counters.cloud_failures.fetch_add(1, Ordering::Relaxed);
+                warn!("Cloud transcription failed, keeping local transcript: {}", e);
+                None
+            }
+        }
+    }
+}
+
+impl TranscriptionEngine for HybridEngine {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.local.sample_rate()
+    }
+
+    fn supports_prompt(&self) -> bool {
+        self.local.supports_prompt() && self.cloud.supports_prompt()
+    }
+
+    fn identify_language(&self, chunk: &AudioChunk) -> TranscriptionResult<Vec<LanguageScore>> {
+        self.local.identify_language(chunk)
+    }
+
+    fn new_session(&self) -> Option<Arc<dyn TranscriptionEngine>> {
+        Some(Arc::new(Self {
+            local: session_engine(&self.local),
+            cloud: session_engine(&self.cloud),
+            config: self.config.clone(),
+            budget: CloudBudget::new(self.config.budget_seconds * 1000.0),
+            counters: Arc::clone(&self.counters),
+            name: self.name.clone(),
+        }))
+    }
+
+    fn transcribe(
+        &self,
+        chunk: &AudioChunk,
+        options: &TranscriptionOptions,
+    ) -> TranscriptionResult<Vec<TranscriptSegment>> {
+        // A requested language the local model isn't trusted with goes to the cloud whole
+        if let Some(language) = options.language.as_deref().filter(|l| !self.config.trusts_locally(l)) {
+            let whole = Span { start_ms: chunk.start_ms, end_ms: chunk.start_ms + chunk.buffer.duration_ms() };
+            debug!("Sending chunk {} to the cloud for {}", chunk.index, language);
+            if let Some(segments) = self.transcribe_span(chunk, whole, options) {
+                return Ok(segments);
+            }
+        }
+
+        let mut segments = self.local.transcribe(chunk, options)?;
+        for span in self.spans(chunk, &segments) {
+            let Some(replacement) = self.transcribe_span(chunk, span, options) else {
+                continue;
+            };
+
+            // Cloud segments replace the local ones they overlap; padding may
+            // pick up neighbouring words, which stay local
+            let midpoint = |s: &TranscriptSegment| (s.start_ms + s.end_ms) / 2.0;
+            let replaced: Vec<Span> = segments
+                .iter()
+                .filter(|s| self.needs_cloud(s) && midpoint(s) >= span.start_ms && midpoint(s) <= span.end_ms)
+                .map(|s| Span { start_ms: s.start_ms, end_ms: s.end_ms })
+                .collect();
+            let covered = |s: &TranscriptSegment| {
+                replaced.iter().any(|r| midpoint(s) >= r.start_ms && midpoint(s) < r.end_ms)
+            };
+            let additions: Vec<TranscriptSegment> = replacement.into_iter().filter(covered).collect();
+            segments.retain(|s| !replaced.iter().any(|r| r.start_ms == s.start_ms && r.end_ms == s.end_ms));
+            segments.extend(additions);
+        }
+
+        segments.sort_by(|a, b| a.start_ms.total_cmp(&b.start_ms));
+        Ok(segments)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use crate::transcription::MockEngine;
+
+    /// Local engine that is unsure of every other second
+    struct Hesitant(MockEngine);
+
+    impl TranscriptionEngine for Hesitant {
+        fn name(&self) -> &str {
+            "local"
+        }
+
+        fn transcribe(&self, chunk: &AudioChunk, options: &TranscriptionOptions) -> TranscriptionResult<Vec<TranscriptSegment>> {
+            Ok(self.0.transcribe(chunk, options)?
+                .into_iter()
+                .map(|mut segment| {
+                    segment.model = "local".to_string();
+                    if (segment.start_ms / 1000.0) as usize % 2 == 1 {
+                        segment.confidence = 0.2;
+                    }
+                    segment
+                })
+                .collect())
+        }
+    }
+
+    fn chunk(seconds: usize) -> AudioChunk {
+        AudioChunk { index: 0, start_ms: 0.0, buffer: AudioBuffer::new(vec![0.2; 16000 * seconds], 16000, 1) }
+    }
+
+    fn hybrid(config: RoutingConfig) -> (HybridEngine, Arc<MockEngine>) {
+        let cloud = Arc::new(MockEngine::new().with_script(&["cloud"]).with_window(Duration::from_millis(1500)));
+        let engine = HybridEngine::new(Arc::new(Hesitant(MockEngine::new())), cloud.clone(), config);
+        (engine, cloud)
+    }
+
+    #[test]
+    fn test_low_confidence_segments_are_replaced_from_cloud() {
+        let (engine, cloud) = hybrid(RoutingConfig::default());
+        let segments = engine.transcribe(&chunk(4), &TranscriptionOptions::default()).unwrap();
+
+        let texts: Vec<_> = segments.iter().map(|s| (s.text.as_str(), s.model.as_str())).collect();
+        assert_eq!(texts, vec![("alpha", "local"), ("cloud", "mock"), ("charlie", "local"), ("cloud", "mock")]);
+        assert_eq!(cloud.calls(), 2);
+        // Padding is metered along with the segment itself
+        assert_eq!(engine.budget().used_ms(), 1500.0 + 1250.0);
+    }
+
+    #[test]
+    fn test_spent_budget_keeps_local_transcript() {
+        let (engine, cloud) = hybrid(RoutingConfig { budget_seconds: 2.0, ..Default::default() });
+        let segments = engine.transcribe(&chunk(4), &TranscriptionOptions::default()).unwrap();
+
+        assert_eq!(segments.iter().filter(|s| s.model == "mock").count(), 1);
+        assert_eq!(segments[3].text, "delta");
+        assert_eq!(cloud.calls(), 1);
+        assert_eq!(engine.routing_stats().over_budget, 1);
+    }
+
+    #[test]
+    fn test_each_session_has_its_own_budget() {
+        let (engine, cloud) = hybrid(RoutingConfig { budget_seconds: 2.0, ..Default::default() });
+        let first = engine.new_session().unwrap();
+        let second = engine.new_session().unwrap();
+
+        // One meeting spending its budget leaves the other's, and the engine's, alone
+        first.transcribe(&chunk(4), &TranscriptionOptions::default()).unwrap();
+        let segments = second.transcribe(&chunk(2), &TranscriptionOptions::default()).unwrap();
+        assert_eq!(segments[1].model, "mock");
+        assert_eq!(cloud.calls(), 2);
+        assert_eq!(engine.budget().remaining_ms(), 2000.0);
+        // Routing is counted across sessions
+        assert_eq!(engine.routing_stats().cloud_requests, 2);
+        assert_eq!(engine.routing_stats().over_budget, 1);
+    }
+
+    #[test]
+    fn test_untrusted_language_goes_to_cloud_whole() {
+        let config = RoutingConfig { local_languages: vec!["en".to_string()], ..Default::default() };
+        let (engine, _) = hybrid(config);
+        let options = TranscriptionOptions { language: Some("ja".to_string()), ..Default::default() };
+        let segments = engine.transcribe(&chunk(2), &options).unwrap();
+
+        assert!(segments.iter().all(|s| s.model == "mock" && s.language == "ja"));
+        assert_eq!(engine.budget().used_ms(), 2000.0);
+    }
+
+    #[test]
+    fn test_cloud_failure_refunds_budget() {
+        let cloud = Arc::new(MockEngine::new().failing_on(&[0]));
+        let engine = HybridEngine::new(Arc::new(Hesitant(MockEngine::new())), cloud, RoutingConfig::default());
+        let segments = engine.transcribe(&chunk(2), &TranscriptionOptions::default()).unwrap();
+
+        assert_eq!(segments[1].text, "bravo");
+        assert_eq!(engine.budget().used_ms(), 0.0);
+        assert_eq!(engine.routing_stats().cloud_failures, 1);
+    }
+}
@@ -0,0 +1,945 @@
+//! Scheduling audio chunks onto a transcription engine
+//!
+//! Chunks come from the file importer or, via `ChunkAssembler`, from live
+//! capture. Each one is converted to what the engine expects and handed to it
+//! on a blocking thread; with more than one chunk in flight, results are put
+//! back in chunk order before their segments are published. When capture
+//! has separate microphone and system-audio tracks, segments are attributed
+//! to one or the other before they go out.
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
+use tokio::sync::{broadcast, mpsc};
+use tokio::task::{JoinHandle, JoinSet};
+use tracing::{debug, error, info, warn};
+
+use crate::audio::{downmix_into, AudioBuffer, AudioChunk, LinearResampler, TrackAudio};
+
+use super::alignment::fill_word_timings;
+use super::attribution::{AttributionConfig, TrackAttributor};
+use super::engine::{session_engine, TranscriptionEngine};
+use super::language::resolve_options;
+use super::streaming::{StreamingConfig, StreamingTranscriber, TranscriptEvent};
+use super::vocabulary::Vocabulary;
+use super::types::{
+    SpeakerHint, TranscriptSegment, TranscriptionError, TranscriptionOptions, TranscriptionResult,
+    TranscriptionStats,
+};
+
+/// What a live session finalized, once its capture stream has closed
+#[derive(Debug, Clone, Default)]
+pub struct LiveTranscript {
+    /// Finalized segments in order, attributed to capture tracks if set up
+    pub segments: Vec<TranscriptSegment>,
+    pub stats: TranscriptionStats,
+    /// Name of the engine the session used
+    pub engine: String,
+}
+
+/// Settings for the transcription pipeline
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TranscriptionConfig {
+    /// Length of the chunks live capture is cut into
+    pub chunk_ms: f64,
+    /// Chunks the engine may work on at once
+    pub max_concurrent_chunks: usize,
+    /// Chunks that may wait for the engine before producers are held up
+    pub queue_capacity: usize,
+    /// Language to transcribe in; `None` lets the engine detect it per chunk
+    pub language: Option<String>,
+    /// Languages per-chunk detection may choose from; empty allows any
+    pub candidate_languages: Vec<String>,
+    /// How live transcripts are produced and settled
+    pub streaming: StreamingConfig,
+    /// How segments are told apart by capture track
+    pub attribution: AttributionConfig,
+}
+
+impl Default for TranscriptionConfig {
+    fn default() -> Self {
+        Self {
+            chunk_ms: 10_000.0,
+            max_concurrent_chunks: 1,
+            queue_capacity: 16,
+            language: None,
+            candidate_languages: Vec::new(),
+            streaming: StreamingConfig::default(),
+            attribution: AttributionConfig::default(),
+        }
+    }
+}
+
+impl TranscriptionConfig {
+    /// Check the settings are usable
+    pub fn validate(&self) -> TranscriptionResult<()> {
+        if !(1000.0..=60_000.0).contains(&self.chunk_ms) {
+            return Err(TranscriptionError::InvalidConfig {
+                details: format!("Chunk length of {}ms is out of range (1-60s)", self.chunk_ms),
+            });
+        }
+        if self.max_concurrent_chunks == 0 || self.queue_capacity == 0 {
+            return Err(TranscriptionError::InvalidConfig {
+                details: "Concurrency and queue capacity must be at least 1".to_string(),
+            });
+        }
+        self.streaming.validate()?;
+        self.attribution.validate()
+    }
+}
+
+/// Cuts a stream of capture buffers into fixed-length, timestamped chunks
+#[derive(Debug)]
+pub struct ChunkAssembler {
+    chunk_ms: f64,
+    pending: Vec<f32>,
+    sample_rate: u32,
+    channels: u16,
+    next_index: usize,
+    /// Frames already emitted, which places the pending audio on the timeline
+    emitted_frames: u64,
+    emitted_ms: f64,
+}
+
+impl ChunkAssembler {
+    pub fn new(chunk: Duration) -> Self {
+        Self {
+            chunk_ms: chunk.as_secs_f64() * 1000.0,
+            pending: Vec::new(),
+            sample_rate: 0,
+            channels: 0,
+            next_index: 0,
+            emitted_frames: 0,
+            emitted_ms: 0.0,
+        }
+    }
+
+    /// Add a buffer, returning any chunks it completed
+    pub fn push(&mut self, buffer: &AudioBuffer) -> Vec<AudioChunk> {
+        let mut chunks = Vec::new();
+
+        // A format change closes the current chunk; the timeline carries on
+        if buffer.sample_rate != self.sample_rate || buffer.channels != self.channels {
+            chunks.extend(self.finish());
+            self.emitted_ms = self.position_ms();
+            self.emitted_frames = 0;
+            self.sample_rate = buffer.sample_rate;
+            self.channels = buffer.channels;
+        }
+
+        self.pending.extend_from_slice(&buffer.samples);
+        let chunk_len = self.chunk_len();
+        while self.pending.len() >= chunk_len {
+            let rest = self.pending.split_off(chunk_len);
+            let samples = std::mem::replace(&mut self.pending, rest);
+            chunks.push(self.emit(samples));
+        }
+
+        chunks
+    }
+
+    /// Emit whatever audio is still pending as a final, shorter chunk
+    pub fn finish(&mut self) -> Option<AudioChunk> {
+        if self.pending.is_empty() {
+            return None;
+        }
+        let samples = std::mem::take(&mut self.pending);
+        Some(self.emit(samples))
+    }
+
+    /// Move the timeline past audio that never arrived
+    ///
+    /// Pending audio can't be joined to what follows the gap, so it is
+    /// emitted as a shorter chunk first.
+    pub fn skip(&mut self, gap_ms: f64) -> Option<AudioChunk> {
+        let chunk = self.finish();
+        self.emitted_ms = self.position_ms() + gap_ms;
+        self.emitted_frames = 0;
+        chunk
+    }
+
+    /// Timeline position of the end of the audio seen so far
+    pub fn position_ms(&self) -> f64 {
+        let frames = self.emitted_frames + (self.pending.len() / self.channels.max(1) as usize) as u64;
+        self.emitted_ms + frames as f64 * 1000.0 / self.sample_rate.max(1) as f64
+    }
+
+    fn chunk_len(&self) -> usize {
+        let frames = (self.chunk_ms / 1000.0 * self.sample_rate as f64).round() as usize;
+        frames.max(1) * self.channels.max(1) as usize
+    }
+
+    fn emit(&mut self, samples: Vec<f32>) -> AudioChunk {
+        let start_ms = self.emitted_ms + self.emitted_frames as f64 * 1000.0 / self.sample_rate.max(1) as f64;
+        self.emitted_frames += (samples.len() / self.channels.max(1) as usize) as u64;
+
+        let chunk = AudioChunk {
+            index: self.next_index,
+            start_ms,
+            buffer: AudioBuffer::new(samples, self.sample_rate, self.channels),
+        };
+        self.next_index += 1;
+        chunk
+    }
+}
+
+/// Converts a chunk to mono at the engine's sample rate
+pub fn prepare_chunk(chunk: AudioChunk, sample_rate: u32) -> TranscriptionResult<AudioChunk> {
+    let buffer = &chunk.buffer;
+    if buffer.sample_rate == 0 || buffer.channels == 0 {
+        return Err(TranscriptionError::InvalidAudio {
+            details: format!("{} Hz, {} channels", buffer.sample_rate, buffer.channels),
+        });
+    }
+    if buffer.channels == 1 && buffer.sample_rate == sample_rate {
+        return Ok(chunk);
+    }
+
+    let frames = buffer.samples.len() / buffer.channels as usize;
+    let mut mono = vec![0.0; frames];
+    downmix_into(&buffer.samples, buffer.channels, &mut mono);
+
+    let samples = if buffer.sample_rate == sample_rate {
+        mono
+    } else {
+        let mut resampler = LinearResampler::new(buffer.sample_rate, sample_rate, 1);
+        let mut resampled = vec![0.0; resampler.max_output_frames(frames)];
+        let written = resampler.process_into(&mono, &mut resampled);
+        resampled.truncate(written);
+        resampled
+    };
+
+    Ok(AudioChunk {
+        index: chunk.index,
+        start_ms: chunk.start_ms,
+        buffer: AudioBuffer::new(samples, sample_rate, 1),
+    })
+}
+
+/// Runs chunks through a transcription engine and publishes the segments
+pub struct TranscriptionService {
+    engine: Arc<dyn TranscriptionEngine>,
+    config: TranscriptionConfig,
+    segment_broadcaster: broadcast::Sender<TranscriptSegment>,
+    transcript_broadcaster: broadcast::Sender<TranscriptEvent>,
+    stats: Arc<RwLock<TranscriptionStats>>,
+    /// Terms and meeting context for the next session
+    vocabulary: Vocabulary,
+    /// Track levels collected for the next session to attribute segments by
+    attribution: Mutex<Option<Arc<RwLock<TrackAttributor>>>>,
+}
+
+impl TranscriptionService {
+    /// Create a service around `engine` with default settings
+    pub fn new(engine: Arc<dyn TranscriptionEngine>) -> Self {
+        Self::with_config(engine, TranscriptionConfig::default())
+    }
+
+    pub fn with_config(engine: Arc<dyn TranscriptionEngine>, config: TranscriptionConfig) -> Self {
+        let (segment_broadcaster, _) = broadcast::channel(256);
+        let (transcript_broadcaster, _) = broadcast::channel(256);
+        info!("Created transcription service with engine {}", engine.name());
+
+        Self {
+            engine,
+            config,
+            segment_broadcaster,
+            transcript_broadcaster,
+            stats: Arc::new(RwLock::new(TranscriptionStats::default())),
+            vocabulary: Vocabulary::default(),
+            attribution: Mutex::new(None),
+        }
+    }
+
+    pub fn engine_name(&self) -> &str {
+        self.engine.name()
+    }
+
+    /// The engine, to share with work outside the service such as the job queue
+    pub fn engine(&self) -> Arc<dyn TranscriptionEngine> {
+        Arc::clone(&self.engine)
+    }
+
+    pub fn config(&self) -> &TranscriptionConfig {
+        &self.config
+    }
+
+    /// Pin the language or restrict detection to candidates, e.g. for the
+    /// next meeting; takes effect for sessions started afterwards
+    pub fn set_language_policy(&mut self, language: Option<String>, candidates: Vec<String>) {
+        self.config.language = language;
+        self.config.candidate_languages = candidates;
+    }
+
+    /// Bias recognition towards `vocabulary`, e.g. the next meeting's terms,
+    /// title and attendees; takes effect for sessions started afterwards
+    pub fn set_vocabulary(&mut self, vocabulary: Vocabulary) {
+        self.vocabulary = vocabulary;
+    }
+
+    /// Subscribe to segments as they are transcribed, in timeline order
+    pub fn subscribe_segments(&self) -> broadcast::Receiver<TranscriptSegment> {
+        self.segment_broadcaster.subscribe()
+    }
+
+    /// Subscribe to live transcript updates from `stream_capture`
+    pub fn subscribe_transcript(&self) -> broadcast::Receiver<TranscriptEvent> {
+        self.transcript_broadcaster.subscribe()
+    }
+
+    pub fn stats(&self) -> TranscriptionStats {
+        self.stats.read()
+            .map(|stats| stats.clone())
+            .unwrap_or_default()
+    }
+
+    /// Transcribe a single chunk without publishing the result
+    pub async fn transcribe_chunk(&self, chunk: AudioChunk) -> TranscriptionResult<Vec<TranscriptSegment>> {
+        let outcome = run_engine(Arc::clone(&self.engine), chunk, self.options()).await;
+        record(&self.stats, &outcome);
+        outcome.result
+    }
+
+    /// Collect capture track audio to attribute the next session's segments by
+    ///
+    /// `roles` gives each track's speaker, in track order. Call it before
+    /// `start` or `stream_capture`; that session then hints every segment
+    /// with the track it was spoken on. Tracks that can't tell the local
+    /// speaker from the others are ignored.
+    pub fn attribute_tracks(
+        &self,
+        mut tracks: broadcast::Receiver<TrackAudio>,
+        roles: Vec<SpeakerHint>,
+    ) -> Option<JoinHandle<()>> {
+        let track_count = roles.len().max(1);
+        let attributor = TrackAttributor::new(roles, self.config.attribution);
+        if !attributor.distinguishes_roles() {
+            debug!("Capture tracks don't separate speakers, segments won't be attributed");
+            return None;
+        }
+
+        let attributor = Arc::new(RwLock::new(attributor));
+        if let Ok(mut pending) = self.attribution.lock() {
+            *pending = Some(Arc::clone(&attributor));
+        }
+
+        Some(tokio::spawn(async move {
+            let mut skipped_buffers = 0;
+            loop {
+                let audio = match tracks.recv().await {
+                    Ok(audio) => audio,
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!("Track attribution fell behind capture, {} buffers skipped", skipped);
+                        skipped_buffers += skipped;
+                        continue;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+                if let Ok(mut attributor) = attributor.write() {
+                    // The tracks take turns, so each lost its share of the skipped buffers
+                    let gap = std::mem::take(&mut skipped_buffers);
+                    if gap > 0 {
+                        attributor.skip(gap as f64 / track_count as f64 * audio.buffer.duration_ms());
+                    }
+                    attributor.push(audio.track, &audio.buffer);
+                }
+            }
+            debug!("Track attribution ended");
+        }))
+    }
+
+    /// Start transcribing a meeting, returning the queue to send chunks to
+    ///
+    /// The task ends once every sender is dropped and the queue has drained,
+    /// returning the session's stats.
+    pub fn start(&self) -> (mpsc::Sender<AudioChunk>, JoinHandle<TranscriptionStats>) {
+        let (chunk_tx, chunk_rx) = mpsc::channel(self.config.queue_capacity.max(1));
+        (chunk_tx, self.spawn_scheduler(session_engine(&self.engine), chunk_rx))
+    }
+
+    /// Cut live capture audio into chunks and send them to `chunks`
+    pub fn chunk_capture(
+        &self,
+        mut audio: broadcast::Receiver<AudioBuffer>,
+        chunks: mpsc::Sender<AudioChunk>,
+    ) -> JoinHandle<()> {
+        let chunk = Duration::from_secs_f64(self.config.chunk_ms / 1000.0);
+
+        tokio::spawn(async move {
+            let mut assembler = ChunkAssembler::new(chunk);
+            let mut skipped_buffers = 0;
+
+            loop {
+                let buffer = match audio.recv().await {
+                    Ok(buffer) => buffer,
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!("Transcription fell behind capture, {} buffers skipped", skipped);
+                        skipped_buffers += skipped;
+                        continue;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+
+                // Capture buffers are all the same length, so the next one
+                // tells how much audio was lost
+                let gap = std::mem::take(&mut skipped_buffers);
+                let before_gap = (gap > 0)
+                    .then(|| assembler.skip(gap as f64 * buffer.duration_ms()))
+                    .flatten();
+                for chunk in before_gap.into_iter().chain(assembler.push(&buffer)) {
+                    if chunks.send(chunk).await.is_err() {
+                        debug!("Transcription queue closed, stopping capture chunking");
+                        return;
+                    }
+                }
+            }
+
+            if let Some(chunk) = assembler.finish() {
+                let _ = chunks.send(chunk).await;
+            }
+            debug!("Capture chunking ended");
+        })
+    }
+
+    /// Transcribe live capture audio as it arrives
+    ///
+    /// Partial, revised and finalized segments go to `subscribe_transcript`;
+    /// finalized ones are also published to `subscribe_segments`. The task
+    /// ends when the capture stream closes, finalizing whatever is left, and
+    /// returns the session's finalized segments for storing.
+    pub fn stream_capture(&self, mut audio: broadcast::Receiver<AudioBuffer>) -> JoinHandle<LiveTranscript> {
+        let mut streamer = StreamingTranscriber::new(
+            session_engine(&self.engine),
+            self.config.streaming,
+            self.options(),
+        );
+        let events = self.transcript_broadcaster.clone();
+        let segments = self.segment_broadcaster.clone();
+        let stats = Arc::clone(&self.stats);
+        let attributor = self.take_attribution();
+        let engine = self.engine.name().to_string();
+
+        tokio::task::spawn_blocking(move || {
+            let mut finalized = Vec::new();
+            let mut skipped_buffers = 0;
+            loop {
+                let buffer = match audio.blocking_recv() {
+                    Ok(buffer) => buffer,
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!("Live transcription fell behind capture, {} buffers skipped", skipped);
+                        skipped_buffers += skipped;
+                        continue;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+                let gap = std::mem::take(&mut skipped_buffers);
+                if gap > 0 {
+                    let result = streamer.skip(gap as f64 * buffer.duration_ms());
+                    publish_events(&events, &segments, attributor.as_deref(), result, &mut finalized);
+                }
+                let result = streamer.push(&buffer);
+                publish_events(&events, &segments, attributor.as_deref(), result, &mut finalized);
+            }
+            let result = streamer.finish();
+            publish_events(&events, &segments, attributor.as_deref(), result, &mut finalized);
+
+            let session = streamer.stats().clone();
+            if let Ok(mut stats) = stats.write() {
+                stats.merge(&session);
+            }
+            info!(
+                "Live transcription finished after {} passes, {} segments",
+                streamer.passes(), session.segments
+            );
+            LiveTranscript { segments: finalized, stats: session, engine }
+        })
+    }
+
+    fn options(&self) -> TranscriptionOptions {
+        TranscriptionOptions {
+            language: self.config.language.clone(),
+            candidate_languages: self.config.candidate_languages.clone(),
+            vocabulary: self.vocabulary.clone(),
+        }
+    }
+
+    /// The track levels `attribute_tracks` set up, for one session only
+    fn take_attribution(&self) -> Option<Arc<RwLock<TrackAttributor>>> {
+        self.attribution.lock().ok().and_then(|mut pending| pending.take())
+    }
+
+    fn spawn_scheduler(
+        &self,
+        engine: Arc<dyn TranscriptionEngine>,
+        mut chunk_rx: mpsc::Receiver<AudioChunk>,
+    ) -> JoinHandle<TranscriptionStats> {
+        let options = self.options();
+        let broadcaster = self.segment_broadcaster.clone();
+        let stats = Arc::clone(&self.stats);
+        let attributor = self.take_attribution();
+        let max_in_flight = self.config.max_concurrent_chunks.max(1);
+
+        tokio::spawn(async move {
+            let mut in_flight = JoinSet::new();
+            // Finished chunks waiting for an earlier one, by arrival order
+            let mut finished: BTreeMap<u64, ChunkOutcome> = BTreeMap::new();
+            let mut next_submitted = 0u64;
+            let mut next_published = 0u64;
+            let mut receiving = true;
+
+            while receiving || !in_flight.is_empty() {
+                tokio::select! {
+                    chunk = chunk_rx.recv(), if receiving && in_flight.len() < max_in_flight => {
+                        match chunk {
+                            Some(chunk) => {
+                                let sequence = next_submitted;
+                                next_submitted += 1;
+                                let engine = Arc::clone(&engine);
+                                let options = options.clone();
+                                in_flight.spawn(async move {
+                                    (sequence, run_engine(engine, chunk, options).await)
+                                });
+                            }
+                            None => receiving = false,
+                        }
+                    }
+                    Some(joined) = in_flight.join_next(), if !in_flight.is_empty() => {
+                        match joined {
+                            Ok((sequence, outcome)) => {
+                                finished.insert(sequence, outcome);
+                            }
+                            Err(e) => {
+                                // Only reachable if a task panicked outside the engine call
+                                error!("Transcription task failed: {}", e);
+                                break;
+                            }
+                        }
+
+                        while let Some(outcome) = finished.remove(&next_published) {
+                            next_published += 1;
+                            publish(&broadcaster, &stats, attributor.as_deref(), outcome);
+                        }
+                    }
+                }
+            }
+
+            let stats = stats.read().map(|stats| stats.clone()).unwrap_or_default();
+            info!(
+                "Transcription finished: {} chunks, {} failed, {} segments",
+                stats.chunks_processed, stats.chunks_failed, stats.segments
+            );
+            stats
+        })
+    }
+}
+
+/// Transcribe one chunk with `engine` the way the service does: converted
+/// for the engine, empty segments dropped, words timed and, for engines
+/// without prompts, the vocabulary applied
+pub async fn transcribe_with(
+    engine: Arc<dyn TranscriptionEngine>,
+    chunk: AudioChunk,
+    options: TranscriptionOptions,
+) -> TranscriptionResult<Vec<TranscriptSegment>> {
+    run_engine(engine, chunk, options).await.result
+}
+
+/// What became of one chunk
+struct ChunkOutcome {
+    index: usize,
+    audio_ms: f64,
+    elapsed: Duration,
+    result: TranscriptionResult<Vec<TranscriptSegment>>,
+}
+
+async fn run_engine(
+    engine: Arc<dyn TranscriptionEngine>,
+    chunk: AudioChunk,
+    options: TranscriptionOptions,
+) -> ChunkOutcome {
+    let index = chunk.index;
+    let audio_ms = chunk.buffer.duration_ms();
+    let started = Instant::now();
+
+    let result = tokio::task::spawn_blocking(move || {
+        let chunk = prepare_chunk(chunk, engine.sample_rate())?;
+        let options = resolve_options(engine.as_ref(), &chunk, &options)?;
+        let mut segments = engine.transcribe(&chunk, &options)?;
+        segments.retain(|segment| !segment.text.trim().is_empty());
+        segments.sort_by(|a, b| a.start_ms.total_cmp(&b.start_ms));
+        fill_word_timings(&chunk, &mut segments);
+        if !engine.supports_prompt() {
+            options.vocabulary.apply(&mut segments);
+        }
+        Ok(segments)
+    })
+    .await
+    .unwrap_or_else(|e| Err(TranscriptionError::Internal {
+        message: format!("Transcription engine panicked: {}", e),
+    }));
+
+    ChunkOutcome { index, audio_ms, elapsed: started.elapsed(), result }
+}
+
+fn record(stats: &RwLock<TranscriptionStats>, outcome: &ChunkOutcome) {
+    if let Ok(mut stats) = stats.write() {
+        stats.processing_ms += outcome.elapsed.as_secs_f64() * 1000.0;
+        match &outcome.result {
+            Ok(segments) => {
+                stats.chunks_processed += 1;
+                stats.audio_ms += outcome.audio_ms;
+                for segment in segments {
+                    stats.count_segment(segment);
+                }
+            }
+            Err(_) => stats.chunks_failed += 1,
+        }
+    }
+}
+
+fn publish(
+    broadcaster: &broadcast::Sender<TranscriptSegment>,
+    stats: &RwLock<TranscriptionStats>,
+    attributor: Option<&RwLock<TrackAttributor>>,
+    outcome: ChunkOutcome,
+) {
+    record(stats, &outcome);
+    match outcome.result {
+        Ok(mut segments) => {
+            if let Some(attributor) = attributor.and_then(|a| a.read().ok()) {
+                attributor.attribute(&mut segments);
+            }
+            debug!("Chunk {} transcribed into {} segments", outcome.index, segments.len());
+            for segment in segments {
+                let _ = broadcaster.send(segment);
+            }
+        }
+        // A bad chunk leaves a gap; the rest of the meeting still gets transcribed
+        Err(e) => error!("Failed to transcribe chunk {}: {}", outcome.index, e),
+    }
+}
+
+fn publish_events(
+    events: &broadcast::Sender<TranscriptEvent>,
+    segments: &broadcast::Sender<TranscriptSegment>,
+    attributor: Option<&RwLock<TrackAttributor>>,
+    result: TranscriptionResult<Vec<TranscriptEvent>>,
+    finalized: &mut Vec<TranscriptSegment>,
+) {
+    match result {
+        Ok(updates) => {
+            let attributor = attributor.and_then(|a| a.read().ok());
+            for mut update in updates {
+                if let (
+                    Some(attributor),
+                    TranscriptEvent::Partial { segment, .. }
+                    | TranscriptEvent::Revised { segment, .. }
+                    | TranscriptEvent::Finalized { segment, .. },
+                ) = (attributor.as_ref(), &mut update)
+                {
+                    attributor.attribute(std::slice::from_mut(segment));
+                }
+                if let TranscriptEvent::Finalized { segment, .. } = &update {
+                    let _ = segments.send(segment.clone());
+                    finalized.push(segment.clone());
+                }
+                let _ = events.send(update);
+            }
+        }
+        // The window is kept, so the next pass retries the same audio
+        Err(e) => error!("Live transcription pass failed: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transcription::MockEngine;
+
+    async fn collect(mut segments: broadcast::Receiver<TranscriptSegment>) -> Vec<TranscriptSegment> {
+        let mut collected = Vec::new();
+        while let Ok(segment) = segments.recv().await {
+            collected.push(segment);
+        }
+        collected
+    }
+
+    fn chunk(index: usize, start_ms: f64, seconds: usize) -> AudioChunk {
+        AudioChunk {
+            index,
+            start_ms,
+            buffer: AudioBuffer::new(vec![0.2; 16000 * seconds], 16000, 1),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_segments_published_in_order_with_concurrent_chunks() {
+        let engine = Arc::new(MockEngine::new().with_latency(Duration::from_millis(20)));
+        let config = TranscriptionConfig { max_concurrent_chunks: 4, ..Default::default() };
+        let service = TranscriptionService::with_config(engine.clone(), config);
+        let segments = service.subscribe_segments();
+
+        let (queue, handle) = service.start();
+        for i in 0..8 {
+            queue.send(chunk(i, i as f64 * 2000.0, 2)).await.unwrap();
+        }
+        drop(queue);
+        let stats = handle.await.unwrap();
+        drop(service);
+
+        let segments = collect(segments).await;
+        assert_eq!(segments.len(), 16);
+        assert!(segments.windows(2).all(|pair| pair[0].end_ms <= pair[1].start_ms));
+        assert_eq!(segments[3].text, engine.word_at(3000.0));
+        // The mock doesn't time words, so the alignment pass has
+        assert_eq!(segments[3].words.len(), 1);
+        assert_eq!((segments[3].words[0].start_ms, segments[3].words[0].end_ms), (3000.0, 4000.0));
+        assert_eq!(stats.chunks_processed, 8);
+        assert_eq!(stats.audio_ms, 16000.0);
+    }
+
+    #[tokio::test]
+    async fn test_failed_chunk_leaves_gap_and_pipeline_continues() {
+        let engine = Arc::new(MockEngine::new().failing_on(&[1]));
+        let service = TranscriptionService::new(engine);
+        let segments = service.subscribe_segments();
+
+        let (queue, handle) = service.start();
+        for i in 0..3 {
+            queue.send(chunk(i, i as f64 * 1000.0, 1)).await.unwrap();
+        }
+        drop(queue);
+        let stats = handle.await.unwrap();
+        drop(service);
+
+        let starts: Vec<f64> = collect(segments).await.iter().map(|s| s.start_ms).collect();
+        assert_eq!(starts, vec![0.0, 2000.0]);
+        assert_eq!(stats.chunks_failed, 1);
+        assert_eq!(stats.chunks_processed, 2);
+    }
+
+    #[tokio::test]
+    async fn test_vocabulary_corrects_engines_without_prompts() {
+        use crate::transcription::{Vocabulary, VocabularyTerm};
+
+        let engine = Arc::new(MockEngine::new().with_script(&["okay", "cubernetes", "meetingmind"]));
+        let mut service = TranscriptionService::new(engine);
+        service.set_vocabulary(Vocabulary {
+            terms: vec![VocabularyTerm::new("Kubernetes").with_variants(&["cubernetes"]), VocabularyTerm::new("MeetingMind")],
+            ..Default::default()
+        });
+        let segments = service.subscribe_segments();
+
+        let (queue, handle) = service.start();
+        queue.send(chunk(0, 0.0, 3)).await.unwrap();
+        drop(queue);
+        handle.await.unwrap();
+        drop(service);
+
+        let segments = collect(segments).await;
+        let text: Vec<_> = segments.iter().map(|s| s.text.as_str()).collect();
+        assert_eq!(text, vec!["okay", "Kubernetes", "MeetingMind"]);
+        assert_eq!(segments[1].words[0].text, "Kubernetes");
+    }
+
+    #[tokio::test]
+    async fn test_capture_audio_is_chunked_and_converted() {
+        let engine = Arc::new(MockEngine::new());
+        let config = TranscriptionConfig { chunk_ms: 1500.0, ..Default::default() };
+        let service = TranscriptionService::with_config(engine.clone(), config);
+        let segments = service.subscribe_segments();
+
+        // 48kHz stereo capture delivered in 100ms buffers
+        let (audio_tx, audio_rx) = broadcast::channel(64);
+        let (queue, handle) = service.start();
+        let chunker = service.chunk_capture(audio_rx, queue);
+        for _ in 0..40 {
+            audio_tx.send(AudioBuffer::new(vec![0.2; 9600], 48000, 2)).unwrap();
+        }
+        drop(audio_tx);
+        chunker.await.unwrap();
+        let stats = handle.await.unwrap();
+        drop(service);
+
+        // 4s of audio: two 1.5s chunks and a 1s tail
+        assert_eq!(engine.calls(), 3);
+        assert!((stats.audio_ms - 4000.0).abs() < 1e-6);
+        let segments = collect(segments).await;
+        let starts: Vec<f64> = segments.iter().map(|s| s.start_ms).collect();
+        assert_eq!(starts, vec![0.0, 1000.0, 1500.0, 2500.0, 3000.0]);
+    }
+
+    #[tokio::test]
+    async fn test_live_capture_streams_partials_and_final_segments() {
+        let service = TranscriptionService::new(Arc::new(MockEngine::new()));
+        let mut updates = service.subscribe_transcript();
+        let segments = service.subscribe_segments();
+
+        let (audio_tx, audio_rx) = broadcast::channel(64);
+        let handle = service.stream_capture(audio_rx);
+        for _ in 0..30 {
+            audio_tx.send(AudioBuffer::new(vec![0.2; 9600], 48000, 2)).unwrap();
+        }
+        drop(audio_tx);
+        let live = handle.await.unwrap();
+        drop(service);
+
+        let mut events = Vec::new();
+        while let Ok(event) = updates.recv().await {
+            events.push(event);
+        }
+        assert!(matches!(events[0], TranscriptEvent::Partial { .. }));
+        let finalized = events.iter().filter(|e| matches!(e, TranscriptEvent::Finalized { .. })).count();
+        assert_eq!(finalized, 3);
+        assert_eq!(live.stats.segments, 3);
+
+        let words: Vec<_> = collect(segments).await.into_iter().map(|s| s.text).collect();
+        assert_eq!(words, vec!["alpha", "bravo", "charlie"]);
+
+        // The session hands back what it finalized, for storing
+        let kept: Vec<_> = live.segments.iter().map(|s| s.text.as_str()).collect();
+        assert_eq!(kept, words);
+        assert_eq!(live.engine, "mock");
+    }
+
+    #[tokio::test]
+    async fn test_live_segments_are_hinted_with_their_capture_track() {
+        let service = TranscriptionService::new(Arc::new(MockEngine::new()));
+        let segments = service.subscribe_segments();
+
+        // Mic, then the far end, then the mic again, one second each
+        let (track_tx, track_rx) = broadcast::channel(64);
+        let attribution = service
+            .attribute_tracks(track_rx, vec![SpeakerHint::Me, SpeakerHint::Others])
+            .unwrap();
+        for (mic, system) in [(0.2, 0.0), (0.01, 0.2), (0.3, 0.02)] {
+            for (track, level) in [(0, mic), (1, system)] {
+                let buffer = AudioBuffer::new(vec![level; 16000], 16000, 1);
+                track_tx.send(TrackAudio { track, buffer }).unwrap();
+            }
+        }
+        drop(track_tx);
+        attribution.await.unwrap();
+
+        let (audio_tx, audio_rx) = broadcast::channel(64);
+        let handle = service.stream_capture(audio_rx);
+        for _ in 0..30 {
+            audio_tx.send(AudioBuffer::new(vec![0.2; 9600], 48000, 2)).unwrap();
+        }
+        drop(audio_tx);
+        handle.await.unwrap();
+
+        // The attribution was for that session only
+        assert!(service.take_attribution().is_none());
+        drop(service);
+
+        let hints: Vec<_> = collect(segments).await.into_iter().map(|s| s.speaker_hint).collect();
+        assert_eq!(hints, vec![Some(SpeakerHint::Me), Some(SpeakerHint::Others), Some(SpeakerHint::Me)]);
+    }
+
+    #[tokio::test]
+    async fn test_track_attribution_stays_on_the_timeline_after_a_lag() {
+        let service = TranscriptionService::new(Arc::new(MockEngine::new()));
+        let segments = service.subscribe_segments();
+
+        // Three seconds of both tracks into a channel that holds one second
+        let (track_tx, track_rx) = broadcast::channel(2);
+        for (mic, system) in [(0.2, 0.0), (0.01, 0.2), (0.3, 0.02)] {
+            for (track, level) in [(0, mic), (1, system)] {
+                let buffer = AudioBuffer::new(vec![level; 16000], 16000, 1);
+                track_tx.send(TrackAudio { track, buffer }).unwrap();
+            }
+        }
+        drop(track_tx);
+        service
+            .attribute_tracks(track_rx, vec![SpeakerHint::Me, SpeakerHint::Others])
+            .unwrap()
+            .await
+            .unwrap();
+
+        let (audio_tx, audio_rx) = broadcast::channel(64);
+        let handle = service.stream_capture(audio_rx);
+        for _ in 0..30 {
+            audio_tx.send(AudioBuffer::new(vec![0.2; 9600], 48000, 2)).unwrap();
+        }
+        drop(audio_tx);
+        handle.await.unwrap();
+        drop(service);
+
+        // Only the last second arrived, and it is still the last second
+        let hints: Vec<_> = collect(segments).await.into_iter().map(|s| s.speaker_hint).collect();
+        assert_eq!(hints, vec![None, None, Some(SpeakerHint::Me)]);
+    }
+
+    #[tokio::test]
+    async fn test_single_track_capture_is_not_attributed() {
+        let service = TranscriptionService::new(Arc::new(MockEngine::new()));
+        let (_track_tx, track_rx) = broadcast::channel(1);
+        assert!(service.attribute_tracks(track_rx, vec![SpeakerHint::Me]).is_none());
+        assert!(service.take_attribution().is_none());
+    }
+
+    #[test]
+    fn test_assembler_keeps_timeline_across_format_change() {
+        let mut assembler = ChunkAssembler::new(Duration::from_secs(1));
+        assert!(assembler.push(&AudioBuffer::new(vec![0.0; 8000], 16000, 1)).is_empty());
+
+        // Switching devices mid-chunk closes the chunk early
+        let chunks = assembler.push(&AudioBuffer::new(vec![0.0; 72000], 48000, 1));
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[0].start_ms, 0.0);
+        assert_eq!(chunks[0].buffer.duration_ms(), 500.0);
+        assert_eq!(chunks[1].start_ms, 500.0);
+        assert_eq!(chunks[1].buffer.sample_rate, 48000);
+        assert_eq!(chunks[1].index, 1);
+        assert_eq!(assembler.finish().unwrap().start_ms, 1500.0);
+        assert_eq!(assembler.position_ms(), 2000.0);
+    }
+
+    #[tokio::test]
+    async fn test_capture_chunks_stay_on_the_timeline_after_a_lag() {
+        let engine = Arc::new(MockEngine::new());
+        let config = TranscriptionConfig { chunk_ms: 1000.0, ..Default::default() };
+        let service = TranscriptionService::with_config(engine, config);
+        let segments = service.subscribe_segments();
+
+        // Capture runs five 600ms buffers ahead of a channel that holds two
+        let (audio_tx, audio_rx) = broadcast::channel(2);
+        for _ in 0..5 {
+            audio_tx.send(AudioBuffer::new(vec![0.2; 9600], 16000, 1)).unwrap();
+        }
+        drop(audio_tx);
+        let (queue, handle) = service.start();
+        let chunker = service.chunk_capture(audio_rx, queue);
+        chunker.await.unwrap();
+        handle.await.unwrap();
+        drop(service);
+
+        // Only the last two buffers arrive, 1.8s into the recording
+        let starts: Vec<f64> = collect(segments).await.iter().map(|s| s.start_ms).collect();
+        assert_eq!(starts, vec![1800.0, 2800.0]);
+    }
+
+    #[tokio::test]
+    async fn test_live_transcript_stays_on_the_timeline_after_a_lag() {
+        let service = TranscriptionService::new(Arc::new(MockEngine::new()));
+        let segments = service.subscribe_segments();
+
+        // Ten one-second buffers into a channel that holds four
+        let (audio_tx, audio_rx) = broadcast::channel(4);
+        for _ in 0..10 {
+            audio_tx.send(AudioBuffer::new(vec![0.2; 16000], 16000, 1)).unwrap();
+        }
+        drop(audio_tx);
+        let handle = service.stream_capture(audio_rx);
+        let stats = handle.await.unwrap().stats;
+        drop(service);
+
+        let segments = collect(segments).await;
+        assert_eq!(segments.first().map(|s| s.start_ms), Some(6000.0));
+        assert_eq!(segments.last().map(|s| s.end_ms), Some(10_000.0));
+        assert_eq!(stats.audio_ms, 4000.0);
+    }
+}
@@ -0,0 +1,641 @@
+//! Live transcription with provisional results
+//!
+//! Rather than waiting for whole chunks, the streaming transcriber re-decodes
+//! a rolling window of recent audio every `step_ms`. Each pass yields a
+//! hypothesis; segments in it are published straight away as partials and
+//! revised by later passes. A segment is finalized once two consecutive passes
+//! agree on its text and it ends far enough before the newest audio that more
+//! context is unlikely to change it; in continuous speech, where a segment
+//! keeps growing, the window is cut at the last settled word once it passes
+//! `max_window_ms`. Finalized audio is dropped from the window, so the window
+//! only ever holds undecided speech.
+//!
+//! Segments keep their ID across revisions: each new hypothesis segment takes
+//! over the ID of the provisional segment it overlaps most.
+
+use std::sync::Arc;
+use std::time::Instant;
+use serde::{Deserialize, Serialize};
+
+use crate::audio::{downmix_into, AudioBuffer, AudioChunk, LinearResampler};
+
+use super::alignment::fill_word_timings;
+use super::engine::TranscriptionEngine;
+use super::language::{resolve_options, LanguageSwitch};
+use super::types::{
+    TranscriptSegment, TranscriptionError, TranscriptionOptions, TranscriptionResult,
+    TranscriptionStats, WordTiming,
+};
+
+/// Identifies a segment from its first partial until it is finalized
+pub type SegmentId = u64;
+
+/// Settings for live transcription
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StreamingConfig {
+    /// New audio needed before the window is decoded again
+    pub step_ms: f64,
+    /// Segments ending closer than this to the newest audio stay provisional
+    pub stability_margin_ms: f64,
+    /// Window length beyond which settled segments are finalized without
+    /// waiting for agreement, and a segment still growing is cut at its last
+    /// settled word
+    pub max_window_ms: f64,
+}
+
+impl Default for StreamingConfig {
+    fn default() -> Self {
+        Self {
+            step_ms: 1000.0,
+            stability_margin_ms: 1500.0,
+            max_window_ms: 20_000.0,
+        }
+    }
+}
+
+impl StreamingConfig {
+    /// Check the settings are usable
+    pub fn validate(&self) -> TranscriptionResult<()> {
+        if !(100.0..=10_000.0).contains(&self.step_ms) {
+            return Err(TranscriptionError::InvalidConfig {
+                details: format!("Streaming step of {}ms is out of range (100ms-10s)", self.step_ms),
+            });
+        }
+        if self.stability_margin_ms < 0.0 || self.max_window_ms <= self.stability_margin_ms + self.step_ms {
+            return Err(TranscriptionError::InvalidConfig {
+                details: format!(
+                    "Streaming window of {}ms must exceed the {}ms stability margin plus one step",
+                    self.max_window_ms, self.stability_margin_ms
+                ),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// A change to the live transcript
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TranscriptEvent {
+    /// A new provisional segment
+    Partial { id: SegmentId, segment: TranscriptSegment },
+    /// A provisional segment's text or timing changed
+    Revised { id: SegmentId, segment: TranscriptSegment },
+    /// The segment will not change again
+    Finalized { id: SegmentId, segment: TranscriptSegment },
+    /// A provisional segment turned out not to exist
+    Retracted { id: SegmentId },
+    /// The language changes at a finalized segment
+    LanguageChanged { id: SegmentId, switch: LanguageSwitch },
+}
+
+impl TranscriptEvent {
+    pub fn id(&self) -> SegmentId {
+        match self {
+            Self::Partial { id, .. }
+            | Self::Revised { id, .. }
+            | Self::Finalized { id, .. }
+            | Self::Retracted { id }
+            | Self::LanguageChanged { id, .. } => *id,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Provisional {
+    id: SegmentId,
+    segment: TranscriptSegment,
+    /// The previous pass produced the same text
+    agreed: bool,
+}
+
+/// Incrementally transcribes a stream of capture buffers
+pub struct StreamingTranscriber {
+    engine: Arc<dyn TranscriptionEngine>,
+    config: StreamingConfig,
+    options: TranscriptionOptions,
+    sample_rate: u32,
+    /// Converts capture audio to mono at the engine's rate
+    input_format: Option<(u32, u16)>,
+    resampler: Option<LinearResampler>,
+    mono_scratch: Vec<f32>,
+    resample_scratch: Vec<f32>,
+    /// Undecided audio, starting at `window_start_ms`
+    window: Vec<f32>,
+    window_start_ms: f64,
+    pending_samples: usize,
+    provisional: Vec<Provisional>,
+    next_id: SegmentId,
+    passes: usize,
+    stats: TranscriptionStats,
+    /// Language of the last finalized segment
+    language: Option<String>,
+}
+
+impl StreamingTranscriber {
+    pub fn new(
+        engine: Arc<dyn TranscriptionEngine>,
+        config: StreamingConfig,
+        options: TranscriptionOptions,
+    ) -> Self {
+        let sample_rate = engine.sample_rate();
+        Self {
+            engine,
+            config,
+            options,
+            sample_rate,
+            input_format: None,
+            resampler: None,
+            mono_scratch: Vec::new(),
+            resample_scratch: Vec::new(),
+            window: Vec::new(),
+            window_start_ms: 0.0,
+            pending_samples: 0,
+            provisional: Vec::new(),
+            next_id: 1,
+            passes: 0,
+            stats: TranscriptionStats::default(),
+            language: None,
+        }
+    }
+
+    /// Add captured audio, decoding the window when a step's worth has arrived
+    pub fn push(&mut self, buffer: &AudioBuffer) -> TranscriptionResult<Vec<TranscriptEvent>> {
+        self.append(buffer);
+
+        let step = (self.config.step_ms / 1000.0 * self.sample_rate as f64) as usize;
+        if self.pending_samples < step.max(1) {
+            return Ok(Vec::new());
+        }
+        self.pending_samples = 0;
+        self.decode(false)
+    }
+
+    /// Decode what is left and finalize everything
+    pub fn finish(&mut self) -> TranscriptionResult<Vec<TranscriptEvent>> {
+        self.decode(true)
+    }
+
+    /// Move the timeline past audio that never arrived
+    ///
+    /// Nothing decoded so far can be continued across the gap, so the window
+    /// is finalized first.
+    pub fn skip(&mut self, gap_ms: f64) -> TranscriptionResult<Vec<TranscriptEvent>> {
+        let result = self.decode(true);
+        self.trim_window(self.position_ms());
+        self.window_start_ms += gap_ms;
+        self.pending_samples = 0;
+        result
+    }
+
+    /// End of the audio received so far on the meeting timeline
+    pub fn position_ms(&self) -> f64 {
+        self.window_start_ms + self.samples_to_ms(self.window.len())
+    }
+
+    /// Decoding passes run so far
+    pub fn passes(&self) -> usize {
+        self.passes
+    }
+
+    pub fn stats(&self) -> &TranscriptionStats {
+        &self.stats
+    }
+
+    fn append(&mut self, buffer: &AudioBuffer) {
+        let format = (buffer.sample_rate, buffer.channels.max(1));
+        if self.input_format != Some(format) {
+            self.input_format = Some(format);
+            self.resampler = (buffer.sample_rate != self.sample_rate)
+                .then(|| LinearResampler::new(buffer.sample_rate, self.sample_rate, 1));
+        }
+
+        let frames = buffer.samples.len() / format.1 as usize;
+        if self.mono_scratch.len() < frames {
+            self.mono_scratch.resize(frames, 0.0);
+        }
+        let frames = downmix_into(&buffer.samples, format.1, &mut self.mono_scratch);
+
+        let before = self.window.len();
+        match self.resampler.as_mut() {
+            Some(resampler) => {
+                let capacity = resampler.max_output_frames(frames);
+                if self.resample_scratch.len() < capacity {
+                    self.resample_scratch.resize(capacity, 0.0);
+                }
+                let written = resampler.process_into(&self.mono_scratch[..frames], &mut self.resample_scratch);
+                self.window.extend_from_slice(&self.resample_scratch[..written]);
+            }
+            None => self.window.extend_from_slice(&self.mono_scratch[..frames]),
+        }
+        self.pending_samples += self.window.len() - before;
+    }
+
+    fn decode(&mut self, finishing: bool) -> TranscriptionResult<Vec<TranscriptEvent>> {
+        if self.window.is_empty() {
+            return Ok(self.retract_all());
+        }
+
+        let chunk = AudioChunk {
+            index: self.passes,
+            start_ms: self.window_start_ms,
+            buffer: AudioBuffer::new(self.window.clone(), self.sample_rate, 1),
+        };
+        let started = Instant::now();
+        let result = resolve_options(self.engine.as_ref(), &chunk, &self.options)
+            .and_then(|options| self.engine.transcribe(&chunk, &options));
+        self.passes += 1;
+        self.stats.processing_ms += started.elapsed().as_secs_f64() * 1000.0;
+        let mut hypothesis = match result {
+            Ok(segments) => segments,
+            Err(e) => {
+                self.stats.chunks_failed += 1;
+                // Without a hypothesis nothing settles, so cap the window as the
+                // overlong path would; provisional segments left outside it are
+                // retracted by the next pass that succeeds
+                let keep_from = if finishing {
+                    self.position_ms()
+                } else {
+                    self.position_ms() - self.config.max_window_ms
+                };
+                self.trim_window(keep_from);
+                return Err(e);
+            }
+        };
+        self.stats.chunks_processed += 1;
+        hypothesis.retain(|segment| !segment.text.trim().is_empty());
+        hypothesis.sort_by(|a, b| a.start_ms.total_cmp(&b.start_ms));
+        fill_word_timings(&chunk, &mut hypothesis);
+        if !self.engine.supports_prompt() {
+            self.options.vocabulary.apply(&mut hypothesis);
+        }
+
+        let window_end = self.position_ms();
+        let settled_before = window_end - self.config.stability_margin_ms;
+        let overlong = window_end - self.window_start_ms > self.config.max_window_ms;
+
+        // Carry IDs over and note which segments two passes agree on
+        let mut previous = std::mem::take(&mut self.provisional);
+        let mut current: Vec<Provisional> = Vec::with_capacity(hypothesis.len());
+        let mut events = Vec::new();
+        for segment in hypothesis {
+            match take_best_match(&mut previous, &segment) {
+                Some(old) => {
+                    let agreed = normalize(&old.segment.text) == normalize(&segment.text);
+                    if old.segment != segment {
+                        events.push(TranscriptEvent::Revised { id: old.id, segment: segment.clone() });
+                    }
+                    current.push(Provisional { id: old.id, segment, agreed });
+                }
+                None => {
+                    let id = self.next_id;
+                    self.next_id += 1;
+                    events.push(TranscriptEvent::Partial { id, segment: segment.clone() });
+                    current.push(Provisional { id, segment, agreed: false });
+                }
+            }
+        }
+        events.extend(previous.into_iter().map(|old| TranscriptEvent::Retracted { id: old.id }));
+
+        // Finalize the settled prefix of the hypothesis
+        let settled = current
+            .iter()
+            .take_while(|p| finishing || (p.segment.end_ms <= settled_before && (p.agreed || overlong)))
+            .count();
+        let mut finalized_until = None;
+        for done in current.drain(..settled) {
+            finalized_until = Some(done.segment.end_ms);
+            self.finalize(done, &mut events);
+        }
+
+        // Continuous speech can keep one segment growing so it never
+        // settles; once the window is overlong, finalize its settled words
+        if overlong && !finishing && current.first().is_some_and(|p| p.segment.start_ms < settled_before) {
+            let head = current.remove(0);
+            let (head, tail) = split_at_settled_word(head, settled_before);
+            finalized_until = Some(head.segment.end_ms);
+            self.finalize(head, &mut events);
+            if let Some(segment) = tail {
+                let id = self.next_id;
+                self.next_id += 1;
+                events.push(TranscriptEvent::Partial { id, segment: segment.clone() });
+                current.insert(0, Provisional { id, segment, agreed: false });
+            }
+        }
+        self.provisional = current;
+
+        // Drop audio nothing can refer to any more
+        let keep_from = if finishing {
+            window_end
+        } else if let Some(end) = finalized_until {
+            end
+        } else if self.provisional.is_empty() {
+            // Nothing heard: only the tail might still start a word
+            settled_before
+        } else {
+            self.window_start_ms
+        };
+        self.trim_window(keep_from);
+
+        Ok(events)
+    }
+
+    fn finalize(&mut self, done: Provisional, events: &mut Vec<TranscriptEvent>) {
+        self.stats.count_segment(&done.segment);
+        let switch = match self.language.as_deref() {
+            Some(from) if !done.segment.language.is_empty() && from != done.segment.language => {
+                Some(LanguageSwitch {
+                    at_ms: done.segment.start_ms,
+                    from: from.to_string(),
+                    to: done.segment.language.clone(),
+                })
+            }
+            _ => None,
+        };
+        if !done.segment.language.is_empty() {
+            self.language = Some(done.segment.language.clone());
+        }
+        events.push(TranscriptEvent::Finalized { id: done.id, segment: done.segment });
+        if let Some(switch) = switch {
+            events.push(TranscriptEvent::LanguageChanged { id: done.id, switch });
+        }
+    }
+
+    fn retract_all(&mut self) -> Vec<TranscriptEvent> {
+        self.provisional
+            .drain(..)
+            .map(|old| TranscriptEvent::Retracted { id: old.id })
+            .collect()
+    }
+
+    fn trim_window(&mut self, keep_from_ms: f64) {
+        if keep_from_ms <= self.window_start_ms {
+            return;
+        }
+        let drop = ((keep_from_ms - self.window_start_ms) / 1000.0 * self.sample_rate as f64) as usize;
+        let drop = drop.min(self.window.len());
+        self.window.drain(..drop);
+        self.window_start_ms += self.samples_to_ms(drop);
+        self.stats.audio_ms += self.samples_to_ms(drop);
+    }
+
+    fn samples_to_ms(&self, samples: usize) -> f64 {
+        samples as f64 * 1000.0 / self.sample_rate.max(1) as f64
+    }
+}
+
+/// Remove and return the provisional segment overlapping `segment` most
+fn take_best_match(previous: &mut Vec<Provisional>, segment: &TranscriptSegment) -> Option<Provisional> {
+    let (index, overlap) = previous
+        .iter()
+        .enumerate()
+        .map(|(i, p)| {
+            let overlap = p.segment.end_ms.min(segment.end_ms) - p.segment.start_ms.max(segment.start_ms);
+            (i, overlap)
+        })
+        .max_by(|a, b| a.1.total_cmp(&b.1))?;
+
+    (overlap > 0.0).then(|| previous.remove(index))
+}
+
+/// Cut a segment after its last word ending before `settled_before`
+///
+/// The settled words keep the segment's ID and the rest come back as a new
+/// segment. Without such a word the whole segment is returned to be
+/// finalized as it stands.
+fn split_at_settled_word(mut head: Provisional, settled_before: f64) -> (Provisional, Option<TranscriptSegment>) {
+    let settled = head.segment.words.iter().take_while(|w| w.end_ms <= settled_before).count();
+    if settled == 0 || settled == head.segment.words.len() {
+        return (head, None);
+    }
+
+    let join = |words: &[WordTiming]| words.iter().map(|w| w.text.as_str()).collect::<Vec<_>>().join(" ");
+    let mut tail = head.segment.clone();
+    tail.words = head.segment.words.split_off(settled);
+    tail.text = join(&tail.words);
+    tail.start_ms = tail.words[0].start_ms;
+    tail.original_text = None;
+
+    let segment = &mut head.segment;
+    segment.text = join(&segment.words);
+    segment.end_ms = segment.words[settled - 1].end_ms;
+    segment.original_text = None;
+    (head, Some(tail))
+}
+
+/// Text compared for agreement, ignoring case and punctuation
+fn normalize(text: &str) -> String {
+    text.chars()
+        .filter(|c| c.is_alphanumeric() || c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use crate::transcription::MockEngine;
+
+    fn speech(seconds: f64) -> AudioBuffer {
+        AudioBuffer::new(vec![0.2; (16000.0 * seconds) as usize], 16000, 1)
+    }
+
+    fn streamer(engine: MockEngine) -> StreamingTranscriber {
+        StreamingTranscriber::new(Arc::new(engine), StreamingConfig::default(), TranscriptionOptions::default())
+    }
+
+    #[test]
+    fn test_partials_arrive_each_step_and_finalize_once_agreed() {
+        let mut streamer = streamer(MockEngine::new());
+        let mut events = Vec::new();
+
+        // The first second already yields a partial
+        events.extend(streamer.push(&speech(1.0)).unwrap());
+        assert!(matches!(events[0], TranscriptEvent::Partial { id: 1, .. }));
+
+        for _ in 0..5 {
+            events.extend(streamer.push(&speech(1.0)).unwrap());
+        }
+        events.extend(streamer.finish().unwrap());
+
+        // Every ID is finalized exactly once and nothing changes after that
+        let mut finalized: HashMap<SegmentId, TranscriptSegment> = HashMap::new();
+        for event in &events {
+            assert!(!finalized.contains_key(&event.id()), "event after finalize: {:?}", event);
+            if let TranscriptEvent::Finalized { id, segment } = event {
+                finalized.insert(*id, segment.clone());
+            }
+        }
+        let mut segments: Vec<_> = finalized.into_values().collect();
+        segments.sort_by(|a, b| a.start_ms.total_cmp(&b.start_ms));
+        let words: Vec<_> = segments.iter().map(|s| s.text.as_str()).collect();
+        assert_eq!(words, vec!["alpha", "bravo", "charlie", "delta", "echo", "foxtrot"]);
+
+        // Settled segments were finalized before the end, not all at once
+        let first_final = events.iter().position(|e| matches!(e, TranscriptEvent::Finalized { .. })).unwrap();
+        assert!(first_final < events.len() - 3);
+        assert!(streamer.position_ms() >= 6000.0);
+    }
+
+    #[test]
+    fn test_revised_segments_keep_their_id() {
+        // Windows shorter than a second get a different word than full ones,
+        // so the trailing partial is revised as more audio arrives
+        let mut streamer = StreamingTranscriber::new(
+            Arc::new(MockEngine::new()),
+            StreamingConfig { step_ms: 500.0, ..Default::default() },
+            TranscriptionOptions::default(),
+        );
+
+        let first = streamer.push(&speech(0.5)).unwrap();
+        let id = match &first[..] {
+            [TranscriptEvent::Partial { id, segment }] => {
+                assert_eq!(segment.end_ms, 500.0);
+                *id
+            }
+            other => panic!("unexpected events {:?}", other),
+        };
+
+        let second = streamer.push(&speech(0.5)).unwrap();
+        match &second[..] {
+            [TranscriptEvent::Revised { id: revised, segment }] => {
+                assert_eq!(*revised, id);
+                assert_eq!(segment.end_ms, 1000.0);
+            }
+            other => panic!("unexpected events {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_silence_is_dropped_from_window() {
+        let mut streamer = streamer(MockEngine::new());
+        for _ in 0..10 {
+            let events = streamer.push(&AudioBuffer::new(vec![0.0; 16000], 16000, 1)).unwrap();
+            assert!(events.is_empty());
+        }
+        // Only the stability margin is kept for re-decoding
+        assert!(streamer.window.len() <= 16000 * 2);
+        assert_eq!(streamer.position_ms(), 10_000.0);
+    }
+
+    #[test]
+    fn test_language_change_is_reported_once_finalized() {
+        let engine = MockEngine::new().with_language("en").with_language_at(3000.0, "es");
+        let mut streamer = streamer(engine);
+        let mut events = Vec::new();
+        for _ in 0..6 {
+            events.extend(streamer.push(&speech(1.0)).unwrap());
+        }
+        events.extend(streamer.finish().unwrap());
+
+        let switches: Vec<_> = events
+            .iter()
+            .filter_map(|e| match e {
+                TranscriptEvent::LanguageChanged { switch, .. } => Some(switch.clone()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(switches, vec![LanguageSwitch { at_ms: 3000.0, from: "en".to_string(), to: "es".to_string() }]);
+    }
+
+    /// Hears the mock's words as one segment that runs to the newest audio
+    struct Monologue(MockEngine);
+
+    impl TranscriptionEngine for Monologue {
+        fn name(&self) -> &str {
+            "monologue"
+        }
+
+        fn transcribe(
+            &self,
+            chunk: &AudioChunk,
+            options: &TranscriptionOptions,
+        ) -> TranscriptionResult<Vec<TranscriptSegment>> {
+            let segments = self.0.transcribe(chunk, options)?;
+            let (Some(first), Some(last)) = (segments.first(), segments.last()) else {
+                return Ok(segments);
+            };
+            let words: Vec<WordTiming> = segments
+                .iter()
+                .map(|s| WordTiming { text: s.text.clone(), start_ms: s.start_ms, end_ms: s.end_ms, confidence: s.confidence })
+                .collect();
+            Ok(vec![TranscriptSegment {
+                text: segments.iter().map(|s| s.text.as_str()).collect::<Vec<_>>().join(" "),
+                start_ms: first.start_ms,
+                end_ms: last.end_ms,
+                words,
+                ..first.clone()
+            }])
+        }
+    }
+
+    #[test]
+    fn test_continuous_speech_is_finalized_once_the_window_is_overlong() {
+        let config = StreamingConfig::default();
+        let mut streamer = StreamingTranscriber::new(
+            Arc::new(Monologue(MockEngine::new())),
+            config,
+            TranscriptionOptions::default(),
+        );
+
+        let mut events = Vec::new();
+        for _ in 0..60 {
+            events.extend(streamer.push(&speech(1.0)).unwrap());
+            let window_ms = streamer.position_ms() - streamer.window_start_ms;
+            assert!(window_ms <= config.max_window_ms + config.step_ms, "window grew to {}ms", window_ms);
+        }
+        let live = events.iter().filter(|e| matches!(e, TranscriptEvent::Finalized { .. })).count();
+        assert!(live >= 2, "only {} segments finalized while speaking", live);
+
+        // Every word is finalized once, in order, across the cuts
+        events.extend(streamer.finish().unwrap());
+        let words: Vec<String> = events
+            .iter()
+            .filter_map(|e| match e {
+                TranscriptEvent::Finalized { segment, .. } => Some(segment.words.iter().map(|w| w.text.clone())),
+                _ => None,
+            })
+            .flatten()
+            .collect();
+        let expected: Vec<String> = (0..60)
+            .map(|second| {
+                let chunk = AudioChunk { index: 0, start_ms: second as f64 * 1000.0, buffer: speech(1.0) };
+                MockEngine::new().transcribe(&chunk, &TranscriptionOptions::default()).unwrap()[0].text.clone()
+            })
+            .collect();
+        assert_eq!(words, expected);
+    }
+
+    #[test]
+    fn test_window_stays_capped_while_the_engine_fails() {
+        let failing: Vec<usize> = (0..40).collect();
+        let config = StreamingConfig::default();
+        let mut streamer = streamer(MockEngine::new().failing_on(&failing));
+
+        let mut failures = 0;
+        for _ in 0..60 {
+            if streamer.push(&speech(1.0)).is_err() {
+                failures += 1;
+            }
+            let window_ms = streamer.position_ms() - streamer.window_start_ms;
+            assert!(window_ms <= config.max_window_ms + config.step_ms, "window grew to {}ms", window_ms);
+        }
+        assert!(failures > 0);
+
+        // Once the engine recovers, the rest is still transcribed
+        let events = streamer.finish().unwrap();
+        assert!(events.iter().any(|e| matches!(e, TranscriptEvent::Finalized { .. })));
+        assert!(streamer.window.is_empty());
+    }
+
+    #[test]
+    fn test_events_serialize_with_type_tag() {
+        let event = TranscriptEvent::Retracted { id: 7 };
+        assert_eq!(serde_json::to_value(&event).unwrap(), serde_json::json!({ "type": "retracted", "id": 7 }));
+    }
+}
@@ -0,0 +1,167 @@
+//! Core types shared by transcription engines and the pipeline
+
+use std::collections::BTreeMap;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::error::AppError;
+
+use super::vocabulary::Vocabulary;
+
+/// Transcription-specific error types
+#[derive(Error, Debug)]
+pub enum TranscriptionError {
+    #[error("Transcription engine error: {message}")]
+    Engine { message: String },
+
+    #[error("Audio not usable for transcription: {details}")]
+    InvalidAudio { details: String },
+
+    #[error("Transcription model not available: {model}")]
+    ModelUnavailable { model: String },
+
+    #[error("Model {model} failed its integrity check: expected SHA-256 {expected}, found {actual}")]
+    ChecksumMismatch { model: String, expected: String, actual: String },
+
+    #[error("Invalid transcription settings: {details}")]
+    InvalidConfig { details: String },
+
+    #[error("Transcription queue closed")]
+    QueueClosed,
+
+    #[error("Internal transcription error: {message}")]
+    Internal { message: String },
+}
+
+impl From<TranscriptionError> for AppError {
+    fn from(err: TranscriptionError) -> Self {
+        AppError::transcription(err.to_string())
+    }
+}
+
+/// Result type for transcription operations
+pub type TranscriptionResult<T> = Result<T, TranscriptionError>;
+
+/// A stretch of recognised speech
+///
+/// Times are on the meeting timeline: the chunk's offset plus the position
+/// within the chunk.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TranscriptSegment {
+    pub text: String,
+    pub start_ms: f64,
+    pub end_ms: f64,
+    /// Engine confidence between 0 and 1
+    pub confidence: f32,
+    /// BCP 47 language code, e.g. "en"
+    pub language: String,
+    /// Model that produced the segment, e.g. "whisper-base"
+    pub model: String,
+    /// Timing of each word, in order; empty until the engine or the
+    /// alignment pass fills it in
+    #[serde(default)]
+    pub words: Vec<WordTiming>,
+    /// Capture track the speech came from, when capture had separate tracks
+    #[serde(default)]
+    pub speaker_hint: Option<SpeakerHint>,
+    /// Text as the engine produced it, when formatting has changed it
+    #[serde(default)]
+    pub original_text: Option<String>,
+}
+
+impl TranscriptSegment {
+    pub fn duration_ms(&self) -> f64 {
+        self.end_ms - self.start_ms
+    }
+}
+
+/// Who a segment was heard from, judged by capture track
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SpeakerHint {
+    /// The local microphone
+    Me,
+    /// System audio, i.e. the other people on the call
+    Others,
+}
+
+impl SpeakerHint {
+    /// Value stored in the `speaker_hint` column
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Me => "me",
+            Self::Others => "others",
+        }
+    }
+
+    /// Parse a `speaker_hint` column value
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "me" => Some(Self::Me),
+            "others" => Some(Self::Others),
+            _ => None,
+        }
+    }
+}
+
+/// When one word of a segment was spoken, on the meeting timeline
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WordTiming {
+    pub text: String,
+    pub start_ms: f64,
+    pub end_ms: f64,
+    /// Engine confidence between 0 and 1
+    pub confidence: f32,
+}
+
+/// Per-request settings passed to an engine
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TranscriptionOptions {
+    /// Language to transcribe in; `None` lets the engine detect it
+    pub language: Option<String>,
+    /// Languages detection may choose from; empty allows any
+    #[serde(default)]
+    pub candidate_languages: Vec<String>,
+    /// Terms and meeting context to bias recognition with
+    #[serde(default)]
+    pub vocabulary: Vocabulary,
+}
+
+/// Counters for a transcription session
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct TranscriptionStats {
+    pub chunks_processed: u64,
+    pub chunks_failed: u64,
+    pub segments: u64,
+    /// Segments produced by each model
+    pub segments_by_model: BTreeMap<String, u64>,
+    /// Audio transcribed
+    pub audio_ms: f64,
+    /// Wall-clock time spent in the engine
+    pub processing_ms: f64,
+}
+
+impl TranscriptionStats {
+    /// Count a published segment
+    pub fn count_segment(&mut self, segment: &TranscriptSegment) {
+        self.segments += 1;
+        *self.segments_by_model.entry(segment.model.clone()).or_default() += 1;
+    }
+
+    /// Add another session's counters to these
+    pub fn merge(&mut self, other: &TranscriptionStats) {
+        self.chunks_processed += other.chunks_processed;
+        self.chunks_failed += other.chunks_failed;
+        self.segments += other.segments;
+        for (model, count) in &other.segments_by_model {
+            *self.segments_by_model.entry(model.clone()).or_default() += count;
+        }
+        self.audio_ms += other.audio_ms;
+        self.processing_ms += other.processing_ms;
+    }
+
+    /// Engine time per second of audio; below 1 is faster than real time
+    pub fn real_time_factor(&self) -> Option<f64> {
+        (self.audio_ms > 0.0).then(|| self.processing_ms / self.audio_ms)
+    }
+}
@@ -0,0 +1,313 @@
+//! Custom vocabulary and meeting context
+//!
+//! Product names, acronyms and people's names are where speech models go
+//! wrong most. A `Vocabulary` collects the terms a user has taught us
+//! (globally, for a project and for one meeting) along with what the meeting
+//! itself says about its content: the title and who is attending. Engines
+//! that take a prompt are primed with it; for the rest the pipeline corrects
+//! known misrecognitions after the fact.
+
+use std::borrow::Cow;
+use serde::{Deserialize, Serialize};
+
+use super::types::TranscriptSegment;
+
+/// Longest prompt handed to an engine; Whisper only looks at the last 224
+/// tokens of its prompt, which is roughly this many characters
+pub const MAX_PROMPT_CHARS: usize = 800;
+
+/// A term and the ways it tends to be misheard
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VocabularyTerm {
+    /// Spelling the transcript should use, e.g. "Kubernetes"
+    pub term: String,
+    /// Misrecognitions to replace with the term, e.g. "cooper netties"
+    #[serde(default)]
+    pub variants: Vec<String>,
+}
+
+impl VocabularyTerm {
+    pub fn new(term: impl Into<String>) -> Self {
+        Self { term: term.into(), variants: Vec::new() }
+    }
+
+    pub fn with_variants(mut self, variants: &[&str]) -> Self {
+        self.variants = variants.iter().map(|v| v.to_string()).collect();
+        self
+    }
+}
+
+/// Terms and context to bias a meeting's transcription with
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Vocabulary {
+    /// Most specific first: a meeting's own terms, its project's, then global
+    pub terms: Vec<VocabularyTerm>,
+    /// What the meeting is about, usually its title
+    pub topic: Option<String>,
+    /// Names of the people attending
+    pub participants: Vec<String>,
+}
+
+impl Vocabulary {
+    /// Vocabulary for a meeting with `title` and `participants`, which may be
+    /// names or email addresses
+    pub fn for_meeting(terms: Vec<VocabularyTerm>, title: &str, participants: &[String]) -> Self {
+        let title = title.trim();
+        Self {
+            terms,
+            topic: (!title.is_empty()).then(|| title.to_string()),
+            participants: participants.iter().filter_map(|p| participant_name(p)).collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty() && self.topic.is_none() && self.participants.is_empty()
+    }
+
+    /// Text to prime an engine with, written like a transcript would be
+    ///
+    /// Terms that don't fit within `MAX_PROMPT_CHARS` are left out, least
+    /// specific first.
+    pub fn prompt(&self) -> Option<String> {
+        let mut prompt = String::new();
+        if let Some(topic) = &self.topic {
+            prompt.push_str(topic.trim_end_matches('.'));
+            prompt.push('.');
+        }
+        if !self.participants.is_empty() {
+            let names = format!("With {}.", self.participants.join(", "));
+            if prompt.len() + names.len() < MAX_PROMPT_CHARS {
+                push_sentence(&mut prompt, &names);
+            }
+        }
+
+        let mut glossary = String::new();
+        for term in &self.terms {
+            let separator = if glossary.is_empty() { "" } else { ", " };
+            // One more character for the final full stop
+            if prompt.len() + 1 + glossary.len() + separator.len() + term.term.len() + 1 > MAX_PROMPT_CHARS {
+                break;
+            }
+            glossary.push_str(separator);
+            glossary.push_str(&term.term);
+        }
+        if !glossary.is_empty() {
+            glossary.push('.');
+            push_sentence(&mut prompt, &glossary);
+        }
+
+        (!prompt.is_empty()).then_some(prompt)
+    }
+
+    /// Replace misrecognitions of the terms in `text`
+    ///
+    /// Matching is by whole words, ignoring case and surrounding punctuation,
+    /// so "cooper netties," becomes "Kubernetes,". A term also matches itself,
+    /// which fixes its spelling and casing. Longer matches win.
+    pub fn correct<'t>(&self, text: &'t str) -> Cow<'t, str> {
+        let patterns = self.patterns();
+        if patterns.is_empty() {
+            return Cow::Borrowed(text);
+        }
+
+        let tokens: Vec<Token> = text.split_whitespace().map(Token::new).collect();
+        let mut corrected: Vec<String> = Vec::with_capacity(tokens.len());
+        let mut changed = false;
+        let mut i = 0;
+        while i < tokens.len() {
+            let found = patterns.iter().find(|(words, _)| {
+                tokens.len() - i >= words.len()
+                    && words.iter().zip(&tokens[i..]).all(|(word, token)| token.core_lower == *word)
+            });
+            match found {
+                Some((words, term)) => {
+                    let last = &tokens[i + words.len() - 1];
+                    let replacement = format!("{}{}{}", tokens[i].leading, term, last.trailing);
+                    let original = tokens[i..i + words.len()].iter().map(|t| t.raw).collect::<Vec<_>>().join(" ");
+                    changed |= replacement != original;
+                    corrected.push(replacement);
+                    i += words.len();
+                }
+                None => {
+                    corrected.push(tokens[i].raw.to_string());
+                    i += 1;
+                }
+            }
+        }
+
+        if changed {
+            Cow::Owned(corrected.join(" "))
+        } else {
+            Cow::Borrowed(text)
+        }
+    }
+
+    /// Correct the text of `segments` and of their words
+    ///
+    /// Words only take single-word corrections, so a variant spanning
+    /// several words fixes the segment text but leaves its words as heard.
+    pub fn apply(&self, segments: &mut [TranscriptSegment]) {
+        if self.terms.is_empty() {
+            return;
+        }
+        for segment in segments {
+            if let Cow::Owned(text) = self.correct(&segment.text) {
+                segment.text = text;
+            }
+            for word in &mut segment.words {
+                if let Cow::Owned(text) = self.correct(&word.text) {
+                    word.text = text;
+                }
+            }
+        }
+    }
+
+    /// Lowercased word sequences to look for and the term each stands for,
+    /// longest first
+    fn patterns(&self) -> Vec<(Vec<String>, &str)> {
+        let mut patterns: Vec<(Vec<String>, &str)> = self
+            .terms
+            .iter()
+            .flat_map(|term| {
+                std::iter::once(term.term.as_str())
+                    .chain(term.variants.iter().map(String::as_str))
+                    .map(move |spoken| (spoken, term.term.as_str()))
+            })
+            .map(|(spoken, term)| {
+                let words = spoken.split_whitespace().map(|w| Token::new(w).core_lower).collect::<Vec<_>>();
+                (words, term)
+            })
+            .filter(|(words, _)| !words.is_empty() && words.iter().all(|w| !w.is_empty()))
+            .collect();
+        // Stable, so earlier (more specific) terms win among equal lengths
+        patterns.sort_by_key(|(words, _)| std::cmp::Reverse(words.len()));
+        patterns
+    }
+}
+
+/// A whitespace-separated piece of text, split around its punctuation
+struct Token<'t> {
+    raw: &'t str,
+    leading: &'t str,
+    trailing: &'t str,
+    core_lower: String,
+}
+
+impl<'t> Token<'t> {
+    fn new(raw: &'t str) -> Self {
+        let is_edge = |c: char| !c.is_alphanumeric();
+        let start = raw.len() - raw.trim_start_matches(is_edge).len();
+        let end = raw.trim_end_matches(is_edge).len().max(start);
+        Self {
+            raw,
+            leading: &raw[..start],
+            trailing: &raw[end..],
+            core_lower: raw[start..end].to_lowercase(),
+        }
+    }
+}
+
+fn push_sentence(prompt: &mut String, sentence: &str) {
+    if !prompt.is_empty() {
+        prompt.push(' ');
+    }
+    prompt.push_str(sentence);
+}
+
+/// A participant's name, from the name itself or their email address
+fn participant_name(participant: &str) -> Option<String> {
+    let participant = participant.trim();
+    let Some((local, _)) = participant.split_once('@') else {
+        return (!participant.is_empty()).then(|| participant.to_string());
+    };
+
+    let name = local
+        .split(['.', '_', '-', '+'])
+        .filter(|part| part.chars().any(char::is_alphabetic) && !part.chars().any(|c| c.is_ascii_digit()))
+        .map(|part| {
+            let mut chars = part.chars();
+            chars.next().map(|first| first.to_uppercase().chain(chars).collect::<String>()).unwrap_or_default()
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+    (!name.is_empty()).then_some(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vocabulary() -> Vocabulary {
+        Vocabulary::for_meeting(
+            vec![
+                VocabularyTerm::new("Kubernetes").with_variants(&["cooper netties", "cube earnest"]),
+                VocabularyTerm::new("OKR").with_variants(&["okay are"]),
+                VocabularyTerm::new("MeetingMind"),
+            ],
+            "Platform sync",
+            &["ana.silva@example.com".to_string(), "Bruno".to_string(), "ops-2@example.com".to_string()],
+        )
+    }
+
+    #[test]
+    fn test_prompt_reads_like_a_transcript() {
+        assert_eq!(
+            vocabulary().prompt().unwrap(),
+            "Platform sync. With Ana Silva, Bruno, Ops. Kubernetes, OKR, MeetingMind."
+        );
+        assert_eq!(Vocabulary::default().prompt(), None);
+    }
+
+    #[test]
+    fn test_prompt_drops_terms_that_do_not_fit() {
+        let terms = (0..200).map(|i| VocabularyTerm::new(format!("Term{}", i))).collect();
+        let prompt = Vocabulary::for_meeting(terms, "Long glossary", &[]).prompt().unwrap();
+
+        assert!(prompt.len() <= MAX_PROMPT_CHARS);
+        assert!(prompt.starts_with("Long glossary. Term0, Term1,"));
+        assert!(prompt.ends_with('.'));
+        assert!(!prompt.contains("Term199"));
+    }
+
+    #[test]
+    fn test_misrecognitions_are_corrected() {
+        let vocabulary = vocabulary();
+
+        assert_eq!(
+            vocabulary.correct("We moved to Cooper Netties, and the okay are review is next."),
+            "We moved to Kubernetes, and the OKR review is next."
+        );
+        assert_eq!(vocabulary.correct("meetingmind works"), "MeetingMind works");
+        // Partial words and unrelated text are left alone
+        assert!(matches!(vocabulary.correct("a cube of earnest cooperation"), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn test_segment_words_take_single_word_corrections() {
+        let mut segment = TranscriptSegment {
+            text: "cube earnest and meetingmind".to_string(),
+            start_ms: 0.0,
+            end_ms: 2000.0,
+            confidence: 0.8,
+            language: "en".to_string(),
+            model: "mock".to_string(),
+            words: ["cube", "earnest", "and", "meetingmind"]
+                .iter()
+                .map(|text| crate::transcription::WordTiming {
+                    text: text.to_string(),
+                    start_ms: 0.0,
+                    end_ms: 500.0,
+                    confidence: 0.8,
+                })
+                .collect(),
+            speaker_hint: None,
+            original_text: None,
+        };
+        vocabulary().apply(std::slice::from_mut(&mut segment));
+
+        assert_eq!(segment.text, "Kubernetes and MeetingMind");
+        let words: Vec<_> = segment.words.iter().map(|w| w.text.as_str()).collect();
+        assert_eq!(words, vec!["cube", "earnest", "and", "MeetingMind"]);
+    }
+}
@@ -0,0 +1,320 @@
+//! Local Whisper inference through whisper.cpp
+//!
+//! Only built with the `whisper` cargo feature. Models are whisper.cpp GGML
+//! files (e.g. `ggml-base.bin`), loaded once and shared; each concurrent
+//! transcription gets its own decoder state, kept in a pool between calls.
+
+use std::os::raw::c_int;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tracing::{debug, info};
+use whisper_rs::{
+    FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters, WhisperState,
+};
+
+use crate::audio::AudioChunk;
+use crate::config::AIConfig;
+
+use super::alignment::push_token;
+use super::engine::{TranscriptionEngine, DEFAULT_ENGINE_SAMPLE_RATE};
+use super::language::LanguageScore;
+use super::types::{
+    TranscriptSegment, TranscriptionError, TranscriptionOptions, TranscriptionResult,
+};
+
+/// Chunks shorter than this hold no words worth decoding
+const MIN_CHUNK_MS: f64 = 100.0;
+
+/// Thread count used when none is configured
+const DEFAULT_MAX_THREADS: usize = 4;
+
+/// How to load and run a Whisper model
+#[derive(Debug, Clone, PartialEq)]
+pub struct WhisperConfig {
+    pub model_path: PathBuf,
+    /// Model size, used in the engine name
+    pub model_size: String,
+    /// CPU threads per transcription; `None` uses the available cores, up to four
+    pub threads: Option<usize>,
+}
+
+impl From<&AIConfig> for WhisperConfig {
+    fn from(config: &AIConfig) -> Self {
+        Self {
+            model_path: config.whisper_model_path.clone(),
+            model_size: config.whisper_model_size.clone(),
+            threads: (config.whisper_threads > 0).then_some(config.whisper_threads as usize),
+        }
+    }
+}
+
+impl WhisperConfig {
+    fn thread_count(&self) -> usize {
+        self.threads.unwrap_or_else(|| {
+            std::thread::available_parallelism()
+                .map(|cores| cores.get())
+                .unwrap_or(1)
+                .min(DEFAULT_MAX_THREADS)
+        })
+        .max(1)
+    }
+}
+
+/// CPU Whisper backend
+pub struct WhisperEngine {
+    context: WhisperContext,
+    name: String,
+    threads: usize,
+    states: Mutex<Vec<WhisperState>>,
+}
+
+impl WhisperEngine {
+    /// Load the model named by `config`
+    pub fn load(config: &WhisperConfig) -> TranscriptionResult<Self> {
+        let path = config.model_path.to_str().ok_or_else(|| TranscriptionError::ModelUnavailable {
+            model: config.model_path.display().to_string(),
+        })?;
+        if !config.model_path.is_file() {
+            return Err(TranscriptionError::ModelUnavailable { model: path.to_string() });
+        }
+
+        let mut parameters = WhisperContextParameters::default();
+        parameters.use_gpu(false);
+        let context = WhisperContext::new_with_params(path, parameters)
+            .map_err(|e| TranscriptionError::Engine {
+                message: format!("Failed to load Whisper model {}: {}", path, e),
+            })?;
+
+        let threads = config.thread_count();
+        info!("Loaded Whisper {} model from {} ({} threads)", config.model_size, path, threads);
+
+        Ok(Self {
+            context,
+            name: format!("whisper-{}", config.model_size),
+            threads,
+            states: Mutex::new(Vec::new()),
+        })
+    }
+
+    pub fn threads(&self) -> usize {
+        self.threads
+    }
+
+    fn take_state(&self) -> TranscriptionResult<WhisperState> {
+        let pooled = self.states.lock()
+            .map_err(|_| TranscriptionError::Internal {
+                message: "Failed to acquire Whisper state lock".to_string(),
+            })?
+            .pop();
+
+        match pooled {
+            Some(state) => Ok(state),
+            None => self.context.create_state().map_err(|e| TranscriptionError::Engine {
+                message: format!("Failed to create Whisper state: {}", e),
+            }),
+        }
+    }
+
+    fn return_state(&self, state: WhisperState) {
+        if let Ok(mut states) = self.states.lock() {
+            states.push(state);
+        }
+    }
+
+    /// Read the decoded segments out of `state`
+    fn segments(
+        &self,
+        state: &WhisperState,
+        chunk: &AudioChunk,
+    ) -> Result<Vec<TranscriptSegment>, whisper_rs::WhisperError> {
+        let language = whisper_rs::get_lang_str(state.full_lang_id_from_state()?)
+            .unwrap_or("en")
+            .to_string();
+        let first_special = self.context.token_eot();
+        let count = state.full_n_segments()?;
+
+        let mut segments = Vec::with_capacity(count.max(0) as usize);
+        for i in 0..count {
+            // Whisper timestamps are in 10ms units
+            let start_ms = chunk.start_ms + state.full_get_segment_t0(i)? as f64 * 10.0;
+            let end_ms = chunk.start_ms + state.full_get_segment_t1(i)? as f64 * 10.0;
+
+            // Confidence is the mean probability of the segment's text tokens,
+            // which are also joined into timed words
+            let mut probability = 0.0;
+            let mut tokens = 0;
+            let mut words = Vec::new();
+            for t in 0..state.full_n_tokens(i)? {
+                let token = state.full_get_token_data(i, t)?;
+                if token.id < first_special {
+                    probability += token.p;
+                    tokens += 1;
+                    push_token(
+                        &mut words,
+                        &state.full_get_token_text_lossy(i, t)?,
+                        chunk.start_ms + token.t0 as f64 * 10.0,
+                        chunk.start_ms + token.t1 as f64 * 10.0,
+                        token.p,
+                    );
+                }
+            }
+
+            segments.push(TranscriptSegment {
+                text: state.full_get_segment_text_lossy(i)?.trim().to_string(),
+                start_ms,
+                end_ms: end_ms.max(start_ms),
+                confidence: if tokens > 0 { probability / tokens as f32 } else { 0.0 },
+                language: language.clone(),
+                model: self.name.clone(),
+                words,
+                speaker_hint: None,
+                original_text: None,
+            });
+        }
+
+        Ok(segments)
+    }
+}
+
+impl TranscriptionEngine for WhisperEngine {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn sample_rate(&self) -> u32 {
+        DEFAULT_ENGINE_SAMPLE_RATE
+    }
+
+    fn identify_language(&self, chunk: &AudioChunk) -> TranscriptionResult<Vec<LanguageScore>> {
+        if chunk.buffer.duration_ms() < MIN_CHUNK_MS || !self.context.is_multilingual() {
+            return Ok(Vec::new());
+        }
+
+        let mut state = self.take_state()?;
+        let result = state
+            .pcm_to_mel(&chunk.buffer.samples, self.threads)
+            .and_then(|_| state.lang_detect(0, self.threads));
+        self.return_state(state);
+
+        let (_, probabilities) = result.map_err(|e| TranscriptionError::Engine {
+            message: format!("Whisper language detection failed on chunk {}: {}", chunk.index, e),
+        })?;
+        let mut scores: Vec<LanguageScore> = probabilities
+            .into_iter()
+            .enumerate()
+            .filter_map(|(id, probability)| {
+                whisper_rs::get_lang_str(id as i32).map(|language| LanguageScore {
+                    language: language.to_string(),
+                    probability,
+                })
+            })
+            .collect();
+        scores.sort_by(|a, b| b.probability.total_cmp(&a.probability));
+        Ok(scores)
+    }
+
+    fn transcribe(
+        &self,
+        chunk: &AudioChunk,
+        options: &TranscriptionOptions,
+    ) -> TranscriptionResult<Vec<TranscriptSegment>> {
+        if chunk.buffer.duration_ms() < MIN_CHUNK_MS {
+            return Ok(Vec::new());
+        }
+
+        // Outlives `params`, which borrow it
+        let prompt = options.vocabulary.prompt();
+        let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
+        params.set_n_threads(self.threads as c_int);
+        params.set_language(Some(options.language.as_deref().unwrap_or("auto")));
+        params.set_no_context(true);
+        params.set_suppress_blank(true);
+        params.set_print_special(false);
+        params.set_print_progress(false);
+        params.set_print_realtime(false);
+        params.set_print_timestamps(false);
+        params.set_token_timestamps(true);
+        if let Some(prompt) = prompt.as_deref() {
+            params.set_initial_prompt(prompt);
+        }
+
+        let mut state = self.take_state()?;
+        let result = state
+            .full(params, &chunk.buffer.samples)
+            .and_then(|_| self.segments(&state, chunk));
+        self.return_state(state);
+
+        let segments = result.map_err(|e| TranscriptionError::Engine {
+            message: format!("Whisper failed on chunk {}: {}", chunk.index, e),
+        })?;
+        debug!("Whisper produced {} segments for chunk {}", segments.len(), chunk.index);
+        Ok(segments)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+    use crate::audio::{AudioConfig, AudioFileImporter};
+
+    /// Fixtures fetched by `tests/fixtures/whisper/fetch.sh`
+    fn fixture(name: &str) -> PathBuf {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/whisper").join(name);
+        assert!(path.is_file(), "{} missing, run tests/fixtures/whisper/fetch.sh", path.display());
+        path
+    }
+
+    fn load_utterance(path: &Path) -> Vec<f32> {
+        let mut samples = Vec::new();
+        AudioFileImporter::new(AudioConfig::default())
+            .import(path, |chunk| { samples.extend(chunk.buffer.samples); Ok(()) }, |_| {})
+            .unwrap();
+        samples
+    }
+
+    #[test]
+    #[ignore = "needs tests/fixtures/whisper/fetch.sh"]
+    fn test_tiny_model_transcribes_known_utterance() {
+        let (model, utterance) = (fixture("ggml-tiny.en.bin"), fixture("jfk.wav"));
+        let engine = WhisperEngine::load(&WhisperConfig {
+            model_path: model,
+            model_size: "tiny.en".to_string(),
+            threads: Some(2),
+        })
+        .unwrap();
+        assert_eq!(engine.threads(), 2);
+        assert_eq!(engine.name(), "whisper-tiny.en");
+
+        let chunk = AudioChunk {
+            index: 0,
+            start_ms: 60_000.0,
+            buffer: crate::audio::AudioBuffer::new(load_utterance(&utterance), 16000, 1),
+        };
+        let options = TranscriptionOptions { language: Some("en".to_string()), ..Default::default() };
+        let segments = engine.transcribe(&chunk, &options).unwrap();
+
+        let text: String = segments.iter().map(|s| s.text.to_lowercase()).collect::<Vec<_>>().join(" ");
+        assert!(text.contains("ask not what your country can do for you"), "{}", text);
+        assert!(segments.iter().all(|s| s.start_ms >= 60_000.0 && s.end_ms <= 72_000.0));
+        assert!(segments.iter().all(|s| s.language == "en" && s.confidence > 0.3));
+        assert!(segments.iter().all(|s| !s.words.is_empty()
+            && s.words.iter().all(|w| w.start_ms >= 60_000.0 && w.start_ms <= w.end_ms)));
+
+        // The pooled state is reused for the next call
+        assert_eq!(engine.transcribe(&chunk, &options).unwrap(), segments);
+    }
+
+    #[test]
+    fn test_missing_model_is_reported() {
+        let config = WhisperConfig {
+            model_path: PathBuf::from("/nonexistent/ggml-base.bin"),
+            model_size: "base".to_string(),
+            threads: None,
+        };
+        assert!(matches!(
+            WhisperEngine::load(&config),
+            Err(TranscriptionError::ModelUnavailable { .. })
+        ));
+    }
+}
@@ -0,0 +1,10 @@
+#!/bin/sh
+# Download the fixtures used by the Whisper backend tests:
+# the tiny English whisper.cpp model and the JFK sample utterance.
+# Then run them with: cargo test --features whisper -- --ignored
+set -e
+cd "$(dirname "$0")"
+
+BASE=https://huggingface.co/ggerganov/whisper.cpp/resolve/main
+[ -f ggml-tiny.en.bin ] || curl -fL -o ggml-tiny.en.bin "$BASE/ggml-tiny.en.bin"
+[ -f jfk.wav ] || curl -fL -o jfk.wav https://github.com/ggerganov/whisper.cpp/raw/master/samples/jfk.wav
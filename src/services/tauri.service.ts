@@ -15,7 +15,41 @@ import {
   AudioLevelEvent,
   AudioStatusEvent,
   AudioDeviceChangeEvent,
+  AudioQualityEvent,
+  PeakSlice,
+  AudioQualityReport,
+  AudioImportProgress,
+  ImportedMeeting,
+  ArchiveSettings,
+  ArchiveSummary,
 } from '../types/audio.types';
+import {
+  CaptionConfig,
+  CaptionFormat,
+  DiskUsage,
+  InstalledModel,
+  JobEvent,
+  JobPriority,
+  LanguageSwitch,
+  MeetingSpeaker,
+  ModelKind,
+  RetranscriptionBatch,
+  SegmentEdit,
+  Speaker,
+  StoredSegment,
+  StoredVocabularyTerm,
+  StoredWord,
+  TextChange,
+  Transcript,
+  TranscriptRevision,
+  TranscriptUpdateEvent,
+  TranscriptionJob,
+  TranscriptionStats,
+  Vocabulary,
+  VocabularyScope,
+  VersionComparison,
+  VocabularyTerm,
+} from '../types/transcription.types';
 
 export class TauriAudioService {
   private eventListeners: Map<string, UnlistenFn> = new Map();
@@ -27,6 +61,257 @@ export class TauriAudioService {
     await invoke('init_audio_service');
   }
 
+  /**
+   * Initialize the transcription service, returning the engine name
+   *
+   * Once initialized, live transcripts are streamed while capturing.
+   */
+  async initTranscriptionService(): Promise<string> {
+    return await invoke<string>('init_transcription_service');
+  }
+
+  /**
+   * Get transcription statistics
+   */
+  async getTranscriptionStats(): Promise<TranscriptionStats> {
+    return await invoke<TranscriptionStats>('get_transcription_stats');
+  }
+
+  /**
+   * Pin a meeting's language, or restrict detection to candidate languages
+   */
+  async setMeetingLanguages(meetingId: number, pinned: string | null, candidates: string[]): Promise<void> {
+    await invoke('set_meeting_languages', { meetingId, pinned, candidates });
+  }
+
+  /**
+   * Get the points in a stored transcript where the spoken language changes
+   */
+  async getLanguageSwitches(transcriptionId: number): Promise<LanguageSwitch[]> {
+    return await invoke<LanguageSwitch[]>('get_language_switches', { transcriptionId });
+  }
+
+  /**
+   * Get the words of a stored transcript spoken between two timeline positions
+   */
+  async getWordsBetween(transcriptionId: number, startMs: number, endMs: number): Promise<StoredWord[]> {
+    return await invoke<StoredWord[]>('get_words_between', { transcriptionId, startMs, endMs });
+  }
+
+  /**
+   * Work out who spoke in a meeting, recognising voices named before
+   */
+  async diarizeMeeting(meetingId: number): Promise<MeetingSpeaker[]> {
+    return await invoke<MeetingSpeaker[]>('diarize_meeting', { meetingId });
+  }
+
+  /**
+   * Make a readable copy of a transcript; the original is kept
+   */
+  async formatTranscript(transcriptionId: number): Promise<Transcript> {
+    return await invoke<Transcript>('format_transcript', { transcriptionId });
+  }
+
+  /**
+   * Get a stored transcript's segments with their words
+   */
+  async getTranscriptSegments(transcriptionId: number): Promise<StoredSegment[]> {
+    return await invoke<StoredSegment[]>('get_transcript_segments', { transcriptionId });
+  }
+
+  /**
+   * Correct a transcript or undo an earlier edit; every edit is kept as a revision
+   */
+  async editTranscript(transcriptionId: number, edit: SegmentEdit, author: string): Promise<TranscriptRevision> {
+    return await invoke<TranscriptRevision>('edit_transcript', { transcriptionId, edit, author });
+  }
+
+  /**
+   * Get every edit made to a transcript, oldest first
+   */
+  async getTranscriptHistory(transcriptionId: number): Promise<TranscriptRevision[]> {
+    return await invoke<TranscriptRevision[]>('get_transcript_history', { transcriptionId });
+  }
+
+  /**
+   * Get the words an edit changed
+   */
+  async getRevisionDiff(revisionId: number): Promise<TextChange[]> {
+    return await invoke<TextChange[]>('get_revision_diff', { revisionId });
+  }
+
+  /**
+   * Find transcripts containing every word of a query
+   */
+  async searchTranscripts(query: string): Promise<Transcript[]> {
+    return await invoke<Transcript[]>('search_transcripts', { query });
+  }
+
+  /**
+   * Export a transcript as SRT, WebVTT or TTML captions; options override the configured settings
+   */
+  async exportCaptions(
+    transcriptionId: number,
+    format: CaptionFormat,
+    options?: CaptionConfig
+  ): Promise<string> {
+    return await invoke<string>('export_captions', { transcriptionId, format, options });
+  }
+
+  /**
+   * Get the voices heard in a meeting
+   */
+  async getMeetingSpeakers(meetingId: number): Promise<MeetingSpeaker[]> {
+    return await invoke<MeetingSpeaker[]>('get_meeting_speakers', { meetingId });
+  }
+
+  /**
+   * Name a voice heard in a meeting so it is recognised in later meetings
+   */
+  async nameMeetingSpeaker(meetingSpeakerId: number, name: string): Promise<Speaker> {
+    return await invoke<Speaker>('name_meeting_speaker', { meetingSpeakerId, name });
+  }
+
+  /**
+   * Get every known speaker
+   */
+  async listSpeakers(): Promise<Speaker[]> {
+    return await invoke<Speaker[]>('list_speakers');
+  }
+
+  /**
+   * Teach transcription a term for every meeting, a project or one meeting
+   */
+  async addVocabularyTerm(term: VocabularyTerm, scope: VocabularyScope): Promise<number> {
+    return await invoke<number>('add_vocabulary_term', { term, scope });
+  }
+
+  /**
+   * Get the vocabulary terms added to a scope
+   */
+  async listVocabulary(scope: VocabularyScope): Promise<StoredVocabularyTerm[]> {
+    return await invoke<StoredVocabularyTerm[]>('list_vocabulary', { scope });
+  }
+
+  /**
+   * Remove a vocabulary term
+   */
+  async deleteVocabularyTerm(termId: number): Promise<void> {
+    await invoke('delete_vocabulary_term', { termId });
+  }
+
+  /**
+   * File a meeting under a project, or under none
+   */
+  async setMeetingProject(meetingId: number, project: string | null): Promise<void> {
+    await invoke('set_meeting_project', { meetingId, project });
+  }
+
+  /**
+   * Prime live transcription with a meeting's vocabulary before recording
+   */
+  async applyMeetingVocabulary(meetingId: number): Promise<Vocabulary> {
+    return await invoke<Vocabulary>('apply_meeting_vocabulary', { meetingId });
+  }
+
+  /**
+   * Get the installed speech and embedding models
+   */
+  async listModels(): Promise<InstalledModel[]> {
+    return await invoke<InstalledModel[]>('list_models');
+  }
+
+  /**
+   * Import a model file, or every model in a directory, checking published checksums
+   */
+  async importModels(path: string): Promise<InstalledModel[]> {
+    return await invoke<InstalledModel[]>('import_models', { path });
+  }
+
+  /**
+   * Download a model from the configured mirror
+   */
+  async downloadModel(file: string, sha256?: string): Promise<InstalledModel> {
+    return await invoke<InstalledModel>('download_model', { file, sha256: sha256 ?? null });
+  }
+
+  /**
+   * Check an installed model still matches its recorded SHA-256
+   */
+  async verifyModel(modelId: string): Promise<InstalledModel> {
+    return await invoke<InstalledModel>('verify_model', { modelId });
+  }
+
+  /**
+   * Uninstall a model and delete its file
+   */
+  async removeModel(modelId: string): Promise<InstalledModel> {
+    return await invoke<InstalledModel>('remove_model', { modelId });
+  }
+
+  /**
+   * Get the disk space taken by installed models
+   */
+  async getModelDiskUsage(): Promise<DiskUsage> {
+    return await invoke<DiskUsage>('get_model_disk_usage');
+  }
+
+  /**
+   * Get the installed model best suited to this machine, if any
+   */
+  async recommendModel(kind: ModelKind): Promise<InstalledModel | null> {
+    return await invoke<InstalledModel | null>('recommend_model', { kind });
+  }
+
+  /**
+   * Start the background transcription queue, resuming interrupted jobs
+   */
+  async startTranscriptionQueue(): Promise<void> {
+    await invoke('start_transcription_queue');
+  }
+
+  /**
+   * Queue a meeting's recording for transcription
+   */
+  async enqueueTranscription(meetingId: number, priority: JobPriority): Promise<TranscriptionJob> {
+    return await invoke<TranscriptionJob>('enqueue_transcription', { meetingId, priority });
+  }
+
+  /**
+   * Cancel a transcription job; false if it had already finished
+   */
+  async cancelTranscriptionJob(jobId: number): Promise<boolean> {
+    return await invoke<boolean>('cancel_transcription_job', { jobId });
+  }
+
+  /**
+   * Get every transcription job, unfinished ones first
+   */
+  async listTranscriptionJobs(): Promise<TranscriptionJob[]> {
+    return await invoke<TranscriptionJob[]>('list_transcription_jobs');
+  }
+
+  /**
+   * Queue meetings to be transcribed again with the current model and settings
+   */
+  async retranscribeMeetings(meetingIds: number[]): Promise<RetranscriptionBatch> {
+    return await invoke<RetranscriptionBatch>('retranscribe_meetings', { meetingIds });
+  }
+
+  /**
+   * Get every version of a meeting's transcript, oldest first
+   */
+  async getTranscriptVersions(meetingId: number): Promise<Transcript[]> {
+    return await invoke<Transcript[]>('get_transcript_versions', { meetingId });
+  }
+
+  /**
+   * Compare two versions of a meeting's transcript
+   */
+  async compareTranscriptVersions(beforeId: number, afterId: number): Promise<VersionComparison> {
+    return await invoke<VersionComparison>('compare_transcript_versions', { beforeId, afterId });
+  }
+
   /**
    * Get available audio input devices
    */
@@ -35,10 +320,10 @@ export class TauriAudioService {
   }
 
   /**
-   * Start audio capture
+   * Start audio capture, returning the id of the meeting it is stored as
    */
-  async startAudioCapture(request: StartCaptureRequest): Promise<void> {
-    await invoke('start_audio_capture', { request });
+  async startAudioCapture(request: StartCaptureRequest): Promise<number> {
+    return await invoke<number>('start_audio_capture', { request });
   }
 
   /**
@@ -69,6 +354,39 @@ export class TauriAudioService {
     return await invoke<AudioStats>('get_audio_stats');
   }
 
+  /**
+   * Get the audio quality report for the current recording
+   */
+  async getAudioQualityReport(): Promise<AudioQualityReport> {
+    return await invoke<AudioQualityReport>('get_audio_quality_report');
+  }
+
+  /**
+   * Get waveform peaks for a time range of the current recording
+   */
+  async getWaveformPeaks(startMs: number, endMs: number, maxPoints: number): Promise<PeakSlice> {
+    return await invoke<PeakSlice>('get_waveform_peaks', { startMs, endMs, maxPoints });
+  }
+
+  /**
+   * Save the current recording's waveform overview next to its audio file
+   */
+  async saveWaveformPeaks(audioPath: string): Promise<string> {
+    return await invoke<string>('save_waveform_peaks', { audioPath });
+  }
+
+  /**
+   * Load waveform peaks for a past recording
+   */
+  async loadWaveformPeaks(
+    audioPath: string,
+    startMs: number,
+    endMs: number,
+    maxPoints: number
+  ): Promise<PeakSlice> {
+    return await invoke<PeakSlice>('load_waveform_peaks', { audioPath, startMs, endMs, maxPoints });
+  }
+
   /**
    * Set the active audio device
    */
@@ -97,6 +415,40 @@ export class TauriAudioService {
     return await invoke<AudioDevice[]>('refresh_audio_devices');
   }
 
+  /**
+   * Import an existing recording as a meeting
+   */
+  async importAudioFile(path: string, title?: string): Promise<ImportedMeeting> {
+    return await invoke<ImportedMeeting>('import_audio_file', { path, title });
+  }
+
+  /**
+   * Compress a finished meeting's recording to Opus
+   */
+  async archiveMeetingRecording(meetingId: number, settings?: ArchiveSettings): Promise<ArchiveSummary> {
+    return await invoke<ArchiveSummary>('archive_meeting_recording', { meetingId, settings });
+  }
+
+  /**
+   * Get the archive written during the last recording, once it is complete
+   */
+  async getLiveArchiveSummary(): Promise<ArchiveSummary | null> {
+    return await invoke<ArchiveSummary | null>('get_live_archive_summary');
+  }
+
+  /**
+   * Subscribe to audio import progress
+   */
+  async subscribeToImportProgress(
+    callback: (event: AudioImportProgress) => void
+  ): Promise<void> {
+    const unlisten = await listen<AudioImportProgress>('audio_import_progress', (event) => {
+      callback(event.payload);
+    });
+    
+    this.eventListeners.set('audio_import_progress', unlisten);
+  }
+
   /**
    * Subscribe to audio level updates
    */
@@ -123,6 +475,45 @@ export class TauriAudioService {
     this.eventListeners.set('audio_status_changed', unlisten);
   }
 
+  /**
+   * Subscribe to quality events from buffer validation
+   */
+  async subscribeToAudioQualityEvents(
+    callback: (event: AudioQualityEvent) => void
+  ): Promise<void> {
+    const unlisten = await listen<AudioQualityEvent>('audio_quality_event', (event) => {
+      callback(event.payload);
+    });
+    
+    this.eventListeners.set('audio_quality_event', unlisten);
+  }
+
+  /**
+   * Subscribe to live transcript updates while capturing
+   */
+  async subscribeToTranscriptUpdates(
+    callback: (event: TranscriptUpdateEvent) => void
+  ): Promise<void> {
+    const unlisten = await listen<TranscriptUpdateEvent>('transcript_update', (event) => {
+      callback(event.payload);
+    });
+    
+    this.eventListeners.set('transcript_update', unlisten);
+  }
+
+  /**
+   * Subscribe to transcription job queue changes and progress
+   */
+  async subscribeToTranscriptionJobs(
+    callback: (event: JobEvent) => void
+  ): Promise<void> {
+    const unlisten = await listen<JobEvent>('transcription_job', (event) => {
+      callback(event.payload);
+    });
+    
+    this.eventListeners.set('transcription_job', unlisten);
+  }
+
   /**
    * Subscribe to audio device changes
    */
@@ -23,6 +23,31 @@ export interface AudioCaptureConfig {
   sample_rate: number;
   channels: number;
   buffer_size: number;
+  secondary_device?: string;
+  validation?: ValidationConfig;
+  worker?: WorkerConfig;
+}
+
+// Scheduling and CPU budget of the audio processing thread
+export interface WorkerConfig {
+  frame_ms: number;
+  realtime_priority: boolean;
+  budget_ratio: number;
+  miss_window: number;
+  degrade_misses: number;
+  recover_frames: number;
+}
+
+// Thresholds for validating captured buffers
+export interface ValidationConfig {
+  min_sample_rate: number;
+  max_sample_rate: number;
+  min_buffer_duration_ms: number;
+  max_buffer_duration_ms: number;
+  silence_threshold: number;
+  clipping_threshold: number;
+  silence_alert_ms: number;
+  event_interval_ms: number;
 }
 
 // Audio capture status
@@ -42,6 +67,10 @@ export interface AudioStats {
   average_latency_ms: number;
   peak_level: number;
   rms_level: number;
+  clock_drift_ppm?: number;
+  deadline_misses: number;
+  processing_load: number;
+  degraded_stages: number;
 }
 
 // Audio level event from backend
@@ -68,6 +97,103 @@ export interface AudioDeviceChangeEvent {
 export interface StartCaptureRequest {
   device_name?: string;
   config?: AudioCaptureConfig;
+  archive_path?: string;
+  archive_settings?: ArchiveSettings;
+  title?: string;
+}
+
+// When and how recordings are compressed to Opus
+export interface ArchiveSettings {
+  mode: 'disabled' | 'after_session' | 'while_recording';
+  bitrate_bps: number;
+  keep_original: boolean;
+}
+
+// Result of archiving a recording
+export interface ArchiveSummary {
+  archive_path: string;
+  duration_ms: number;
+  sample_rate: number;
+  channels: number;
+  bitrate_bps: number;
+  original_bytes: number;
+  archive_bytes: number;
+  original_kept: boolean;
+}
+
+// Quality problem found in a recording
+export type QualityWarning =
+  | { kind: 'low_snr'; snr_db: number; threshold_db: number }
+  | { kind: 'clipping'; ratio: number; threshold: number }
+  | { kind: 'dropouts'; count: number }
+  | { kind: 'buffer_overruns'; count: number }
+  | { kind: 'too_quiet'; loudness_lufs: number; threshold_lufs: number };
+
+// A problem found while validating captured buffers
+export type ValidationIssue =
+  | { kind: 'clipping'; clipped_samples: number; ratio: number; peak: number }
+  | { kind: 'silence'; duration_ms: number; threshold: number }
+  | { kind: 'invalid_samples'; count: number }
+  | { kind: 'oversized_buffer'; count: number; duration_ms: number; max_duration_ms: number };
+
+// Rate-limited quality event emitted while capturing
+export interface AudioQualityEvent {
+  at_ms: number;
+  issue: ValidationIssue;
+  timestamp: number;
+}
+
+// Audio quality summary for a recording session
+export interface AudioQualityReport {
+  duration_ms: number;
+  noise_floor_dbfs?: number;
+  speech_level_dbfs?: number;
+  snr_db?: number;
+  speech_ratio: number;
+  clipped_samples: number;
+  clipping_ratio: number;
+  dropouts: number;
+  buffer_overruns: number;
+  buffer_underruns: number;
+  integrated_loudness_lufs?: number;
+  warnings: QualityWarning[];
+}
+
+// Min/max pair summarising one block of samples
+export interface Peak {
+  min: number;
+  max: number;
+}
+
+// Waveform peaks for a time range at one zoom level
+export interface PeakSlice {
+  level: number;
+  samples_per_peak: number;
+  sample_rate: number;
+  start_ms: number;
+  peaks: Peak[];
+}
+
+// Progress of an audio file import
+export interface AudioImportProgress {
+  meeting_id: number;
+  processed_ms: number;
+  total_ms?: number;
+  chunks: number;
+}
+
+// Result of importing an audio file as a meeting
+export interface ImportedMeeting {
+  meeting_id: number;
+  summary: {
+    codec: string;
+    source_sample_rate: number;
+    source_channels: number;
+    sample_rate: number;
+    channels: number;
+    duration_ms: number;
+    chunks: number;
+  };
 }
 
 // Audio visualization data
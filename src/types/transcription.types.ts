@@ -0,0 +1,300 @@
+/**
+ * Transcription-related TypeScript type definitions
+ * 
+ * These types match the Rust backend types for consistent data flow
+ * between frontend and backend via Tauri commands.
+ */
+
+// A stretch of transcribed speech on the meeting timeline
+export interface TranscriptSegment {
+  text: string;
+  start_ms: number;
+  end_ms: number;
+  confidence: number; // 0-1
+  language: string;
+  model: string; // e.g. "whisper-base" or "cloud-whisper-1"
+  words: WordTiming[];
+  speaker_hint?: SpeakerHint;
+  original_text?: string; // engine output, when formatting changed the text
+}
+
+// Capture track a segment was heard on: the local mic or system audio
+export type SpeakerHint = 'me' | 'others';
+
+// When one word of a segment was spoken
+export interface WordTiming {
+  text: string;
+  start_ms: number;
+  end_ms: number;
+  confidence: number; // 0-1
+}
+
+// A stored transcript; a formatted one points at the transcript it was made from,
+// and a re-transcribed one at the version it replaced
+export interface Transcript {
+  id: number;
+  meeting_id: number;
+  content: string;
+  language?: string;
+  confidence?: number;
+  model_used?: string;
+  processing_time_ms?: number;
+  created_at: string;
+  source_transcription_id?: number;
+  previous_transcription_id?: number;
+}
+
+// A stored segment of a transcript
+export interface StoredSegment extends TranscriptSegment {
+  id: number;
+  transcription_id: number;
+  speaker_id?: number;
+  meeting_speaker_id?: number;
+  is_edited: boolean;
+}
+
+// A change made to a transcript by hand; offset is a character position in the segment's text
+export type SegmentEdit =
+  | { type: 'text'; segment_id: number; text: string }
+  | { type: 'speaker'; segment_id: number; meeting_speaker_id?: number }
+  | { type: 'split'; segment_id: number; offset: number }
+  | { type: 'merge'; segment_id: number }
+  | { type: 'revert'; revision_id: number };
+
+// A recorded edit, with the segments it touched before and after
+export interface TranscriptRevision {
+  id: number;
+  transcription_id: number;
+  author: string;
+  edit: SegmentEdit;
+  before: StoredSegment[];
+  after: StoredSegment[];
+  reverted_by?: number;
+  created_at: string;
+}
+
+// A stretch of an edit's word-level diff
+export type TextChange =
+  | { kind: 'same'; text: string }
+  | { kind: 'added'; text: string }
+  | { kind: 'removed'; text: string };
+
+// A stored word and the segment it belongs to
+export interface StoredWord extends WordTiming {
+  id: number;
+  segment_id: number;
+}
+
+// A person who speaks in meetings
+export interface Speaker {
+  id: number;
+  name?: string;
+  email?: string;
+  color_hex?: string;
+  total_meetings: number;
+  last_seen?: string;
+  voice_fingerprint?: number[];
+  fingerprint_samples: number;
+}
+
+// A voice heard in one meeting; label is "Speaker N" until it is named
+export interface MeetingSpeaker {
+  id: number;
+  meeting_id: number;
+  label: string;
+  speaker_id?: number;
+  speech_ms: number;
+  similarity?: number; // 0-1, when recognised from a voice profile
+}
+
+// A term transcription should spell right, and how it tends to be misheard
+export interface VocabularyTerm {
+  term: string;
+  variants: string[];
+}
+
+// Where a vocabulary term applies
+export type VocabularyScope =
+  | { kind: 'global' }
+  | { kind: 'project'; name: string }
+  | { kind: 'meeting'; meeting_id: number };
+
+// A vocabulary term the user has added
+export interface StoredVocabularyTerm extends VocabularyTerm {
+  id: number;
+  scope: VocabularyScope;
+}
+
+// Terms and context a meeting's transcription is primed with
+export interface Vocabulary {
+  terms: VocabularyTerm[];
+  topic?: string;
+  participants: string[];
+}
+
+// Point on the timeline where the spoken language changes
+export interface LanguageSwitch {
+  at_ms: number;
+  from: string;
+  to: string;
+}
+
+// Change to the live transcript; a segment keeps its id until finalized
+export type TranscriptEvent =
+  | { type: 'partial'; id: number; segment: TranscriptSegment }
+  | { type: 'revised'; id: number; segment: TranscriptSegment }
+  | { type: 'finalized'; id: number; segment: TranscriptSegment }
+  | { type: 'retracted'; id: number }
+  | { type: 'language_changed'; id: number; switch: LanguageSwitch };
+
+// Live transcript update event
+export type TranscriptUpdateEvent = TranscriptEvent & { timestamp: number };
+
+// Live transcription settings
+export interface StreamingConfig {
+  step_ms: number;
+  stability_margin_ms: number;
+  max_window_ms: number;
+}
+
+// Counters for a transcription session
+export interface TranscriptionStats {
+  chunks_processed: number;
+  chunks_failed: number;
+  segments: number;
+  segments_by_model: Record<string, number>;
+  audio_ms: number;
+  processing_ms: number;
+}
+
+// When low-confidence audio is sent to the cloud API, and how much
+export interface RoutingConfig {
+  min_confidence: number;
+  local_languages: string[]; // empty trusts the local model with all
+  budget_seconds: number; // per meeting
+  padding_ms: number;
+  api_base_url: string;
+  model: string;
+  timeout_secs: number;
+}
+
+// What an installed model is used for
+export type ModelKind = 'speech' | 'embedding';
+
+// A model file in the model registry
+export interface InstalledModel {
+  id: string; // file name without extension, e.g. "ggml-base.en-q5_1"
+  kind: ModelKind;
+  file: string;
+  variant: string; // e.g. "base", "large-v3"
+  size_bytes: number;
+  languages: string[]; // empty when multilingual
+  quantization: string; // e.g. "f16", "q5_1"
+  sha256: string;
+  source: string;
+  installed_at: string;
+}
+
+// Space taken by one installed model
+export interface ModelUsage {
+  id: string;
+  kind: ModelKind;
+  size_bytes: number;
+  present: boolean; // false if the file has gone missing
+}
+
+// Space taken by the models directory
+export interface DiskUsage {
+  directory: string;
+  total_bytes: number;
+  speech_bytes: number;
+  embedding_bytes: number;
+  available_bytes: number | null;
+  models: ModelUsage[];
+}
+
+// Per-job settings handed to the transcription engine
+export interface TranscriptionOptions {
+  language?: string; // pinned language; detected per chunk when absent
+  candidate_languages: string[];
+  vocabulary: Vocabulary;
+}
+
+// How soon a transcription job runs: live meetings first, the backlog last
+export type JobPriority = 'backlog' | 'normal' | 'live';
+
+// Where a transcription job is in its life
+export type JobStatus = 'queued' | 'running' | 'completed' | 'failed' | 'cancelled';
+
+// A recording queued for transcription
+export interface TranscriptionJob {
+  id: number;
+  meeting_id: number;
+  priority: JobPriority;
+  status: JobStatus;
+  audio_path: string;
+  chunk_ms: number;
+  options: TranscriptionOptions;
+  total_ms?: number; // known once decoding has started
+  completed_chunks: number;
+  attempts: number; // failed attempts so far
+  last_error?: string;
+  run_after?: string; // a failed job isn't retried before this
+  transcription_id?: number;
+  replaces_transcription_id?: number; // set when re-transcribing a meeting
+  created_at: string;
+  updated_at: string;
+}
+
+// Change in the transcription job queue
+export type JobEvent =
+  | { type: 'queued'; job: TranscriptionJob }
+  | { type: 'started'; job_id: number; meeting_id: number; resumed_chunks: number }
+  | { type: 'progress'; job_id: number; meeting_id: number; completed_chunks: number; processed_ms: number; total_ms?: number }
+  | { type: 'paused'; job_id: number }
+  | { type: 'retrying'; job_id: number; attempts: number; error: string; retry_at: string }
+  | { type: 'completed'; job_id: number; meeting_id: number; transcription_id: number }
+  | { type: 'failed'; job_id: number; error: string }
+  | { type: 'cancelled'; job_id: number };
+
+// A meeting left out of a re-transcription batch, and why
+export interface SkippedMeeting {
+  meeting_id: number;
+  reason: string;
+}
+
+// Meetings queued for re-transcription, and those that weren't
+export interface RetranscriptionBatch {
+  queued: TranscriptionJob[];
+  skipped: SkippedMeeting[];
+}
+
+// Word-level changes over a stretch of a meeting's timeline
+export interface ComparedSection {
+  start_ms: number;
+  end_ms: number;
+  changes: TextChange[];
+}
+
+// Two versions of a meeting's transcript side by side
+export interface VersionComparison {
+  before: Transcript;
+  after: Transcript;
+  sections: ComparedSection[];
+  words_added: number;
+  words_removed: number;
+  edits_kept: number; // hand edits carried into the newer version
+  edits_lost: StoredSegment[]; // edited segments that no longer line up
+}
+
+// Subtitle file formats
+export type CaptionFormat = 'srt' | 'webvtt' | 'ttml';
+
+// How exported subtitles are cut into cues
+export interface CaptionConfig {
+  max_line_chars: number;
+  max_lines: number;
+  min_duration_ms: number; // unless the next cue starts sooner
+  max_duration_ms: number;
+  offset_ms: number; // shift applied to every cue; may be negative
+}